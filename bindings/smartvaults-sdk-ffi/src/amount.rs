@@ -2,9 +2,10 @@
 // Distributed under the MIT software license
 
 use std::ops::Deref;
+use std::sync::Arc;
 
 use smartvaults_sdk::core;
-use uniffi::Object;
+use uniffi::{Object, Record};
 
 #[derive(Object)]
 pub struct Amount {
@@ -35,3 +36,10 @@ impl Amount {
         }
     }
 }
+
+/// Spending recipient
+#[derive(Record)]
+pub struct Recipient {
+    pub address: String,
+    pub amount: Arc<Amount>,
+}
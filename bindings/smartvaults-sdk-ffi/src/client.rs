@@ -23,11 +23,14 @@ use smartvaults_sdk::nostr::block_on;
 use uniffi::Object;
 
 use crate::error::Result;
+use crate::signer::ProposalSignerWrapper;
 use crate::{
     AbortHandle, AddressIndex, Amount, Balance, CompletedProposal, Config, GetAddress, GetApproval,
-    GetCompletedProposal, GetPolicy, GetProposal, GetSharedSigner, GetSigner, GetTransaction,
-    KeyAgent, Message, Network, NostrConnectRequest, NostrConnectSession, OutPoint, Period,
-    PolicyTemplate, Seed, Signer, SignerOffering, Utxo, WordCount,
+    GetCompletedProposal, GetPolicy, GetProposal, GetProposalComment, GetProposalRejection,
+    GetSharedSigner, GetSigner, GetTransaction, KeyAgent, Message, Network, NostrConnectRequest,
+    NostrConnectSession, OutPoint, Period, PolicyTemplate, ProposalSigner, ProposalStatus,
+    PsbtFileApproval, PsbtFileFormat, Recipient, Seed, SeedAccountReuse, SharedKeyRotation, Signer,
+    SignerOffering, SignerType, UrEncoder, Utxo, VaultMetadata, WordCount,
 };
 
 #[derive(Object)]
@@ -452,8 +455,7 @@ impl SmartVaults {
     pub fn spend(
         &self,
         policy_id: Arc<EventId>,
-        to_address: String,
-        amount: Arc<Amount>,
+        recipients: Vec<Recipient>,
         description: String,
         target_blocks: u8,
         utxos: Option<Vec<Arc<OutPoint>>>,
@@ -461,13 +463,15 @@ impl SmartVaults {
         skip_frozen_utxos: bool,
     ) -> Result<Arc<GetProposal>> {
         block_on(async move {
-            let to_address = Address::from_str(&to_address)?;
+            let mut list = Vec::with_capacity(recipients.len());
+            for Recipient { address, amount } in recipients.into_iter() {
+                list.push((Address::from_str(&address)?, **amount));
+            }
             let proposal = self
                 .inner
                 .spend(
                     **policy_id,
-                    to_address,
-                    **amount,
+                    list,
                     description,
                     FeeRate::Priority(Priority::Custom(target_blocks)),
                     utxos.map(|utxos| utxos.into_iter().map(|u| u.as_ref().into()).collect()),
@@ -514,9 +518,72 @@ impl SmartVaults {
         })
     }
 
-    pub fn approve(&self, password: String, proposal_id: Arc<EventId>) -> Result<Arc<EventId>> {
+    pub fn bump_fee(
+        &self,
+        policy_id: Arc<EventId>,
+        txid: String,
+        target_blocks: u8,
+        policy_path: Option<HashMap<String, Vec<u64>>>,
+    ) -> Result<Arc<GetProposal>> {
         block_on(async move {
-            let (approval_id, ..) = self.inner.approve(password, **proposal_id).await?;
+            let txid = Txid::from_str(&txid)?;
+            let proposal = self
+                .inner
+                .bump_fee(
+                    **policy_id,
+                    txid,
+                    FeeRate::Priority(Priority::Custom(target_blocks)),
+                    policy_path.map(|pp| {
+                        pp.into_iter()
+                            .map(|(k, v)| (k, v.into_iter().map(|i| i as usize).collect()))
+                            .collect()
+                    }),
+                )
+                .await?;
+            Ok(Arc::new(proposal.into()))
+        })
+    }
+
+    pub fn cpfp(
+        &self,
+        policy_id: Arc<EventId>,
+        parent_txid: String,
+        target_blocks: u8,
+        policy_path: Option<HashMap<String, Vec<u64>>>,
+    ) -> Result<Arc<GetProposal>> {
+        block_on(async move {
+            let parent_txid = Txid::from_str(&parent_txid)?;
+            let proposal = self
+                .inner
+                .cpfp(
+                    **policy_id,
+                    parent_txid,
+                    FeeRate::Priority(Priority::Custom(target_blocks)),
+                    policy_path.map(|pp| {
+                        pp.into_iter()
+                            .map(|(k, v)| (k, v.into_iter().map(|i| i as usize).collect()))
+                            .collect()
+                    }),
+                )
+                .await?;
+            Ok(Arc::new(proposal.into()))
+        })
+    }
+
+    /// Approve a proposal
+    ///
+    /// Proposals that break the vault spending rules are refused, unless `override_spending_rules` is `true`.
+    pub fn approve(
+        &self,
+        password: String,
+        proposal_id: Arc<EventId>,
+        override_spending_rules: bool,
+    ) -> Result<Arc<EventId>> {
+        block_on(async move {
+            let (approval_id, ..) = self
+                .inner
+                .approve(password, **proposal_id, override_spending_rules)
+                .await?;
             Ok(Arc::new(approval_id.into()))
         })
     }
@@ -525,21 +592,180 @@ impl SmartVaults {
         &self,
         proposal_id: Arc<EventId>,
         signed_psbt: String,
+        override_spending_rules: bool,
     ) -> Result<Arc<EventId>> {
         block_on(async move {
             let signed_psbt = PartiallySignedTransaction::from_str(&signed_psbt)?;
             let (approval_id, ..) = self
                 .inner
-                .approve_with_signed_psbt(**proposal_id, signed_psbt)
+                .approve_with_signed_psbt(**proposal_id, signed_psbt, override_spending_rules)
                 .await?;
             Ok(Arc::new(approval_id.into()))
         })
     }
 
+    /// Get the `crypto-psbt` UR encoder of a proposal, to show it as animated QR code to an air-gapped signer
+    pub fn proposal_ur_encoder(
+        &self,
+        proposal_id: Arc<EventId>,
+        max_fragment_len: u64,
+    ) -> Result<Arc<UrEncoder>> {
+        block_on(async move {
+            let encoder = self
+                .inner
+                .proposal_ur_encoder(**proposal_id, max_fragment_len as usize)
+                .await?;
+            Ok(Arc::new(encoder.into()))
+        })
+    }
+
+    /// Approve a proposal with the signed PSBT received as `crypto-psbt` UR parts (any order)
+    pub fn approve_with_ur(
+        &self,
+        proposal_id: Arc<EventId>,
+        parts: Vec<String>,
+        override_spending_rules: bool,
+    ) -> Result<Arc<EventId>> {
+        block_on(async move {
+            let (approval_id, ..) = self
+                .inner
+                .approve_with_ur(**proposal_id, parts, override_spending_rules)
+                .await?;
+            Ok(Arc::new(approval_id.into()))
+        })
+    }
+
+    /// Export the PSBT of a proposal to `<dir>/<proposal-id>.psbt`
+    ///
+    /// Return the path of the exported file.
+    pub fn export_proposal_psbt(
+        &self,
+        proposal_id: Arc<EventId>,
+        dir: String,
+        format: PsbtFileFormat,
+    ) -> Result<String> {
+        block_on(async move {
+            let path = self
+                .inner
+                .export_proposal_psbt(**proposal_id, dir, format.into())
+                .await?;
+            Ok(path.display().to_string())
+        })
+    }
+
+    /// Export the Coldcard registration file of a vault to `<dir>/<policy-id>-coldcard.txt`
+    ///
+    /// Return the path of the exported file.
+    pub fn export_vault_coldcard_registration(
+        &self,
+        policy_id: Arc<EventId>,
+        dir: String,
+    ) -> Result<String> {
+        block_on(async move {
+            let path = self
+                .inner
+                .export_vault_coldcard_registration(**policy_id, dir)
+                .await?;
+            Ok(path.display().to_string())
+        })
+    }
+
+    /// Approve the pending proposals with the signed `.psbt` files (binary or base64) found in `dir`
+    pub fn approve_signed_psbts_from_dir(
+        &self,
+        dir: String,
+        override_spending_rules: bool,
+    ) -> Result<Vec<PsbtFileApproval>> {
+        block_on(async move {
+            Ok(self
+                .inner
+                .approve_signed_psbts_from_dir(dir, override_spending_rules)
+                .await?
+                .into_iter()
+                .map(|(proposal_id, approval_id)| PsbtFileApproval {
+                    proposal_id: Arc::new(proposal_id.into()),
+                    approval_id: Arc::new(approval_id.into()),
+                })
+                .collect())
+        })
+    }
+
+    /// Register a proposal signer for a signer type, used by `approve` (replace the previous one, if any)
+    pub fn register_proposal_signer(
+        &self,
+        signer_type: SignerType,
+        proposal_signer: Box<dyn ProposalSigner>,
+    ) {
+        self.inner.register_proposal_signer(
+            signer_type.into(),
+            Arc::new(ProposalSignerWrapper::from(proposal_signer)),
+        );
+    }
+
+    pub fn unregister_proposal_signer(&self, signer_type: SignerType) {
+        self.inner.unregister_proposal_signer(signer_type.into());
+    }
+
     pub fn revoke_approval(&self, approval_id: &EventId) -> Result<()> {
         block_on(async move { Ok(self.inner.revoke_approval(**approval_id).await?) })
     }
 
+    pub fn comment_on_proposal(
+        &self,
+        proposal_id: Arc<EventId>,
+        text: String,
+    ) -> Result<Arc<EventId>> {
+        block_on(async move {
+            let comment_id = self.inner.comment_on_proposal(**proposal_id, text).await?;
+            Ok(Arc::new(comment_id.into()))
+        })
+    }
+
+    pub fn get_proposal_comments(
+        &self,
+        proposal_id: Arc<EventId>,
+    ) -> Result<Vec<Arc<GetProposalComment>>> {
+        block_on(async move {
+            Ok(self
+                .inner
+                .get_proposal_comments(**proposal_id)
+                .await?
+                .into_iter()
+                .map(|res| Arc::new(res.into()))
+                .collect())
+        })
+    }
+
+    pub fn reject_proposal(
+        &self,
+        proposal_id: Arc<EventId>,
+        reason: Option<String>,
+    ) -> Result<Arc<EventId>> {
+        block_on(async move {
+            let rejection_id = self.inner.reject_proposal(**proposal_id, reason).await?;
+            Ok(Arc::new(rejection_id.into()))
+        })
+    }
+
+    pub fn get_proposal_rejections(
+        &self,
+        proposal_id: Arc<EventId>,
+    ) -> Result<Vec<Arc<GetProposalRejection>>> {
+        block_on(async move {
+            Ok(self
+                .inner
+                .get_proposal_rejections(**proposal_id)
+                .await?
+                .into_iter()
+                .map(|res| Arc::new(res.into()))
+                .collect())
+        })
+    }
+
+    pub fn get_proposal_status(&self, proposal_id: Arc<EventId>) -> Result<ProposalStatus> {
+        block_on(async move { Ok(self.inner.get_proposal_status(**proposal_id).await?.into()) })
+    }
+
     pub fn finalize(&self, proposal_id: &EventId) -> Result<CompletedProposal> {
         block_on(async move { Ok(self.inner.finalize(**proposal_id).await?.into()) })
     }
@@ -560,12 +786,81 @@ impl SmartVaults {
         })
     }
 
+    pub fn new_aggregated_proof_proposal(
+        &self,
+        policy_ids: Vec<Arc<EventId>>,
+        message: String,
+    ) -> Result<Vec<Arc<GetProposal>>> {
+        block_on(async move {
+            let policy_ids = policy_ids.into_iter().map(|id| **id).collect();
+            Ok(self
+                .inner
+                .new_aggregated_proof_proposal(policy_ids, message)
+                .await?
+                .into_iter()
+                .map(|p| Arc::new(p.into()))
+                .collect())
+        })
+    }
+
     // TODO: add verify_proof
 
     // TODO: add verify_proof_by_id
 
     // TODO: add save_signer
 
+    /// Save an air-gapped signer from `crypto-account` or `crypto-output` UR parts (any order)
+    pub fn save_signer_from_ur(&self, name: String, parts: Vec<String>) -> Result<Arc<EventId>> {
+        block_on(async move {
+            Ok(Arc::new(
+                self.inner.save_signer_from_ur(name, parts).await?.into(),
+            ))
+        })
+    }
+
+    /// Save an air-gapped signer from a wallet export (Sparrow/Specter, descriptor, Coldcard/Keystone/Passport/Jade
+    /// account export or key origin), detecting its format
+    pub fn save_signer_from_export(&self, name: String, export: String) -> Result<Arc<EventId>> {
+        block_on(async move {
+            Ok(Arc::new(
+                self.inner
+                    .save_signer_from_export(name, export)
+                    .await?
+                    .into(),
+            ))
+        })
+    }
+
+    /// Derive a seed signer from the keychain seed, using `account` as account index, and save it
+    pub fn save_seed_signer(
+        &self,
+        password: String,
+        name: String,
+        account: u32,
+    ) -> Result<Arc<EventId>> {
+        block_on(async move {
+            Ok(Arc::new(
+                self.inner
+                    .save_seed_signer(password, name, account)
+                    .await?
+                    .into(),
+            ))
+        })
+    }
+
+    /// Check if the seed signers (accounts) involved in a new vault are already used in other vaults
+    pub fn seed_account_reuse(&self, descriptor: String) -> Result<Vec<SeedAccountReuse>> {
+        block_on(async move {
+            Ok(self
+                .inner
+                .seed_account_reuse(descriptor)
+                .await?
+                .into_iter()
+                .map(|r| r.into())
+                .collect())
+        })
+    }
+
     pub fn smartvaults_signer_exists(&self) -> bool {
         block_on(async move { self.inner.smartvaults_signer_exists().await })
     }
@@ -758,6 +1053,40 @@ impl SmartVaults {
         })
     }
 
+    pub fn save_vault_metadata(
+        &self,
+        policy_id: Arc<EventId>,
+        metadata: VaultMetadata,
+    ) -> Result<Arc<EventId>> {
+        block_on(async move {
+            Ok(Arc::new(
+                self.inner
+                    .save_vault_metadata(**policy_id, metadata.try_into()?)
+                    .await?
+                    .into(),
+            ))
+        })
+    }
+
+    pub fn get_vault_metadata(&self, policy_id: Arc<EventId>) -> Result<VaultMetadata> {
+        block_on(async move { Ok(self.inner.get_vault_metadata(**policy_id).await?.into()) })
+    }
+
+    pub fn rotate_shared_key(
+        &self,
+        policy_id: Arc<EventId>,
+        public_keys: Vec<Arc<PublicKey>>,
+    ) -> Result<SharedKeyRotation> {
+        block_on(async move {
+            let public_keys: Vec<_> = public_keys.into_iter().map(|p| **p).collect();
+            Ok(self
+                .inner
+                .rotate_shared_key(**policy_id, public_keys)
+                .await?
+                .into())
+        })
+    }
+
     // TODO: add notifications methods
 
     pub fn new_nostr_connect_session(&self, uri: Arc<NostrConnectURI>) -> Result<()> {
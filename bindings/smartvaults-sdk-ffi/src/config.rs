@@ -1,8 +1,11 @@
 // Copyright (c) 2022-2024 Smart Vaults
 // Distributed under the MIT software license
 
+use std::time::Duration;
+
 use smartvaults_sdk::config;
 use smartvaults_sdk::nostr::{block_on, Url};
+use smartvaults_sdk::protocol::v2::ProtocolVersion;
 use uniffi::Object;
 
 use crate::error::Result;
@@ -43,4 +46,32 @@ impl Config {
     pub fn block_explorer(&self) -> Result<String> {
         block_on(async move { Ok(self.inner.block_explorer().await?.to_string()) })
     }
+
+    pub fn set_gift_wrap(&self, enabled: bool) {
+        block_on(async move { self.inner.set_gift_wrap(enabled).await })
+    }
+
+    pub fn gift_wrap(&self) -> bool {
+        block_on(async move { self.inner.gift_wrap().await })
+    }
+
+    pub fn set_protocol_version(&self, version: u8) -> Result<()> {
+        block_on(async move {
+            let version = ProtocolVersion::try_from(version)?;
+            self.inner.set_protocol_version(version).await;
+            Ok(())
+        })
+    }
+
+    pub fn protocol_version(&self) -> u8 {
+        block_on(async move { self.inner.protocol_version().await.into() })
+    }
+
+    pub fn set_proposal_expiration(&self, expiration: Option<Duration>) {
+        block_on(async move { self.inner.set_proposal_expiration(expiration).await })
+    }
+
+    pub fn proposal_expiration(&self) -> Option<Duration> {
+        block_on(async move { self.inner.proposal_expiration().await })
+    }
 }
@@ -24,6 +24,12 @@ impl fmt::Display for SmartVaultsError {
     }
 }
 
+impl From<uniffi::UnexpectedUniFFICallbackError> for SmartVaultsError {
+    fn from(e: uniffi::UnexpectedUniFFICallbackError) -> SmartVaultsError {
+        Self::Generic(e.reason)
+    }
+}
+
 impl From<async_utility::thread::Error> for SmartVaultsError {
     fn from(e: async_utility::thread::Error) -> SmartVaultsError {
         Self::Generic(e.to_string())
@@ -54,6 +60,12 @@ impl From<smartvaults_sdk::config::Error> for SmartVaultsError {
     }
 }
 
+impl From<smartvaults_sdk::protocol::v2::EnvelopeError> for SmartVaultsError {
+    fn from(e: smartvaults_sdk::protocol::v2::EnvelopeError) -> Self {
+        Self::Generic(e.to_string())
+    }
+}
+
 impl From<smartvaults_sdk::nostr::types::url::ParseError> for SmartVaultsError {
     fn from(e: smartvaults_sdk::nostr::types::url::ParseError) -> Self {
         Self::Generic(e.to_string())
@@ -126,6 +138,12 @@ impl From<smartvaults_sdk::core::util::dir::Error> for SmartVaultsError {
     }
 }
 
+impl From<smartvaults_sdk::core::ur::Error> for SmartVaultsError {
+    fn from(e: smartvaults_sdk::core::ur::Error) -> Self {
+        Self::Generic(e.to_string())
+    }
+}
+
 impl From<smartvaults_sdk::core::policy::Error> for SmartVaultsError {
     fn from(e: smartvaults_sdk::core::policy::Error) -> Self {
         Self::Generic(e.to_string())
@@ -25,10 +25,11 @@ mod proposal;
 mod seed;
 mod signer;
 mod transaction;
+mod ur;
 
 pub use self::abortable::AbortHandle;
 pub use self::address::{AddressIndex, GetAddress};
-pub use self::amount::Amount;
+pub use self::amount::{Amount, Recipient};
 pub use self::balance::Balance;
 pub use self::client::{SmartVaults, SyncHandler};
 pub use self::config::Config;
@@ -42,16 +43,21 @@ pub use self::nip46::{NostrConnectRequest, NostrConnectSession};
 pub use self::policy::{
     AbsoluteLockTime, DecayingTime, GetPolicy, Locktime, Policy, PolicyPath, PolicyPathSelector,
     PolicyPathSigner, PolicyTemplate, PolicyTemplateType, RecoveryTemplate, RelativeLockTime,
+    SpendingRules, SpendingWindow, VaultMetadata,
 };
 pub use self::proposal::{
-    ApprovedProposal, CompletedProposal, GetApproval, GetCompletedProposal, GetProposal, Period,
-    Proposal,
+    ApprovedProposal, CompletedProposal, GetApproval, GetCompletedProposal, GetProposal,
+    GetProposalComment, GetProposalRejection, MigratedProposal, Period, Proposal,
+    ProposalRecipient, ProposalStatus, PsbtFileApproval, PsbtFileFormat, SharedKeyRotation,
 };
 pub use self::seed::{Seed, WordCount};
-pub use self::signer::{GetSharedSigner, GetSigner, SharedSigner, Signer, SignerType};
+pub use self::signer::{
+    GetSharedSigner, GetSigner, ProposalSigner, SeedAccountReuse, SharedSigner, Signer, SignerType,
+};
 pub use self::transaction::{
     BlockTime, GetTransaction, OutPoint, Transaction, TransactionDetails, TxIn, TxOut, Utxo,
 };
+pub use self::ur::{UrDecoder, UrEncoder};
 
 #[derive(Object)]
 pub struct SmartVaultsLibrary;
@@ -11,8 +11,11 @@ use uniffi::Enum;
 pub enum EventHandled {
     SharedKey { event_id: Arc<EventId> },
     Policy { policy_id: Arc<EventId> },
+    PolicyUpdated { policy_id: Arc<EventId> },
     Proposal { proposal_id: Arc<EventId> },
     Approval { proposal_id: Arc<EventId> },
+    ProposalComment { proposal_id: Arc<EventId> },
+    ProposalRejection { proposal_id: Arc<EventId> },
     CompletedProposal { completed_proposal_id: Arc<EventId> },
     Signer { signer_id: Arc<EventId> },
     MySharedSigner { my_shared_signer_id: Arc<EventId> },
@@ -21,6 +24,7 @@ pub enum EventHandled {
     Metadata { public_key: Arc<PublicKey> },
     NostrConnectRequest { request_id: Arc<EventId> },
     Label,
+    VaultMetadata { policy_id: Arc<EventId> },
     EventDeletion,
     RelayList,
     KeyAgentSignerOffering,
@@ -36,12 +40,21 @@ impl From<EventHandledSdk> for EventHandled {
             EventHandledSdk::Policy(id) => Self::Policy {
                 policy_id: Arc::new(id.into()),
             },
+            EventHandledSdk::PolicyUpdated(id) => Self::PolicyUpdated {
+                policy_id: Arc::new(id.into()),
+            },
             EventHandledSdk::Proposal(id) => Self::Proposal {
                 proposal_id: Arc::new(id.into()),
             },
             EventHandledSdk::Approval { proposal_id } => Self::Approval {
                 proposal_id: Arc::new(proposal_id.into()),
             },
+            EventHandledSdk::ProposalComment { proposal_id } => Self::ProposalComment {
+                proposal_id: Arc::new(proposal_id.into()),
+            },
+            EventHandledSdk::ProposalRejection { proposal_id } => Self::ProposalRejection {
+                proposal_id: Arc::new(proposal_id.into()),
+            },
             EventHandledSdk::CompletedProposal(id) => Self::CompletedProposal {
                 completed_proposal_id: Arc::new(id.into()),
             },
@@ -62,6 +75,9 @@ impl From<EventHandledSdk> for EventHandled {
                 request_id: Arc::new(id.into()),
             },
             EventHandledSdk::Label => Self::Label,
+            EventHandledSdk::VaultMetadata(id) => Self::VaultMetadata {
+                policy_id: Arc::new(id.into()),
+            },
             EventHandledSdk::EventDeletion => Self::EventDeletion,
             EventHandledSdk::RelayList => Self::RelayList,
             EventHandledSdk::KeyAgentSignerOffering => Self::KeyAgentSignerOffering,
@@ -74,6 +90,7 @@ impl From<EventHandledSdk> for EventHandled {
 pub enum Message {
     EventHandledMsg { event: EventHandled },
     WalletSyncCompleted { policy_id: Arc<EventId> },
+    ProposalExpired { proposal_id: Arc<EventId> },
     BlockHeightUpdated,
     MempoolFeesUpdated,
 }
@@ -87,6 +104,9 @@ impl From<MessageSdk> for Message {
             MessageSdk::WalletSyncCompleted(policy_id) => Self::WalletSyncCompleted {
                 policy_id: Arc::new(policy_id.into()),
             },
+            MessageSdk::ProposalExpired(proposal_id) => Self::ProposalExpired {
+                proposal_id: Arc::new(proposal_id.into()),
+            },
             MessageSdk::BlockHeightUpdated => Self::BlockHeightUpdated,
             MessageSdk::MempoolFeesUpdated(..) => Self::MempoolFeesUpdated,
         }
@@ -3,11 +3,14 @@
 
 use std::collections::HashMap;
 use std::ops::Deref;
+use std::str::FromStr;
 use std::sync::Arc;
 
 use nostr_ffi::{EventId, Timestamp};
-use smartvaults_sdk::core::{policy, SelectableCondition};
+use smartvaults_sdk::core::bitcoin::Address;
+use smartvaults_sdk::core::{policy, spending_rules, SelectableCondition};
 use smartvaults_sdk::protocol::v1::util::SerdeSer;
+use smartvaults_sdk::protocol::v1::vault_metadata;
 use smartvaults_sdk::types;
 use uniffi::{Enum, Object, Record};
 
@@ -17,7 +20,7 @@ pub use self::template::{
     AbsoluteLockTime, DecayingTime, Locktime, PolicyTemplate, PolicyTemplateType, RecoveryTemplate,
     RelativeLockTime,
 };
-use crate::error::Result;
+use crate::error::{Result, SmartVaultsError};
 use crate::{Balance, Signer};
 
 #[derive(Clone, Object)]
@@ -103,6 +106,104 @@ impl Policy {
     }
 }
 
+#[derive(Record)]
+pub struct SpendingWindow {
+    /// Max amount in sat
+    pub max: u64,
+    /// Window length in secs
+    pub period: u64,
+}
+
+#[derive(Record)]
+pub struct SpendingRules {
+    pub max_per_tx: Option<u64>,
+    pub windows: Vec<SpendingWindow>,
+    /// Allowed recipient addresses (empty = any)
+    pub allowlist: Vec<String>,
+    pub description_required_above: Option<u64>,
+}
+
+impl From<spending_rules::SpendingRules> for SpendingRules {
+    fn from(value: spending_rules::SpendingRules) -> Self {
+        Self {
+            max_per_tx: value.max_per_tx,
+            windows: value
+                .windows
+                .into_iter()
+                .map(|w| SpendingWindow {
+                    max: w.max,
+                    period: w.period,
+                })
+                .collect(),
+            allowlist: value
+                .allowlist
+                .into_iter()
+                .map(|a| a.assume_checked().to_string())
+                .collect(),
+            description_required_above: value.description_required_above,
+        }
+    }
+}
+
+impl TryFrom<SpendingRules> for spending_rules::SpendingRules {
+    type Error = SmartVaultsError;
+
+    fn try_from(value: SpendingRules) -> Result<Self> {
+        Ok(Self {
+            max_per_tx: value.max_per_tx,
+            windows: value
+                .windows
+                .into_iter()
+                .map(|w| spending_rules::SpendingWindow::new(w.max, w.period))
+                .collect(),
+            allowlist: value
+                .allowlist
+                .iter()
+                .map(|a| Address::from_str(a))
+                .collect::<Result<Vec<_>, _>>()?,
+            description_required_above: value.description_required_above,
+        })
+    }
+}
+
+#[derive(Record)]
+pub struct VaultMetadata {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub icon: Option<String>,
+    pub color: Option<String>,
+    pub tags: Vec<String>,
+    pub spending_rules: SpendingRules,
+}
+
+impl From<vault_metadata::VaultMetadata> for VaultMetadata {
+    fn from(value: vault_metadata::VaultMetadata) -> Self {
+        Self {
+            name: value.name,
+            description: value.description,
+            icon: value.icon,
+            color: value.color,
+            tags: value.tags,
+            spending_rules: value.spending_rules.into(),
+        }
+    }
+}
+
+impl TryFrom<VaultMetadata> for vault_metadata::VaultMetadata {
+    type Error = SmartVaultsError;
+
+    fn try_from(value: VaultMetadata) -> Result<Self> {
+        Ok(Self {
+            name: value.name,
+            description: value.description,
+            icon: value.icon,
+            color: value.color,
+            tags: value.tags,
+            spending_rules: value.spending_rules.try_into()?,
+        })
+    }
+}
+
 #[derive(Clone, Object)]
 pub struct GetPolicy {
     inner: types::GetPolicy,
@@ -124,6 +225,10 @@ impl GetPolicy {
         Arc::new(self.inner.policy.clone().into())
     }
 
+    pub fn metadata(&self) -> VaultMetadata {
+        self.inner.metadata.clone().into()
+    }
+
     pub fn balance(&self) -> Arc<Balance> {
         Arc::new(self.inner.balance.clone().into())
     }
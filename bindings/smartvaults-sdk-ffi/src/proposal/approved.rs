@@ -14,6 +14,7 @@ pub enum ApprovedProposal {
     Spending { psbt: String },
     KeyAgentPayment { psbt: String },
     ProofOfReserve { psbt: String },
+    FeeBump { psbt: String },
 }
 
 impl From<proposal::ApprovedProposal> for ApprovedProposal {
@@ -28,6 +29,9 @@ impl From<proposal::ApprovedProposal> for ApprovedProposal {
             proposal::ApprovedProposal::ProofOfReserve { psbt } => Self::ProofOfReserve {
                 psbt: psbt.to_string(),
             },
+            proposal::ApprovedProposal::FeeBump { psbt } => Self::FeeBump {
+                psbt: psbt.to_string(),
+            },
         }
     }
 }
@@ -0,0 +1,39 @@
+// Copyright (c) 2022-2024 Smart Vaults
+// Distributed under the MIT software license
+
+use std::sync::Arc;
+
+use nostr_ffi::{EventId, Timestamp};
+use nostr_sdk_ffi::profile::Profile;
+use smartvaults_sdk::types;
+use uniffi::Object;
+
+#[derive(Object)]
+pub struct GetProposalComment {
+    inner: types::GetProposalComment,
+}
+
+impl From<types::GetProposalComment> for GetProposalComment {
+    fn from(inner: types::GetProposalComment) -> Self {
+        Self { inner }
+    }
+}
+
+#[uniffi::export]
+impl GetProposalComment {
+    pub fn comment_id(&self) -> Arc<EventId> {
+        Arc::new(self.inner.comment_id.into())
+    }
+
+    pub fn user(&self) -> Arc<Profile> {
+        Arc::new(self.inner.user.clone().into())
+    }
+
+    pub fn text(&self) -> String {
+        self.inner.comment.text.clone()
+    }
+
+    pub fn timestamp(&self) -> Arc<Timestamp> {
+        Arc::new(self.inner.timestamp.into())
+    }
+}
@@ -26,6 +26,13 @@ pub enum CompletedProposal {
         descriptor: String,
         message: String,
         psbt: String,
+        height: Option<u32>,
+        other_descriptors: Vec<String>,
+    },
+    FeeBump {
+        txid: String,
+        replaced_txid: String,
+        description: String,
     },
 }
 
@@ -54,10 +61,23 @@ impl From<proposal::CompletedProposal> for CompletedProposal {
                 descriptor,
                 message,
                 psbt,
+                height,
+                other_descriptors,
             } => Self::ProofOfReserve {
                 descriptor: descriptor.to_string(),
                 message,
                 psbt: psbt.to_string(),
+                height,
+                other_descriptors: other_descriptors.iter().map(|d| d.to_string()).collect(),
+            },
+            proposal::CompletedProposal::FeeBump {
+                tx,
+                replaced_txid,
+                description,
+            } => Self::FeeBump {
+                txid: tx.txid().to_string(),
+                replaced_txid: replaced_txid.to_string(),
+                description,
             },
         }
     }
@@ -87,4 +107,9 @@ impl GetCompletedProposal {
     pub fn completed_proposal(&self) -> CompletedProposal {
         self.inner.proposal.clone().into()
     }
+
+    /// Completed fee bump proposal that replaced this transaction
+    pub fn replaced_by(&self) -> Option<Arc<EventId>> {
+        self.inner.replaced_by.map(|id| Arc::new(id.into()))
+    }
 }
@@ -4,16 +4,20 @@
 use std::collections::HashMap;
 use std::sync::Arc;
 
-use nostr_ffi::{EventId, Timestamp};
+use nostr_ffi::{EventId, PublicKey, Timestamp};
 use smartvaults_sdk::core::proposal;
 use smartvaults_sdk::types;
 use uniffi::{Enum, Object, Record};
 
 mod approved;
+mod comment;
 mod completed;
+mod rejection;
 
 pub use self::approved::{ApprovedProposal, GetApproval};
+pub use self::comment::GetProposalComment;
 pub use self::completed::{CompletedProposal, GetCompletedProposal};
+pub use self::rejection::{GetProposalRejection, ProposalStatus};
 
 #[derive(Record)]
 pub struct Period {
@@ -30,15 +34,99 @@ impl From<Period> for proposal::Period {
     }
 }
 
+#[derive(Record)]
+pub struct ProposalRecipient {
+    pub address: String,
+    pub amount: u64,
+}
+
+impl From<proposal::Recipient> for ProposalRecipient {
+    fn from(value: proposal::Recipient) -> Self {
+        Self {
+            address: value.address.assume_checked().to_string(),
+            amount: value.amount,
+        }
+    }
+}
+
+#[derive(Enum)]
+pub enum PsbtFileFormat {
+    Binary,
+    Base64,
+}
+
+impl From<PsbtFileFormat> for types::PsbtFileFormat {
+    fn from(value: PsbtFileFormat) -> Self {
+        match value {
+            PsbtFileFormat::Binary => Self::Binary,
+            PsbtFileFormat::Base64 => Self::Base64,
+        }
+    }
+}
+
+#[derive(Record)]
+pub struct PsbtFileApproval {
+    pub proposal_id: Arc<EventId>,
+    pub approval_id: Arc<EventId>,
+}
+
+#[derive(Record)]
+pub struct MigratedProposal {
+    pub proposal_id: Arc<EventId>,
+    pub new_proposal_id: Arc<EventId>,
+    pub lost_approvals: Vec<Arc<PublicKey>>,
+    pub lost_rejections: Vec<Arc<PublicKey>>,
+}
+
+impl From<types::MigratedProposal> for MigratedProposal {
+    fn from(value: types::MigratedProposal) -> Self {
+        Self {
+            proposal_id: Arc::new(value.proposal_id.into()),
+            new_proposal_id: Arc::new(value.new_proposal_id.into()),
+            lost_approvals: value
+                .lost_approvals
+                .into_iter()
+                .map(|p| Arc::new(p.into()))
+                .collect(),
+            lost_rejections: value
+                .lost_rejections
+                .into_iter()
+                .map(|p| Arc::new(p.into()))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Record)]
+pub struct SharedKeyRotation {
+    pub event_id: Arc<EventId>,
+    pub migrated_proposals: Vec<MigratedProposal>,
+}
+
+impl From<types::SharedKeyRotation> for SharedKeyRotation {
+    fn from(value: types::SharedKeyRotation) -> Self {
+        Self {
+            event_id: Arc::new(value.event_id.into()),
+            migrated_proposals: value
+                .migrated_proposals
+                .into_iter()
+                .map(|m| m.into())
+                .collect(),
+        }
+    }
+}
+
 #[derive(Enum)]
 pub enum Proposal {
     Spending {
         descriptor: String,
         to_address: String,
         amount: u64,
+        other_recipients: Vec<ProposalRecipient>,
         description: String,
         psbt: String,
         policy_path: Option<HashMap<String, Vec<u64>>>,
+        cpfp_parent: Option<String>,
     },
     KeyAgentPayment {
         descriptor: String,
@@ -53,6 +141,15 @@ pub enum Proposal {
         descriptor: String,
         message: String,
         psbt: String,
+        height: Option<u32>,
+        other_descriptors: Vec<String>,
+    },
+    FeeBump {
+        descriptor: String,
+        replaced_txid: String,
+        description: String,
+        psbt: String,
+        policy_path: Option<HashMap<String, Vec<u64>>>,
     },
 }
 
@@ -63,13 +160,16 @@ impl From<proposal::Proposal> for Proposal {
                 descriptor,
                 to_address,
                 amount,
+                other_recipients,
                 description,
                 psbt,
                 policy_path,
+                cpfp_parent,
             } => Self::Spending {
                 descriptor: descriptor.to_string(),
                 to_address: to_address.assume_checked().to_string(),
                 amount,
+                other_recipients: other_recipients.into_iter().map(|r| r.into()).collect(),
                 description,
                 psbt: psbt.to_string(),
                 policy_path: policy_path.map(|path| {
@@ -77,6 +177,7 @@ impl From<proposal::Proposal> for Proposal {
                         .map(|(k, v)| (k.to_string(), v.into_iter().map(|x| x as u64).collect()))
                         .collect()
                 }),
+                cpfp_parent: cpfp_parent.map(|txid| txid.to_string()),
             },
             proposal::Proposal::KeyAgentPayment {
                 descriptor,
@@ -106,10 +207,31 @@ impl From<proposal::Proposal> for Proposal {
                 descriptor,
                 message,
                 psbt,
+                height,
+                other_descriptors,
             } => Self::ProofOfReserve {
                 descriptor: descriptor.to_string(),
                 message,
                 psbt: psbt.to_string(),
+                height,
+                other_descriptors: other_descriptors.iter().map(|d| d.to_string()).collect(),
+            },
+            proposal::Proposal::FeeBump {
+                descriptor,
+                replaced_txid,
+                description,
+                psbt,
+                policy_path,
+            } => Self::FeeBump {
+                descriptor: descriptor.to_string(),
+                replaced_txid: replaced_txid.to_string(),
+                description,
+                psbt: psbt.to_string(),
+                policy_path: policy_path.map(|path| {
+                    path.into_iter()
+                        .map(|(k, v)| (k.to_string(), v.into_iter().map(|x| x as u64).collect()))
+                        .collect()
+                }),
             },
         }
     }
@@ -143,4 +265,8 @@ impl GetProposal {
     pub fn is_signed(&self) -> bool {
         self.inner.signed
     }
+
+    pub fn expiration(&self) -> Option<Arc<Timestamp>> {
+        self.inner.expiration.map(|t| Arc::new(t.into()))
+    }
 }
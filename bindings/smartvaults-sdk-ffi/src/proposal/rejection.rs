@@ -0,0 +1,68 @@
+// Copyright (c) 2022-2024 Smart Vaults
+// Distributed under the MIT software license
+
+use std::sync::Arc;
+
+use nostr_ffi::{EventId, PublicKey, Timestamp};
+use nostr_sdk_ffi::profile::Profile;
+use smartvaults_sdk::types;
+use uniffi::{Object, Record};
+
+#[derive(Object)]
+pub struct GetProposalRejection {
+    inner: types::GetProposalRejection,
+}
+
+impl From<types::GetProposalRejection> for GetProposalRejection {
+    fn from(inner: types::GetProposalRejection) -> Self {
+        Self { inner }
+    }
+}
+
+#[uniffi::export]
+impl GetProposalRejection {
+    pub fn rejection_id(&self) -> Arc<EventId> {
+        Arc::new(self.inner.rejection_id.into())
+    }
+
+    pub fn user(&self) -> Arc<Profile> {
+        Arc::new(self.inner.user.clone().into())
+    }
+
+    pub fn reason(&self) -> Option<String> {
+        self.inner.rejection.reason.clone()
+    }
+
+    pub fn timestamp(&self) -> Arc<Timestamp> {
+        Arc::new(self.inner.timestamp.into())
+    }
+}
+
+#[derive(Record)]
+pub struct ProposalStatus {
+    pub approved_by: Vec<Arc<PublicKey>>,
+    pub rejected_by: Vec<Arc<PublicKey>>,
+    pub signed: bool,
+    pub approvable: bool,
+    pub rejected: bool,
+}
+
+impl From<types::ProposalStatus> for ProposalStatus {
+    fn from(value: types::ProposalStatus) -> Self {
+        Self {
+            rejected: value.is_rejected(),
+            approved_by: value
+                .approved_by
+                .into_iter()
+                .map(|p| Arc::new(p.into()))
+                .collect(),
+            rejected_by: value
+                .rejected_by
+                .into_iter()
+                .map(|p| Arc::new(p.into()))
+                .collect(),
+            signed: value.signed,
+            approvable: value.approvable,
+        }
+    }
+}
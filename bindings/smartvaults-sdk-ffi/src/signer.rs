@@ -1,14 +1,17 @@
 // Copyright (c) 2022-2024 Smart Vaults
 // Distributed under the MIT software license
 
+use std::fmt::Debug;
 use std::ops::Deref;
+use std::str::FromStr;
 use std::sync::Arc;
 
 use nostr_ffi::EventId;
 use nostr_sdk_ffi::profile::Profile;
-use smartvaults_sdk::core::signer;
+use smartvaults_sdk::core::bitcoin::psbt::PartiallySignedTransaction;
+use smartvaults_sdk::core::{proposal, signer};
 use smartvaults_sdk::types;
-use uniffi::{Enum, Object};
+use uniffi::{Enum, Object, Record};
 
 use crate::error::Result;
 use crate::Descriptor;
@@ -30,6 +33,16 @@ impl From<signer::SignerType> for SignerType {
     }
 }
 
+impl From<SignerType> for signer::SignerType {
+    fn from(value: SignerType) -> Self {
+        match value {
+            SignerType::Seed => Self::Seed,
+            SignerType::Hardware => Self::Hardware,
+            SignerType::AirGap => Self::AirGap,
+        }
+    }
+}
+
 #[derive(Object)]
 pub struct GetSigner {
     inner: types::GetSigner,
@@ -89,11 +102,34 @@ impl Signer {
         self.inner.signer_type().into()
     }
 
+    pub fn account(&self) -> Option<u32> {
+        self.inner.account()
+    }
+
     pub fn display(&self) -> String {
         self.inner.to_string()
     }
 }
 
+#[derive(Record)]
+pub struct SeedAccountReuse {
+    pub signer: Arc<Signer>,
+    pub policy_ids: Vec<Arc<EventId>>,
+}
+
+impl From<types::SeedAccountReuse> for SeedAccountReuse {
+    fn from(value: types::SeedAccountReuse) -> Self {
+        Self {
+            signer: Arc::new(value.signer.into()),
+            policy_ids: value
+                .policy_ids
+                .into_iter()
+                .map(|id| Arc::new(id.into()))
+                .collect(),
+        }
+    }
+}
+
 #[derive(Object)]
 pub struct GetSharedSigner {
     inner: types::GetSharedSigner,
@@ -141,3 +177,35 @@ impl SharedSigner {
         Ok(Arc::new(self.inner.descriptor_public_key()?.into()))
     }
 }
+
+/// External signer (hardware wallet, HSM, remote signing daemon, ...)
+#[uniffi::export(callback_interface)]
+pub trait ProposalSigner: Send + Sync + Debug {
+    /// Sign the PSBT (base64) with the key of the signer and return the signed PSBT (base64)
+    fn sign(&self, psbt: String, signer: Arc<Signer>) -> Result<String>;
+}
+
+#[derive(Debug)]
+pub(crate) struct ProposalSignerWrapper {
+    inner: Box<dyn ProposalSigner>,
+}
+
+impl From<Box<dyn ProposalSigner>> for ProposalSignerWrapper {
+    fn from(inner: Box<dyn ProposalSigner>) -> Self {
+        Self { inner }
+    }
+}
+
+impl proposal::ProposalSigner for ProposalSignerWrapper {
+    fn sign(
+        &self,
+        psbt: PartiallySignedTransaction,
+        signer: &signer::Signer,
+    ) -> Result<PartiallySignedTransaction, proposal::Error> {
+        let signed_psbt: String = self
+            .inner
+            .sign(psbt.to_string(), Arc::new(signer.clone().into()))
+            .map_err(|e| proposal::Error::ExternalSigner(e.to_string()))?;
+        Ok(PartiallySignedTransaction::from_str(&signed_psbt)?)
+    }
+}
@@ -0,0 +1,83 @@
+// Copyright (c) 2022-2024 Smart Vaults
+// Distributed under the MIT software license
+
+use std::sync::{Arc, Mutex};
+
+use smartvaults_sdk::core::ur;
+use uniffi::Object;
+
+use crate::error::Result;
+use crate::{Network, Signer};
+
+/// Multi-part UR encoder (animated QR code frames)
+#[derive(Object)]
+pub struct UrEncoder {
+    inner: Mutex<ur::UrEncoder>,
+}
+
+impl From<ur::UrEncoder> for UrEncoder {
+    fn from(inner: ur::UrEncoder) -> Self {
+        Self {
+            inner: Mutex::new(inner),
+        }
+    }
+}
+
+#[uniffi::export]
+impl UrEncoder {
+    /// Number of fragments needed to transmit the whole message
+    pub fn fragment_count(&self) -> u64 {
+        let encoder = self.inner.lock().expect("Impossible to lock UR encoder");
+        encoder.fragment_count() as u64
+    }
+
+    /// Get next part (QR frame)
+    pub fn next_part(&self) -> Result<String> {
+        let mut encoder = self.inner.lock().expect("Impossible to lock UR encoder");
+        Ok(encoder.next_part()?)
+    }
+}
+
+/// UR decoder (parts can be received in any order)
+#[derive(Object)]
+pub struct UrDecoder {
+    inner: Mutex<ur::UrDecoder>,
+}
+
+#[uniffi::export]
+impl UrDecoder {
+    #[uniffi::constructor]
+    pub fn new() -> Self {
+        Self {
+            inner: Mutex::new(ur::UrDecoder::new()),
+        }
+    }
+
+    /// Receive a part (QR frame)
+    pub fn receive(&self, part: String) -> Result<()> {
+        let mut decoder = self.inner.lock().expect("Impossible to lock UR decoder");
+        Ok(decoder.receive(part)?)
+    }
+
+    pub fn is_complete(&self) -> bool {
+        let decoder = self.inner.lock().expect("Impossible to lock UR decoder");
+        decoder.is_complete()
+    }
+
+    pub fn ur_type(&self) -> Option<String> {
+        let decoder = self.inner.lock().expect("Impossible to lock UR decoder");
+        decoder.ur_type().map(|t| t.to_string())
+    }
+
+    /// Decode `crypto-psbt` (base64 PSBT)
+    pub fn psbt(&self) -> Result<String> {
+        let decoder = self.inner.lock().expect("Impossible to lock UR decoder");
+        Ok(decoder.psbt()?.to_string())
+    }
+
+    /// Decode air-gapped signer from `crypto-account` or `crypto-output`
+    pub fn signer(&self, name: String, network: Network) -> Result<Arc<Signer>> {
+        let decoder = self.inner.lock().expect("Impossible to lock UR decoder");
+        Ok(Arc::new(decoder.signer(name, network.into())?.into()))
+    }
+}
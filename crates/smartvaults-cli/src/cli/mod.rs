@@ -7,20 +7,21 @@ use std::net::SocketAddr;
 use std::path::PathBuf;
 
 use clap::{Parser, Subcommand};
-use smartvaults_sdk::core::bips::bip32::Fingerprint;
 use smartvaults_sdk::core::bitcoin::address::NetworkUnchecked;
-use smartvaults_sdk::core::bitcoin::Address;
-use smartvaults_sdk::core::miniscript::{Descriptor, DescriptorPublicKey};
+use smartvaults_sdk::core::bitcoin::{Address, Txid};
+use smartvaults_sdk::core::miniscript::Descriptor;
+use smartvaults_sdk::core::ur::DEFAULT_MAX_FRAGMENT_LEN;
 use smartvaults_sdk::nostr::prelude::NostrConnectURI;
 use smartvaults_sdk::nostr::{EventId, PublicKey, Url};
 use smartvaults_sdk::protocol::v1::{BasisPoints, DeviceType, LabelData, Price, Temperature};
+use smartvaults_sdk::protocol::v2::ProtocolVersion;
 
 pub mod batch;
 pub mod io;
 pub mod parser;
 mod types;
 
-use self::types::{CliNetwork, CliWordCount};
+use self::types::{CliNetwork, CliRecipient, CliWordCount};
 
 #[derive(Debug, Parser)]
 #[clap(author, version, about, long_about)]
@@ -73,6 +74,22 @@ pub enum CliCommand {
     },
     /// List keychains
     List,
+    /// Verify an exported Proof Of Reserve (offline)
+    #[command(arg_required_else_help = true)]
+    VerifyProof {
+        /// Exported proof file
+        #[arg(required = true)]
+        proof: PathBuf,
+        /// Vault descriptor
+        #[arg(required = true)]
+        descriptor: Descriptor<String>,
+        /// Message
+        #[arg(required = true)]
+        message: String,
+        /// UTXOs JSON file (list of `txid`, `vout`, `value` and `script_pubkey`)
+        #[arg(required = true)]
+        utxos: PathBuf,
+    },
     /// Config
     Config {
         #[command(subcommand)]
@@ -96,6 +113,15 @@ pub enum ConfigCommand {
         /// Block explorer
         #[clap(long)]
         block_explorer: Option<Url>,
+        /// Seal and gift wrap (NIP-59) vault events to hide the members
+        #[clap(long)]
+        gift_wrap: Option<bool>,
+        /// Protocol version of the published vault events (keep 1 until all members upgraded)
+        #[clap(long)]
+        protocol_version: Option<ProtocolVersion>,
+        /// Expiration of the new proposals, in hours
+        #[clap(long)]
+        proposal_expiration: Option<u64>,
     },
 
     /// Unset
@@ -110,6 +136,15 @@ pub enum ConfigCommand {
         /// Block explorer
         #[clap(long)]
         block_explorer: bool,
+        /// Gift wrap
+        #[clap(long)]
+        gift_wrap: bool,
+        /// Protocol version (reset to 1)
+        #[clap(long)]
+        protocol_version: bool,
+        /// Proposal expiration (never expire)
+        #[clap(long)]
+        proposal_expiration: bool,
     },
 }
 
@@ -136,15 +171,19 @@ pub enum Command {
         /// Policy id
         #[arg(required = true)]
         policy_id: EventId,
-        /// To address
-        #[arg(required = true)]
-        to_address: Address<NetworkUnchecked>,
-        /// Amount in sat
-        #[arg(required = true)]
-        amount: u64,
-        /// Description
-        #[arg(required = true)]
-        description: String,
+        /// To address or BIP21 URI (`bitcoin:<address>?amount=<btc>&label=<label>&message=<message>`)
+        #[arg(required = true)]
+        to_address: String,
+        /// Amount in sat (not allowed with BIP21 URI)
+        amount: Option<u64>,
+        /// Description (not allowed with BIP21 URI)
+        description: Option<String>,
+        /// Additional recipient for a batch payment (`<address>:<amount>`, can be repeated)
+        #[clap(long = "to")]
+        recipients: Vec<CliRecipient>,
+        /// Send all the remaining funds to this address
+        #[clap(long)]
+        drain_to: Option<Address<NetworkUnchecked>>,
         /// Taget blocks
         #[clap(short, long, default_value_t = 6)]
         target_blocks: u8,
@@ -164,11 +203,68 @@ pub enum Command {
         #[clap(short, long, default_value_t = 6)]
         target_blocks: u8,
     },
+    /// Create a fee bump (RBF) proposal for an unconfirmed transaction
+    BumpFee {
+        /// Policy id
+        #[arg(required = true)]
+        policy_id: EventId,
+        /// Txid of the transaction to replace
+        #[arg(required = true)]
+        txid: Txid,
+        /// Taget blocks
+        #[clap(short, long, default_value_t = 1)]
+        target_blocks: u8,
+    },
+    /// Create a child-pays-for-parent (CPFP) proposal for an unconfirmed incoming transaction
+    Cpfp {
+        /// Policy id
+        #[arg(required = true)]
+        policy_id: EventId,
+        /// Txid of the parent transaction
+        #[arg(required = true)]
+        parent_txid: Txid,
+        /// Taget blocks
+        #[clap(short, long, default_value_t = 1)]
+        target_blocks: u8,
+    },
     /// Approve a spending proposal
     Approve {
         /// Proposal id
         #[arg(required = true)]
         proposal_id: EventId,
+        /// Approve even if the proposal breaks the vault spending rules
+        #[arg(long)]
+        override_spending_rules: bool,
+    },
+    /// Approve a proposal with a signed PSBT received as `crypto-psbt` UR parts
+    ApproveUr {
+        /// Proposal id
+        #[arg(required = true)]
+        proposal_id: EventId,
+        /// UR parts (any order)
+        #[arg(required = true)]
+        parts: Vec<String>,
+        /// Approve even if the proposal breaks the vault spending rules
+        #[arg(long)]
+        override_spending_rules: bool,
+    },
+    /// Comment a proposal
+    Comment {
+        /// Proposal id
+        #[arg(required = true)]
+        proposal_id: EventId,
+        /// Comment
+        #[arg(required = true)]
+        text: String,
+    },
+    /// Reject a proposal
+    Reject {
+        /// Proposal id
+        #[arg(required = true)]
+        proposal_id: EventId,
+        /// Reason
+        #[arg(long)]
+        reason: Option<String>,
     },
     /// Finalize proposal
     Finalize {
@@ -176,6 +272,12 @@ pub enum Command {
         #[arg(required = true)]
         proposal_id: EventId,
     },
+    /// SD card (PSBT files) commands
+    #[command(arg_required_else_help = true)]
+    SdCard {
+        #[command(subcommand)]
+        command: SdCardCommand,
+    },
     /// Proof of Reserve commands
     #[command(arg_required_else_help = true)]
     Proof {
@@ -230,12 +332,55 @@ pub enum Command {
         #[command(subcommand)]
         command: SettingCommand,
     },
+    /// Rotate the shared key of a vault and re-share it only with the current members
+    RotateSharedKey {
+        /// Policy id
+        #[arg(required = true)]
+        policy_id: EventId,
+        /// Nostr pubkeys of the current members
+        #[arg(required = true)]
+        nostr_pubkeys: Vec<PublicKey>,
+    },
     /// Rebroadcast all events to connected relays
     Rebroadcast,
     /// Exit
     Exit,
 }
 
+#[derive(Debug, Subcommand)]
+pub enum SdCardCommand {
+    /// Export the PSBT of a proposal to `<dir>/<proposal-id>.psbt`
+    ExportPsbt {
+        /// Proposal id
+        #[arg(required = true)]
+        proposal_id: EventId,
+        /// Destination directory
+        #[arg(long, default_value = ".")]
+        dir: PathBuf,
+        /// Export as base64 instead of binary
+        #[arg(long)]
+        base64: bool,
+    },
+    /// Export the Coldcard registration file of a vault to `<dir>/<policy-id>-coldcard.txt`
+    ExportColdcard {
+        /// Policy id
+        #[arg(required = true)]
+        policy_id: EventId,
+        /// Destination directory
+        #[arg(long, default_value = ".")]
+        dir: PathBuf,
+    },
+    /// Approve the pending proposals with the signed `.psbt` files found in a directory
+    Approve {
+        /// Directory with the signed PSBTs
+        #[arg(required = true)]
+        dir: PathBuf,
+        /// Approve even if the proposals break the vault spending rules
+        #[arg(long)]
+        override_spending_rules: bool,
+    },
+}
+
 #[derive(Debug, Subcommand)]
 pub enum ProofCommand {
     /// New Proof Of Reserve
@@ -247,6 +392,15 @@ pub enum ProofCommand {
         #[arg(required = true)]
         message: String,
     },
+    /// New Proof Of Reserve spanning several vaults
+    NewAggregated {
+        /// Message
+        #[arg(required = true)]
+        message: String,
+        /// Policy ids
+        #[arg(required = true, num_args = 2..)]
+        policy_ids: Vec<EventId>,
+    },
     /// Verify Proof Of Reserve
     Verify {
         /// Proposal id
@@ -368,17 +522,39 @@ pub enum AddCommand {
         #[arg(long)]
         share_with_contacts: bool,
     },
-    /// Add AirGapped Signer
+    /// Add a Seed Signer derived from the keychain seed with a custom account index
+    SeedSigner {
+        /// Signer name
+        #[arg(required = true)]
+        name: String,
+        /// Account index
+        #[arg(required = true)]
+        account: u32,
+        /// Share with contacts
+        #[arg(long)]
+        share_with_contacts: bool,
+    },
+    /// Add AirGapped Signer (the export format is detected automatically)
     Signer {
         /// Signer name
         #[arg(required = true)]
         name: String,
-        /// Signer fingerprint (master fingerprint)
+        /// Export file or text: Sparrow/Specter wallet export, descriptor, Coldcard/Keystone/Passport/Jade
+        /// account export or key origin (`[fingerprint/path]xpub`, SLIP-132 keys supported)
         #[arg(required = true)]
-        fingerprint: Fingerprint,
+        export: String,
+        /// Share with contacts
+        #[arg(long)]
+        share_with_contacts: bool,
+    },
+    /// Add AirGapped Signer from `crypto-account` or `crypto-output` UR parts
+    SignerUr {
         /// Signer name
         #[arg(required = true)]
-        descriptor: Descriptor<DescriptorPublicKey>,
+        name: String,
+        /// UR parts (any order)
+        #[arg(required = true)]
+        parts: Vec<String>,
         /// Share with contacts
         #[arg(long)]
         share_with_contacts: bool,
@@ -412,6 +588,27 @@ pub enum GetCommand {
         #[arg(required = true)]
         proposal_id: EventId,
     },
+    /// Get proposal comments
+    Comments {
+        /// Proposal id
+        #[arg(required = true)]
+        proposal_id: EventId,
+    },
+    /// Get proposal rejections
+    Rejections {
+        /// Proposal id
+        #[arg(required = true)]
+        proposal_id: EventId,
+    },
+    /// Get proposal PSBT as `crypto-psbt` UR parts (for air-gapped signers)
+    ProposalUr {
+        /// Proposal id
+        #[arg(required = true)]
+        proposal_id: EventId,
+        /// Max fragment length
+        #[arg(long, default_value_t = DEFAULT_MAX_FRAGMENT_LEN)]
+        max_fragment_len: usize,
+    },
     /// Get signers
     Signers,
     /// Get relays
@@ -453,6 +650,45 @@ pub enum SetCommand {
         #[arg(required = true)]
         text: String,
     },
+    /// Set vault metadata (unspecified fields are kept)
+    VaultMetadata {
+        /// Policy id
+        #[arg(required = true)]
+        policy_id: EventId,
+        /// Name
+        #[arg(short, long)]
+        name: Option<String>,
+        /// Description
+        #[arg(short, long)]
+        description: Option<String>,
+        /// Icon (URL or emoji)
+        #[arg(long)]
+        icon: Option<String>,
+        /// Color (i.e. #f7931a)
+        #[arg(long)]
+        color: Option<String>,
+        /// Tags (replace the current ones)
+        #[arg(long = "tag")]
+        tags: Vec<String>,
+    },
+    /// Set vault spending rules (replace the current ones)
+    SpendingRules {
+        /// Policy id
+        #[arg(required = true)]
+        policy_id: EventId,
+        /// Max amount (sat) for a single transaction
+        #[arg(long)]
+        max_per_tx: Option<u64>,
+        /// Max amount (sat) spendable every 24 hours
+        #[arg(long)]
+        max_per_day: Option<u64>,
+        /// Allowed recipient address (any if not specified)
+        #[arg(long = "allow")]
+        allowlist: Vec<Address<NetworkUnchecked>>,
+        /// Require a description when spending more than this amount (sat)
+        #[arg(long)]
+        description_required_above: Option<u64>,
+    },
 }
 
 #[derive(Debug, Subcommand)]
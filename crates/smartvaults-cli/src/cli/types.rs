@@ -1,8 +1,11 @@
 // Copyright (c) 2022-2024 Smart Vaults
 // Distributed under the MIT software license
 
+use std::str::FromStr;
+
 use clap::ValueEnum;
-use smartvaults_sdk::core::bitcoin::Network;
+use smartvaults_sdk::core::bitcoin::address::NetworkUnchecked;
+use smartvaults_sdk::core::bitcoin::{Address, Network};
 use smartvaults_sdk::core::types::WordCount;
 
 #[derive(Debug, Clone, ValueEnum)]
@@ -43,3 +46,27 @@ impl From<CliWordCount> for WordCount {
         }
     }
 }
+
+/// Batch payment recipient (`<address>:<amount>`)
+#[derive(Debug, Clone)]
+pub struct CliRecipient {
+    pub address: Address<NetworkUnchecked>,
+    /// Amount in sat
+    pub amount: u64,
+}
+
+impl FromStr for CliRecipient {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (address, amount) = s
+            .rsplit_once(':')
+            .ok_or_else(|| String::from("expected <address>:<amount>"))?;
+        Ok(Self {
+            address: Address::from_str(address).map_err(|e| e.to_string())?,
+            amount: amount
+                .parse()
+                .map_err(|_| format!("invalid amount: {amount}"))?,
+        })
+    }
+}
@@ -3,8 +3,9 @@
 
 #![forbid(unsafe_code)]
 
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::{BufRead, BufReader};
+use std::iter;
 use std::path::PathBuf;
 use std::str::FromStr;
 use std::time::Duration;
@@ -15,13 +16,17 @@ use rustyline::error::ReadlineError;
 use rustyline::DefaultEditor;
 use smartvaults_sdk::config::Config;
 use smartvaults_sdk::core::bips::bip39::Mnemonic;
-use smartvaults_sdk::core::bitcoin::Network;
+use smartvaults_sdk::core::bitcoin::address::NetworkUnchecked;
+use smartvaults_sdk::core::bitcoin::{Address, Network};
+use smartvaults_sdk::core::reserves::{self, ExportedProof, ProofUtxo};
 use smartvaults_sdk::core::signer::Signer;
+use smartvaults_sdk::core::spending_rules::SpendingWindow;
 use smartvaults_sdk::core::types::Priority;
-use smartvaults_sdk::core::{Amount, CompletedProposal, FeeRate, Keychain, Result};
+use smartvaults_sdk::core::{Amount, CompletedProposal, FeeRate, Keychain, Result, SpendingRules};
 use smartvaults_sdk::nostr::{EventId, Metadata};
 use smartvaults_sdk::protocol::v1::{Label, SignerOffering};
-use smartvaults_sdk::types::{GetPolicy, GetProposal};
+use smartvaults_sdk::protocol::v2::ProtocolVersion;
+use smartvaults_sdk::types::{GetPolicy, GetProposal, PsbtFileFormat, SeedAccountReuse};
 use smartvaults_sdk::util::format;
 use smartvaults_sdk::{logger, SmartVaults};
 
@@ -30,8 +35,8 @@ mod util;
 
 use crate::cli::batch::BatchCommand;
 use crate::cli::{
-    io, Cli, CliCommand, Command, DeleteCommand, GetCommand, ProofCommand, SettingCommand,
-    ShareCommand,
+    io, Cli, CliCommand, Command, DeleteCommand, GetCommand, ProofCommand, SdCardCommand,
+    SettingCommand, ShareCommand,
 };
 
 fn base_path() -> Result<PathBuf> {
@@ -202,6 +207,25 @@ async fn run() -> Result<()> {
             }
             Ok(())
         }
+        CliCommand::VerifyProof {
+            proof,
+            descriptor,
+            message,
+            utxos,
+        } => {
+            let proof = ExportedProof::from_json(fs::read_to_string(proof)?)?;
+            let utxos: Vec<ProofUtxo> = ProofUtxo::list_from_json(fs::read_to_string(utxos)?)?;
+            let spendable =
+                reserves::verify_exported_proof(&proof, &descriptor, message, utxos, network)?;
+            println!(
+                "Valid Proof - Spendable amount: {} sat",
+                format::number(spendable)
+            );
+            if let Some(height) = proof.height {
+                println!("Created at block height {height}");
+            }
+            Ok(())
+        }
         CliCommand::Config { command } => match command {
             ConfigCommand::View => {
                 let config = Config::try_from_file(base_path, network)?;
@@ -212,6 +236,9 @@ async fn run() -> Result<()> {
                 electrum_server,
                 proxy,
                 block_explorer,
+                gift_wrap,
+                protocol_version,
+                proposal_expiration,
             } => {
                 let config = Config::try_from_file(base_path, network)?;
 
@@ -227,6 +254,20 @@ async fn run() -> Result<()> {
                     config.set_block_explorer(Some(block_explorer)).await;
                 }
 
+                if let Some(gift_wrap) = gift_wrap {
+                    config.set_gift_wrap(gift_wrap).await;
+                }
+
+                if let Some(version) = protocol_version {
+                    config.set_protocol_version(version).await;
+                }
+
+                if let Some(hours) = proposal_expiration {
+                    config
+                        .set_proposal_expiration(Some(Duration::from_secs(hours * 60 * 60)))
+                        .await;
+                }
+
                 config.save().await?;
 
                 Ok(())
@@ -235,6 +276,9 @@ async fn run() -> Result<()> {
                 electrum_server,
                 proxy,
                 block_explorer,
+                gift_wrap,
+                protocol_version,
+                proposal_expiration,
             } => {
                 let config = Config::try_from_file(base_path, network)?;
 
@@ -250,6 +294,20 @@ async fn run() -> Result<()> {
                     config.set_block_explorer(None).await;
                 }
 
+                if gift_wrap {
+                    config.set_gift_wrap(false).await;
+                }
+
+                if protocol_version {
+                    config
+                        .set_protocol_version(ProtocolVersion::default())
+                        .await;
+                }
+
+                if proposal_expiration {
+                    config.set_proposal_expiration(None).await;
+                }
+
                 config.save().await?;
 
                 Ok(())
@@ -270,15 +328,54 @@ async fn handle_command(command: Command, client: &SmartVaults) -> Result<()> {
             to_address,
             amount,
             description,
+            recipients,
+            drain_to,
             target_blocks,
         } => {
+            let fee_rate = FeeRate::Priority(Priority::Custom(target_blocks));
+
+            // BIP21 URI
+            if to_address.to_lowercase().starts_with("bitcoin:") {
+                if amount.is_some()
+                    || description.is_some()
+                    || !recipients.is_empty()
+                    || drain_to.is_some()
+                {
+                    return Err(
+                        "amount, description and other recipients can't be used with a BIP21 URI"
+                            .into(),
+                    );
+                }
+
+                let GetProposal { proposal_id, .. } = client
+                    .spend_bip21(policy_id, to_address, fee_rate, None, None, false)
+                    .await?;
+                println!("Spending proposal {proposal_id} sent");
+                return Ok(());
+            }
+
+            let to_address: Address<NetworkUnchecked> = Address::from_str(&to_address)?;
+            let amount: u64 = amount.ok_or("amount not specified")?;
+            let description: String = description.ok_or("description not specified")?;
+
+            let mut recipients: Vec<(Address<NetworkUnchecked>, Amount)> =
+                iter::once((to_address, Amount::Custom(amount)))
+                    .chain(
+                        recipients
+                            .into_iter()
+                            .map(|r| (r.address, Amount::Custom(r.amount))),
+                    )
+                    .collect();
+            if let Some(address) = drain_to {
+                recipients.push((address, Amount::Max));
+            }
+
             let GetProposal { proposal_id, .. } = client
                 .spend(
                     policy_id,
-                    to_address,
-                    Amount::Custom(amount),
+                    recipients,
                     description,
-                    FeeRate::Priority(Priority::Custom(target_blocks)),
+                    fee_rate,
                     None,
                     None,
                     false,
@@ -296,8 +393,7 @@ async fn handle_command(command: Command, client: &SmartVaults) -> Result<()> {
             let GetProposal { proposal_id, .. } = client
                 .spend(
                     policy_id,
-                    to_address,
-                    Amount::Max,
+                    vec![(to_address, Amount::Max)],
                     description,
                     FeeRate::Priority(Priority::Custom(target_blocks)),
                     None,
@@ -308,12 +404,73 @@ async fn handle_command(command: Command, client: &SmartVaults) -> Result<()> {
             println!("Spending proposal {proposal_id} sent");
             Ok(())
         }
-        Command::Approve { proposal_id } => {
+        Command::BumpFee {
+            policy_id,
+            txid,
+            target_blocks,
+        } => {
+            let GetProposal { proposal_id, .. } = client
+                .bump_fee(
+                    policy_id,
+                    txid,
+                    FeeRate::Priority(Priority::Custom(target_blocks)),
+                    None,
+                )
+                .await?;
+            println!("Fee bump proposal {proposal_id} sent");
+            Ok(())
+        }
+        Command::Cpfp {
+            policy_id,
+            parent_txid,
+            target_blocks,
+        } => {
+            let GetProposal { proposal_id, .. } = client
+                .cpfp(
+                    policy_id,
+                    parent_txid,
+                    FeeRate::Priority(Priority::Custom(target_blocks)),
+                    None,
+                )
+                .await?;
+            println!("CPFP proposal {proposal_id} sent");
+            Ok(())
+        }
+        Command::Approve {
+            proposal_id,
+            override_spending_rules,
+        } => {
             let password: String = io::get_password()?;
-            let (event_id, _) = client.approve(password, proposal_id).await?;
+            let (event_id, _) = client
+                .approve(password, proposal_id, override_spending_rules)
+                .await?;
+            println!("Proposal {proposal_id} approved: {event_id}");
+            Ok(())
+        }
+        Command::ApproveUr {
+            proposal_id,
+            parts,
+            override_spending_rules,
+        } => {
+            let (event_id, _) = client
+                .approve_with_ur(proposal_id, parts, override_spending_rules)
+                .await?;
             println!("Proposal {proposal_id} approved: {event_id}");
             Ok(())
         }
+        Command::Comment { proposal_id, text } => {
+            let event_id = client.comment_on_proposal(proposal_id, text).await?;
+            println!("Comment sent: {event_id}");
+            Ok(())
+        }
+        Command::Reject {
+            proposal_id,
+            reason,
+        } => {
+            let event_id = client.reject_proposal(proposal_id, reason).await?;
+            println!("Proposal rejected: {event_id}");
+            Ok(())
+        }
         Command::Finalize { proposal_id } => {
             let completed_proposal: CompletedProposal = client.finalize(proposal_id).await?;
 
@@ -348,26 +505,132 @@ async fn handle_command(command: Command, client: &SmartVaults) -> Result<()> {
                         _ => (),
                     };
                 }
+                CompletedProposal::FeeBump {
+                    tx, replaced_txid, ..
+                } => {
+                    let txid = tx.txid();
+
+                    println!("Fee bump broadcasted: {txid} (replaces {replaced_txid})");
+
+                    match client.network() {
+                        Network::Bitcoin => {
+                            println!("\nExplorer: https://blockstream.info/tx/{txid} \n")
+                        }
+                        Network::Testnet => {
+                            println!("\nExplorer: https://blockstream.info/testnet/tx/{txid} \n")
+                        }
+                        _ => (),
+                    };
+                }
                 CompletedProposal::ProofOfReserve { .. } => println!("Proof of Reserve finalized"),
             };
 
             Ok(())
         }
+        Command::RotateSharedKey {
+            policy_id,
+            nostr_pubkeys,
+        } => {
+            let rotation = client.rotate_shared_key(policy_id, nostr_pubkeys).await?;
+            println!(
+                "Shared key rotated: policy re-published at event {}",
+                rotation.event_id
+            );
+            for migrated in rotation.migrated_proposals.into_iter() {
+                println!(
+                    "Proposal {} migrated to {}",
+                    migrated.proposal_id, migrated.new_proposal_id
+                );
+                for public_key in migrated.lost_approvals.into_iter() {
+                    println!("  Approval of {public_key} lost: must approve again");
+                }
+                for public_key in migrated.lost_rejections.into_iter() {
+                    println!("  Rejection of {public_key} lost: must reject again");
+                }
+            }
+            Ok(())
+        }
         Command::Rebroadcast => {
             client.rebroadcast_all_events().await?;
             Ok(())
         }
+        Command::SdCard { command } => match command {
+            SdCardCommand::ExportPsbt {
+                proposal_id,
+                dir,
+                base64,
+            } => {
+                let format = if base64 {
+                    PsbtFileFormat::Base64
+                } else {
+                    PsbtFileFormat::Binary
+                };
+                let path = client
+                    .export_proposal_psbt(proposal_id, dir, format)
+                    .await?;
+                println!("PSBT exported to {}", path.display());
+                Ok(())
+            }
+            SdCardCommand::ExportColdcard { policy_id, dir } => {
+                let path = client
+                    .export_vault_coldcard_registration(policy_id, dir)
+                    .await?;
+                println!("Coldcard registration file exported to {}", path.display());
+                Ok(())
+            }
+            SdCardCommand::Approve {
+                dir,
+                override_spending_rules,
+            } => {
+                let approved = client
+                    .approve_signed_psbts_from_dir(dir, override_spending_rules)
+                    .await?;
+                if approved.is_empty() {
+                    println!("No signed PSBTs to approve");
+                }
+                for (proposal_id, approval_id) in approved.into_iter() {
+                    println!("Proposal {proposal_id} approved: {approval_id}");
+                }
+                Ok(())
+            }
+        },
         Command::Proof { command } => match command {
             ProofCommand::New { policy_id, message } => {
                 let (proposal_id, ..) = client.new_proof_proposal(policy_id, message).await?;
                 println!("Proof of Reserve proposal {proposal_id} sent");
                 Ok(())
             }
+            ProofCommand::NewAggregated {
+                message,
+                policy_ids,
+            } => {
+                let proposals = client
+                    .new_aggregated_proof_proposal(policy_ids, message)
+                    .await?;
+                for GetProposal {
+                    proposal_id,
+                    policy_id,
+                    ..
+                } in proposals.into_iter()
+                {
+                    println!("Proof of Reserve proposal {proposal_id} sent to vault {policy_id}");
+                }
+                Ok(())
+            }
             ProofCommand::Verify { proposal_id } => {
-                let spendable = client.verify_proof_by_id(proposal_id).await?;
+                let amounts = client.verify_aggregated_proof_by_id(proposal_id).await?;
+                if amounts.vaults.len() > 1 {
+                    for (policy_id, amount) in amounts.vaults.into_iter() {
+                        println!(
+                            "- Vault {}: {} sat",
+                            smartvaults_sdk::util::cut_event_id(policy_id),
+                            format::number(amount)
+                        );
+                    }
+                }
                 println!(
                     "Valid Proof - Spendable amount: {} sat",
-                    format::number(spendable)
+                    format::number(amounts.total)
                 );
                 Ok(())
             }
@@ -469,12 +732,48 @@ async fn handle_command(command: Command, client: &SmartVaults) -> Result<()> {
                 descriptor,
                 nostr_pubkeys,
             } => {
+                let reuses = client.seed_account_reuse(&descriptor).await?;
+                for SeedAccountReuse { signer, policy_ids } in reuses.iter() {
+                    println!(
+                        "Warning: signer {} (account {}) is already used in {} other vault/s: {}",
+                        signer.name(),
+                        signer
+                            .account()
+                            .map(|a| a.to_string())
+                            .unwrap_or_else(|| String::from("-")),
+                        policy_ids.len(),
+                        policy_ids
+                            .iter()
+                            .map(|id| smartvaults_sdk::util::cut_event_id(*id))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    );
+                }
+                if !reuses.is_empty() && !io::ask("Reuse the account/s in this vault?")? {
+                    return Ok(());
+                }
+
                 let policy_id = client
                     .save_policy(name, description, descriptor, nostr_pubkeys)
                     .await?;
                 println!("Policy saved: {policy_id}");
                 Ok(())
             }
+            AddCommand::SeedSigner {
+                name,
+                account,
+                share_with_contacts,
+            } => {
+                let password: String = io::get_password()?;
+                let signer_id = client.save_seed_signer(password, name, account).await?;
+                if share_with_contacts {
+                    for user in client.get_contacts().await? {
+                        client.share_signer(signer_id, user.public_key()).await?;
+                    }
+                }
+                println!("Seed signer saved: {signer_id}");
+                Ok(())
+            }
             AddCommand::SmartVaultsSigner {
                 share_with_contacts,
             } => {
@@ -488,12 +787,29 @@ async fn handle_command(command: Command, client: &SmartVaults) -> Result<()> {
             }
             AddCommand::Signer {
                 name,
-                fingerprint,
-                descriptor,
+                export,
+                share_with_contacts,
+            } => {
+                let path = PathBuf::from(&export);
+                let export: String = if path.is_file() {
+                    fs::read_to_string(path)?
+                } else {
+                    export
+                };
+                let signer_id = client.save_signer_from_export(name, export).await?;
+                if share_with_contacts {
+                    for user in client.get_contacts().await? {
+                        client.share_signer(signer_id, user.public_key()).await?;
+                    }
+                }
+                Ok(())
+            }
+            AddCommand::SignerUr {
+                name,
+                parts,
                 share_with_contacts,
             } => {
-                let signer = Signer::airgap(name, None, fingerprint, descriptor, client.network())?;
-                let signer_id = client.save_signer(signer).await?;
+                let signer_id = client.save_signer_from_ur(name, parts).await?;
                 if share_with_contacts {
                     for user in client.get_contacts().await? {
                         client.share_signer(signer_id, user.public_key()).await?;
@@ -542,7 +858,30 @@ async fn handle_command(command: Command, client: &SmartVaults) -> Result<()> {
             }
             GetCommand::Proposal { proposal_id } => {
                 let proposal = client.get_proposal_by_id(proposal_id).await?;
-                util::print_proposal(proposal);
+                let status = client.get_proposal_status(proposal_id).await?;
+                util::print_proposal(proposal, status);
+                Ok(())
+            }
+            GetCommand::Comments { proposal_id } => {
+                let comments = client.get_proposal_comments(proposal_id).await?;
+                util::print_proposal_comments(comments);
+                Ok(())
+            }
+            GetCommand::Rejections { proposal_id } => {
+                let rejections = client.get_proposal_rejections(proposal_id).await?;
+                util::print_proposal_rejections(rejections);
+                Ok(())
+            }
+            GetCommand::ProposalUr {
+                proposal_id,
+                max_fragment_len,
+            } => {
+                let encoder = client
+                    .proposal_ur_encoder(proposal_id, max_fragment_len)
+                    .await?;
+                for part in encoder.parts()?.into_iter() {
+                    println!("{part}");
+                }
                 Ok(())
             }
             GetCommand::Signers => {
@@ -592,6 +931,52 @@ async fn handle_command(command: Command, client: &SmartVaults) -> Result<()> {
                 println!("Label saved at event {event_id}");
                 Ok(())
             }
+            SetCommand::VaultMetadata {
+                policy_id,
+                name,
+                description,
+                icon,
+                color,
+                tags,
+            } => {
+                let mut metadata = client.get_vault_metadata(policy_id).await?;
+                if name.is_some() {
+                    metadata.name = name;
+                }
+                if description.is_some() {
+                    metadata.description = description;
+                }
+                if icon.is_some() {
+                    metadata.icon = icon;
+                }
+                if color.is_some() {
+                    metadata.color = color;
+                }
+                if !tags.is_empty() {
+                    metadata.tags = tags;
+                }
+                let event_id = client.save_vault_metadata(policy_id, metadata).await?;
+                println!("Vault metadata saved at event {event_id}");
+                Ok(())
+            }
+            SetCommand::SpendingRules {
+                policy_id,
+                max_per_tx,
+                max_per_day,
+                allowlist,
+                description_required_above,
+            } => {
+                let mut metadata = client.get_vault_metadata(policy_id).await?;
+                metadata.spending_rules = SpendingRules {
+                    max_per_tx,
+                    windows: max_per_day.map(SpendingWindow::daily).into_iter().collect(),
+                    allowlist,
+                    description_required_above,
+                };
+                let event_id = client.save_vault_metadata(policy_id, metadata).await?;
+                println!("Spending rules saved at event {event_id}");
+                Ok(())
+            }
         },
         Command::Share { command } => match command {
             ShareCommand::Signer {
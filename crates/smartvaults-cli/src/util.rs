@@ -14,12 +14,13 @@ use smartvaults_sdk::core::bips::bip32::Bip32;
 use smartvaults_sdk::core::bitcoin::bip32::ExtendedPubKey;
 use smartvaults_sdk::core::bitcoin::{Network, ScriptBuf};
 use smartvaults_sdk::core::proposal::{CompletedProposal, Proposal};
-use smartvaults_sdk::core::{Keychain, Purpose, Result, SECP256K1};
+use smartvaults_sdk::core::{Keychain, Purpose, Result, SpendingRules, SECP256K1};
 use smartvaults_sdk::nostr::prelude::{FromMnemonic, NostrConnectURI, ToBech32};
 use smartvaults_sdk::nostr::{EventId, Keys, Profile, PublicKey, Relay, Timestamp, Url};
 use smartvaults_sdk::types::{
-    GetAddress, GetCompletedProposal, GetPolicy, GetProposal, GetSigner, GetSignerOffering,
-    GetTransaction, GetUtxo, NostrConnectRequest,
+    GetAddress, GetCompletedProposal, GetPolicy, GetProposal, GetProposalComment,
+    GetProposalRejection, GetSigner, GetSignerOffering, GetTransaction, GetUtxo,
+    NostrConnectRequest, ProposalStatus,
 };
 use smartvaults_sdk::util::{self, format};
 use termtree::Tree;
@@ -90,6 +91,38 @@ pub fn print_contacts(contacts: BTreeSet<Profile>) {
     table.printstd();
 }
 
+fn print_spending_rules(rules: &SpendingRules) {
+    if rules.is_empty() {
+        return;
+    }
+
+    println!("- Spending rules:");
+    if let Some(max) = rules.max_per_tx {
+        println!("  - Max per transaction: {} sat", format::number(max));
+    }
+    for window in rules.windows.iter() {
+        println!(
+            "  - Max {} sat every {} hours",
+            format::number(window.max),
+            window.period / 3600
+        );
+    }
+    if !rules.allowlist.is_empty() {
+        let allowlist: Vec<String> = rules
+            .allowlist
+            .iter()
+            .map(|a| a.clone().assume_checked().to_string())
+            .collect();
+        println!("  - Allowed recipients: {}", allowlist.join(", "));
+    }
+    if let Some(threshold) = rules.description_required_above {
+        println!(
+            "  - Description required above {} sat",
+            format::number(threshold)
+        );
+    }
+}
+
 pub fn print_policy(
     policy: GetPolicy,
     policy_id: EventId,
@@ -102,6 +135,16 @@ pub fn print_policy(
     println!("- ID: {policy_id}");
     println!("- Name: {}", policy.name());
     println!("- Description: {}", policy.description());
+    if let Some(icon) = &policy.metadata.icon {
+        println!("- Icon: {icon}");
+    }
+    if let Some(color) = &policy.metadata.color {
+        println!("- Color: {color}");
+    }
+    if !policy.metadata.tags.is_empty() {
+        println!("- Tags: {}", policy.metadata.tags.join(", "));
+    }
+    print_spending_rules(&policy.metadata.spending_rules);
 
     let mut tree: Tree<String> = Tree::new("- Descriptor".to_string());
     tree.push(add_node(&item));
@@ -335,12 +378,13 @@ pub fn print_policies(policies: Vec<GetPolicy>) {
     table.printstd();
 }
 
-pub fn print_proposal(proposal: GetProposal) {
+pub fn print_proposal(proposal: GetProposal, status: ProposalStatus) {
     let GetProposal {
         proposal_id,
         policy_id,
         proposal,
         signed,
+        expiration,
         ..
     } = proposal;
     println!();
@@ -350,13 +394,25 @@ pub fn print_proposal(proposal: GetProposal) {
         Proposal::Spending {
             to_address,
             amount,
+            other_recipients,
             description,
+            cpfp_parent,
             ..
         } => {
             println!("- Type: spending");
             println!("- Description: {description}");
             println!("- To address: {}", to_address.assume_checked());
             println!("- Amount: {amount}");
+            for recipient in other_recipients.iter() {
+                println!(
+                    "- To address: {} ({} sat)",
+                    recipient.address.clone().assume_checked(),
+                    format::number(recipient.amount)
+                );
+            }
+            if let Some(parent_txid) = cpfp_parent {
+                println!("- CPFP parent: {parent_txid}");
+            }
             println!("- Signed: {signed}");
         }
         Proposal::KeyAgentPayment {
@@ -371,10 +427,69 @@ pub fn print_proposal(proposal: GetProposal) {
             println!("- Amount: {amount}");
             println!("- Signed: {signed}");
         }
-        Proposal::ProofOfReserve { message, .. } => {
+        Proposal::ProofOfReserve {
+            message, height, ..
+        } => {
             println!("- Type: proof-of-reserve");
             println!("- Message: {message}");
+            if let Some(height) = height {
+                println!("- Height: {height}");
+            }
         }
+        Proposal::FeeBump {
+            replaced_txid,
+            description,
+            ..
+        } => {
+            println!("- Type: fee-bump");
+            println!("- Description: {description}");
+            println!("- Replaced txid: {replaced_txid}");
+            println!("- Signed: {signed}");
+        }
+    }
+    if let Some(expiration) = expiration {
+        println!("- Expiration: {}", expiration.to_human_datetime());
+    }
+    println!("- Approvals: {}", status.approved_by.len());
+    println!("- Rejections: {}", status.rejected_by.len());
+    if status.is_rejected() {
+        println!("- Status: {}", "rejected".fg::<BlazeOrange>());
+    }
+    println!();
+}
+
+pub fn print_proposal_comments(comments: Vec<GetProposalComment>) {
+    for GetProposalComment {
+        user,
+        comment,
+        timestamp,
+        ..
+    } in comments.into_iter()
+    {
+        println!(
+            "\n{} - {}",
+            user.name().fg::<BlazeOrange>(),
+            timestamp.to_human_datetime()
+        );
+        println!("{}", comment.text);
+    }
+    println!();
+}
+
+pub fn print_proposal_rejections(rejections: Vec<GetProposalRejection>) {
+    for GetProposalRejection {
+        user,
+        rejection,
+        timestamp,
+        ..
+    } in rejections.into_iter()
+    {
+        println!(
+            "\n{} - {}",
+            user.name().fg::<BlazeOrange>(),
+            timestamp.to_human_datetime()
+        );
+        println!("{}", rejection.reason.as_deref().unwrap_or("No reason"));
     }
     println!();
 }
@@ -451,6 +566,22 @@ pub fn print_proposals(proposals: Vec<GetProposal>) {
                     signed,
                 ]);
             }
+            Proposal::FeeBump {
+                replaced_txid,
+                description,
+                ..
+            } => {
+                table.add_row(row![
+                    index + 1,
+                    proposal_id,
+                    util::cut_event_id(policy_id),
+                    "fee-bump",
+                    description,
+                    util::cut_txid(replaced_txid),
+                    "-",
+                    signed,
+                ]);
+            }
         }
     }
 
@@ -468,10 +599,16 @@ pub fn print_completed_proposals(proposals: Vec<GetCompletedProposal>) {
             policy_id,
             completed_proposal_id,
             proposal,
+            replaced_by,
             ..
         },
     ) in proposals.into_iter().enumerate()
     {
+        let replaced: &str = if replaced_by.is_some() {
+            " (replaced)"
+        } else {
+            ""
+        };
         match proposal {
             CompletedProposal::Spending {
                 tx, description, ..
@@ -480,7 +617,7 @@ pub fn print_completed_proposals(proposals: Vec<GetCompletedProposal>) {
                     index + 1,
                     completed_proposal_id,
                     util::cut_event_id(policy_id),
-                    "spending",
+                    format!("spending{replaced}"),
                     tx.txid(),
                     description,
                 ]);
@@ -492,7 +629,7 @@ pub fn print_completed_proposals(proposals: Vec<GetCompletedProposal>) {
                     index + 1,
                     completed_proposal_id,
                     util::cut_event_id(policy_id),
-                    "key-agent-payment",
+                    format!("key-agent-payment{replaced}"),
                     tx.txid(),
                     description,
                 ]);
@@ -507,6 +644,18 @@ pub fn print_completed_proposals(proposals: Vec<GetCompletedProposal>) {
                     message,
                 ]);
             }
+            CompletedProposal::FeeBump {
+                tx, description, ..
+            } => {
+                table.add_row(row![
+                    index + 1,
+                    completed_proposal_id,
+                    util::cut_event_id(policy_id),
+                    format!("fee-bump{replaced}"),
+                    tx.txid(),
+                    description,
+                ]);
+            }
         }
     }
 
@@ -516,7 +665,7 @@ pub fn print_completed_proposals(proposals: Vec<GetCompletedProposal>) {
 pub fn print_signers(signers: Vec<GetSigner>) {
     let mut table = Table::new();
 
-    table.set_titles(row!["#", "ID", "Name", "Fingerprint", "Type",]);
+    table.set_titles(row!["#", "ID", "Name", "Fingerprint", "Type", "Account"]);
 
     for (index, GetSigner { signer_id, signer }) in signers.into_iter().enumerate() {
         table.add_row(row![
@@ -525,6 +674,10 @@ pub fn print_signers(signers: Vec<GetSigner>) {
             signer.name(),
             signer.fingerprint(),
             signer.signer_type(),
+            signer
+                .account()
+                .map(|a| a.to_string())
+                .unwrap_or_else(|| String::from("-")),
         ]);
     }
 
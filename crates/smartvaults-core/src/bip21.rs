@@ -0,0 +1,300 @@
+// Copyright (c) 2022-2024 Smart Vaults
+// Distributed under the MIT software license
+
+//! BIP21 payment request URI
+//!
+//! <https://github.com/bitcoin/bips/blob/master/bip-0021.mediawiki>
+
+use core::fmt;
+use core::str::FromStr;
+
+use keechain_core::bitcoin::address::{self, NetworkUnchecked};
+use keechain_core::bitcoin::{Address, Network};
+
+const SCHEME: &str = "bitcoin:";
+const SAT_DECIMALS: usize = 8;
+const MAX_MONEY: u64 = 21_000_000 * 100_000_000;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Address(#[from] address::Error),
+    #[error("invalid scheme: must start with `bitcoin:`")]
+    InvalidScheme,
+    #[error("address not valid for {0} network")]
+    WrongNetwork(Network),
+    #[error("invalid amount: {0}")]
+    InvalidAmount(String),
+    #[error("invalid amount precision: max 8 decimals allowed")]
+    AmountPrecision,
+    #[error("amount not specified")]
+    MissingAmount,
+    #[error("duplicated parameter: {0}")]
+    DuplicatedParameter(String),
+    #[error("unsupported required parameter: {0}")]
+    UnsupportedRequiredParameter(String),
+    #[error("invalid percent-encoding")]
+    InvalidEncoding,
+}
+
+/// BIP21 payment request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bip21 {
+    pub address: Address<NetworkUnchecked>,
+    /// Amount in sat
+    pub amount: Option<u64>,
+    pub label: Option<String>,
+    pub message: Option<String>,
+}
+
+impl Bip21 {
+    /// Check that the address is valid for the passed [`Network`]
+    pub fn require_network(&self, network: Network) -> Result<(), Error> {
+        if self.address.is_valid_for_network(network) {
+            Ok(())
+        } else {
+            Err(Error::WrongNetwork(network))
+        }
+    }
+
+    /// Description of the payment request, composed by `label` and `message`
+    pub fn description(&self) -> String {
+        match (&self.label, &self.message) {
+            (Some(label), Some(message)) => format!("{label}: {message}"),
+            (Some(label), None) => label.clone(),
+            (None, Some(message)) => message.clone(),
+            (None, None) => String::new(),
+        }
+    }
+}
+
+impl FromStr for Bip21 {
+    type Err = Error;
+
+    fn from_str(uri: &str) -> Result<Self, Self::Err> {
+        let uri: &str = uri.trim();
+
+        // Scheme is case-insensitive
+        let scheme: &str = uri.get(..SCHEME.len()).ok_or(Error::InvalidScheme)?;
+        if !scheme.eq_ignore_ascii_case(SCHEME) {
+            return Err(Error::InvalidScheme);
+        }
+        let uri: &str = &uri[SCHEME.len()..];
+
+        let (address, query) = match uri.split_once('?') {
+            Some((address, query)) => (address, Some(query)),
+            None => (uri, None),
+        };
+
+        let mut request = Self {
+            address: Address::from_str(address)?,
+            amount: None,
+            label: None,
+            message: None,
+        };
+
+        for param in query.into_iter().flat_map(|q| q.split('&')) {
+            if param.is_empty() {
+                continue;
+            }
+
+            let (key, value) = param.split_once('=').unwrap_or((param, ""));
+            match key {
+                "amount" => {
+                    if request.amount.is_some() {
+                        return Err(Error::DuplicatedParameter(key.to_string()));
+                    }
+                    request.amount = Some(parse_btc_amount(value)?);
+                }
+                "label" => {
+                    if request.label.is_some() {
+                        return Err(Error::DuplicatedParameter(key.to_string()));
+                    }
+                    request.label = Some(percent_decode(value)?);
+                }
+                "message" => {
+                    if request.message.is_some() {
+                        return Err(Error::DuplicatedParameter(key.to_string()));
+                    }
+                    request.message = Some(percent_decode(value)?);
+                }
+                key if key.starts_with("req-") => {
+                    return Err(Error::UnsupportedRequiredParameter(key.to_string()));
+                }
+                // Unknown optional parameters are ignored
+                _ => (),
+            }
+        }
+
+        Ok(request)
+    }
+}
+
+impl fmt::Display for Bip21 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{SCHEME}{}", self.address.clone().assume_checked())?;
+
+        let mut params: Vec<String> = Vec::new();
+        if let Some(amount) = self.amount {
+            params.push(format!("amount={}", format_btc_amount(amount)));
+        }
+        if let Some(label) = &self.label {
+            params.push(format!("label={}", percent_encode(label)));
+        }
+        if let Some(message) = &self.message {
+            params.push(format!("message={}", percent_encode(message)));
+        }
+
+        if !params.is_empty() {
+            write!(f, "?{}", params.join("&"))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Parse decimal BTC amount to sat
+fn parse_btc_amount(value: &str) -> Result<u64, Error> {
+    let (int, fract) = value.split_once('.').unwrap_or((value, ""));
+
+    if int.is_empty() && fract.is_empty() {
+        return Err(Error::InvalidAmount(value.to_string()));
+    }
+
+    if !int.chars().chain(fract.chars()).all(|c| c.is_ascii_digit()) {
+        return Err(Error::InvalidAmount(value.to_string()));
+    }
+
+    if fract.len() > SAT_DECIMALS {
+        return Err(Error::AmountPrecision);
+    }
+
+    let int: u64 = if int.is_empty() {
+        0
+    } else {
+        int.parse()
+            .map_err(|_| Error::InvalidAmount(value.to_string()))?
+    };
+    let fract: u64 = format!("{fract:0<SAT_DECIMALS$}")
+        .parse()
+        .map_err(|_| Error::InvalidAmount(value.to_string()))?;
+
+    let amount: u64 = int
+        .checked_mul(100_000_000)
+        .and_then(|sat| sat.checked_add(fract))
+        .filter(|sat| *sat <= MAX_MONEY)
+        .ok_or_else(|| Error::InvalidAmount(value.to_string()))?;
+
+    Ok(amount)
+}
+
+fn format_btc_amount(sat: u64) -> String {
+    let btc: String = format!("{}.{:08}", sat / 100_000_000, sat % 100_000_000);
+    btc.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+fn percent_decode(value: &str) -> Result<String, Error> {
+    let bytes: &[u8] = value.as_bytes();
+    let mut decoded: Vec<u8> = Vec::with_capacity(bytes.len());
+    let mut i: usize = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let hex: &str = value.get(i + 1..i + 3).ok_or(Error::InvalidEncoding)?;
+                let byte: u8 = u8::from_str_radix(hex, 16).map_err(|_| Error::InvalidEncoding)?;
+                decoded.push(byte);
+                i += 3;
+            }
+            b => {
+                decoded.push(b);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).map_err(|_| Error::InvalidEncoding)
+}
+
+fn percent_encode(value: &str) -> String {
+    let mut encoded: String = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ADDRESS: &str = "mohjSavDdQYHRYXcS3uS6ttaHP8amyvX78";
+
+    #[test]
+    fn test_parse_bip21() {
+        let uri = format!(
+            "bitcoin:{ADDRESS}?amount=0.0012&label=Luke-Jr&message=Donation%20for%20project%20xyz"
+        );
+        let request = Bip21::from_str(&uri).unwrap();
+        assert_eq!(request.address, Address::from_str(ADDRESS).unwrap());
+        assert_eq!(request.amount, Some(120_000));
+        assert_eq!(request.label.as_deref(), Some("Luke-Jr"));
+        assert_eq!(request.message.as_deref(), Some("Donation for project xyz"));
+        assert_eq!(request.description(), "Luke-Jr: Donation for project xyz");
+        assert!(request.require_network(Network::Testnet).is_ok());
+        assert!(matches!(
+            request.require_network(Network::Bitcoin),
+            Err(Error::WrongNetwork(Network::Bitcoin))
+        ));
+
+        // Round trip
+        assert_eq!(Bip21::from_str(&request.to_string()).unwrap(), request);
+    }
+
+    #[test]
+    fn test_parse_bip21_errors() {
+        assert!(matches!(
+            Bip21::from_str(ADDRESS),
+            Err(Error::InvalidScheme)
+        ));
+        assert!(matches!(
+            Bip21::from_str("bitcoiné:mohjSavDdQYHRYXcS3uS6ttaHP8amyvX78"),
+            Err(Error::InvalidScheme)
+        ));
+        assert!(matches!(
+            Bip21::from_str(&format!("bitcoin:{ADDRESS}?amount=0.000000001")),
+            Err(Error::AmountPrecision)
+        ));
+        assert!(matches!(
+            Bip21::from_str(&format!("bitcoin:{ADDRESS}?amount=1,5")),
+            Err(Error::InvalidAmount(..))
+        ));
+        assert!(matches!(
+            Bip21::from_str(&format!("bitcoin:{ADDRESS}?amount=21000001")),
+            Err(Error::InvalidAmount(..))
+        ));
+        assert!(matches!(
+            Bip21::from_str(&format!(
+                "bitcoin:{ADDRESS}?req-somethingyoudontunderstand=50"
+            )),
+            Err(Error::UnsupportedRequiredParameter(..))
+        ));
+
+        // Unknown optional parameters are ignored
+        let request =
+            Bip21::from_str(&format!("BITCOIN:{ADDRESS}?somethingyoudontunderstand=50")).unwrap();
+        assert_eq!(request.amount, None);
+    }
+
+    #[test]
+    fn test_parse_btc_amount() {
+        assert_eq!(parse_btc_amount("1").unwrap(), 100_000_000);
+        assert_eq!(parse_btc_amount("0.00000001").unwrap(), 1);
+        assert_eq!(parse_btc_amount(".5").unwrap(), 50_000_000);
+        assert_eq!(format_btc_amount(120_000), "0.0012");
+        assert_eq!(format_btc_amount(100_000_000), "1");
+    }
+}
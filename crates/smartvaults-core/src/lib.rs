@@ -12,21 +12,25 @@ use keechain_core::secp256k1::{rand, All, Secp256k1};
 pub use keechain_core::*;
 use once_cell::sync::Lazy;
 
+pub mod bip21;
 pub mod constants;
 pub mod policy;
 pub mod proposal;
 #[cfg(feature = "reserves")]
 pub mod reserves;
 pub mod signer;
+pub mod spending_rules;
 pub mod types;
+pub mod ur;
 pub mod util;
 
 pub use self::policy::{
     AbsoluteLockTime, DecayingTime, Locktime, Policy, PolicyTemplate, PolicyTemplateType,
     RecoveryTemplate, SelectableCondition, Sequence,
 };
-pub use self::proposal::{ApprovedProposal, CompletedProposal, Proposal};
+pub use self::proposal::{ApprovedProposal, CompletedProposal, Proposal, ProposalSigner};
 pub use self::signer::{SharedSigner, Signer, SignerType};
+pub use self::spending_rules::SpendingRules;
 pub use self::types::{Amount, FeeRate, Priority};
 
 pub static SECP256K1: Lazy<Secp256k1<All>> = Lazy::new(|| {
@@ -38,6 +42,7 @@ pub static SECP256K1: Lazy<Secp256k1<All>> = Lazy::new(|| {
 
 #[cfg(test)]
 mod tests {
+    use std::collections::BTreeMap;
     use std::str::FromStr;
 
     use keechain_core::bdk::chain::{BlockId, ConfirmationTime};
@@ -46,16 +51,24 @@ mod tests {
     use keechain_core::bips::bip39::Mnemonic;
     use keechain_core::bitcoin::absolute::Height;
     use keechain_core::bitcoin::hashes::Hash;
-    use keechain_core::bitcoin::{absolute, Address, BlockHash, Network, Transaction, TxOut};
+    use keechain_core::bitcoin::psbt::PartiallySignedTransaction;
+    use keechain_core::bitcoin::{
+        absolute, Address, BlockHash, Network, Transaction, TxIn, TxOut, Txid,
+    };
     use keechain_core::descriptors::ToDescriptor;
-    use keechain_core::miniscript::DescriptorPublicKey;
+    use keechain_core::miniscript::{Descriptor, DescriptorPublicKey};
+    use keechain_core::psbt::PsbtUtility;
     use keechain_core::{Purpose, Result, Seed};
 
     use super::*;
     use crate::constants::SMARTVAULTS_ACCOUNT_INDEX;
+    use crate::policy::ExclusionReason;
     use crate::proposal::ProposalType;
     #[cfg(feature = "reserves")]
-    use crate::reserves::ProofOfReserves;
+    use crate::reserves::{
+        merge_proofs, verify_aggregated_proof, verify_exported_proof, ExportedProof, ProofError,
+        ProofOfReserves, ProofUtxo,
+    };
 
     const NETWORK: Network = Network::Testnet;
     const MNEMONIC_A: &str =
@@ -160,8 +173,10 @@ mod tests {
         let mut wallet = get_funded_wallet(&descriptor).unwrap();
         let proposal: Proposal = policy.spend(
             &mut wallet,
-            Address::from_str("mohjSavDdQYHRYXcS3uS6ttaHP8amyvX78")?,
-            Amount::Custom(1120),
+            vec![(
+                Address::from_str("mohjSavDdQYHRYXcS3uS6ttaHP8amyvX78")?,
+                Amount::Custom(1120),
+            )],
             "Testing",
             FeeRate::from_sat_per_vb(1.0),
             None,
@@ -180,6 +195,388 @@ mod tests {
         Ok(())
     }
 
+    #[derive(Debug)]
+    struct MockSigner {
+        seed: Seed,
+        descriptor: Descriptor<String>,
+    }
+
+    impl ProposalSigner for MockSigner {
+        fn sign(
+            &self,
+            mut psbt: PartiallySignedTransaction,
+            signer: &Signer,
+        ) -> Result<PartiallySignedTransaction, proposal::Error> {
+            if self.seed.fingerprint(NETWORK, &SECP256K1).ok() != Some(signer.fingerprint()) {
+                return Err(proposal::Error::ExternalSigner(String::from(
+                    "unknown signer",
+                )));
+            }
+            psbt.sign_custom(
+                &self.seed,
+                Some(self.descriptor.clone()),
+                Vec::new(),
+                NETWORK,
+                &SECP256K1,
+            )?;
+            Ok(psbt)
+        }
+    }
+
+    #[test]
+    fn test_policy_spend_with_proposal_signer() -> Result<()> {
+        let mnemonic_a: Mnemonic = Mnemonic::from_str(MNEMONIC_A)?;
+        let seed_a: Seed = Seed::from_mnemonic(mnemonic_a);
+        let signer_a = Signer::from_seed("A", None, seed_a.clone(), Some(7291640), NETWORK)?;
+
+        let mnemonic_b: Mnemonic = Mnemonic::from_str(MNEMONIC_B)?;
+        let seed_b: Seed = Seed::from_mnemonic(mnemonic_b);
+        let signer_b = Signer::from_seed("B", None, seed_b.clone(), Some(7291640), NETWORK)?;
+
+        let template = PolicyTemplate::multisig(
+            2,
+            vec![
+                signer_a.descriptor_public_key()?,
+                signer_b.descriptor_public_key()?,
+            ],
+        );
+        let policy: Policy = Policy::from_template("Name", "Description", template, NETWORK)?;
+        let descriptor: String = policy.as_descriptor().to_string();
+
+        let mut wallet = get_funded_wallet(&descriptor).unwrap();
+        let proposal: Proposal = policy.spend(
+            &mut wallet,
+            vec![(
+                Address::from_str("mohjSavDdQYHRYXcS3uS6ttaHP8amyvX78")?,
+                Amount::Custom(1120),
+            )],
+            "Testing",
+            FeeRate::from_sat_per_vb(1.0),
+            None,
+            None,
+            None,
+        )?;
+
+        let mock = MockSigner {
+            seed: seed_b,
+            descriptor: policy.descriptor(),
+        };
+        assert!(proposal.approve_with_signer(&signer_a, &mock).is_err());
+
+        let approved_a: ApprovedProposal = proposal.approve(&seed_a, Vec::new(), NETWORK)?;
+        let approved_b: ApprovedProposal = proposal.approve_with_signer(&signer_b, &mock)?;
+
+        let completed_proposal: CompletedProposal =
+            proposal.finalize(vec![approved_a, approved_b], NETWORK)?;
+
+        assert_eq!(completed_proposal.get_type(), ProposalType::Spending);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_policy_spend_with_seed_accounts() -> Result<()> {
+        let mnemonic_a: Mnemonic = Mnemonic::from_str(MNEMONIC_A)?;
+        let seed_a: Seed = Seed::from_mnemonic(mnemonic_a);
+        let signer_a1 = Signer::from_seed("A1", None, seed_a.clone(), Some(1), NETWORK)?;
+        let signer_a2 = Signer::from_seed("A2", None, seed_a.clone(), Some(2), NETWORK)?;
+        assert_eq!(signer_a1.account(), Some(1));
+        assert_eq!(signer_a2.account(), Some(2));
+
+        let mnemonic_b: Mnemonic = Mnemonic::from_str(MNEMONIC_B)?;
+        let seed_b: Seed = Seed::from_mnemonic(mnemonic_b);
+        let signer_b = Signer::from_seed("B", None, seed_b.clone(), Some(1), NETWORK)?;
+
+        let template = PolicyTemplate::multisig(
+            2,
+            vec![
+                signer_a1.descriptor_public_key()?,
+                signer_a2.descriptor_public_key()?,
+                signer_b.descriptor_public_key()?,
+            ],
+        );
+        let policy: Policy = Policy::from_template("Name", "Description", template, NETWORK)?;
+        let descriptor: String = policy.as_descriptor().to_string();
+
+        let mut wallet = get_funded_wallet(&descriptor).unwrap();
+        let proposal: Proposal = policy.spend(
+            &mut wallet,
+            vec![(
+                Address::from_str("mohjSavDdQYHRYXcS3uS6ttaHP8amyvX78")?,
+                Amount::Custom(1120),
+            )],
+            "Testing",
+            FeeRate::from_sat_per_vb(1.0),
+            None,
+            None,
+            None,
+        )?;
+
+        // Signer not derived from the seed
+        assert!(proposal
+            .approve_with_seed_signers(&seed_a, &[signer_b.clone()], NETWORK)
+            .is_err());
+
+        let approved_a2: ApprovedProposal =
+            proposal.approve_with_seed_signers(&seed_a, &[signer_a2], NETWORK)?;
+        let approved_b: ApprovedProposal =
+            proposal.approve_with_seed_signers(&seed_b, &[signer_b], NETWORK)?;
+
+        let completed_proposal: CompletedProposal =
+            proposal.finalize(vec![approved_a2, approved_b], NETWORK)?;
+
+        assert_eq!(completed_proposal.get_type(), ProposalType::Spending);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_policy_batch_spend() -> Result<()> {
+        let mnemonic_a: Mnemonic = Mnemonic::from_str(MNEMONIC_A)?;
+        let seed_a: Seed = Seed::from_mnemonic(mnemonic_a);
+        let desc_a: DescriptorPublicKey =
+            seed_a.to_descriptor(Purpose::BIP86, Some(7291640), false, NETWORK, &SECP256K1)?;
+
+        let address_a = Address::from_str("mohjSavDdQYHRYXcS3uS6ttaHP8amyvX78")?;
+        let address_b = Address::from_str("tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx")?;
+
+        let template = PolicyTemplate::singlesig(desc_a);
+        let policy: Policy = Policy::from_template("Name", "Description", template, NETWORK)?;
+        let descriptor: String = policy.as_descriptor().to_string();
+
+        let mut wallet = get_funded_wallet(&descriptor).unwrap();
+        let proposal: Proposal = policy.spend(
+            &mut wallet,
+            vec![
+                (address_a.clone(), Amount::Custom(1000)),
+                (address_b.clone(), Amount::Max),
+            ],
+            "Testing",
+            FeeRate::from_sat_per_vb(1.0),
+            None,
+            None,
+            None,
+        )?;
+
+        let recipients = proposal.recipients();
+        assert_eq!(recipients.len(), 2);
+        assert_eq!(recipients[0].address, address_a);
+        assert_eq!(recipients[0].amount, 1000);
+        assert_eq!(recipients[1].address, address_b);
+
+        // All the funds are drained
+        let psbt = proposal.psbt();
+        let fee: u64 = psbt.fee().unwrap().to_sat();
+        assert_eq!(psbt.unsigned_tx.output.len(), 2);
+        assert_eq!(recipients[1].amount, 371 + 3500 + 4149 - 1000 - fee);
+
+        // Only one recipient can send max
+        let res = policy.spend(
+            &mut wallet,
+            vec![(address_a, Amount::Max), (address_b, Amount::Max)],
+            "Batch",
+            FeeRate::from_sat_per_vb(1.0),
+            None,
+            None,
+            None,
+        );
+        assert!(matches!(res, Err(policy::Error::MultipleDrainRecipients)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_policy_bump_fee() -> Result<()> {
+        let mnemonic_a: Mnemonic = Mnemonic::from_str(MNEMONIC_A)?;
+        let seed_a: Seed = Seed::from_mnemonic(mnemonic_a);
+        let desc_a: DescriptorPublicKey =
+            seed_a.to_descriptor(Purpose::BIP86, Some(7291640), false, NETWORK, &SECP256K1)?;
+
+        let template = PolicyTemplate::singlesig(desc_a);
+        let policy: Policy = Policy::from_template("Name", "Description", template, NETWORK)?;
+        let descriptor: String = policy.as_descriptor().to_string();
+
+        let mut wallet = get_funded_wallet(&descriptor).unwrap();
+        let proposal: Proposal = policy.spend(
+            &mut wallet,
+            vec![(
+                Address::from_str("mohjSavDdQYHRYXcS3uS6ttaHP8amyvX78")?,
+                Amount::Custom(1120),
+            )],
+            "Testing",
+            FeeRate::from_sat_per_vb(1.0),
+            None,
+            None,
+            None,
+        )?;
+        let approved: ApprovedProposal = proposal.approve(&seed_a, Vec::new(), NETWORK)?;
+        let completed: CompletedProposal = proposal.finalize(vec![approved], NETWORK)?;
+        let tx: Transaction = completed.tx().unwrap();
+        let txid = tx.txid();
+        wallet
+            .insert_tx(tx, ConfirmationTime::Unconfirmed { last_seen: 0 })
+            .unwrap();
+
+        let proposal: Proposal = policy.bump_fee(
+            &mut wallet,
+            txid,
+            "Bump",
+            FeeRate::from_sat_per_vb(5.0),
+            None,
+        )?;
+        assert_eq!(proposal.get_type(), ProposalType::FeeBump);
+
+        let approved: ApprovedProposal = proposal.approve(&seed_a, Vec::new(), NETWORK)?;
+        let completed: CompletedProposal = proposal.finalize(vec![approved], NETWORK)?;
+        assert_eq!(completed.replaced_txid(), Some(txid));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_policy_cpfp() -> Result<()> {
+        let mnemonic_a: Mnemonic = Mnemonic::from_str(MNEMONIC_A)?;
+        let seed_a: Seed = Seed::from_mnemonic(mnemonic_a);
+        let desc_a: DescriptorPublicKey =
+            seed_a.to_descriptor(Purpose::BIP86, Some(7291640), false, NETWORK, &SECP256K1)?;
+
+        let template = PolicyTemplate::singlesig(desc_a);
+        let policy: Policy = Policy::from_template("Name", "Description", template, NETWORK)?;
+        let descriptor: String = policy.as_descriptor().to_string();
+
+        let mut wallet = get_funded_wallet(&descriptor).unwrap();
+
+        // Incoming payment with a low fee
+        let address = wallet.get_address(AddressIndex::New).address;
+        let parent = Transaction {
+            version: 2,
+            lock_time: absolute::LockTime::ZERO,
+            input: vec![TxIn::default()],
+            output: vec![TxOut {
+                value: 10_000,
+                script_pubkey: address.script_pubkey(),
+            }],
+        };
+        let parent_txid = parent.txid();
+        let parent_vsize: usize = parent.vsize();
+        wallet
+            .insert_tx(parent, ConfirmationTime::Unconfirmed { last_seen: 0 })
+            .unwrap();
+
+        let fee_rate = FeeRate::from_sat_per_vb(10.0);
+        let proposal: Proposal = policy.cpfp(
+            &mut wallet,
+            parent_txid,
+            50,
+            parent_vsize,
+            Address::from_str("mohjSavDdQYHRYXcS3uS6ttaHP8amyvX78")?,
+            "CPFP",
+            fee_rate,
+            None,
+        )?;
+        assert_eq!(proposal.get_type(), ProposalType::Spending);
+        assert_eq!(proposal.cpfp_parent(), Some(parent_txid));
+
+        let psbt = proposal.psbt();
+        assert!(psbt
+            .unsigned_tx
+            .input
+            .iter()
+            .all(|txin| txin.previous_output.txid == parent_txid));
+
+        // The child pays also the missing fee of the parent
+        let child_fee: u64 = psbt.fee().unwrap().to_sat();
+        let approved: ApprovedProposal = proposal.approve(&seed_a, Vec::new(), NETWORK)?;
+        let completed: CompletedProposal = proposal.finalize(vec![approved], NETWORK)?;
+        let child_vsize: usize = completed.tx().unwrap().vsize();
+        assert!(child_fee + 50 >= fee_rate.fee_vb(parent_vsize + child_vsize));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_policy_spend_skip_timelocked_utxos() -> Result<()> {
+        let mnemonic_a: Mnemonic = Mnemonic::from_str(MNEMONIC_A)?;
+        let seed_a: Seed = Seed::from_mnemonic(mnemonic_a);
+        let desc_a: DescriptorPublicKey =
+            seed_a.to_descriptor(Purpose::BIP86, Some(7291640), false, NETWORK, &SECP256K1)?;
+
+        let template = PolicyTemplate::hold(desc_a, Locktime::Older(Sequence(1000)));
+        let policy: Policy = Policy::from_template("Name", "Description", template, NETWORK)?;
+        let descriptor: String = policy.as_descriptor().to_string();
+        let mut path = BTreeMap::new();
+        path.insert(policy.spending_policy()?.id.clone(), vec![1]);
+
+        let mut wallet = get_funded_wallet(&descriptor).unwrap();
+        let proposal: Proposal = policy.spend(
+            &mut wallet,
+            vec![(
+                Address::from_str("mohjSavDdQYHRYXcS3uS6ttaHP8amyvX78")?,
+                Amount::Custom(3000),
+            )],
+            "Testing",
+            FeeRate::from_sat_per_vb(1.0),
+            None,
+            None,
+            Some(path.clone()),
+        )?;
+
+        // Only the last UTXO (confirmed 179 blocks ago) is still locked
+        let excluded = policy.timelocked_utxos(&wallet, Some(&path))?;
+        assert_eq!(excluded.len(), 1);
+        assert!(matches!(
+            excluded[0].reason,
+            ExclusionReason::RelativeTimelock { .. }
+        ));
+
+        assert!(!proposal
+            .psbt()
+            .unsigned_tx
+            .input
+            .iter()
+            .any(|txin| txin.previous_output == excluded[0].outpoint));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_policy_timelock_schedule() -> Result<()> {
+        let mnemonic_a: Mnemonic = Mnemonic::from_str(MNEMONIC_A)?;
+        let seed_a: Seed = Seed::from_mnemonic(mnemonic_a);
+        let desc_a: DescriptorPublicKey =
+            seed_a.to_descriptor(Purpose::BIP86, Some(7291640), false, NETWORK, &SECP256K1)?;
+
+        let template = PolicyTemplate::hold(desc_a, Locktime::Older(Sequence(1000)));
+        let policy: Policy = Policy::from_template("Name", "Description", template, NETWORK)?;
+        let descriptor: String = policy.as_descriptor().to_string();
+
+        let wallet = get_funded_wallet(&descriptor).unwrap();
+        let current_height: u32 = wallet.latest_checkpoint().height();
+        let utxos: Vec<_> = wallet.list_unspent().collect();
+
+        let schedule = policy.timelock_schedule(utxos.iter(), current_height)?;
+
+        // 3 UTXOs, 2 sub-paths each (unspendable key and hold path)
+        assert_eq!(schedule.len(), 6);
+
+        let locked: Vec<_> = schedule
+            .iter()
+            .filter(|s| !s.maturity.is_none())
+            .filter(|s| !s.maturity.is_mature(current_height, 0))
+            .collect();
+        assert_eq!(locked.len(), 1);
+        assert_eq!(locked[0].index, 1);
+        assert_eq!(locked[0].maturity.height, Some(2_520_271 + 1000));
+
+        // Refresh
+        let to_refresh = policy.utxos_to_refresh(utxos.iter(), current_height, 144)?;
+        assert_eq!(to_refresh.len(), 2);
+        let to_refresh = policy.utxos_to_refresh(utxos.iter(), current_height, 1000)?;
+        assert_eq!(to_refresh.len(), 3);
+
+        Ok(())
+    }
+
     #[test]
     #[cfg(feature = "reserves")]
     fn test_proof_of_reserve() -> Result<()> {
@@ -211,8 +608,237 @@ mod tests {
 
         assert_eq!(completed_proposal.get_type(), ProposalType::ProofOfReserve);
 
+        // Offline verification
+        let exported =
+            ExportedProof::from_json(completed_proposal.export_proof().unwrap()).unwrap();
+        let utxos: Vec<ProofUtxo> = wallet
+            .list_unspent()
+            .map(|utxo| ProofUtxo {
+                txid: utxo.outpoint.txid,
+                vout: utxo.outpoint.vout,
+                value: utxo.txout.value,
+                script_pubkey: utxo.txout.script_pubkey,
+            })
+            .collect();
+        let spendable = verify_exported_proof(
+            &exported,
+            policy.as_descriptor(),
+            "Testing proof of reserve",
+            utxos.clone(),
+            NETWORK,
+        )
+        .unwrap();
+        assert_eq!(spendable, 8_020);
+        assert!(matches!(
+            verify_exported_proof(
+                &exported,
+                policy.as_descriptor(),
+                "Wrong message",
+                utxos.clone(),
+                NETWORK,
+            ),
+            Err(ProofError::ChallengeInputMismatch)
+        ));
+        assert!(matches!(
+            verify_exported_proof(
+                &exported,
+                policy.as_descriptor(),
+                "Testing proof of reserve",
+                utxos[1..].to_vec(),
+                NETWORK,
+            ),
+            Err(ProofError::NonSpendableInput(..))
+        ));
+
+        // UTXO not belonging to the vault
+        let mut foreign_utxos: Vec<ProofUtxo> = utxos.clone();
+        let foreign = ProofUtxo {
+            txid: Txid::all_zeros(),
+            vout: 0,
+            value: 100_000,
+            script_pubkey: Address::from_str("mohjSavDdQYHRYXcS3uS6ttaHP8amyvX78")?
+                .assume_checked()
+                .script_pubkey(),
+        };
+        foreign_utxos.push(foreign.clone());
+        assert!(matches!(
+            verify_exported_proof(
+                &exported,
+                policy.as_descriptor(),
+                "Testing proof of reserve",
+                foreign_utxos,
+                NETWORK,
+            ),
+            Err(ProofError::ForeignUtxo(outpoint)) if outpoint == foreign.outpoint()
+        ));
+
+        if let CompletedProposal::ProofOfReserve {
+            message,
+            psbt,
+            height,
+            ..
+        } = completed_proposal
+        {
+            assert_eq!(height, Some(2_520_450));
+            let spendable = wallet.verify_proof(&psbt, &message, None).unwrap();
+            assert_eq!(
+                wallet
+                    .verify_proof_at_height(&psbt, message, height.unwrap())
+                    .unwrap(),
+                spendable
+            );
+        } else {
+            panic!("Unexpected proposal");
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "reserves")]
+    fn test_proof_of_reserve_with_unconfirmed_utxo() -> Result<()> {
+        let mnemonic_a: Mnemonic = Mnemonic::from_str(MNEMONIC_A)?;
+        let seed_a: Seed = Seed::from_mnemonic(mnemonic_a);
+        let desc_a: DescriptorPublicKey =
+            seed_a.to_descriptor(Purpose::BIP86, Some(7291640), false, NETWORK, &SECP256K1)?;
+
+        let template = PolicyTemplate::singlesig(desc_a);
+        let policy: Policy = Policy::from_template("Name", "Description", template, NETWORK)?;
+        let descriptor: String = policy.as_descriptor().to_string();
+
+        let mut wallet = get_funded_wallet(&descriptor).unwrap();
+
+        // Incoming payment still in the mempool
+        let address = wallet.get_address(AddressIndex::New).address;
+        let unconfirmed = Transaction {
+            version: 2,
+            lock_time: absolute::LockTime::ZERO,
+            input: vec![TxIn::default()],
+            output: vec![TxOut {
+                value: 10_000,
+                script_pubkey: address.script_pubkey(),
+            }],
+        };
+        let unconfirmed_txid = unconfirmed.txid();
+        wallet
+            .insert_tx(unconfirmed, ConfirmationTime::Unconfirmed { last_seen: 0 })
+            .unwrap();
+
+        let proposal: Proposal =
+            policy.proof_of_reserve(&mut wallet, "Testing proof of reserve")?;
+        assert!(proposal
+            .psbt()
+            .unsigned_tx
+            .input
+            .iter()
+            .all(|txin| txin.previous_output.txid != unconfirmed_txid));
+
+        let approved_a: ApprovedProposal = proposal.approve(&seed_a, Vec::new(), NETWORK)?;
+        let completed_proposal: CompletedProposal = proposal.finalize(vec![approved_a], NETWORK)?;
+
+        if let CompletedProposal::ProofOfReserve {
+            message,
+            psbt,
+            height,
+            ..
+        } = completed_proposal
+        {
+            assert_eq!(
+                wallet
+                    .verify_proof_at_height(&psbt, message, height.unwrap())
+                    .unwrap(),
+                8_020
+            );
+        } else {
+            panic!("Unexpected proposal");
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "reserves")]
+    fn test_aggregated_proof_of_reserve() -> Result<()> {
+        use keechain_core::bitcoin::OutPoint;
+        use keechain_core::miniscript::Descriptor;
+
+        let mnemonic_a: Mnemonic = Mnemonic::from_str(MNEMONIC_A)?;
+        let seed_a: Seed = Seed::from_mnemonic(mnemonic_a);
+        let mnemonic_b: Mnemonic = Mnemonic::from_str(MNEMONIC_B)?;
+        let seed_b: Seed = Seed::from_mnemonic(mnemonic_b);
+
+        // Two vaults with different accounts
+        let mut vaults: Vec<(Policy, Wallet)> = Vec::new();
+        for account in [7291640, 7291641] {
+            let desc_a: DescriptorPublicKey =
+                seed_a.to_descriptor(Purpose::BIP86, Some(account), false, NETWORK, &SECP256K1)?;
+            let desc_b: DescriptorPublicKey =
+                seed_b.to_descriptor(Purpose::BIP86, Some(account), false, NETWORK, &SECP256K1)?;
+            let template = PolicyTemplate::multisig(2, vec![desc_a, desc_b]);
+            let policy: Policy = Policy::from_template("Name", "Description", template, NETWORK)?;
+            let wallet = get_funded_wallet(&policy.as_descriptor().to_string()).unwrap();
+            vaults.push((policy, wallet));
+        }
+
+        let message = "Testing aggregated proof of reserve";
+        let mut proofs = Vec::new();
+        for (policy, wallet) in vaults.iter_mut() {
+            proofs.push(policy.proof_of_reserve(wallet, message)?.psbt());
+        }
+        let psbt = merge_proofs(proofs).unwrap();
+        assert_eq!(psbt.unsigned_tx.input.len(), 1 + 3 * 2);
+
+        let descriptors: Vec<Descriptor<String>> = vaults
+            .iter()
+            .map(|(policy, _)| policy.as_descriptor().clone())
+            .collect();
+        let proposals: Vec<Proposal> = descriptors
+            .iter()
+            .map(|descriptor| {
+                let others = descriptors
+                    .iter()
+                    .filter(|d| *d != descriptor)
+                    .cloned()
+                    .collect();
+                Proposal::aggregated_proof_of_reserve(
+                    descriptor.clone(),
+                    others,
+                    message,
+                    psbt.clone(),
+                    2_520_450,
+                )
+            })
+            .collect();
+
+        // The members of every vault approve their own proposal
+        let mut approvals: Vec<ApprovedProposal> = Vec::new();
+        for proposal in proposals.iter() {
+            approvals.push(proposal.approve(&seed_a, Vec::new(), NETWORK)?);
+            approvals.push(proposal.approve(&seed_b, Vec::new(), NETWORK)?);
+        }
+
+        // The approvals of a single vault are not enough
+        assert!(proposals[0]
+            .finalize(approvals[..2].to_vec(), NETWORK)
+            .is_err());
+
+        let completed_proposal: CompletedProposal = proposals[0].finalize(approvals, NETWORK)?;
         if let CompletedProposal::ProofOfReserve { message, psbt, .. } = completed_proposal {
-            wallet.verify_proof(&psbt, message, None).unwrap();
+            let outpoints: BTreeMap<usize, Vec<(OutPoint, TxOut)>> = vaults
+                .iter()
+                .enumerate()
+                .map(|(index, (_, wallet))| {
+                    let outpoints = wallet
+                        .list_unspent()
+                        .map(|utxo| (utxo.outpoint, utxo.txout))
+                        .collect();
+                    (index, outpoints)
+                })
+                .collect();
+            let amounts = verify_aggregated_proof(&psbt, message, outpoints, NETWORK).unwrap();
+            assert_eq!(amounts.total, 16_040);
+            assert_eq!(amounts.vaults.get(&0), Some(&8_020));
+            assert_eq!(amounts.vaults.get(&1), Some(&8_020));
         } else {
             panic!("Unexpected proposal");
         }
@@ -251,8 +877,10 @@ mod tests {
         let mut wallet = get_funded_wallet(&descriptor).unwrap();
         let proposal: Proposal = policy.spend(
             &mut wallet,
-            Address::from_str("mohjSavDdQYHRYXcS3uS6ttaHP8amyvX78")?,
-            Amount::Custom(1120),
+            vec![(
+                Address::from_str("mohjSavDdQYHRYXcS3uS6ttaHP8amyvX78")?,
+                Amount::Custom(1120),
+            )],
             "Testing",
             FeeRate::from_sat_per_vb(1.0),
             None,
@@ -286,8 +914,10 @@ mod tests {
         let proposal: Proposal = policy
             .spend(
                 &mut wallet,
-                Address::from_str("mohjSavDdQYHRYXcS3uS6ttaHP8amyvX78").unwrap(),
-                Amount::Custom(2000),
+                vec![(
+                    Address::from_str("mohjSavDdQYHRYXcS3uS6ttaHP8amyvX78").unwrap(),
+                    Amount::Custom(2000),
+                )],
                 "Testing",
                 FeeRate::from_sat_per_vb(1.0),
                 None,
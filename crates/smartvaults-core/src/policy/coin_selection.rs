@@ -0,0 +1,190 @@
+// Copyright (c) 2022-2024 Smart Vaults
+// Distributed under the MIT software license
+
+//! Timelock-aware coin selection
+
+use core::fmt;
+
+use bdk::chain::ConfirmationTime;
+use bdk::wallet::coin_selection::{
+    CoinSelectionAlgorithm, CoinSelectionResult, DefaultCoinSelectionAlgorithm,
+    Error as CoinSelectionError,
+};
+use bdk::{FeeRate, LocalOutput, Utxo, WeightedUtxo};
+use keechain_core::bitcoin::absolute::{self, Height, Time};
+use keechain_core::bitcoin::{relative, OutPoint, Script, Sequence};
+
+/// Why a UTXO can't be spent (yet) with the selected policy path
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ExclusionReason {
+    /// UTXO not confirmed: relative timelock can't be satisfied
+    Unconfirmed { older: Sequence },
+    /// Relative timelock (`older`) not satisfied yet
+    RelativeTimelock { older: Sequence },
+    /// Absolute timelock (`after`) not satisfied yet
+    AbsoluteTimelock { after: absolute::LockTime },
+}
+
+impl fmt::Display for ExclusionReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unconfirmed { older } => {
+                write!(f, "unconfirmed (relative timelock of {})", older)
+            }
+            Self::RelativeTimelock { older } => {
+                write!(f, "relative timelock of {} not satisfied", older)
+            }
+            Self::AbsoluteTimelock { after } => {
+                write!(f, "absolute timelock {} not satisfied", after)
+            }
+        }
+    }
+}
+
+/// UTXO excluded from the coin selection
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ExcludedUtxo {
+    pub outpoint: OutPoint,
+    pub reason: ExclusionReason,
+}
+
+impl fmt::Display for ExcludedUtxo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.outpoint, self.reason)
+    }
+}
+
+/// Coin selection that skip the UTXOs that can't satisfy the timelocks of the selected policy path
+///
+/// The optional UTXOs are filtered and the remaining ones are passed to the inner algorithm.
+/// Required UTXOs (manually selected) are never touched.
+#[derive(Debug, Clone, Default)]
+pub struct TimelockCoinSelection<Cs = DefaultCoinSelectionAlgorithm> {
+    older: Option<Sequence>,
+    after: Option<absolute::LockTime>,
+    current_height: u32,
+    timestamp: u64,
+    inner: Cs,
+}
+
+impl TimelockCoinSelection {
+    pub fn new(
+        older: Option<Sequence>,
+        after: Option<absolute::LockTime>,
+        current_height: u32,
+        timestamp: u64,
+    ) -> Self {
+        Self {
+            older,
+            after,
+            current_height,
+            timestamp,
+            inner: DefaultCoinSelectionAlgorithm::default(),
+        }
+    }
+}
+
+impl<Cs> TimelockCoinSelection<Cs> {
+    /// Replace the inner coin selection algorithm
+    pub fn with_inner<T>(self, inner: T) -> TimelockCoinSelection<T> {
+        TimelockCoinSelection {
+            older: self.older,
+            after: self.after,
+            current_height: self.current_height,
+            timestamp: self.timestamp,
+            inner,
+        }
+    }
+
+    /// Check if the absolute timelock of the policy path is satisfied
+    fn is_after_satisfied(&self) -> Option<ExclusionReason> {
+        let after = self.after?;
+        let height = Height::from_consensus(self.current_height).ok()?;
+        let time = Time::from_consensus(self.timestamp as u32).ok()?;
+        if after.is_satisfied_by(height, time) {
+            None
+        } else {
+            Some(ExclusionReason::AbsoluteTimelock { after })
+        }
+    }
+
+    /// Check if the [`LocalOutput`] can be spent and, if not, return the reason
+    pub fn exclusion_reason(&self, utxo: &LocalOutput) -> Option<ExclusionReason> {
+        if let Some(reason) = self.is_after_satisfied() {
+            return Some(reason);
+        }
+
+        let older: Sequence = self.older?;
+        match utxo.confirmation_time {
+            ConfirmationTime::Confirmed { height, time } => {
+                let mature: bool = match older.to_relative_lock_time() {
+                    Some(relative::LockTime::Blocks(blocks)) => {
+                        self.current_height.saturating_sub(height) >= blocks.value() as u32
+                    }
+                    Some(relative::LockTime::Time(interval)) => {
+                        self.timestamp >= time + interval.value() as u64 * 512
+                    }
+                    None => true,
+                };
+
+                if mature {
+                    None
+                } else {
+                    Some(ExclusionReason::RelativeTimelock { older })
+                }
+            }
+            ConfirmationTime::Unconfirmed { .. } => {
+                if older.is_relative_lock_time() {
+                    Some(ExclusionReason::Unconfirmed { older })
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    /// Get the UTXOs that will be excluded from the coin selection
+    pub fn excluded_utxos<'a, I>(&self, utxos: I) -> Vec<ExcludedUtxo>
+    where
+        I: IntoIterator<Item = &'a LocalOutput>,
+    {
+        utxos
+            .into_iter()
+            .filter_map(|utxo| {
+                self.exclusion_reason(utxo).map(|reason| ExcludedUtxo {
+                    outpoint: utxo.outpoint,
+                    reason,
+                })
+            })
+            .collect()
+    }
+}
+
+impl<Cs> CoinSelectionAlgorithm for TimelockCoinSelection<Cs>
+where
+    Cs: CoinSelectionAlgorithm,
+{
+    fn coin_select(
+        &self,
+        required_utxos: Vec<WeightedUtxo>,
+        optional_utxos: Vec<WeightedUtxo>,
+        fee_rate: FeeRate,
+        target_amount: u64,
+        drain_script: &Script,
+    ) -> Result<CoinSelectionResult, CoinSelectionError> {
+        let optional_utxos: Vec<WeightedUtxo> = optional_utxos
+            .into_iter()
+            .filter(|weighted| match &weighted.utxo {
+                Utxo::Local(utxo) => self.exclusion_reason(utxo).is_none(),
+                Utxo::Foreign { .. } => true,
+            })
+            .collect();
+        self.inner.coin_select(
+            required_utxos,
+            optional_utxos,
+            fee_rate,
+            target_amount,
+            drain_script,
+        )
+    }
+}
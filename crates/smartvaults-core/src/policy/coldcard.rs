@@ -0,0 +1,116 @@
+// Copyright (c) 2022-2024 Smart Vaults
+// Distributed under the MIT software license
+
+//! Coldcard wallet registration
+
+use core::fmt::Write;
+use core::str::FromStr;
+
+use keechain_core::miniscript::descriptor::{DescriptorPublicKey, ShInner, WshInner};
+use keechain_core::miniscript::Descriptor;
+
+use super::{Error, Policy};
+
+/// Max length of the name of a Coldcard multisig wallet
+const COLDCARD_MAX_NAME_LEN: usize = 20;
+
+impl Policy {
+    /// Coldcard wallet registration file
+    ///
+    /// `(sh-)wsh(sortedmulti(..))` vaults are exported as Coldcard multisig setup file,
+    /// any other vault as descriptor (Coldcard miniscript import).
+    pub fn coldcard_registration(&self) -> Result<String, Error> {
+        let descriptor: Descriptor<DescriptorPublicKey> =
+            Descriptor::from_str(&self.descriptor.to_string())?;
+
+        let multisig = match &descriptor {
+            Descriptor::Wsh(wsh) => match wsh.as_inner() {
+                WshInner::SortedMulti(smv) => Some(("P2WSH", smv.k, &smv.pks)),
+                _ => None,
+            },
+            Descriptor::Sh(sh) => match sh.as_inner() {
+                ShInner::Wsh(wsh) => match wsh.as_inner() {
+                    WshInner::SortedMulti(smv) => Some(("P2SH-P2WSH", smv.k, &smv.pks)),
+                    _ => None,
+                },
+                _ => None,
+            },
+            _ => None,
+        };
+
+        if let Some((format, threshold, keys)) = multisig {
+            let mut cosigners: Vec<String> = Vec::with_capacity(keys.len());
+            for key in keys.iter() {
+                match key {
+                    DescriptorPublicKey::XPub(xkey) => match &xkey.origin {
+                        Some((fingerprint, path)) => cosigners.push(format!(
+                            "Derivation: {path}\n{}: {}\n",
+                            fingerprint.to_string().to_uppercase(),
+                            xkey.xkey
+                        )),
+                        None => return Ok(descriptor.to_string()),
+                    },
+                    _ => return Ok(descriptor.to_string()),
+                }
+            }
+
+            let name: String = self
+                .name
+                .chars()
+                .filter(|c| c.is_ascii_graphic() || *c == ' ')
+                .take(COLDCARD_MAX_NAME_LEN)
+                .collect();
+            let name: &str = name.trim();
+
+            let mut file =
+                String::from("# Coldcard Multisig setup file (exported from Smart Vaults)\n#\n");
+            let _ = writeln!(
+                file,
+                "Name: {}",
+                if name.is_empty() {
+                    "Smart Vaults"
+                } else {
+                    name
+                }
+            );
+            let _ = writeln!(file, "Policy: {threshold} of {}", keys.len());
+            let _ = writeln!(file, "Format: {format}\n");
+            file.push_str(&cosigners.join("\n"));
+            return Ok(file);
+        }
+
+        Ok(descriptor.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use keechain_core::bitcoin::Network;
+
+    use super::*;
+
+    #[test]
+    fn test_coldcard_registration() {
+        let descriptor = "wsh(sortedmulti(2,[7356e457/48'/1'/0'/2']tpubDCvLwbJPseNux9EtPbrbA2tgDayzptK4HNkky14Cw6msjHuqyZCE88miedZD86TZUb29Rof3sgtREU4wtzofte7QDSWDiw8ZU6ZYHmAxY9d/0/*,[4eb5d5a1/48'/1'/0'/2']tpubDCLskGdzStPPo1auRQygJUfbmLMwujWr7fmekdUMD7gqSpwEcRso4CfiP5GkRqfXFYkfqTujyvuehb7inymMhBJFdbJqFyHsHVRuwLKCSe9/0/*))";
+        let policy = Policy::from_descriptor(
+            "Family savings vault 2024",
+            "",
+            descriptor,
+            Network::Testnet,
+        )
+        .unwrap();
+        let file = policy.coldcard_registration().unwrap();
+        assert!(file.contains("Name: Family savings vault\n"));
+        assert!(file.contains("Policy: 2 of 2\n"));
+        assert!(file.contains("Format: P2WSH\n"));
+        assert!(file.contains("Derivation: m/48'/1'/0'/2'\n7356E457: tpubDCvLwbJPseNux9EtPbrbA2tgDayzptK4HNkky14Cw6msjHuqyZCE88miedZD86TZUb29Rof3sgtREU4wtzofte7QDSWDiw8ZU6ZYHmAxY9d\n"));
+        assert!(file.contains("4EB5D5A1: tpubDCLskGdzStPPo1auRQygJUfbmLMwujWr7fmekdUMD7gqSpwEcRso4CfiP5GkRqfXFYkfqTujyvuehb7inymMhBJFdbJqFyHsHVRuwLKCSe9\n"));
+
+        let descriptor = "tr([9bf4354b/86'/1'/784923']tpubDCT8uwnkZj7woaY71Xr5hU7Wvjr7B1BXJEpwMzzDLd1H6HLnKTiaLPtt6ZfEizDMwdQ8PT8JCmKbB4ESVXTkCzv51oxhJhX5FLBvkeN9nJ3/0/*,pk([7356e457/86'/1'/784923']tpubDCvLwbJPseNux9EtPbrbA2tgDayzptK4HNkky14Cw6msjHuqyZCE88miedZD86TZUb29Rof3sgtREU4wtzofte7QDSWDiw8ZU6ZYHmAxY9d/0/*))";
+        let policy = Policy::from_descriptor("Vault", "", descriptor, Network::Testnet).unwrap();
+        assert!(policy
+            .coldcard_registration()
+            .unwrap()
+            .starts_with(descriptor));
+    }
+}
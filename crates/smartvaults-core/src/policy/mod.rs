@@ -1,13 +1,13 @@
 // Copyright (c) 2022-2024 Smart Vaults
 // Distributed under the MIT software license
 
-use core::cmp::Ordering;
+use core::cmp::{self, Ordering};
 use core::hash::{Hash, Hasher};
 use core::str::FromStr;
 use std::collections::{BTreeMap, HashMap, HashSet};
 
 use bdk::chain::{ConfirmationTime, PersistBackend};
-use bdk::descriptor::policy::{BuildSatisfaction, PkOrF, SatisfiableItem};
+use bdk::descriptor::policy::{BuildSatisfaction, Condition, PkOrF, SatisfiableItem};
 use bdk::descriptor::{ExtractPolicy, IntoWalletDescriptor, Policy as SpendingPolicy};
 use bdk::signer::SignersContainer;
 use bdk::wallet::tx_builder::AddUtxoError;
@@ -16,9 +16,9 @@ use bdk::{FeeRate, KeychainKind, LocalOutput, Wallet};
 use keechain_core::bitcoin::absolute::{self, Height, Time};
 use keechain_core::bitcoin::address::NetworkUnchecked;
 use keechain_core::bitcoin::bip32::Fingerprint;
-#[cfg(feature = "reserves")]
 use keechain_core::bitcoin::psbt::PartiallySignedTransaction;
-use keechain_core::bitcoin::{Address, Network, OutPoint};
+use keechain_core::bitcoin::relative::LockTime as RelativeLockTime;
+use keechain_core::bitcoin::{Address, Network, OutPoint, ScriptBuf, Transaction, Txid};
 use keechain_core::miniscript::descriptor::DescriptorType;
 use keechain_core::miniscript::policy::Concrete;
 use keechain_core::miniscript::Descriptor;
@@ -26,14 +26,18 @@ use keechain_core::secp256k1::XOnlyPublicKey;
 use keechain_core::util::time;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
+pub mod coin_selection;
+mod coldcard;
 pub mod template;
+pub mod timelock;
 
+pub use self::coin_selection::{ExcludedUtxo, ExclusionReason, TimelockCoinSelection};
 use self::template::PolicyTemplateResult;
 pub use self::template::{
     AbsoluteLockTime, DecayingTime, Locktime, PolicyTemplate, PolicyTemplateType, RecoveryTemplate,
     Sequence,
 };
-use crate::proposal::Proposal;
+use crate::proposal::{Proposal, Recipient};
 #[cfg(feature = "reserves")]
 use crate::reserves::ProofOfReserves;
 use crate::util::{search_network_for_descriptor, Unspendable};
@@ -45,9 +49,13 @@ pub enum Error {
     BdkAddUtxo(#[from] AddUtxoError),
     #[error("{0}")]
     BdkCreateTx(String),
+    #[error("{0}")]
+    BdkFeeBump(String),
     #[error(transparent)]
     BdkDescriptor(#[from] bdk::descriptor::DescriptorError),
     #[error(transparent)]
+    BdkPolicy(#[from] bdk::descriptor::policy::PolicyError),
+    #[error(transparent)]
     Miniscript(#[from] keechain_core::miniscript::Error),
     #[error(transparent)]
     AbsoluteTimelock(#[from] absolute::Error),
@@ -64,8 +72,8 @@ pub enum Error {
     Template(#[from] template::Error),
     #[error("{0}, {1}")]
     DescOrPolicy(Box<Self>, Box<Self>),
-    #[error("must be a taproot descriptor")]
-    NotTaprootDescriptor,
+    #[error("unsupported descriptor: must be a taproot or (sh-)wsh descriptor")]
+    UnsupportedDescriptor,
     #[error("spending policy not found")]
     SpendingPolicyNotFound,
     #[error("no utxos selected")]
@@ -76,6 +84,12 @@ pub enum Error {
     AbsoluteTimelockNotSatisfied,
     #[error("Relative timelock not satisfied")]
     RelativeTimelockNotSatisfied,
+    #[error("no recipients")]
+    NoRecipients,
+    #[error("only one recipient can send max")]
+    MultipleDrainRecipients,
+    #[error("No UTXOs available: all UTXOs are timelocked")]
+    TimelockedUtxos(Vec<ExcludedUtxo>),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
@@ -191,7 +205,12 @@ impl Policy {
     where
         S: Into<String>,
     {
-        if let DescriptorType::Tr = descriptor.desc_type() {
+        if let DescriptorType::Tr
+        | DescriptorType::Wsh
+        | DescriptorType::ShWsh
+        | DescriptorType::WshSortedMulti
+        | DescriptorType::ShWshSortedMulti = descriptor.desc_type()
+        {
             // Check if descriptor match network
             let desc: String = descriptor.to_string();
             let (descriptor_public_key, keymap) =
@@ -214,7 +233,7 @@ impl Policy {
                 network,
             })
         } else {
-            Err(Error::NotTaprootDescriptor)
+            Err(Error::UnsupportedDescriptor)
         }
     }
 
@@ -291,6 +310,20 @@ impl Policy {
         }
     }
 
+    /// Build [`Policy`] from [`PolicyTemplate`] using a P2WSH descriptor
+    pub fn from_template_wsh<S>(
+        name: S,
+        description: S,
+        template: PolicyTemplate,
+        network: Network,
+    ) -> Result<Self, Error>
+    where
+        S: Into<String>,
+    {
+        let descriptor = template.build_wsh()?;
+        Self::from_descriptor(name, description, descriptor.to_string(), network)
+    }
+
     pub fn name(&self) -> String {
         self.name.clone()
     }
@@ -299,6 +332,20 @@ impl Policy {
         self.description.clone()
     }
 
+    pub fn set_name<S>(&mut self, name: S)
+    where
+        S: Into<String>,
+    {
+        self.name = name.into();
+    }
+
+    pub fn set_description<S>(&mut self, description: S)
+    where
+        S: Into<String>,
+    {
+        self.description = description.into();
+    }
+
     pub fn descriptor(&self) -> Descriptor<String> {
         self.descriptor.clone()
     }
@@ -377,6 +424,245 @@ impl Policy {
         }
     }
 
+    /// Check if the [Policy] can still be satisfied without the keys of the passed [Fingerprint]s
+    ///
+    /// Timelocks and hash preimages are considered satisfiable.
+    pub fn satisfiable_without<'a, I>(&self, excluded: I) -> Result<bool, Error>
+    where
+        I: IntoIterator<Item = &'a Fingerprint>,
+    {
+        fn check(item: &SatisfiableItem, excluded: &HashSet<&Fingerprint>) -> bool {
+            match item {
+                SatisfiableItem::EcdsaSignature(key) | SatisfiableItem::SchnorrSignature(key) => {
+                    !is_excluded(key, excluded)
+                }
+                SatisfiableItem::Multisig { keys, threshold } => {
+                    keys.iter().filter(|k| !is_excluded(k, excluded)).count() >= *threshold
+                }
+                SatisfiableItem::Thresh { items, threshold } => {
+                    items.iter().filter(|x| check(&x.item, excluded)).count() >= *threshold
+                }
+                _ => true,
+            }
+        }
+
+        fn is_excluded(key: &PkOrF, excluded: &HashSet<&Fingerprint>) -> bool {
+            match key {
+                PkOrF::Fingerprint(f) => excluded.contains(f),
+                _ => false,
+            }
+        }
+
+        let excluded: HashSet<&Fingerprint> = excluded.into_iter().collect();
+        let item: &SatisfiableItem = self.satisfiable_item()?;
+        Ok(check(item, &excluded))
+    }
+
+    /// Check if the [Policy] can still be satisfied, on the passed `policy_path`, without the keys
+    /// of the passed [Fingerprint]s
+    ///
+    /// Only the sub-paths selected in the `policy_path` are evaluated for the [`SelectableCondition`]s
+    /// (all of them if the condition is not selected). Timelocks are satisfiable only if met by the
+    /// `tx` lock time and input sequences, hash preimages always are.
+    pub fn satisfiable_on_path_without<'a, I>(
+        &self,
+        excluded: I,
+        policy_path: Option<&BTreeMap<String, Vec<usize>>>,
+        tx: &Transaction,
+    ) -> Result<bool, Error>
+    where
+        I: IntoIterator<Item = &'a Fingerprint>,
+    {
+        struct Ctx<'a> {
+            excluded: HashSet<&'a Fingerprint>,
+            selected: HashMap<String, Vec<usize>>,
+            tx: &'a Transaction,
+        }
+
+        fn check(item: &SatisfiableItem, ctx: &Ctx) -> bool {
+            match item {
+                SatisfiableItem::EcdsaSignature(key) | SatisfiableItem::SchnorrSignature(key) => {
+                    !is_excluded(key, ctx)
+                }
+                SatisfiableItem::Multisig { keys, threshold } => {
+                    keys.iter().filter(|k| !is_excluded(k, ctx)).count() >= *threshold
+                }
+                SatisfiableItem::Thresh { items, threshold } => {
+                    let selected: Option<&Vec<usize>> = ctx.selected.get(&item.id());
+                    items
+                        .iter()
+                        .enumerate()
+                        .filter(|(index, ..)| selected.map_or(true, |s| s.contains(index)))
+                        .filter(|(_, x)| check(&x.item, ctx))
+                        .count()
+                        >= *threshold
+                }
+                SatisfiableItem::AbsoluteTimelock { value } => {
+                    value.is_implied_by(ctx.tx.lock_time)
+                }
+                SatisfiableItem::RelativeTimelock { value } => {
+                    match value.to_relative_lock_time() {
+                        Some(value) => {
+                            ctx.tx.version >= 2
+                                && ctx.tx.input.iter().all(|txin| {
+                                    txin.sequence
+                                        .to_relative_lock_time()
+                                        .map_or(false, |lock| value.is_implied_by(lock))
+                                })
+                        }
+                        None => false,
+                    }
+                }
+                _ => true,
+            }
+        }
+
+        fn is_excluded(key: &PkOrF, ctx: &Ctx) -> bool {
+            match key {
+                PkOrF::Fingerprint(f) => ctx.excluded.contains(f),
+                _ => false,
+            }
+        }
+
+        // Selected sub-paths of the selectable conditions
+        let mut selected: HashMap<String, Vec<usize>> = HashMap::new();
+        if let (Some(conditions), Some(policy_path)) = (self.selectable_conditions()?, policy_path)
+        {
+            for SelectableCondition { path, .. } in conditions.into_iter() {
+                if let Some(indexes) = policy_path.get(&path) {
+                    selected.insert(path, indexes.clone());
+                }
+            }
+        }
+
+        let ctx = Ctx {
+            excluded: excluded.into_iter().collect(),
+            selected,
+            tx,
+        };
+        let item: &SatisfiableItem = self.satisfiable_item()?;
+        Ok(check(item, &ctx))
+    }
+
+    /// Get the timelock maturity of every UTXO for every [`SelectableCondition`] sub-path
+    ///
+    /// If the policy has timelocks but not selectable conditions, the root item is used.
+    pub fn timelock_schedule<'a, I>(
+        &self,
+        utxos: I,
+        current_height: u32,
+    ) -> Result<Vec<UtxoTimelock>, Error>
+    where
+        I: IntoIterator<Item = &'a LocalOutput>,
+    {
+        fn find_by_id<'a>(item: &'a SatisfiableItem, id: &str) -> Option<&'a SatisfiableItem> {
+            if item.id() == id {
+                return Some(item);
+            }
+
+            if let SatisfiableItem::Thresh { items, .. } = item {
+                for x in items.iter() {
+                    if let Some(i) = find_by_id(&x.item, id) {
+                        return Some(i);
+                    }
+                }
+            }
+
+            None
+        }
+
+        let conditions: Vec<SelectableCondition> = match self.selectable_conditions()? {
+            Some(conditions) => conditions,
+            None => return Ok(Vec::new()),
+        };
+
+        let root: &SatisfiableItem = self.satisfiable_item()?;
+
+        // (path, index, sub-path, after, older)
+        let mut paths = Vec::new();
+        if conditions.is_empty() {
+            let (mut after, mut older) = (Vec::new(), Vec::new());
+            timelock::collect_timelocks(root, &mut after, &mut older);
+            paths.push((root.id(), 0, root.id(), after, older));
+        } else {
+            for SelectableCondition {
+                path, sub_paths, ..
+            } in conditions.into_iter()
+            {
+                for (index, sub_path) in sub_paths.into_iter().enumerate() {
+                    let (mut after, mut older) = (Vec::new(), Vec::new());
+                    if let Some(item) = find_by_id(root, &sub_path) {
+                        timelock::collect_timelocks(item, &mut after, &mut older);
+                    }
+                    paths.push((path.clone(), index, sub_path, after, older));
+                }
+            }
+        }
+
+        let timestamp: u64 = time::timestamp();
+        let mut schedule = Vec::new();
+        for utxo in utxos.into_iter() {
+            for (path, index, sub_path, after, older) in paths.iter() {
+                schedule.push(UtxoTimelock {
+                    outpoint: utxo.outpoint,
+                    path: path.clone(),
+                    index: *index,
+                    sub_path: sub_path.clone(),
+                    maturity: timelock::maturity(utxo, after, older, current_height, timestamp),
+                });
+            }
+        }
+
+        Ok(schedule)
+    }
+
+    /// Get the UTXOs that are within `threshold` blocks of reaching a relative timelock (`older`)
+    ///
+    /// The earliest (height-based) relative timelock of the policy is used, since it's the first one
+    /// that weaken the vault security. UTXOs that already reached it are included too.
+    pub fn utxos_to_refresh<'a, I>(
+        &self,
+        utxos: I,
+        current_height: u32,
+        threshold: u32,
+    ) -> Result<Vec<OutPoint>, Error>
+    where
+        I: IntoIterator<Item = &'a LocalOutput>,
+    {
+        if !self.has_relative_timelock() {
+            return Ok(Vec::new());
+        }
+
+        let (mut after, mut older) = (Vec::new(), Vec::new());
+        timelock::collect_timelocks(self.satisfiable_item()?, &mut after, &mut older);
+
+        let blocks: Option<u32> = older
+            .into_iter()
+            .filter_map(|sequence| match sequence.to_relative_lock_time() {
+                Some(RelativeLockTime::Blocks(blocks)) => Some(blocks.value() as u32),
+                _ => None,
+            })
+            .min();
+
+        match blocks {
+            Some(blocks) => Ok(utxos
+                .into_iter()
+                .filter_map(|utxo| match utxo.confirmation_time {
+                    ConfirmationTime::Confirmed { height, .. } => {
+                        let unlock_height: u32 = height.saturating_add(blocks);
+                        if current_height.saturating_add(threshold) >= unlock_height {
+                            Some(utxo.outpoint)
+                        } else {
+                            None
+                        }
+                    }
+                    ConfirmationTime::Unconfirmed { .. } => None,
+                })
+                .collect()),
+            None => Ok(Vec::new()),
+        }
+    }
+
     /// Get [SatisfiableItem] by policy path
     fn satisfiable_item_by_path<S>(&self, path: S) -> Result<Option<SatisfiableItem>, Error>
     where
@@ -614,7 +900,13 @@ impl Policy {
 
     /// Check if [`Policy`] match any [`PolicyTemplateType`]
     pub fn template_match(&self) -> Result<Option<PolicyTemplateType>, Error> {
-        match self.satisfiable_item()? {
+        let item: &SatisfiableItem = self.satisfiable_item()?;
+
+        if !matches!(self.descriptor.desc_type(), DescriptorType::Tr) {
+            return Ok(segwit_v0_template_match(item));
+        }
+
+        match item {
             SatisfiableItem::SchnorrSignature(..) => {
                 return Ok(Some(PolicyTemplateType::Singlesig))
             }
@@ -699,6 +991,44 @@ impl Policy {
         Ok(None)
     }
 
+    /// Build the [`TimelockCoinSelection`] for the passed `policy_path`
+    ///
+    /// Return an error if the `policy_path` is not valid for the policy.
+    pub fn timelock_coin_selection(
+        &self,
+        policy_path: Option<&BTreeMap<String, Vec<usize>>>,
+        current_height: u32,
+        timestamp: u64,
+    ) -> Result<TimelockCoinSelection, Error> {
+        let condition: Condition = match &self.spending_policy {
+            Some(spending_policy) => {
+                spending_policy.get_condition(policy_path.unwrap_or(&BTreeMap::new()))?
+            }
+            None => Condition::default(),
+        };
+        Ok(TimelockCoinSelection::new(
+            condition.csv,
+            condition.timelock,
+            current_height,
+            timestamp,
+        ))
+    }
+
+    /// Get the UTXOs that can't be spent (yet) with the passed `policy_path`, and why
+    pub fn timelocked_utxos<D>(
+        &self,
+        wallet: &Wallet<D>,
+        policy_path: Option<&BTreeMap<String, Vec<usize>>>,
+    ) -> Result<Vec<ExcludedUtxo>, Error>
+    where
+        D: PersistBackend<ChangeSet>,
+    {
+        let current_height: u32 = wallet.latest_checkpoint().height();
+        let coin_selection =
+            self.timelock_coin_selection(policy_path, current_height, time::timestamp())?;
+        Ok(coin_selection.excluded_utxos(wallet.list_unspent().collect::<Vec<_>>().iter()))
+    }
+
     /// Estimate TX vsize
     ///
     /// Useful to estimate TX fees
@@ -717,8 +1047,7 @@ impl Policy {
         let proposal = self
             .spend(
                 wallet,
-                address,
-                amount,
+                vec![(address, amount)],
                 "",
                 FeeRate::default_min_relay_fee(),
                 utxos,
@@ -726,15 +1055,16 @@ impl Policy {
                 policy_path,
             )
             .ok()?;
-        let psbt = proposal.psbt();
-        Some(psbt.unsigned_tx.vsize())
+        estimate_signed_vsize(wallet, &proposal.psbt()).ok()
     }
 
+    /// Build a spending [`Proposal`]
+    ///
+    /// Each recipient is an `(address, amount)` pair. Only one recipient can use [`Amount::Max`] (drain output).
     pub fn spend<D, S>(
         &self,
         wallet: &mut Wallet<D>,
-        address: Address<NetworkUnchecked>,
-        amount: Amount,
+        recipients: Vec<(Address<NetworkUnchecked>, Amount)>,
         description: S,
         fee_rate: FeeRate,
         utxos: Option<Vec<OutPoint>>,
@@ -745,6 +1075,20 @@ impl Policy {
         D: PersistBackend<ChangeSet>,
         S: Into<String>,
     {
+        // Check recipients
+        if recipients.is_empty() {
+            return Err(Error::NoRecipients);
+        }
+
+        let drain_count: usize = recipients
+            .iter()
+            .filter(|(_, amount)| matches!(amount, Amount::Max))
+            .count();
+        if drain_count > 1 {
+            return Err(Error::MultipleDrainRecipients);
+        }
+        let drain: bool = drain_count == 1;
+
         let wallet_utxos: HashMap<OutPoint, LocalOutput> = wallet
             .list_unspent()
             .map(|utxo| (utxo.outpoint, utxo))
@@ -775,9 +1119,34 @@ impl Policy {
             }
         }
 
+        // Exclude UTXOs that can't satisfy the timelocks of the policy path
+        let coin_selection: TimelockCoinSelection =
+            self.timelock_coin_selection(policy_path.as_ref(), current_height, timestamp)?;
+        let excluded_utxos: HashMap<OutPoint, ExcludedUtxo> = coin_selection
+            .excluded_utxos(wallet_utxos.values())
+            .into_iter()
+            .map(|excluded| (excluded.outpoint, excluded))
+            .collect();
+
+        if utxos.is_none() && !excluded_utxos.is_empty() {
+            let available: usize = wallet_utxos
+                .keys()
+                .filter(|outpoint| !excluded_utxos.contains_key(outpoint))
+                .filter(|outpoint| match &frozen_utxos {
+                    Some(frozen_utxos) => !frozen_utxos.contains(outpoint),
+                    None => true,
+                })
+                .count();
+            if available == 0 {
+                return Err(Error::TimelockedUtxos(
+                    excluded_utxos.into_values().collect(),
+                ));
+            }
+        }
+
         // Build the PSBT
         let psbt = {
-            let mut builder = wallet.build_tx();
+            let mut builder = wallet.build_tx().coin_selection(coin_selection);
 
             if let Some(frozen_utxos) = frozen_utxos {
                 for unspendable in frozen_utxos.into_iter() {
@@ -785,6 +1154,13 @@ impl Policy {
                 }
             }
 
+            // Drain wallet select all the UTXOs as required, so mark also the timelocked ones as unspendable
+            if drain && utxos.is_none() {
+                for unspendable in excluded_utxos.keys() {
+                    builder.add_unspendable(*unspendable);
+                }
+            }
+
             if let Some(utxos) = utxos {
                 if utxos.is_empty() {
                     return Err(Error::NoUtxosSelected);
@@ -797,19 +1173,20 @@ impl Policy {
                 builder.policy_path(path, KeychainKind::External);
             }
 
-            // TODO: add custom coin selection alorithm (to exclude UTXOs with timelock enabled)
             builder
                 .fee_rate(fee_rate)
                 .enable_rbf()
                 .current_height(current_height);
-            match amount {
-                Amount::Max => builder
-                    .drain_wallet()
-                    .drain_to(address.payload.script_pubkey()),
-                Amount::Custom(amount) => {
-                    builder.add_recipient(address.payload.script_pubkey(), amount)
-                }
-            };
+            for (address, amount) in recipients.iter() {
+                match amount {
+                    Amount::Max => builder
+                        .drain_wallet()
+                        .drain_to(address.payload.script_pubkey()),
+                    Amount::Custom(amount) => {
+                        builder.add_recipient(address.payload.script_pubkey(), *amount)
+                    }
+                };
+            }
             builder
                 .finish()
                 .map_err(|e| Error::BdkCreateTx(format!("{e:?}")))?
@@ -824,46 +1201,199 @@ impl Policy {
                 return Err(Error::AbsoluteTimelockNotSatisfied);
             }
 
+            // Check if relative timelock is satisfied (manually selected UTXOs)
             for txin in psbt.unsigned_tx.input.iter() {
-                let sequence: Sequence = txin.sequence;
-
-                // Check if relative timelock is satisfied
-                if sequence.is_height_locked() || sequence.is_time_locked() {
-                    if let Some(utxo) = wallet_utxos.get(&txin.previous_output) {
-                        match utxo.confirmation_time {
-                            ConfirmationTime::Confirmed { height, .. } => {
-                                if current_height.saturating_sub(height) < sequence.0 {
-                                    return Err(Error::RelativeTimelockNotSatisfied);
-                                }
-                            }
-                            ConfirmationTime::Unconfirmed { .. } => {
-                                return Err(Error::RelativeTimelockNotSatisfied);
-                            }
-                        }
-                    }
+                if let Some(ExcludedUtxo {
+                    reason:
+                        ExclusionReason::RelativeTimelock { .. } | ExclusionReason::Unconfirmed { .. },
+                    ..
+                }) = excluded_utxos.get(&txin.previous_output)
+                {
+                    return Err(Error::RelativeTimelockNotSatisfied);
                 }
             }
         }
 
-        let amount: u64 = match amount {
-            Amount::Max => {
-                let fee: u64 = psbt.fee()?.to_sat();
-                let (sent, received) = wallet.sent_and_received(&psbt.unsigned_tx);
-                sent.saturating_sub(received).saturating_sub(fee)
+        // Amount sent to the drain (send max) recipient
+        let drain_amount: u64 = match recipients
+            .iter()
+            .find(|(_, amount)| matches!(amount, Amount::Max))
+        {
+            Some((drain_address, _)) => {
+                let script: ScriptBuf = drain_address.payload.script_pubkey();
+                let total: u64 = psbt
+                    .unsigned_tx
+                    .output
+                    .iter()
+                    .filter(|txout| txout.script_pubkey == script)
+                    .map(|txout| txout.value)
+                    .sum();
+                let custom: u64 = recipients
+                    .iter()
+                    .filter(|(address, _)| address == drain_address)
+                    .filter_map(|(_, amount)| match amount {
+                        Amount::Custom(amount) => Some(*amount),
+                        Amount::Max => None,
+                    })
+                    .sum();
+                total.saturating_sub(custom)
             }
-            Amount::Custom(amount) => amount,
+            None => 0,
         };
 
-        Ok(Proposal::spending(
+        let mut recipients: Vec<Recipient> = recipients
+            .into_iter()
+            .map(|(address, amount)| {
+                let amount: u64 = match amount {
+                    Amount::Max => drain_amount,
+                    Amount::Custom(amount) => amount,
+                };
+                Recipient { address, amount }
+            })
+            .collect();
+        let Recipient { address, amount } = recipients.remove(0);
+
+        Ok(Proposal::batch_spending(
             self.descriptor.clone(),
             address,
             amount,
+            recipients,
             description,
             psbt,
             policy_path,
         ))
     }
 
+    /// Build a fee bump (RBF) [`Proposal`] to replace an unconfirmed transaction
+    pub fn bump_fee<D, S>(
+        &self,
+        wallet: &mut Wallet<D>,
+        txid: Txid,
+        description: S,
+        fee_rate: FeeRate,
+        policy_path: Option<BTreeMap<String, Vec<usize>>>,
+    ) -> Result<Proposal, Error>
+    where
+        D: PersistBackend<ChangeSet>,
+        S: Into<String>,
+    {
+        let psbt = {
+            let mut builder = wallet
+                .build_fee_bump(txid)
+                .map_err(|e| Error::BdkFeeBump(format!("{e:?}")))?;
+
+            if let Some(path) = policy_path.clone() {
+                builder.policy_path(path, KeychainKind::External);
+            }
+
+            builder.fee_rate(fee_rate).enable_rbf();
+            builder
+                .finish()
+                .map_err(|e| Error::BdkCreateTx(format!("{e:?}")))?
+        };
+
+        Ok(Proposal::fee_bump(
+            self.descriptor.clone(),
+            txid,
+            description,
+            psbt,
+            policy_path,
+        ))
+    }
+
+    /// Build a child-pays-for-parent (CPFP) [`Proposal`] spending the unconfirmed outputs of `parent_txid`
+    ///
+    /// The child fee is calculated to reach the target `fee_rate` for the whole package (parent + child).
+    pub fn cpfp<D, S>(
+        &self,
+        wallet: &mut Wallet<D>,
+        parent_txid: Txid,
+        parent_fee: u64,
+        parent_vsize: usize,
+        address: Address<NetworkUnchecked>,
+        description: S,
+        fee_rate: FeeRate,
+        policy_path: Option<BTreeMap<String, Vec<usize>>>,
+    ) -> Result<Proposal, Error>
+    where
+        D: PersistBackend<ChangeSet>,
+        S: Into<String>,
+    {
+        let parent_utxos: Vec<LocalOutput> = wallet
+            .list_unspent()
+            .filter(|utxo| utxo.outpoint.txid == parent_txid)
+            .filter(|utxo| matches!(utxo.confirmation_time, ConfirmationTime::Unconfirmed { .. }))
+            .collect();
+
+        if parent_utxos.is_empty() {
+            return Err(Error::NoUtxosAvailable(String::from(
+                "parent transaction not contains unconfirmed outputs of the vault",
+            )));
+        }
+
+        let current_height: u32 = wallet.latest_checkpoint().height();
+        let timestamp: u64 = time::timestamp();
+
+        // Unconfirmed UTXOs can't satisfy a relative timelock
+        let excluded_utxos: Vec<ExcludedUtxo> = self
+            .timelock_coin_selection(policy_path.as_ref(), current_height, timestamp)?
+            .excluded_utxos(parent_utxos.iter());
+        if !excluded_utxos.is_empty() {
+            return Err(Error::TimelockedUtxos(excluded_utxos));
+        }
+
+        let outpoints: Vec<OutPoint> = parent_utxos.iter().map(|utxo| utxo.outpoint).collect();
+        let build = |wallet: &mut Wallet<D>, fee: Option<u64>| {
+            let mut builder = wallet.build_tx();
+            builder.manually_selected_only();
+            builder.add_utxos(&outpoints)?;
+
+            if let Some(path) = policy_path.clone() {
+                builder.policy_path(path, KeychainKind::External);
+            }
+
+            match fee {
+                Some(fee) => builder.fee_absolute(fee),
+                None => builder.fee_rate(fee_rate),
+            };
+
+            builder
+                .enable_rbf()
+                .current_height(current_height)
+                .drain_to(address.payload.script_pubkey());
+            builder
+                .finish()
+                .map_err(|e| Error::BdkCreateTx(format!("{e:?}")))
+        };
+
+        // Build the child at the target fee rate to estimate its vsize
+        let psbt: PartiallySignedTransaction = build(wallet, None)?;
+        let child_fee: u64 = psbt.fee()?.to_sat();
+        let child_vsize: usize = estimate_signed_vsize(wallet, &psbt)?;
+
+        // Pay also the missing fee of the parent
+        let package_fee: u64 = fee_rate.fee_vb(parent_vsize + child_vsize);
+        let fee: u64 = cmp::max(child_fee, package_fee.saturating_sub(parent_fee));
+        let psbt: PartiallySignedTransaction = build(wallet, Some(fee))?;
+
+        let amount: u64 = psbt
+            .unsigned_tx
+            .output
+            .iter()
+            .map(|txout| txout.value)
+            .sum();
+
+        Ok(Proposal::cpfp(
+            self.descriptor.clone(),
+            address,
+            amount,
+            description,
+            psbt,
+            policy_path,
+            parent_txid,
+        ))
+    }
+
     #[cfg(feature = "reserves")]
     pub fn proof_of_reserve<D, S>(
         &self,
@@ -884,15 +1414,87 @@ impl Policy {
         path.insert(wallet_policy.id, vec![1]);
 
         let psbt: PartiallySignedTransaction = wallet.create_proof(message)?;
+        let height: u32 = wallet.latest_checkpoint().height();
 
         Ok(Proposal::proof_of_reserve(
             self.descriptor.clone(),
             message,
             psbt,
+            height,
         ))
     }
 }
 
+fn is_signature(item: &SatisfiableItem) -> bool {
+    matches!(
+        item,
+        SatisfiableItem::EcdsaSignature(..) | SatisfiableItem::SchnorrSignature(..)
+    )
+}
+
+fn is_timelock(item: &SatisfiableItem) -> bool {
+    matches!(
+        item,
+        SatisfiableItem::AbsoluteTimelock { .. } | SatisfiableItem::RelativeTimelock { .. }
+    )
+}
+
+/// Match [PolicyTemplateType] for P2WSH descriptors
+///
+/// Segwit v0 descriptors haven't an internal key, so the template is the root item.
+fn segwit_v0_template_match(item: &SatisfiableItem) -> Option<PolicyTemplateType> {
+    match item {
+        SatisfiableItem::EcdsaSignature(..) => Some(PolicyTemplateType::Singlesig),
+        SatisfiableItem::Multisig { .. } => Some(PolicyTemplateType::Multisig),
+        SatisfiableItem::Thresh { items, threshold } => {
+            let keys_count: usize = items.iter().filter(|i| is_signature(&i.item)).count();
+            let timelock_count: usize = items.iter().filter(|i| is_timelock(&i.item)).count();
+
+            // N of M multisig compiled as `thresh`
+            if keys_count == items.len() {
+                return Some(PolicyTemplateType::Multisig);
+            }
+
+            if items.len() == 2 {
+                // Hold template
+                if *threshold == 2 && is_signature(&items[0].item) && is_timelock(&items[1].item) {
+                    return Some(PolicyTemplateType::Hold);
+                }
+
+                // Social Recovery / Inheritance
+                if *threshold == 1 && is_signature(&items[0].item) {
+                    if let SatisfiableItem::Thresh { items, threshold } = &items[1].item {
+                        if *threshold == 2
+                            && items.len() == 2
+                            && matches!(
+                                items[0].item,
+                                SatisfiableItem::EcdsaSignature(..)
+                                    | SatisfiableItem::Multisig { .. }
+                                    | SatisfiableItem::Thresh { .. }
+                            )
+                            && is_timelock(&items[1].item)
+                        {
+                            return Some(PolicyTemplateType::Recovery);
+                        }
+                    }
+                }
+            }
+
+            // Decaying template
+            if threshold < &items.len()
+                && threshold <= &keys_count
+                && timelock_count > 0
+                && keys_count + timelock_count == items.len()
+            {
+                return Some(PolicyTemplateType::Decaying);
+            }
+
+            None
+        }
+        _ => None,
+    }
+}
+
 /// Check if [SatisfiableItem] contains [Fingerprint]
 fn satisfiable_item_contains_fingerprint(
     item: &SatisfiableItem,
@@ -941,10 +1543,28 @@ fn satisfiable_item_contains_fingerprint(
     }
 }
 
+/// Estimate the vsize of the signed transaction, using the max satisfaction weight of the descriptor
+fn estimate_signed_vsize<D>(
+    wallet: &Wallet<D>,
+    psbt: &PartiallySignedTransaction,
+) -> Result<usize, Error>
+where
+    D: PersistBackend<ChangeSet>,
+{
+    let satisfaction_weight: usize = wallet
+        .get_descriptor_for_keychain(KeychainKind::External)
+        .max_weight_to_satisfy()?;
+    let tx = &psbt.unsigned_tx;
+    // Unsigned weight + segwit marker and flag + witnesses
+    let weight: usize = tx.weight().to_wu() as usize + 2 + tx.input.len() * satisfaction_weight;
+    Ok((weight + 3) / 4)
+}
+
 #[cfg(test)]
 mod tests {
     use bdk::keys::DescriptorPublicKey;
     use keechain_core::bips::bip39::Mnemonic;
+    use keechain_core::bitcoin::{Sequence as TxSequence, TxIn};
     use keechain_core::Seed;
 
     use super::*;
@@ -979,6 +1599,22 @@ mod tests {
         assert!(Policy::from_descriptor("", "", descriptor, NETWORK).is_err())
     }
 
+    #[test]
+    fn test_wsh_descriptor() {
+        let descriptor = "wsh(sortedmulti(2,[7356e457/48'/1'/0'/2']tpubDCvLwbJPseNux9EtPbrbA2tgDayzptK4HNkky14Cw6msjHuqyZCE88miedZD86TZUb29Rof3sgtREU4wtzofte7QDSWDiw8ZU6ZYHmAxY9d/0/*,[4eb5d5a1/48'/1'/0'/2']tpubDCLskGdzStPPo1auRQygJUfbmLMwujWr7fmekdUMD7gqSpwEcRso4CfiP5GkRqfXFYkfqTujyvuehb7inymMhBJFdbJqFyHsHVRuwLKCSe9/0/*))";
+        let policy = Policy::from_descriptor("", "", descriptor, NETWORK).unwrap();
+        assert!(policy.spending_policy().is_ok());
+
+        let sh_wsh = format!("sh({descriptor})");
+        assert!(Policy::from_descriptor("", "", sh_wsh, NETWORK).is_ok());
+
+        let pkh = "pkh([7356e457/44'/1'/0']tpubDCvLwbJPseNux9EtPbrbA2tgDayzptK4HNkky14Cw6msjHuqyZCE88miedZD86TZUb29Rof3sgtREU4wtzofte7QDSWDiw8ZU6ZYHmAxY9d/0/*)";
+        assert!(matches!(
+            Policy::from_descriptor("", "", pkh, NETWORK),
+            Err(Error::UnsupportedDescriptor)
+        ));
+    }
+
     #[test]
     fn test_descriptor_with_wrong_network() {
         let descriptor = "tr([9bf4354b/86'/1'/784923']tpubDCT8uwnkZj7woaY71Xr5hU7Wvjr7B1BXJEpwMzzDLd1H6HLnKTiaLPtt6ZfEizDMwdQ8PT8JCmKbB4ESVXTkCzv51oxhJhX5FLBvkeN9nJ3/0/*,pk([7356e457/86'/1'/784923']tpubDCvLwbJPseNux9EtPbrbA2tgDayzptK4HNkky14Cw6msjHuqyZCE88miedZD86TZUb29Rof3sgtREU4wtzofte7QDSWDiw8ZU6ZYHmAxY9d/0/*))#rs0udsfg";
@@ -1218,6 +1854,80 @@ mod tests {
         assert!(!policy.is_fingerprint_involved(&fingerprint).unwrap());
     }
 
+    #[test]
+    fn test_satisfiable_without() {
+        let policy = Policy::from_descriptor("", "", COMPLEX_DESCRIPTOR, NETWORK).unwrap();
+
+        let internal_key = Fingerprint::from_str("7356e457").unwrap();
+        let f3ab64d8 = Fingerprint::from_str("f3ab64d8").unwrap();
+        let f57a6b99 = Fingerprint::from_str("f57a6b99").unwrap();
+        let f4eb5d5a1 = Fingerprint::from_str("4eb5d5a1").unwrap();
+
+        assert!(policy.satisfiable_without([]).unwrap());
+        assert!(policy.satisfiable_without([&internal_key]).unwrap());
+        assert!(policy
+            .satisfiable_without([&internal_key, &f57a6b99])
+            .unwrap());
+        assert!(!policy
+            .satisfiable_without([&internal_key, &f3ab64d8])
+            .unwrap());
+        assert!(!policy
+            .satisfiable_without([&internal_key, &f57a6b99, &f4eb5d5a1])
+            .unwrap());
+    }
+
+    #[test]
+    fn test_satisfiable_on_path_without() {
+        let policy = Policy::from_descriptor("", "", COMPLEX_DESCRIPTOR, NETWORK).unwrap();
+
+        let internal_key = Fingerprint::from_str("7356e457").unwrap();
+        let f57a6b99 = Fingerprint::from_str("f57a6b99").unwrap();
+        let f4eb5d5a1 = Fingerprint::from_str("4eb5d5a1").unwrap();
+
+        let mut tx = Transaction {
+            version: 2,
+            lock_time: absolute::LockTime::ZERO,
+            input: vec![TxIn::default()],
+            output: Vec::new(),
+        };
+
+        // Internal key path
+        let mut path: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+        path.insert(String::from("y46gds64"), vec![0]);
+        assert!(policy
+            .satisfiable_on_path_without([&f4eb5d5a1], Some(&path), &tx)
+            .unwrap());
+        assert!(!policy
+            .satisfiable_on_path_without([&internal_key], Some(&path), &tx)
+            .unwrap());
+
+        // Multisig path
+        let mut path: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+        path.insert(String::from("fx0z8u06"), vec![1]);
+        path.insert(String::from("y46gds64"), vec![1]);
+        assert!(policy
+            .satisfiable_on_path_without([&internal_key, &f57a6b99], Some(&path), &tx)
+            .unwrap());
+        assert!(!policy
+            .satisfiable_on_path_without([&internal_key, &f4eb5d5a1], Some(&path), &tx)
+            .unwrap());
+
+        // Timelock path: satisfiable only when the inputs sequence meet the timelock
+        let mut path: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+        path.insert(String::from("fx0z8u06"), vec![0]);
+        path.insert(String::from("y46gds64"), vec![1]);
+        assert!(!policy
+            .satisfiable_on_path_without([&internal_key], Some(&path), &tx)
+            .unwrap());
+        tx.input[0].sequence = TxSequence::from_height(52000);
+        assert!(policy
+            .satisfiable_on_path_without([&internal_key], Some(&path), &tx)
+            .unwrap());
+        assert!(!policy
+            .satisfiable_on_path_without([&internal_key, &f57a6b99], Some(&path), &tx)
+            .unwrap());
+    }
+
     #[test]
     fn test_policy_template_match() {
         let singlesig = DescriptorPublicKey::from_str("[7356e457/86'/1'/784923']tpubDCvLwbJPseNux9EtPbrbA2tgDayzptK4HNkky14Cw6msjHuqyZCE88miedZD86TZUb29Rof3sgtREU4wtzofte7QDSWDiw8ZU6ZYHmAxY9d/0/*").unwrap();
@@ -1296,6 +2006,45 @@ mod tests {
             Some(PolicyTemplateType::Decaying)
         );
     }
+
+    #[test]
+    fn test_wsh_policy_template_match() {
+        let desc1 = DescriptorPublicKey::from_str("[7356e457/86'/1'/784923']tpubDCvLwbJPseNux9EtPbrbA2tgDayzptK4HNkky14Cw6msjHuqyZCE88miedZD86TZUb29Rof3sgtREU4wtzofte7QDSWDiw8ZU6ZYHmAxY9d/0/*").unwrap();
+        let desc2 = DescriptorPublicKey::from_str("[4eb5d5a1/86'/1'/784923']tpubDCLskGdzStPPo1auRQygJUfbmLMwujWr7fmekdUMD7gqSpwEcRso4CfiP5GkRqfXFYkfqTujyvuehb7inymMhBJFdbJqFyHsHVRuwLKCSe9/0/*").unwrap();
+        let desc3 = DescriptorPublicKey::from_str("[f3ab64d8/86'/1'/784923']tpubDCh4uyVDVretfgTNkazUarV9ESTh7DJy8yvMSuWn5PQFbTDEsJwHGSBvTrNF92kw3x5ZLFXw91gN5LYtuSCbr1Vo6mzQmD49sF2vGpReZp2/0/*").unwrap();
+
+        let template = PolicyTemplate::singlesig(desc1.clone());
+        let policy = Policy::from_template_wsh("Singlesig", "", template, NETWORK).unwrap();
+        assert_eq!(
+            policy.template_match().unwrap(),
+            Some(PolicyTemplateType::Singlesig)
+        );
+
+        let template = PolicyTemplate::multisig(2, vec![desc1.clone(), desc2.clone()]);
+        let policy = Policy::from_template_wsh("Multisig", "", template, NETWORK).unwrap();
+        assert_eq!(
+            policy.template_match().unwrap(),
+            Some(PolicyTemplateType::Multisig)
+        );
+
+        let template = PolicyTemplate::hold(desc1.clone(), Locktime::Older(Sequence(144)));
+        let policy = Policy::from_template_wsh("Hold", "", template, NETWORK).unwrap();
+        assert_eq!(
+            policy.template_match().unwrap(),
+            Some(PolicyTemplateType::Hold)
+        );
+
+        let template = PolicyTemplate::decaying(
+            2,
+            vec![desc1, desc2, desc3],
+            DecayingTime::Single(Locktime::Older(Sequence(2))),
+        );
+        let policy = Policy::from_template_wsh("Decaying", "", template, NETWORK).unwrap();
+        assert_eq!(
+            policy.template_match().unwrap(),
+            Some(PolicyTemplateType::Decaying)
+        );
+    }
 }
 
 #[cfg(bench)]
@@ -6,7 +6,7 @@ use core::fmt;
 pub use keechain_core::bitcoin::absolute::LockTime as AbsoluteLockTime;
 pub use keechain_core::bitcoin::Sequence;
 use keechain_core::miniscript::policy::concrete::Policy;
-use keechain_core::miniscript::DescriptorPublicKey;
+use keechain_core::miniscript::{Descriptor, DescriptorPublicKey, Segwitv0};
 use thiserror::Error;
 
 #[derive(Debug, Error, PartialEq, Eq)]
@@ -15,6 +15,10 @@ pub enum Error {
     InvalidThreshold,
     #[error("not keys")]
     NoKeys,
+    #[error("{0}")]
+    Compiler(String),
+    #[error("{0}")]
+    Descriptor(String),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -224,6 +228,34 @@ impl PolicyTemplate {
             }
         }
     }
+
+    /// Build P2WSH descriptor
+    ///
+    /// Multisig templates are built as `wsh(sortedmulti(..))`
+    pub fn build_wsh(self) -> Result<Descriptor<DescriptorPublicKey>, Error> {
+        let policy: Policy<DescriptorPublicKey> = match self {
+            Self::Multisig { threshold, keys } => {
+                if threshold == 0 || threshold > keys.len() {
+                    return Err(Error::InvalidThreshold);
+                }
+
+                if keys.is_empty() {
+                    return Err(Error::NoKeys);
+                }
+
+                return Descriptor::new_wsh_sortedmulti(threshold, keys)
+                    .map_err(|e| Error::Descriptor(e.to_string()));
+            }
+            template => match template.build()? {
+                PolicyTemplateResult::Singlesig(key) => Policy::Key(key),
+                PolicyTemplateResult::Policy(policy) => policy,
+            },
+        };
+        let ms = policy
+            .compile::<Segwitv0>()
+            .map_err(|e| Error::Compiler(e.to_string()))?;
+        Descriptor::new_wsh(ms).map_err(|e| Error::Descriptor(e.to_string()))
+    }
 }
 
 #[cfg(test)]
@@ -254,6 +286,19 @@ mod test {
         assert_eq!(template.build().unwrap().to_string(), String::from("thresh(2,pk([7356e457/86'/1'/784923']tpubDCvLwbJPseNux9EtPbrbA2tgDayzptK4HNkky14Cw6msjHuqyZCE88miedZD86TZUb29Rof3sgtREU4wtzofte7QDSWDiw8ZU6ZYHmAxY9d/0/*),pk([4eb5d5a1/86'/1'/784923']tpubDCLskGdzStPPo1auRQygJUfbmLMwujWr7fmekdUMD7gqSpwEcRso4CfiP5GkRqfXFYkfqTujyvuehb7inymMhBJFdbJqFyHsHVRuwLKCSe9/0/*))"));
     }
 
+    #[test]
+    fn test_wsh_multisig_template() {
+        let desc1 = DescriptorPublicKey::from_str("[7356e457/86'/1'/784923']tpubDCvLwbJPseNux9EtPbrbA2tgDayzptK4HNkky14Cw6msjHuqyZCE88miedZD86TZUb29Rof3sgtREU4wtzofte7QDSWDiw8ZU6ZYHmAxY9d/0/*").unwrap();
+        let desc2 = DescriptorPublicKey::from_str("[4eb5d5a1/86'/1'/784923']tpubDCLskGdzStPPo1auRQygJUfbmLMwujWr7fmekdUMD7gqSpwEcRso4CfiP5GkRqfXFYkfqTujyvuehb7inymMhBJFdbJqFyHsHVRuwLKCSe9/0/*").unwrap();
+
+        let template = PolicyTemplate::multisig(2, vec![desc1.clone(), desc2.clone()]);
+        let descriptor = template.build_wsh().unwrap().to_string();
+        assert!(descriptor.starts_with("wsh(sortedmulti(2,[7356e457/86'/1'/784923']tpubDCvLwbJPseNux9EtPbrbA2tgDayzptK4HNkky14Cw6msjHuqyZCE88miedZD86TZUb29Rof3sgtREU4wtzofte7QDSWDiw8ZU6ZYHmAxY9d/0/*,[4eb5d5a1/86'/1'/784923']tpubDCLskGdzStPPo1auRQygJUfbmLMwujWr7fmekdUMD7gqSpwEcRso4CfiP5GkRqfXFYkfqTujyvuehb7inymMhBJFdbJqFyHsHVRuwLKCSe9/0/*))"));
+
+        let template = PolicyTemplate::multisig(3, vec![desc1, desc2]);
+        assert_eq!(template.build_wsh().unwrap_err(), Error::InvalidThreshold);
+    }
+
     #[test]
     fn test_invalid_multisig_template() {
         let desc1 = DescriptorPublicKey::from_str("[7356e457/86'/1'/784923']tpubDCvLwbJPseNux9EtPbrbA2tgDayzptK4HNkky14Cw6msjHuqyZCE88miedZD86TZUb29Rof3sgtREU4wtzofte7QDSWDiw8ZU6ZYHmAxY9d/0/*").unwrap();
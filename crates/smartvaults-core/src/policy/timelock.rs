@@ -0,0 +1,149 @@
+// Copyright (c) 2022-2024 Smart Vaults
+// Distributed under the MIT software license
+
+//! Timelock maturity schedule
+
+use core::cmp;
+
+use bdk::chain::ConfirmationTime;
+use bdk::descriptor::policy::SatisfiableItem;
+use bdk::LocalOutput;
+use keechain_core::bitcoin::{absolute, relative, OutPoint, Sequence};
+use serde::{Deserialize, Serialize};
+
+/// When a timelocked path become spendable
+///
+/// The path is unlocked when **both** `height` and `timestamp` are reached.
+/// `None` means that there isn't a restriction of that type.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub struct TimelockMaturity {
+    /// Block height
+    pub height: Option<u32>,
+    /// UNIX timestamp
+    pub timestamp: Option<u64>,
+}
+
+impl TimelockMaturity {
+    /// Check if path hasn't any timelock
+    pub fn is_none(&self) -> bool {
+        self.height.is_none() && self.timestamp.is_none()
+    }
+
+    /// Check if the timelocks are satisfied at the passed `height` and `timestamp`
+    pub fn is_mature(&self, height: u32, timestamp: u64) -> bool {
+        self.height.map_or(true, |h| height >= h) && self.timestamp.map_or(true, |t| timestamp >= t)
+    }
+
+    /// Remaining blocks before maturity (if height locked)
+    pub fn remaining_blocks(&self, height: u32) -> Option<u32> {
+        self.height.map(|h| h.saturating_sub(height))
+    }
+
+    /// Remaining seconds before maturity (if time locked)
+    pub fn remaining_secs(&self, timestamp: u64) -> Option<u64> {
+        self.timestamp.map(|t| t.saturating_sub(timestamp))
+    }
+
+    fn with_height(mut self, height: u32) -> Self {
+        self.height = Some(self.height.map_or(height, |h| cmp::max(h, height)));
+        self
+    }
+
+    fn with_timestamp(mut self, timestamp: u64) -> Self {
+        self.timestamp = Some(self.timestamp.map_or(timestamp, |t| cmp::max(t, timestamp)));
+        self
+    }
+}
+
+/// Timelock maturity of an UTXO for a specific sub-path of a [`SelectableCondition`](super::SelectableCondition)
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct UtxoTimelock {
+    pub outpoint: OutPoint,
+    /// Selectable condition path ID
+    pub path: String,
+    /// Index of the sub-path (the one to use in the `policy_path`)
+    pub index: usize,
+    /// Sub-path ID
+    pub sub_path: String,
+    pub maturity: TimelockMaturity,
+}
+
+/// Collect the timelocks used in the [`SatisfiableItem`]
+pub(crate) fn collect_timelocks(
+    item: &SatisfiableItem,
+    after: &mut Vec<absolute::LockTime>,
+    older: &mut Vec<Sequence>,
+) {
+    match item {
+        SatisfiableItem::AbsoluteTimelock { value } => after.push(*value),
+        SatisfiableItem::RelativeTimelock { value } => older.push(*value),
+        SatisfiableItem::Thresh { items, .. } => {
+            for i in items.iter() {
+                collect_timelocks(&i.item, after, older);
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Compute the [`TimelockMaturity`] of an UTXO
+///
+/// Unconfirmed UTXOs are considered as confirmed in the next block.
+pub(crate) fn maturity(
+    utxo: &LocalOutput,
+    after: &[absolute::LockTime],
+    older: &[Sequence],
+    current_height: u32,
+    timestamp: u64,
+) -> TimelockMaturity {
+    let mut maturity = TimelockMaturity::default();
+
+    for lock in after.iter() {
+        maturity = match lock {
+            absolute::LockTime::Blocks(height) => maturity.with_height(height.to_consensus_u32()),
+            absolute::LockTime::Seconds(time) => {
+                maturity.with_timestamp(time.to_consensus_u32() as u64)
+            }
+        };
+    }
+
+    let (confirmed_height, confirmed_at): (u32, u64) = match utxo.confirmation_time {
+        ConfirmationTime::Confirmed { height, time } => (height, time),
+        ConfirmationTime::Unconfirmed { .. } => (current_height.saturating_add(1), timestamp),
+    };
+
+    for sequence in older.iter() {
+        maturity = match sequence.to_relative_lock_time() {
+            Some(relative::LockTime::Blocks(blocks)) => {
+                maturity.with_height(confirmed_height.saturating_add(blocks.value() as u32))
+            }
+            Some(relative::LockTime::Time(interval)) => {
+                maturity.with_timestamp(confirmed_at.saturating_add(interval.value() as u64 * 512))
+            }
+            None => maturity,
+        };
+    }
+
+    maturity
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timelock_maturity() {
+        let maturity = TimelockMaturity::default()
+            .with_height(100)
+            .with_height(90)
+            .with_timestamp(1_700_000_000);
+        assert_eq!(maturity.height, Some(100));
+        assert!(!maturity.is_mature(99, 1_700_000_000));
+        assert!(!maturity.is_mature(100, 1_699_999_999));
+        assert!(maturity.is_mature(100, 1_700_000_000));
+        assert_eq!(maturity.remaining_blocks(95), Some(5));
+        assert!(TimelockMaturity::default().is_none());
+    }
+}
@@ -30,6 +30,13 @@ pub enum ApprovedProposal {
         )]
         psbt: PartiallySignedTransaction,
     },
+    FeeBump {
+        #[serde(
+            serialize_with = "serialize_psbt",
+            deserialize_with = "deserialize_psbt"
+        )]
+        psbt: PartiallySignedTransaction,
+    },
 }
 
 impl ApprovedProposal {
@@ -45,11 +52,16 @@ impl ApprovedProposal {
         Self::KeyAgentPayment { psbt }
     }
 
+    pub fn fee_bump(psbt: PartiallySignedTransaction) -> Self {
+        Self::FeeBump { psbt }
+    }
+
     pub fn get_type(&self) -> ProposalType {
         match self {
             Self::Spending { .. } => ProposalType::Spending,
             Self::ProofOfReserve { .. } => ProposalType::ProofOfReserve,
             Self::KeyAgentPayment { .. } => ProposalType::KeyAgentPayment,
+            Self::FeeBump { .. } => ProposalType::FeeBump,
         }
     }
 
@@ -58,6 +70,7 @@ impl ApprovedProposal {
             Self::Spending { psbt, .. } => psbt.clone(),
             Self::ProofOfReserve { psbt, .. } => psbt.clone(),
             Self::KeyAgentPayment { psbt } => psbt.clone(),
+            Self::FeeBump { psbt } => psbt.clone(),
         }
     }
 }
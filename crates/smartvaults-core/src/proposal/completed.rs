@@ -2,7 +2,7 @@
 // Distributed under the MIT software license
 
 use keechain_core::bitcoin::psbt::PartiallySignedTransaction;
-use keechain_core::bitcoin::Transaction;
+use keechain_core::bitcoin::{Transaction, Txid};
 use keechain_core::miniscript::Descriptor;
 use serde::{Deserialize, Serialize};
 
@@ -23,6 +23,12 @@ pub enum CompletedProposal {
             deserialize_with = "deserialize_psbt"
         )]
         psbt: PartiallySignedTransaction,
+        /// Block height at which the proof was created
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        height: Option<u32>,
+        /// Descriptors of the other vaults included in an aggregated proof
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        other_descriptors: Vec<Descriptor<String>>,
     },
     KeyAgentPayment {
         tx: Transaction,
@@ -30,6 +36,12 @@ pub enum CompletedProposal {
         description: String,
         period: Period,
     },
+    FeeBump {
+        tx: Transaction,
+        /// TXID of the replaced transaction
+        replaced_txid: Txid,
+        description: String,
+    },
 }
 
 impl CompletedProposal {
@@ -46,7 +58,9 @@ impl CompletedProposal {
     pub fn proof_of_reserve<S>(
         message: S,
         descriptor: Descriptor<String>,
+        other_descriptors: Vec<Descriptor<String>>,
         psbt: PartiallySignedTransaction,
+        height: Option<u32>,
     ) -> Self
     where
         S: Into<String>,
@@ -55,6 +69,8 @@ impl CompletedProposal {
             message: message.into(),
             descriptor,
             psbt,
+            height,
+            other_descriptors,
         }
     }
 
@@ -75,11 +91,23 @@ impl CompletedProposal {
         }
     }
 
+    pub fn fee_bump<S>(tx: Transaction, replaced_txid: Txid, description: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self::FeeBump {
+            tx,
+            replaced_txid,
+            description: description.into(),
+        }
+    }
+
     pub fn get_type(&self) -> ProposalType {
         match self {
             Self::Spending { .. } => ProposalType::Spending,
             Self::ProofOfReserve { .. } => ProposalType::ProofOfReserve,
             Self::KeyAgentPayment { .. } => ProposalType::KeyAgentPayment,
+            Self::FeeBump { .. } => ProposalType::FeeBump,
         }
     }
 
@@ -87,6 +115,7 @@ impl CompletedProposal {
         match self {
             Self::Spending { tx, .. } => Some(tx.clone()),
             Self::KeyAgentPayment { tx, .. } => Some(tx.clone()),
+            Self::FeeBump { tx, .. } => Some(tx.clone()),
             _ => None,
         }
     }
@@ -96,6 +125,15 @@ impl CompletedProposal {
             Self::Spending { description, .. } => description.clone(),
             Self::ProofOfReserve { message, .. } => message.clone(),
             Self::KeyAgentPayment { description, .. } => description.clone(),
+            Self::FeeBump { description, .. } => description.clone(),
+        }
+    }
+
+    /// Get the TXID of the transaction replaced by this one (if fee bump)
+    pub fn replaced_txid(&self) -> Option<Txid> {
+        match self {
+            Self::FeeBump { replaced_txid, .. } => Some(*replaced_txid),
+            _ => None,
         }
     }
 
@@ -105,12 +143,15 @@ impl CompletedProposal {
                 message,
                 descriptor,
                 psbt,
-                ..
+                height,
+                other_descriptors,
             } => {
                 let json = serde_json::json!({
                     "message": message,
                     "descriptor": descriptor.to_string(),
-                    "psbt": psbt.to_string()
+                    "psbt": psbt.to_string(),
+                    "height": height,
+                    "other_descriptors": other_descriptors,
                 });
                 Some(json.to_string())
             }
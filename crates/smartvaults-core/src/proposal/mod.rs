@@ -4,14 +4,17 @@
 use std::cmp::Ordering;
 use std::collections::BTreeMap;
 use std::fmt;
+use std::iter;
 
-use keechain_core::bdk::signer::{SignerError, SignerWrapper};
+use keechain_core::bdk::descriptor::IntoWalletDescriptor;
+use keechain_core::bdk::signer::{SignerError, SignerWrapper, SignersContainer, TransactionSigner};
 use keechain_core::bdk::{SignOptions, Wallet};
 use keechain_core::bitcoin::address::NetworkUnchecked;
 use keechain_core::bitcoin::psbt::{
     Error as PsbtError, PartiallySignedTransaction, PsbtParseError,
 };
-use keechain_core::bitcoin::{Address, Network, PrivateKey};
+use keechain_core::bitcoin::{Address, Network, PrivateKey, Txid};
+use keechain_core::miniscript::descriptor::KeyMap;
 use keechain_core::miniscript::psbt::PsbtExt;
 use keechain_core::miniscript::Descriptor;
 use keechain_core::psbt::{Error as KPsbtError, PsbtUtility};
@@ -20,9 +23,12 @@ use serde::{Deserialize, Serialize};
 
 mod approved;
 mod completed;
+mod signer;
 
 pub use self::approved::ApprovedProposal;
 pub use self::completed::CompletedProposal;
+pub use self::signer::ProposalSigner;
+use crate::signer::Signer;
 use crate::util::{deserialize_psbt, serialize_psbt};
 use crate::SECP256K1;
 
@@ -48,6 +54,10 @@ pub enum Error {
     ImpossibleToFinalizePsbt(Vec<keechain_core::miniscript::psbt::Error>),
     #[error("impossible to finalize the non-std PSBT")]
     ImpossibleToFinalizeNonStdPsbt,
+    #[error(transparent)]
+    Signer(#[from] crate::signer::Error),
+    #[error("external signer: {0}")]
+    ExternalSigner(String),
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -55,6 +65,7 @@ pub enum ProposalType {
     Spending,
     ProofOfReserve,
     KeyAgentPayment,
+    FeeBump,
 }
 
 impl fmt::Display for ProposalType {
@@ -63,6 +74,7 @@ impl fmt::Display for ProposalType {
             Self::Spending => write!(f, "spending"),
             Self::ProofOfReserve => write!(f, "proof-of-reserve"),
             Self::KeyAgentPayment => write!(f, "key-agent-payment"),
+            Self::FeeBump => write!(f, "fee-bump"),
         }
     }
 }
@@ -75,12 +87,23 @@ pub struct Period {
     pub to: u64,
 }
 
+/// Spending proposal recipient
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Recipient {
+    pub address: Address<NetworkUnchecked>,
+    /// Amount in sat
+    pub amount: u64,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Proposal {
     Spending {
         descriptor: Descriptor<String>,
         to_address: Address<NetworkUnchecked>,
         amount: u64,
+        /// Additional recipients (batch payment)
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        other_recipients: Vec<Recipient>,
         description: String,
         #[serde(
             serialize_with = "serialize_psbt",
@@ -88,6 +111,9 @@ pub enum Proposal {
         )]
         psbt: PartiallySignedTransaction,
         policy_path: Option<BTreeMap<String, Vec<usize>>>,
+        /// Parent TXID, if the proposal is a child-pays-for-parent (CPFP)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        cpfp_parent: Option<Txid>,
     },
     ProofOfReserve {
         descriptor: Descriptor<String>,
@@ -97,6 +123,12 @@ pub enum Proposal {
             deserialize_with = "deserialize_psbt"
         )]
         psbt: PartiallySignedTransaction,
+        /// Block height at which the proof was created
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        height: Option<u32>,
+        /// Descriptors of the other vaults included in an aggregated proof
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        other_descriptors: Vec<Descriptor<String>>,
     },
     KeyAgentPayment {
         descriptor: Descriptor<String>,
@@ -112,6 +144,19 @@ pub enum Proposal {
         psbt: PartiallySignedTransaction,
         policy_path: Option<BTreeMap<String, Vec<usize>>>,
     },
+    /// Replace an unconfirmed transaction with a higher fee one (RBF)
+    FeeBump {
+        descriptor: Descriptor<String>,
+        /// TXID of the transaction to replace
+        replaced_txid: Txid,
+        description: String,
+        #[serde(
+            serialize_with = "serialize_psbt",
+            deserialize_with = "deserialize_psbt"
+        )]
+        psbt: PartiallySignedTransaction,
+        policy_path: Option<BTreeMap<String, Vec<usize>>>,
+    },
 }
 
 impl PartialOrd for Proposal {
@@ -142,9 +187,61 @@ impl Proposal {
             descriptor,
             to_address,
             amount,
+            other_recipients: Vec::new(),
             description: description.into(),
             psbt,
             policy_path,
+            cpfp_parent: None,
+        }
+    }
+
+    /// Spending proposal with multiple recipients
+    pub fn batch_spending<S>(
+        descriptor: Descriptor<String>,
+        to_address: Address<NetworkUnchecked>,
+        amount: u64,
+        other_recipients: Vec<Recipient>,
+        description: S,
+        psbt: PartiallySignedTransaction,
+        policy_path: Option<BTreeMap<String, Vec<usize>>>,
+    ) -> Self
+    where
+        S: Into<String>,
+    {
+        Self::Spending {
+            descriptor,
+            to_address,
+            amount,
+            other_recipients,
+            description: description.into(),
+            psbt,
+            policy_path,
+            cpfp_parent: None,
+        }
+    }
+
+    /// Spending proposal that bump the fee of `parent_txid` (child-pays-for-parent)
+    pub fn cpfp<S>(
+        descriptor: Descriptor<String>,
+        to_address: Address<NetworkUnchecked>,
+        amount: u64,
+        description: S,
+        psbt: PartiallySignedTransaction,
+        policy_path: Option<BTreeMap<String, Vec<usize>>>,
+        parent_txid: Txid,
+    ) -> Self
+    where
+        S: Into<String>,
+    {
+        Self::Spending {
+            descriptor,
+            to_address,
+            amount,
+            other_recipients: Vec::new(),
+            description: description.into(),
+            psbt,
+            policy_path,
+            cpfp_parent: Some(parent_txid),
         }
     }
 
@@ -152,6 +249,7 @@ impl Proposal {
         descriptor: Descriptor<String>,
         message: S,
         psbt: PartiallySignedTransaction,
+        height: u32,
     ) -> Self
     where
         S: Into<String>,
@@ -160,6 +258,30 @@ impl Proposal {
             descriptor,
             message: message.into(),
             psbt,
+            height: Some(height),
+            other_descriptors: Vec::new(),
+        }
+    }
+
+    /// Proof of reserve spanning several vaults
+    ///
+    /// The same PSBT is shared by the proposals of every participating vault.
+    pub fn aggregated_proof_of_reserve<S>(
+        descriptor: Descriptor<String>,
+        other_descriptors: Vec<Descriptor<String>>,
+        message: S,
+        psbt: PartiallySignedTransaction,
+        height: u32,
+    ) -> Self
+    where
+        S: Into<String>,
+    {
+        Self::ProofOfReserve {
+            descriptor,
+            message: message.into(),
+            psbt,
+            height: Some(height),
+            other_descriptors,
         }
     }
 
@@ -186,11 +308,31 @@ impl Proposal {
         }
     }
 
+    pub fn fee_bump<S>(
+        descriptor: Descriptor<String>,
+        replaced_txid: Txid,
+        description: S,
+        psbt: PartiallySignedTransaction,
+        policy_path: Option<BTreeMap<String, Vec<usize>>>,
+    ) -> Self
+    where
+        S: Into<String>,
+    {
+        Self::FeeBump {
+            descriptor,
+            replaced_txid,
+            description: description.into(),
+            psbt,
+            policy_path,
+        }
+    }
+
     pub fn get_type(&self) -> ProposalType {
         match self {
             Self::Spending { .. } => ProposalType::Spending,
             Self::ProofOfReserve { .. } => ProposalType::ProofOfReserve,
             Self::KeyAgentPayment { .. } => ProposalType::KeyAgentPayment,
+            Self::FeeBump { .. } => ProposalType::FeeBump,
         }
     }
 
@@ -199,6 +341,7 @@ impl Proposal {
             Self::Spending { descriptor, .. } => descriptor.clone(),
             Self::ProofOfReserve { descriptor, .. } => descriptor.clone(),
             Self::KeyAgentPayment { descriptor, .. } => descriptor.clone(),
+            Self::FeeBump { descriptor, .. } => descriptor.clone(),
         }
     }
 
@@ -207,6 +350,56 @@ impl Proposal {
             Self::Spending { description, .. } => description.clone(),
             Self::ProofOfReserve { message, .. } => message.clone(),
             Self::KeyAgentPayment { description, .. } => description.clone(),
+            Self::FeeBump { description, .. } => description.clone(),
+        }
+    }
+
+    /// Get all the recipients of a spending proposal
+    pub fn recipients(&self) -> Vec<Recipient> {
+        match self {
+            Self::Spending {
+                to_address,
+                amount,
+                other_recipients,
+                ..
+            } => {
+                let mut recipients: Vec<Recipient> = Vec::with_capacity(other_recipients.len() + 1);
+                recipients.push(Recipient {
+                    address: to_address.clone(),
+                    amount: *amount,
+                });
+                recipients.extend(other_recipients.iter().cloned());
+                recipients
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Get the descriptors of the other vaults included in an aggregated proof of reserve
+    pub fn other_descriptors(&self) -> Vec<Descriptor<String>> {
+        match self {
+            Self::ProofOfReserve {
+                other_descriptors, ..
+            } => other_descriptors.clone(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Get the parent TXID if is a child-pays-for-parent (CPFP) proposal
+    pub fn cpfp_parent(&self) -> Option<Txid> {
+        match self {
+            Self::Spending { cpfp_parent, .. } => *cpfp_parent,
+            _ => None,
+        }
+    }
+
+    /// Get the policy path used to build the PSBT (if any)
+    pub fn policy_path(&self) -> Option<BTreeMap<String, Vec<usize>>> {
+        match self {
+            Self::Spending { policy_path, .. } => policy_path.clone(),
+            Self::ProofOfReserve { .. } => None,
+            Self::KeyAgentPayment { policy_path, .. } => policy_path.clone(),
+            Self::FeeBump { policy_path, .. } => policy_path.clone(),
         }
     }
 
@@ -215,6 +408,7 @@ impl Proposal {
             Self::Spending { psbt, .. } => psbt.clone(),
             Self::ProofOfReserve { psbt, .. } => psbt.clone(),
             Self::KeyAgentPayment { psbt, .. } => psbt.clone(),
+            Self::FeeBump { psbt, .. } => psbt.clone(),
         }
     }
 
@@ -237,7 +431,44 @@ impl Proposal {
             Proposal::Spending { .. } => Ok(ApprovedProposal::spending(psbt)),
             Proposal::ProofOfReserve { .. } => Ok(ApprovedProposal::proof_of_reserve(psbt)),
             Proposal::KeyAgentPayment { .. } => Ok(ApprovedProposal::key_agent_payment(psbt)),
+            Proposal::FeeBump { .. } => Ok(ApprovedProposal::fee_bump(psbt)),
+        }
+    }
+
+    /// Approve signing only with the account keys of the passed seed [`Signer`]s
+    pub fn approve_with_seed_signers<'a, I>(
+        &self,
+        seed: &Seed,
+        signers: I,
+        network: Network,
+    ) -> Result<ApprovedProposal, Error>
+    where
+        I: IntoIterator<Item = &'a Signer>,
+    {
+        let mut keymap: KeyMap = KeyMap::new();
+        for signer in signers.into_iter() {
+            keymap.insert(
+                signer.descriptor_public_key()?,
+                signer.secret_key(seed, network)?,
+            );
         }
+
+        let (descriptor, _) = self
+            .descriptor()
+            .to_string()
+            .into_wallet_descriptor(&SECP256K1, network)?;
+        let container = SignersContainer::build(keymap, &descriptor, &SECP256K1);
+
+        let mut psbt: PartiallySignedTransaction = self.psbt();
+        let sign_options = SignOptions {
+            trust_witness_utxo: true,
+            ..Default::default()
+        };
+        for signer in container.signers().into_iter() {
+            signer.sign_transaction(&mut psbt, &sign_options, &SECP256K1)?;
+        }
+
+        self.approve_with_signed_psbt(psbt)
     }
 
     pub fn approve_with_signed_psbt(
@@ -254,37 +485,34 @@ impl Proposal {
                 Proposal::KeyAgentPayment { .. } => {
                     Ok(ApprovedProposal::key_agent_payment(signed_psbt))
                 }
+                Proposal::FeeBump { .. } => Ok(ApprovedProposal::fee_bump(signed_psbt)),
             }
         } else {
             Err(Error::PsbtNotSigned)
         }
     }
 
-    // pub fn approve_with_hwi_signer(
-    // &self,
-    // signer: Signer,
-    // network: Network,
-    // ) -> Result<ApprovedProposal, Error> {
-    // let client = HWIClient::find_device(
-    // None,
-    // None,
-    // Some(&signer.fingerprint().to_string()),
-    // false,
-    // network,
-    // )?;
-    // let base_psbt = self.psbt();
-    // let hwi_psbt = client.sign_tx(&base_psbt)?;
-    // if hwi_psbt.psbt != base_psbt {
-    // match self {
-    // Proposal::Spending { .. } => Ok(ApprovedProposal::spending(hwi_psbt.psbt)),
-    // Proposal::ProofOfReserve { .. } => {
-    // Ok(ApprovedProposal::proof_of_reserve(hwi_psbt.psbt))
-    // }
-    // }
-    // } else {
-    // Err(Error::PsbtNotSigned)
-    // }
-    // }
+    /// Approve using an external [`ProposalSigner`]
+    pub fn approve_with_signer(
+        &self,
+        signer: &Signer,
+        proposal_signer: &dyn ProposalSigner,
+    ) -> Result<ApprovedProposal, Error> {
+        let signed_psbt: PartiallySignedTransaction = proposal_signer.sign(self.psbt(), signer)?;
+        self.approve_with_signed_psbt(signed_psbt)
+    }
+
+    /// Approve combining the PSBTs signed by several signers
+    pub fn approve_with_signed_psbts<I>(&self, signed_psbts: I) -> Result<ApprovedProposal, Error>
+    where
+        I: IntoIterator<Item = PartiallySignedTransaction>,
+    {
+        let mut psbt: PartiallySignedTransaction = self.psbt();
+        for signed_psbt in signed_psbts.into_iter() {
+            psbt.combine(signed_psbt)?;
+        }
+        self.approve_with_signed_psbt(psbt)
+    }
 
     pub fn finalize<I>(
         &self,
@@ -331,22 +559,48 @@ impl Proposal {
                     *period,
                 ))
             }
+            Self::FeeBump {
+                replaced_txid,
+                description,
+                ..
+            } => {
+                base_psbt
+                    .finalize_mut(&SECP256K1)
+                    .map_err(Error::ImpossibleToFinalizePsbt)?;
+                Ok(CompletedProposal::fee_bump(
+                    base_psbt.extract_tx(),
+                    *replaced_txid,
+                    description,
+                ))
+            }
             Self::ProofOfReserve {
                 descriptor,
                 message,
+                height,
+                other_descriptors,
                 ..
             } => {
-                let wallet = Wallet::new_no_persist(&descriptor.to_string(), None, network)?;
                 let signopts = SignOptions {
                     trust_witness_utxo: true,
                     remove_partial_sigs: false,
                     ..Default::default()
                 };
-                if wallet.finalize_psbt(&mut base_psbt, signopts)? {
+
+                // Finalize the inputs of every vault: the already finalized inputs are skipped,
+                // so the last iteration tells if the PSBT is completely finalized
+                let mut finalized: bool = false;
+                for descriptor in iter::once(descriptor).chain(other_descriptors.iter()) {
+                    let wallet = Wallet::new_no_persist(&descriptor.to_string(), None, network)?;
+                    finalized = wallet.finalize_psbt(&mut base_psbt, signopts.clone())?;
+                }
+
+                if finalized {
                     Ok(CompletedProposal::proof_of_reserve(
                         message,
                         descriptor.clone(),
+                        other_descriptors.clone(),
                         base_psbt,
+                        *height,
                     ))
                 } else {
                     Err(Error::ImpossibleToFinalizeNonStdPsbt)
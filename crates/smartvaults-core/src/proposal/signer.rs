@@ -0,0 +1,23 @@
+// Copyright (c) 2022-2024 Smart Vaults
+// Distributed under the MIT software license
+
+//! External proposal signer
+
+use core::fmt;
+
+use keechain_core::bitcoin::psbt::PartiallySignedTransaction;
+
+use super::Error;
+use crate::signer::Signer;
+
+/// External signer (hardware wallet, HSM, remote signing daemon, ...)
+pub trait ProposalSigner: fmt::Debug + Send + Sync {
+    /// Sign the `psbt` with the key of the [`Signer`]
+    ///
+    /// Use [`Error::ExternalSigner`] to report signer-specific errors.
+    fn sign(
+        &self,
+        psbt: PartiallySignedTransaction,
+        signer: &Signer,
+    ) -> Result<PartiallySignedTransaction, Error>;
+}
@@ -1,10 +1,14 @@
 // Copyright (c) 2022-2024 Smart Vaults
 // Distributed under the MIT software license
 
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::str::FromStr;
+
 use keechain_core::bdk::chain::{ConfirmationTime, PersistBackend};
 use keechain_core::bdk::wallet::tx_builder::{AddForeignUtxoError, TxOrdering};
 use keechain_core::bdk::wallet::{ChangeSet, Wallet};
 use keechain_core::bitcoin::address::Payload;
+use keechain_core::bitcoin::bip32::{ChildNumber, KeySource};
 use keechain_core::bitcoin::blockdata::opcodes;
 use keechain_core::bitcoin::blockdata::script::{Builder, Script};
 use keechain_core::bitcoin::blockdata::transaction::{OutPoint, TxIn, TxOut};
@@ -13,7 +17,12 @@ use keechain_core::bitcoin::hash_types::{PubkeyHash, Txid};
 use keechain_core::bitcoin::hashes::{hash160, sha256d, Hash};
 use keechain_core::bitcoin::psbt::{Input, PartiallySignedTransaction};
 use keechain_core::bitcoin::sighash::EcdsaSighashType;
-use keechain_core::bitcoin::{Address, Network, Sequence};
+use keechain_core::bitcoin::{Address, Network, ScriptBuf, Sequence};
+use keechain_core::miniscript::descriptor::ConversionError;
+use keechain_core::miniscript::{Descriptor, DescriptorPublicKey};
+use serde::{Deserialize, Serialize};
+
+use crate::util::{deserialize_psbt, serialize_psbt};
 
 /// Proof error
 #[derive(Debug, thiserror::Error)]
@@ -59,20 +68,97 @@ pub enum ProofError {
     BdkAddForeignUtxo(#[from] AddForeignUtxoError),
     #[error("{0}")]
     BdkCreateTx(String),
+    /// The proof was created for another descriptor
+    #[error("descriptor does not match")]
+    DescriptorMismatch,
+    /// Supplied UTXO not belonging to the descriptors of the proof
+    #[error("UTXO {0} not belongs to the vault")]
+    ForeignUtxo(OutPoint),
+    /// Miniscript error
+    #[error(transparent)]
+    Miniscript(#[from] keechain_core::miniscript::Error),
+    /// Descriptor derivation error
+    #[error(transparent)]
+    DescriptorConversion(#[from] ConversionError),
+    /// No proofs to merge
+    #[error("no proofs to merge")]
+    NoProofs,
+    /// JSON error
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+}
+
+/// Proof exported with [`CompletedProposal::export_proof`](crate::CompletedProposal::export_proof)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportedProof {
+    pub message: String,
+    pub descriptor: Descriptor<String>,
+    #[serde(
+        serialize_with = "serialize_psbt",
+        deserialize_with = "deserialize_psbt"
+    )]
+    pub psbt: PartiallySignedTransaction,
+    /// Block height at which the proof was created
+    #[serde(default)]
+    pub height: Option<u32>,
+    /// Descriptors of the other vaults included in an aggregated proof
+    #[serde(default)]
+    pub other_descriptors: Vec<Descriptor<String>>,
+}
+
+impl ExportedProof {
+    pub fn from_json<S>(json: S) -> Result<Self, ProofError>
+    where
+        S: AsRef<str>,
+    {
+        Ok(serde_json::from_str(json.as_ref())?)
+    }
+}
+
+/// UTXO supplied for the offline verification of a proof
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofUtxo {
+    pub txid: Txid,
+    pub vout: u32,
+    /// Value in sat
+    pub value: u64,
+    pub script_pubkey: ScriptBuf,
+}
+
+impl ProofUtxo {
+    /// Parse a JSON list of UTXOs
+    pub fn list_from_json<S>(json: S) -> Result<Vec<Self>, ProofError>
+    where
+        S: AsRef<str>,
+    {
+        Ok(serde_json::from_str(json.as_ref())?)
+    }
+
+    pub fn outpoint(&self) -> OutPoint {
+        OutPoint::new(self.txid, self.vout)
+    }
+
+    pub fn txout(&self) -> TxOut {
+        TxOut {
+            value: self.value,
+            script_pubkey: self.script_pubkey.clone(),
+        }
+    }
 }
 
 /// The API for proof of reserves
 pub trait ProofOfReserves {
-    /// Create a proof for all spendable UTXOs in a wallet
+    /// Create a proof for all confirmed spendable UTXOs in a wallet
+    ///
+    /// Unconfirmed UTXOs are excluded, so the proof is verifiable at the current tip height.
     fn create_proof<S>(&mut self, message: S) -> Result<PartiallySignedTransaction, ProofError>
     where
         S: Into<String>;
 
     /// Make sure this is a proof, and not a spendable transaction.
-    /// Make sure the proof is valid.
-    /// Currently proofs can only be validated against the tip of the chain.
-    /// If some of the UTXOs in the proof were spent in the meantime, the proof will fail.
-    /// We can currently not validate whether it was valid at a certain block height.
+    /// Make sure the proof is valid against the tip of the chain.
+    /// If some of the UTXOs in the proof were spent in the meantime, the proof will fail:
+    /// use [`ProofOfReserves::verify_proof_at_height`] to validate it at a certain block height.
     /// With the max_block_height parameter the caller can ensure that only UTXOs with sufficient confirmations are considered.
     /// If no max_block_height is provided, also UTXOs from transactions in the mempool are considered.
     /// Returns the spendable amount of the proof.
@@ -84,6 +170,23 @@ pub trait ProofOfReserves {
     ) -> Result<u64, ProofError>
     where
         S: Into<String>;
+
+    /// Get the outputs that were unspent at the given block height.
+    /// The UTXO state is reconstructed from the transaction graph, so outputs spent after `height` are included.
+    fn historical_outputs(&self, height: u32) -> Vec<(OutPoint, TxOut)>;
+
+    /// Make sure the proof was valid at the given block height.
+    /// Only outputs confirmed at or below `height` and not spent at or below `height` are considered,
+    /// so the proof remains verifiable after the coins moved.
+    /// Returns the spendable amount of the proof.
+    fn verify_proof_at_height<S>(
+        &self,
+        psbt: &PartiallySignedTransaction,
+        message: S,
+        height: u32,
+    ) -> Result<u64, ProofError>
+    where
+        S: Into<String>;
 }
 
 impl<D> ProofOfReserves for Wallet<D>
@@ -113,10 +216,18 @@ where
         let out_script_unspendable =
             Address::new(self.network(), Payload::PubkeyHash(pkh)).script_pubkey();
 
+        // Unconfirmed UTXOs would make the proof not verifiable at the current height
+        let unconfirmed: Vec<OutPoint> = self
+            .list_unspent()
+            .filter(|utxo| matches!(utxo.confirmation_time, ConfirmationTime::Unconfirmed { .. }))
+            .map(|utxo| utxo.outpoint)
+            .collect();
+
         let psbt = {
             let mut builder = self.build_tx();
             builder
                 .drain_wallet()
+                .unspendable(unconfirmed)
                 .add_foreign_utxo(challenge_txin.previous_output, challenge_psbt_inp, 42)?
                 .fee_absolute(0)
                 .only_witness_utxo()
@@ -168,16 +279,69 @@ where
 
         verify_proof(psbt, message, outpoints, self.network())
     }
+
+    fn historical_outputs(&self, height: u32) -> Vec<(OutPoint, TxOut)> {
+        let confirmed_at_or_below = |txid: Txid| -> bool {
+            match self.get_tx(txid) {
+                Some(tx_details) => match tx_details.chain_position.cloned().into() {
+                    ConfirmationTime::Confirmed {
+                        height: confirmation_height,
+                        ..
+                    } => confirmation_height <= height,
+                    ConfirmationTime::Unconfirmed { .. } => false,
+                },
+                None => false,
+            }
+        };
+
+        let graph = self.tx_graph();
+        let mut outputs: Vec<(OutPoint, TxOut)> = Vec::new();
+        for canonical_tx in self.transactions() {
+            let txid: Txid = canonical_tx.tx_node.txid;
+            if !confirmed_at_or_below(txid) {
+                continue;
+            }
+
+            for (vout, txout) in canonical_tx.tx_node.tx.output.iter().enumerate() {
+                if !self.is_mine(&txout.script_pubkey) {
+                    continue;
+                }
+
+                let outpoint = OutPoint::new(txid, vout as u32);
+                let spent: bool = graph
+                    .outspends(outpoint)
+                    .iter()
+                    .any(|spending_txid| confirmed_at_or_below(*spending_txid));
+                if !spent {
+                    outputs.push((outpoint, txout.clone()));
+                }
+            }
+        }
+
+        outputs
+    }
+
+    fn verify_proof_at_height<S>(
+        &self,
+        psbt: &PartiallySignedTransaction,
+        message: S,
+        height: u32,
+    ) -> Result<u64, ProofError>
+    where
+        S: Into<String>,
+    {
+        let outpoints = self.historical_outputs(height);
+        verify_proof(psbt, message, outpoints, self.network())
+    }
 }
 
 /// Make sure this is a proof, and not a spendable transaction.
-/// Make sure the proof is valid.
-/// Currently proofs can only be validated against the tip of the chain.
-/// If some of the UTXOs in the proof were spent in the meantime, the proof will fail.
-/// We can currently not validate whether it was valid at a certain block height.
-/// Since the caller provides the outpoints, he is also responsible to make sure they have enough confirmations.
+/// Make sure the proof is valid against the provided outpoints.
+/// To verify a proof at a certain block height, pass the outputs that were unspent at that height
+/// (see [`ProofOfReserves::historical_outputs`]).
+/// Since the caller provides the outpoints, they are also responsible to make sure they have enough confirmations.
 /// Returns the spendable amount of the proof.
-fn verify_proof<S>(
+pub fn verify_proof<S>(
     psbt: &PartiallySignedTransaction,
     message: S,
     outpoints: Vec<(OutPoint, TxOut)>,
@@ -308,6 +472,206 @@ where
     Ok(sum)
 }
 
+/// Derivation indexes always checked when deriving the scripts of a descriptor
+const DERIVATION_LOOKAHEAD: u32 = 1_000;
+
+/// Derive the scripts of `descriptor`, at the indexes `0..DERIVATION_LOOKAHEAD`
+/// and at the indexes found in the key origins of the PSBT inputs
+fn descriptor_scripts(
+    descriptor: &Descriptor<String>,
+    psbt: &PartiallySignedTransaction,
+) -> Result<HashSet<ScriptBuf>, ProofError> {
+    let descriptor: Descriptor<DescriptorPublicKey> =
+        Descriptor::from_str(&descriptor.to_string())?;
+
+    if !descriptor.has_wildcard() {
+        let script: ScriptBuf = descriptor.at_derivation_index(0)?.script_pubkey();
+        return Ok(HashSet::from([script]));
+    }
+
+    let key_sources = psbt.inputs.iter().flat_map(|input| {
+        input
+            .bip32_derivation
+            .values()
+            .chain(input.tap_key_origins.values().map(|(_, source)| source))
+    });
+    let mut indexes: BTreeSet<u32> = (0..DERIVATION_LOOKAHEAD).collect();
+    indexes.extend(
+        key_sources.filter_map(|(_, path): &KeySource| match path.into_iter().last() {
+            Some(ChildNumber::Normal { index }) => Some(*index),
+            _ => None,
+        }),
+    );
+
+    indexes
+        .into_iter()
+        .map(|index| Ok(descriptor.at_derivation_index(index)?.script_pubkey()))
+        .collect()
+}
+
+/// Verify an exported proof without a wallet or a nostr identity.
+/// The proof must have been created for `descriptor` and its inputs must be in the supplied `utxos`
+/// (for example, the UTXOs of the vault at the height the proof was created).
+///
+/// Every supplied UTXO must be a script of `descriptor` or, for aggregated proofs,
+/// of the descriptors of the other vaults.
+/// Returns the spendable amount of the proof belonging to `descriptor`.
+pub fn verify_exported_proof<S>(
+    proof: &ExportedProof,
+    descriptor: &Descriptor<String>,
+    message: S,
+    utxos: Vec<ProofUtxo>,
+    network: Network,
+) -> Result<u64, ProofError>
+where
+    S: Into<String>,
+{
+    if &proof.descriptor != descriptor && !proof.other_descriptors.contains(descriptor) {
+        return Err(ProofError::DescriptorMismatch);
+    }
+
+    // Scripts of the vault and of the other vaults of an aggregated proof
+    let scripts: HashSet<ScriptBuf> = descriptor_scripts(descriptor, &proof.psbt)?;
+    let mut other_scripts: HashSet<ScriptBuf> = HashSet::new();
+    for other in std::iter::once(&proof.descriptor)
+        .chain(proof.other_descriptors.iter())
+        .filter(|d| *d != descriptor)
+    {
+        other_scripts.extend(descriptor_scripts(other, &proof.psbt)?);
+    }
+
+    if let Some(utxo) = utxos.iter().find(|utxo| {
+        !scripts.contains(&utxo.script_pubkey) && !other_scripts.contains(&utxo.script_pubkey)
+    }) {
+        return Err(ProofError::ForeignUtxo(utxo.outpoint()));
+    }
+
+    let outpoints: Vec<(OutPoint, TxOut)> = utxos
+        .into_iter()
+        .map(|utxo| (utxo.outpoint(), utxo.txout()))
+        .collect();
+
+    verify_proof(&proof.psbt, message, outpoints.clone(), network)?;
+
+    // Sum only the inputs of the vault
+    Ok(proof
+        .psbt
+        .unsigned_tx
+        .input
+        .iter()
+        .skip(1)
+        .filter_map(|txin| {
+            outpoints
+                .iter()
+                .find(|(op, ..)| *op == txin.previous_output)
+        })
+        .filter(|(_, txout)| scripts.contains(&txout.script_pubkey))
+        .map(|(_, txout)| txout.value)
+        .sum())
+}
+
+/// Merge proofs created with the same message into a single proof.
+/// The merged proof has one challenge input, the inputs of every proof and one unspendable output.
+pub fn merge_proofs<I>(psbts: I) -> Result<PartiallySignedTransaction, ProofError>
+where
+    I: IntoIterator<Item = PartiallySignedTransaction>,
+{
+    let mut psbts = psbts.into_iter();
+    let mut merged: PartiallySignedTransaction = psbts.next().ok_or(ProofError::NoProofs)?;
+
+    if merged.unsigned_tx.output.len() != 1 {
+        return Err(ProofError::WrongNumberOfOutputs);
+    }
+    if merged.unsigned_tx.input.is_empty() {
+        return Err(ProofError::WrongNumberOfInputs);
+    }
+
+    for psbt in psbts {
+        if psbt.unsigned_tx.output.len() != 1 {
+            return Err(ProofError::WrongNumberOfOutputs);
+        }
+        if psbt.unsigned_tx.output[0].script_pubkey != merged.unsigned_tx.output[0].script_pubkey {
+            return Err(ProofError::InvalidOutput);
+        }
+        match psbt.unsigned_tx.input.first() {
+            Some(txin) if txin.previous_output == merged.unsigned_tx.input[0].previous_output => {}
+            _ => return Err(ProofError::ChallengeInputMismatch),
+        }
+
+        // Skip the challenge input and the already included UTXOs
+        for (txin, input) in psbt
+            .unsigned_tx
+            .input
+            .into_iter()
+            .zip(psbt.inputs.into_iter())
+            .skip(1)
+        {
+            if !merged
+                .unsigned_tx
+                .input
+                .iter()
+                .any(|i| i.previous_output == txin.previous_output)
+            {
+                merged.unsigned_tx.input.push(txin);
+                merged.inputs.push(input);
+            }
+        }
+
+        merged.xpub.extend(psbt.xpub);
+    }
+
+    // No miner fee: the output value is the sum of the inputs
+    merged.unsigned_tx.output[0].value = merged
+        .inputs
+        .iter()
+        .filter_map(|input| input.witness_utxo.as_ref())
+        .map(|txout| txout.value)
+        .sum();
+
+    Ok(merged)
+}
+
+/// Amounts proven by an aggregated proof
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AggregatedProofAmounts<K> {
+    /// Spendable amount of every vault
+    pub vaults: BTreeMap<K, u64>,
+    pub total: u64,
+}
+
+/// Verify a proof spanning several vaults.
+/// `vaults` holds the outpoints of every vault.
+/// Returns the spendable amount of each vault and the total.
+pub fn verify_aggregated_proof<K, S>(
+    psbt: &PartiallySignedTransaction,
+    message: S,
+    vaults: BTreeMap<K, Vec<(OutPoint, TxOut)>>,
+    network: Network,
+) -> Result<AggregatedProofAmounts<K>, ProofError>
+where
+    K: Ord,
+    S: Into<String>,
+{
+    let outpoints: Vec<(OutPoint, TxOut)> = vaults.values().flatten().cloned().collect();
+    let total: u64 = verify_proof(psbt, message, outpoints, network)?;
+
+    let inputs = &psbt.unsigned_tx.input;
+    let vaults: BTreeMap<K, u64> = vaults
+        .into_iter()
+        .map(|(vault, outpoints)| {
+            let amount: u64 = inputs
+                .iter()
+                .skip(1)
+                .filter_map(|txin| outpoints.iter().find(|op| op.0 == txin.previous_output))
+                .map(|(_, txout)| txout.value)
+                .sum();
+            (vault, amount)
+        })
+        .collect();
+
+    Ok(AggregatedProofAmounts { vaults, total })
+}
+
 /// Construct a challenge input with the message
 fn challenge_txin<S>(message: S) -> TxIn
 where
@@ -324,6 +688,8 @@ where
 
 #[cfg(test)]
 mod tests {
+    use keechain_core::bitcoin::{absolute, ScriptBuf, Transaction};
+
     use super::*;
     use crate::tests::get_funded_wallet;
 
@@ -404,6 +770,60 @@ mod tests {
     // assert_eq!(spendable, 50_000);
     // }
 
+    #[test]
+    fn historical_outputs() {
+        let mut wallet = get_funded_wallet(DESCRIPTOR).unwrap();
+
+        let sum = |outputs: Vec<(OutPoint, TxOut)>| -> u64 {
+            outputs.into_iter().map(|(_, txout)| txout.value).sum()
+        };
+
+        assert!(wallet.historical_outputs(2_477_483).is_empty());
+        assert_eq!(sum(wallet.historical_outputs(2_505_449)), 3_871);
+        assert_eq!(sum(wallet.historical_outputs(2_520_271)), 8_020);
+
+        // Spend the first UTXO
+        let utxo = wallet
+            .list_unspent()
+            .find(|utxo| utxo.txout.value == 371)
+            .unwrap();
+        wallet
+            .insert_tx(
+                Transaction {
+                    version: 1,
+                    lock_time: absolute::LockTime::ZERO,
+                    input: vec![TxIn {
+                        previous_output: utxo.outpoint,
+                        ..Default::default()
+                    }],
+                    output: vec![TxOut {
+                        value: 200,
+                        script_pubkey: ScriptBuf::new(),
+                    }],
+                },
+                ConfirmationTime::Confirmed {
+                    height: 2_520_300,
+                    time: 1696300000,
+                },
+            )
+            .unwrap();
+
+        // Outputs spent after the target height are still considered
+        assert_eq!(sum(wallet.historical_outputs(2_520_271)), 8_020);
+        assert_eq!(sum(wallet.historical_outputs(2_520_300)), 7_649);
+    }
+
+    #[test]
+    #[should_panic(expected = "NonSpendableInput")]
+    fn verify_at_height_before_funding() {
+        let wallet = get_funded_wallet(DESCRIPTOR).unwrap();
+
+        let psbt = get_signed_proof();
+        wallet
+            .verify_proof_at_height(&psbt, MESSAGE, 2_505_449)
+            .unwrap();
+    }
+
     #[test]
     #[should_panic(expected = "ChallengeInputMismatch")]
     fn wrong_message() {
@@ -0,0 +1,386 @@
+// Copyright (c) 2022-2024 Smart Vaults
+// Distributed under the MIT software license
+
+//! Signer import from wallet exports
+//!
+//! * Sparrow/Specter wallet exports and output descriptors
+//! * Coldcard, Keystone, Passport and Jade account exports (JSON)
+//! * Key origins (`[fingerprint/path]xpub`)
+//! * SLIP-132 keys (`ypub`, `zpub`, `Ypub`, `Zpub` and their testnet versions)
+
+use core::str::FromStr;
+
+use keechain_core::bips::bip32::{ChildNumber, DerivationPath, ExtendedPubKey, Fingerprint};
+use keechain_core::bitcoin::base58;
+use keechain_core::bitcoin::Network;
+use keechain_core::miniscript::{Descriptor, DescriptorPublicKey, ForEachKey};
+use serde_json::Value;
+
+use super::{Error, Signer};
+
+/// Mainnet `xpub` version bytes
+const XPUB_VERSION: [u8; 4] = [0x04, 0x88, 0xb2, 0x1e];
+/// Testnet `tpub` version bytes
+const TPUB_VERSION: [u8; 4] = [0x04, 0x35, 0x87, 0xcf];
+/// Mainnet SLIP-132 version bytes (`ypub`, `Ypub`, `zpub` and `Zpub`)
+const SLIP132_MAINNET_VERSIONS: [[u8; 4]; 4] = [
+    [0x04, 0x9d, 0x7c, 0xb2],
+    [0x02, 0x95, 0xb4, 0x3f],
+    [0x04, 0xb2, 0x47, 0x46],
+    [0x02, 0xaa, 0x7e, 0xd3],
+];
+/// Testnet SLIP-132 version bytes (`upub`, `Upub`, `vpub` and `Vpub`)
+const SLIP132_TESTNET_VERSIONS: [[u8; 4]; 4] = [
+    [0x04, 0x4a, 0x52, 0x62],
+    [0x02, 0x42, 0x89, 0xef],
+    [0x04, 0x5f, 0x1c, 0xf6],
+    [0x02, 0x57, 0x54, 0x83],
+];
+
+/// Fingerprint fields of the account exports
+const FINGERPRINT_FIELDS: [&str; 4] = [
+    "xfp",
+    "MasterFingerprint",
+    "master_fingerprint",
+    "fingerprint",
+];
+/// `(path, key)` fields of the account exports
+const KEY_FIELDS: [(&str, &str); 7] = [
+    ("p2tr_deriv", "p2tr"),
+    ("p2wsh_deriv", "p2wsh"),
+    ("p2sh_p2wsh_deriv", "p2sh_p2wsh"),
+    ("AccountKeyPath", "ExtPubKey"),
+    ("deriv", "xpub"),
+    ("derivation", "xpub"),
+    ("path", "xpub"),
+];
+
+/// Signer export format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SignerExportFormat {
+    /// Sparrow/Specter wallet export (JSON) or output descriptor
+    Wallet,
+    /// Coldcard, Keystone, Passport or Jade account export (JSON)
+    Account,
+    /// Key origin (`[fingerprint/path]xpub`)
+    KeyOrigin,
+}
+
+impl SignerExportFormat {
+    /// Detect the format of an export
+    pub fn detect<S>(export: S) -> Self
+    where
+        S: AsRef<str>,
+    {
+        let export: &str = export.as_ref().trim();
+        if export.starts_with('{') {
+            match serde_json::from_str::<Value>(export) {
+                Ok(json) if json.get("descriptor").is_some() => Self::Wallet,
+                _ => Self::Account,
+            }
+        } else if export.starts_with('[') {
+            Self::KeyOrigin
+        } else {
+            Self::Wallet
+        }
+    }
+}
+
+impl Signer {
+    /// Build [`Signer`] from an export, detecting its [`SignerExportFormat`]
+    pub fn from_export<S, E>(name: S, export: E, network: Network) -> Result<Self, Error>
+    where
+        S: Into<String>,
+        E: AsRef<str>,
+    {
+        let export: &str = export.as_ref();
+        match SignerExportFormat::detect(export) {
+            SignerExportFormat::Wallet => Self::from_wallet_export(name, export, network),
+            SignerExportFormat::Account => Self::from_account_export(name, export, network),
+            SignerExportFormat::KeyOrigin => Self::from_key_origin(name, export, network),
+        }
+    }
+
+    /// Build taproot [`Signer`] from a key origin (`[fingerprint/path]xpub`)
+    ///
+    /// The key can be a SLIP-132 key and must be a BIP86 or BIP48 account key.
+    pub fn from_key_origin<S, K>(name: S, key_origin: K, network: Network) -> Result<Self, Error>
+    where
+        S: Into<String>,
+        K: AsRef<str>,
+    {
+        let key_origin: &str = key_origin.as_ref().trim();
+        let (origin, key) = key_origin
+            .strip_prefix('[')
+            .and_then(|k| k.split_once(']'))
+            .ok_or(Error::InvalidKeyOrigin)?;
+        let (fingerprint, path) = origin.split_once('/').ok_or(Error::InvalidKeyOrigin)?;
+        let fingerprint: Fingerprint = parse_fingerprint(fingerprint)?;
+        let path: DerivationPath = parse_path(path)?;
+        // Skip the derivation steps after the key (i.e. `/0/*`)
+        let xpub: ExtendedPubKey = parse_xpub(key.split('/').next().unwrap_or_default())?;
+        Self::from_account_key(name, fingerprint, path, xpub, network)
+    }
+
+    /// Build [`Signer`] from a Sparrow/Specter wallet export or from an output descriptor
+    ///
+    /// The wallet must have a single key, with a BIP48 or BIP86 account origin: whatever the script
+    /// type (i.e. `wpkh`, `wsh`), the descriptor is converted into a taproot descriptor.
+    pub fn from_wallet_export<S, E>(name: S, export: E, network: Network) -> Result<Self, Error>
+    where
+        S: Into<String>,
+        E: AsRef<str>,
+    {
+        let export: &str = export.as_ref().trim();
+        let descriptor: String = if export.starts_with('{') {
+            let json: Value = serde_json::from_str(export)?;
+            json.get("descriptor")
+                .and_then(|d| d.as_str())
+                .ok_or(Error::KeyNotFound)?
+                .to_string()
+        } else {
+            // Skip comments (i.e. Sparrow output descriptor exports)
+            export
+                .lines()
+                .map(|l| l.trim())
+                .find(|l| !l.is_empty() && !l.starts_with('#'))
+                .ok_or(Error::KeyNotFound)?
+                .to_string()
+        };
+        let descriptor: Descriptor<DescriptorPublicKey> = Descriptor::from_str(&descriptor)?;
+
+        let mut keys: Vec<DescriptorPublicKey> = Vec::new();
+        descriptor.for_each_key(|key| {
+            keys.push(key.clone());
+            true
+        });
+        if keys.len() != 1 {
+            return Err(Error::NotSingleKeyDescriptor);
+        }
+
+        match keys.remove(0) {
+            DescriptorPublicKey::XPub(key) => {
+                let (fingerprint, path) = key.origin.ok_or(Error::InvalidKeyOrigin)?;
+                Self::from_account_key(name, fingerprint, path, key.xkey, network)
+            }
+            DescriptorPublicKey::MultiXPub(key) => {
+                let (fingerprint, path) = key.origin.ok_or(Error::InvalidKeyOrigin)?;
+                Self::from_account_key(name, fingerprint, path, key.xkey, network)
+            }
+            DescriptorPublicKey::Single(..) => Err(Error::InvalidKeyOrigin),
+        }
+    }
+
+    /// Build taproot [`Signer`] from a Coldcard, Keystone, Passport or Jade account export (JSON)
+    ///
+    /// BIP48 taproot keys are preferred over BIP86 ones and BIP48 P2WSH keys.
+    pub fn from_account_export<S, E>(name: S, export: E, network: Network) -> Result<Self, Error>
+    where
+        S: Into<String>,
+        E: AsRef<str>,
+    {
+        let json: Value = serde_json::from_str(export.as_ref())?;
+        let master_fingerprint: Option<&str> = find_str(&json, &FINGERPRINT_FIELDS);
+
+        // Collect the account keys, both at top level and in nested objects (i.e. Coldcard `bip86`)
+        let mut candidates: Vec<(&str, &str, &str)> = Vec::new();
+        let mut objects: Vec<(Option<&str>, &Value)> = vec![(master_fingerprint, &json)];
+        if let Some(obj) = json.as_object() {
+            for value in obj.values().filter(|v| v.is_object()) {
+                let fingerprint = find_str(value, &FINGERPRINT_FIELDS).or(master_fingerprint);
+                objects.push((fingerprint, value));
+            }
+        }
+        for (fingerprint, value) in objects.into_iter() {
+            if let Some(fingerprint) = fingerprint {
+                for (path_field, key_field) in KEY_FIELDS.iter() {
+                    if let (Some(path), Some(key)) = (
+                        value.get(path_field).and_then(|v| v.as_str()),
+                        value.get(key_field).and_then(|v| v.as_str()),
+                    ) {
+                        candidates.push((fingerprint, path, key));
+                    }
+                }
+            }
+        }
+
+        let mut best: Option<(u8, Fingerprint, DerivationPath, &str)> = None;
+        for (fingerprint, path, key) in candidates.into_iter() {
+            let path: DerivationPath = parse_path(path)?;
+            if let Some(priority) = path_priority(&path) {
+                if best.as_ref().map_or(true, |(p, ..)| priority < *p) {
+                    best = Some((priority, parse_fingerprint(fingerprint)?, path, key));
+                }
+            }
+        }
+
+        let (_, fingerprint, path, key) = best.ok_or(Error::KeyNotFound)?;
+        let xpub: ExtendedPubKey = parse_xpub(key)?;
+        Self::from_account_key(name, fingerprint, path, xpub, network)
+    }
+
+    fn from_account_key<S>(
+        name: S,
+        fingerprint: Fingerprint,
+        path: DerivationPath,
+        xpub: ExtendedPubKey,
+        network: Network,
+    ) -> Result<Self, Error>
+    where
+        S: Into<String>,
+    {
+        if path_priority(&path).is_none() {
+            return Err(Error::UnsupportedDerivationPath(path));
+        }
+
+        let path: String = path.to_string();
+        let path: &str = path.trim_start_matches('m');
+        let descriptor = Descriptor::from_str(&format!("tr([{fingerprint}{path}]{xpub}/0/*)"))?;
+        Self::airgap(name, None, fingerprint, descriptor, network)
+    }
+}
+
+/// Priority of the account path (lower is better), `None` if not a BIP48 or BIP86 account path
+fn path_priority(path: &DerivationPath) -> Option<u8> {
+    let path: &[ChildNumber] = path.as_ref();
+    match path {
+        [ChildNumber::Hardened { index: 48 }, ChildNumber::Hardened { .. }, ChildNumber::Hardened { .. }, ChildNumber::Hardened { index: script }] => {
+            match *script {
+                3 => Some(0),
+                2 => Some(2),
+                1 => Some(3),
+                _ => None,
+            }
+        }
+        [ChildNumber::Hardened { index: 86 }, ChildNumber::Hardened { .. }, ChildNumber::Hardened { .. }] => {
+            Some(1)
+        }
+        _ => None,
+    }
+}
+
+fn find_str<'a>(json: &'a Value, fields: &[&str]) -> Option<&'a str> {
+    fields
+        .iter()
+        .find_map(|field| json.get(field).and_then(|v| v.as_str()))
+}
+
+fn parse_fingerprint(fingerprint: &str) -> Result<Fingerprint, Error> {
+    Fingerprint::from_str(&fingerprint.trim().to_lowercase())
+        .map_err(|_| Error::InvalidFingerprint(fingerprint.to_string()))
+}
+
+/// Parse a derivation path, with or without the `m/` prefix
+fn parse_path(path: &str) -> Result<DerivationPath, Error> {
+    let path: &str = path.trim();
+    let path: &str = path.strip_prefix("m/").unwrap_or(path);
+    let path: Vec<ChildNumber> = path
+        .split('/')
+        .filter(|c| !c.is_empty() && *c != "m")
+        .map(ChildNumber::from_str)
+        .collect::<Result<_, _>>()?;
+    Ok(DerivationPath::from(path))
+}
+
+/// Parse an extended public key, converting the SLIP-132 keys to `xpub`/`tpub`
+fn parse_xpub(key: &str) -> Result<ExtendedPubKey, Error> {
+    let mut data: Vec<u8> = base58::decode_check(key.trim())?;
+    if data.len() < 4 {
+        return Err(Error::InvalidExtendedPubKey);
+    }
+
+    let version: [u8; 4] = [data[0], data[1], data[2], data[3]];
+    if SLIP132_MAINNET_VERSIONS.contains(&version) {
+        data[..4].copy_from_slice(&XPUB_VERSION);
+    } else if SLIP132_TESTNET_VERSIONS.contains(&version) {
+        data[..4].copy_from_slice(&TPUB_VERSION);
+    }
+
+    Ok(ExtendedPubKey::decode(&data)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NETWORK: Network = Network::Testnet;
+    const TPUB: &str = "tpubDCvLwbJPseNux9EtPbrbA2tgDayzptK4HNkky14Cw6msjHuqyZCE88miedZD86TZUb29Rof3sgtREU4wtzofte7QDSWDiw8ZU6ZYHmAxY9d";
+    const VPUB: &str = "Vpub5jnGwFsKRxtsQKqBDMmEN1i7GKdiAcWbDnmgxvUqCx6dVzBbARQHpgeYAtWjADRP6sKcBrxBJUjmtsxyMyHMZDQaFdUUGzJMER7iLcdraNQ";
+
+    #[test]
+    fn test_from_key_origin() {
+        let signer =
+            Signer::from_key_origin("", format!("[7356e457/48h/1h/0h/2h]{VPUB}"), NETWORK).unwrap();
+        assert_eq!(signer.fingerprint().to_string(), "7356e457");
+        assert_eq!(
+            signer.descriptor().to_string().split('#').next().unwrap(),
+            format!("tr([7356e457/48'/1'/0'/2']{TPUB}/0/*)")
+        );
+        assert_eq!(
+            SignerExportFormat::detect(format!("[7356e457/48'/1'/0'/2']{TPUB}/0/*")),
+            SignerExportFormat::KeyOrigin
+        );
+
+        // Not a BIP48 or BIP86 account
+        assert!(matches!(
+            Signer::from_key_origin("", format!("[7356e457/84'/1'/0']{TPUB}"), NETWORK),
+            Err(Error::UnsupportedDerivationPath(..))
+        ));
+    }
+
+    #[test]
+    fn test_from_wallet_export() {
+        let export = format!(
+            r#"{{"label":"Sparrow","blockheight":0,"descriptor":"wpkh([7356e457/86'/1'/0']{TPUB}/<0;1>/*)"}}"#
+        );
+        assert_eq!(
+            SignerExportFormat::detect(&export),
+            SignerExportFormat::Wallet
+        );
+        let signer = Signer::from_export("", &export, NETWORK).unwrap();
+        assert_eq!(
+            signer.descriptor().to_string().split('#').next().unwrap(),
+            format!("tr([7356e457/86'/1'/0']{TPUB}/0/*)")
+        );
+
+        let export = format!("# Receive descriptor\nwsh(pk([7356e457/48'/1'/0'/2']{TPUB}/0/*))");
+        let signer = Signer::from_export("", export, NETWORK).unwrap();
+        assert_eq!(
+            signer.descriptor().to_string().split('#').next().unwrap(),
+            format!("tr([7356e457/48'/1'/0'/2']{TPUB}/0/*)")
+        );
+
+        // Not a BIP48 or BIP86 account
+        let export = format!("tr([7356e457/84'/1'/0']{TPUB}/0/*)");
+        assert!(matches!(
+            Signer::from_wallet_export("", export, NETWORK),
+            Err(Error::UnsupportedDerivationPath(..))
+        ));
+    }
+
+    #[test]
+    fn test_from_account_export() {
+        // Coldcard-like multisig export (Keystone, Passport, Jade)
+        let export = format!(
+            r#"{{"xfp":"7356E457","account":"0","p2sh_p2wsh_deriv":"m/48'/1'/0'/1'","p2sh_p2wsh":"{TPUB}","p2wsh_deriv":"m/48'/1'/0'/2'","p2wsh":"{VPUB}"}}"#
+        );
+        assert_eq!(
+            SignerExportFormat::detect(&export),
+            SignerExportFormat::Account
+        );
+        let signer = Signer::from_export("", export, NETWORK).unwrap();
+        assert_eq!(
+            signer.descriptor().to_string().split('#').next().unwrap(),
+            format!("tr([7356e457/48'/1'/0'/2']{TPUB}/0/*)")
+        );
+
+        // Keystone account export, not a BIP48 or BIP86 account
+        let export = format!(
+            r#"{{"ExtPubKey":"{TPUB}","MasterFingerprint":"7356E457","AccountKeyPath":"m/84'/1'/0'"}}"#
+        );
+        assert!(matches!(
+            Signer::from_account_export("", export, NETWORK),
+            Err(Error::KeyNotFound)
+        ));
+    }
+}
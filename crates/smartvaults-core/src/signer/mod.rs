@@ -2,20 +2,28 @@
 // Distributed under the MIT software license
 
 use core::fmt;
+use core::str::FromStr;
 
 use bdk::descriptor::IntoWalletDescriptor;
 use bdk::miniscript::descriptor::Tr;
-use keechain_core::bips::bip32::{self, Bip32, Fingerprint};
+use keechain_core::bips::bip32::{
+    self, Bip32, ChildNumber, DerivationPath, ExtendedPrivKey, ExtendedPubKey, Fingerprint,
+};
 use keechain_core::bips::bip48::ScriptType;
 use keechain_core::bitcoin::Network;
 use keechain_core::crypto::hash;
 use keechain_core::descriptors::{self, ToDescriptor};
-use keechain_core::miniscript::descriptor::{DescriptorKeyParseError, DescriptorType};
-use keechain_core::miniscript::{Descriptor, DescriptorPublicKey};
+use keechain_core::miniscript::descriptor::{
+    DescriptorKeyParseError, DescriptorSecretKey, DescriptorType, DescriptorXKey,
+};
+use keechain_core::miniscript::{Descriptor, DescriptorPublicKey, ForEachKey};
 use keechain_core::{ColdcardGenericJson, Purpose, Seed};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+mod import;
+
+pub use self::import::SignerExportFormat;
 use crate::constants::SMARTVAULTS_ACCOUNT_INDEX;
 use crate::SECP256K1;
 
@@ -33,8 +41,26 @@ pub enum Error {
     BdkDescriptor(#[from] bdk::descriptor::DescriptorError),
     #[error(transparent)]
     Coldcard(#[from] keechain_core::export::coldcard::Error),
-    #[error("must be a taproot descriptor")]
-    NotTaprootDescriptor,
+    #[error("unsupported descriptor: must be a taproot or (sh-)wsh descriptor")]
+    UnsupportedDescriptor,
+    #[error("descriptor must contain exactly one key")]
+    NotSingleKeyDescriptor,
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error(transparent)]
+    Base58(#[from] keechain_core::bitcoin::base58::Error),
+    #[error("invalid key origin: must be `[fingerprint/path]xpub`")]
+    InvalidKeyOrigin,
+    #[error("invalid fingerprint: {0}")]
+    InvalidFingerprint(String),
+    #[error("invalid extended public key")]
+    InvalidExtendedPubKey,
+    #[error("unsupported derivation path {0}: must be a BIP48 or BIP86 account path")]
+    UnsupportedDerivationPath(DerivationPath),
+    #[error("account key not found")]
+    KeyNotFound,
+    #[error("signer not derived from the seed")]
+    SeedMismatch,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
@@ -70,7 +96,10 @@ impl fmt::Display for Signer {
 }
 
 impl Signer {
-    fn new<S>(
+    /// Compose signer
+    ///
+    /// The `descriptor` must be a single key (sh-)wsh or taproot descriptor of the `network`.
+    pub fn new<S>(
         name: S,
         description: Option<S>,
         fingerprint: Fingerprint,
@@ -81,7 +110,9 @@ impl Signer {
     where
         S: Into<String>,
     {
-        if let DescriptorType::Tr = descriptor.desc_type() {
+        if let DescriptorType::Tr | DescriptorType::Wsh | DescriptorType::ShWsh =
+            descriptor.desc_type()
+        {
             // Check network
             descriptor
                 .clone()
@@ -96,7 +127,7 @@ impl Signer {
                 t,
             })
         } else {
-            Err(Error::NotTaprootDescriptor)
+            Err(Error::UnsupportedDescriptor)
         }
     }
 
@@ -182,10 +213,30 @@ impl Signer {
         Self::airgap(name, None, coldcard.fingerprint(), descriptor, network)
     }
 
+    /// Build P2WSH [`Signer`] from Coldcard generic JSON
+    pub fn from_coldcard_wsh<S>(
+        name: S,
+        coldcard: ColdcardGenericJson,
+        network: Network,
+    ) -> Result<Self, Error>
+    where
+        S: Into<String>,
+    {
+        let descriptor = coldcard.descriptor(Purpose::BIP48 {
+            script: ScriptType::P2WSH,
+        })?;
+        let descriptor = Descriptor::from_str(&format!("wsh(pk({descriptor}))"))?;
+        Self::airgap(name, None, coldcard.fingerprint(), descriptor, network)
+    }
+
     pub fn name(&self) -> String {
         self.name.clone()
     }
 
+    pub fn description(&self) -> Option<String> {
+        self.description.clone()
+    }
+
     pub fn fingerprint(&self) -> Fingerprint {
         self.fingerprint
     }
@@ -195,16 +246,53 @@ impl Signer {
     }
 
     pub fn descriptor_public_key(&self) -> Result<DescriptorPublicKey, Error> {
-        match &self.descriptor {
-            Descriptor::Tr(tr) => Ok(tr.internal_key().clone()),
-            _ => Err(Error::NotTaprootDescriptor),
-        }
+        descriptor_public_key(&self.descriptor)
     }
 
     pub fn signer_type(&self) -> SignerType {
         self.t
     }
 
+    /// Account index (last hardened step of the key origin path, i.e. `m/86'/0'/<account>'`)
+    pub fn account(&self) -> Option<u32> {
+        match self.descriptor_public_key().ok()? {
+            DescriptorPublicKey::XPub(key) => {
+                let (_, path) = key.origin?;
+                match path.as_ref().get(2)? {
+                    ChildNumber::Hardened { index } => Some(*index),
+                    ChildNumber::Normal { .. } => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Derive the account secret key of a signer built from `seed`
+    pub fn secret_key(&self, seed: &Seed, network: Network) -> Result<DescriptorSecretKey, Error> {
+        match self.descriptor_public_key()? {
+            DescriptorPublicKey::XPub(key) => {
+                let (fingerprint, path) = key.origin.ok_or(Error::SeedMismatch)?;
+                if fingerprint != seed.fingerprint(network, &SECP256K1)? {
+                    return Err(Error::SeedMismatch);
+                }
+
+                let root: ExtendedPrivKey = seed.to_bip32_root_key(network)?;
+                let xprv: ExtendedPrivKey = root.derive_priv(&SECP256K1, &path)?;
+                if ExtendedPubKey::from_priv(&SECP256K1, &xprv) != key.xkey {
+                    return Err(Error::SeedMismatch);
+                }
+
+                Ok(DescriptorSecretKey::XPrv(DescriptorXKey {
+                    origin: Some((fingerprint, path)),
+                    xkey: xprv,
+                    derivation_path: key.derivation_path,
+                    wildcard: key.wildcard,
+                }))
+            }
+            _ => Err(Error::SeedMismatch),
+        }
+    }
+
     /// Generate deterministic identifier
     pub fn generate_identifier(&self, network: Network) -> String {
         let unhashed: String = format!("{}:{}", network.magic(), self.fingerprint);
@@ -217,6 +305,28 @@ impl Signer {
     }
 }
 
+/// Get the signer key: the internal key for taproot or the single key for (sh-)wsh descriptors
+fn descriptor_public_key(
+    descriptor: &Descriptor<DescriptorPublicKey>,
+) -> Result<DescriptorPublicKey, Error> {
+    match descriptor {
+        Descriptor::Tr(tr) => Ok(tr.internal_key().clone()),
+        Descriptor::Wsh(..) | Descriptor::Sh(..) => {
+            let mut keys: Vec<DescriptorPublicKey> = Vec::new();
+            descriptor.for_each_key(|key| {
+                keys.push(key.clone());
+                true
+            });
+            if keys.len() == 1 {
+                Ok(keys.remove(0))
+            } else {
+                Err(Error::NotSingleKeyDescriptor)
+            }
+        }
+        _ => Err(Error::UnsupportedDescriptor),
+    }
+}
+
 pub fn smartvaults_signer(seed: Seed, network: Network) -> Result<Signer, Error> {
     Signer::from_seed(
         "SmartVaults",
@@ -243,6 +353,13 @@ impl From<Signer> for SharedSigner {
 }
 
 impl SharedSigner {
+    pub fn new(fingerprint: Fingerprint, descriptor: Descriptor<DescriptorPublicKey>) -> Self {
+        Self {
+            fingerprint,
+            descriptor,
+        }
+    }
+
     pub fn fingerprint(&self) -> Fingerprint {
         self.fingerprint
     }
@@ -252,9 +369,6 @@ impl SharedSigner {
     }
 
     pub fn descriptor_public_key(&self) -> Result<DescriptorPublicKey, Error> {
-        match &self.descriptor {
-            Descriptor::Tr(tr) => Ok(tr.internal_key().clone()),
-            _ => Err(Error::NotTaprootDescriptor),
-        }
+        descriptor_public_key(&self.descriptor)
     }
 }
@@ -0,0 +1,264 @@
+// Copyright (c) 2022-2024 Smart Vaults
+// Distributed under the MIT software license
+
+//! Spending rules
+//!
+//! Per-transaction caps, rolling-window caps and address allowlist of a vault.
+
+use keechain_core::bitcoin::address::NetworkUnchecked;
+use keechain_core::bitcoin::Address;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum Error {
+    #[error("spending {amount} sat exceeds the per-transaction limit of {max} sat")]
+    MaxPerTxExceeded { amount: u64, max: u64 },
+    #[error("spending {amount} sat exceeds the limit of {max} sat every {period} secs ({spent} sat already spent)")]
+    WindowExceeded {
+        amount: u64,
+        spent: u64,
+        max: u64,
+        period: u64,
+    },
+    #[error("address not allowed: {0}")]
+    AddressNotAllowed(String),
+    #[error("output without an address not allowed ({0} sat)")]
+    OutputWithoutAddress(u64),
+    #[error("a description is required when spending more than {0} sat")]
+    DescriptionRequired(u64),
+}
+
+/// Rolling-window cap
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SpendingWindow {
+    /// Max amount in sat
+    pub max: u64,
+    /// Window length in secs
+    pub period: u64,
+}
+
+impl SpendingWindow {
+    pub fn new(max: u64, period: u64) -> Self {
+        Self { max, period }
+    }
+
+    /// Max `max` sat every 24 hours
+    pub fn daily(max: u64) -> Self {
+        Self::new(max, 60 * 60 * 24)
+    }
+}
+
+/// Spending record, used as history for the rolling windows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SpendingRecord {
+    /// Unix timestamp
+    pub timestamp: u64,
+    /// Amount in sat
+    pub amount: u64,
+}
+
+/// Outgoing payment (output not owned by the vault)
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct OutgoingPayment {
+    /// `None` if the output script has not an address form (i.e. `OP_RETURN`)
+    pub address: Option<Address<NetworkUnchecked>>,
+    /// Amount in sat
+    pub amount: u64,
+}
+
+/// Vault spending rules
+///
+/// Only the outgoing payments (change and outputs to the vault itself excluded) must be checked.
+/// Outputs without an address count against the caps and never match the allowlist.
+///
+/// The rules are part of the vault metadata, that every member can replace: they guard against
+/// mistakes and compromised proposers, not against a member holding the vault shared key.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpendingRules {
+    /// Max amount in sat for a single transaction
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_per_tx: Option<u64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub windows: Vec<SpendingWindow>,
+    /// Allowed recipients (empty = any)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowlist: Vec<Address<NetworkUnchecked>>,
+    /// Spending more than this amount in sat requires a description
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description_required_above: Option<u64>,
+}
+
+impl SpendingRules {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Check if there are no rules
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Check an outgoing payment made at `now` (unix timestamp) against the rules
+    pub fn check<I>(
+        &self,
+        payments: &[OutgoingPayment],
+        description: &str,
+        history: I,
+        now: u64,
+    ) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = SpendingRecord>,
+    {
+        let amount: u64 = payments.iter().map(|p| p.amount).sum();
+
+        if let Some(max) = self.max_per_tx {
+            if amount > max {
+                return Err(Error::MaxPerTxExceeded { amount, max });
+            }
+        }
+
+        if let Some(threshold) = self.description_required_above {
+            if amount > threshold && description.trim().is_empty() {
+                return Err(Error::DescriptionRequired(threshold));
+            }
+        }
+
+        if !self.allowlist.is_empty() {
+            for payment in payments.iter() {
+                match &payment.address {
+                    Some(address) => {
+                        if !self.allowlist.iter().any(|a| a.payload == address.payload) {
+                            return Err(Error::AddressNotAllowed(
+                                address.clone().assume_checked().to_string(),
+                            ));
+                        }
+                    }
+                    None => return Err(Error::OutputWithoutAddress(payment.amount)),
+                }
+            }
+        }
+
+        if !self.windows.is_empty() {
+            let history: Vec<SpendingRecord> = history.into_iter().collect();
+            for window in self.windows.iter() {
+                let since: u64 = now.saturating_sub(window.period);
+                let spent: u64 = history
+                    .iter()
+                    .filter(|r| r.timestamp > since && r.timestamp <= now)
+                    .map(|r| r.amount)
+                    .sum();
+                if spent.saturating_add(amount) > window.max {
+                    return Err(Error::WindowExceeded {
+                        amount,
+                        spent,
+                        max: window.max,
+                        period: window.period,
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn recipient(address: &str, amount: u64) -> OutgoingPayment {
+        OutgoingPayment {
+            address: Some(Address::from_str(address).unwrap()),
+            amount,
+        }
+    }
+
+    #[test]
+    fn test_spending_rules() {
+        let rules = SpendingRules {
+            max_per_tx: Some(100_000),
+            windows: vec![SpendingWindow::daily(150_000)],
+            allowlist: vec![
+                Address::from_str("tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx").unwrap(),
+            ],
+            description_required_above: Some(50_000),
+        };
+        let allowed = "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx";
+
+        assert!(rules
+            .check(&[recipient(allowed, 10_000)], "", Vec::new(), NOW)
+            .is_ok());
+        assert_eq!(
+            rules.check(&[recipient(allowed, 120_000)], "Rent", Vec::new(), NOW),
+            Err(Error::MaxPerTxExceeded {
+                amount: 120_000,
+                max: 100_000
+            })
+        );
+        assert_eq!(
+            rules.check(&[recipient(allowed, 60_000)], " ", Vec::new(), NOW),
+            Err(Error::DescriptionRequired(50_000))
+        );
+        assert!(matches!(
+            rules.check(
+                &[recipient("mohjSavDdQYHRYXcS3uS6ttaHP8amyvX78", 10_000)],
+                "",
+                Vec::new(),
+                NOW
+            ),
+            Err(Error::AddressNotAllowed(..))
+        ));
+        let no_address = OutgoingPayment {
+            address: None,
+            amount: 10_000,
+        };
+        assert_eq!(
+            rules.check(&[no_address.clone()], "", Vec::new(), NOW),
+            Err(Error::OutputWithoutAddress(10_000))
+        );
+        assert_eq!(
+            SpendingRules {
+                max_per_tx: Some(100_000),
+                ..Default::default()
+            }
+            .check(
+                &[recipient(allowed, 95_000), no_address],
+                "",
+                Vec::new(),
+                NOW
+            ),
+            Err(Error::MaxPerTxExceeded {
+                amount: 105_000,
+                max: 100_000
+            })
+        );
+
+        // Rolling window
+        let history = vec![
+            SpendingRecord {
+                timestamp: NOW - 60 * 60,
+                amount: 100_000,
+            },
+            SpendingRecord {
+                timestamp: NOW - 60 * 60 * 48,
+                amount: 100_000,
+            },
+        ];
+        assert!(rules
+            .check(&[recipient(allowed, 50_000)], "Rent", history.clone(), NOW)
+            .is_ok());
+        assert_eq!(
+            rules.check(&[recipient(allowed, 60_000)], "Rent", history, NOW),
+            Err(Error::WindowExceeded {
+                amount: 60_000,
+                spent: 100_000,
+                max: 150_000,
+                period: 60 * 60 * 24
+            })
+        );
+    }
+}
@@ -0,0 +1,273 @@
+// Copyright (c) 2022-2024 Smart Vaults
+// Distributed under the MIT software license
+
+//! Minimal CBOR (RFC 8949) codec
+//!
+//! Only the subset needed by the UR registry types is supported (no floats, no indefinite lengths).
+
+use super::Error;
+
+const MAJOR_UNSIGNED: u8 = 0;
+const MAJOR_NEGATIVE: u8 = 1;
+const MAJOR_BYTES: u8 = 2;
+const MAJOR_TEXT: u8 = 3;
+const MAJOR_ARRAY: u8 = 4;
+const MAJOR_MAP: u8 = 5;
+const MAJOR_TAG: u8 = 6;
+const MAJOR_SIMPLE: u8 = 7;
+
+/// Max nesting level
+const MAX_DEPTH: usize = 16;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Value {
+    Unsigned(u64),
+    Negative(u64),
+    Bytes(Vec<u8>),
+    Text(String),
+    Array(Vec<Value>),
+    Map(Vec<(Value, Value)>),
+    Tag(u64, Box<Value>),
+    Bool(bool),
+    Null,
+}
+
+impl Value {
+    pub fn decode(bytes: &[u8]) -> Result<Self, Error> {
+        let mut decoder = Decoder { bytes, pos: 0 };
+        let value = decoder.value(0)?;
+        if decoder.pos != bytes.len() {
+            return Err(Error::Cbor("trailing bytes"));
+        }
+        Ok(value)
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf: Vec<u8> = Vec::new();
+        self.encode_into(&mut buf);
+        buf
+    }
+
+    fn encode_into(&self, buf: &mut Vec<u8>) {
+        match self {
+            Self::Unsigned(n) => write_header(buf, MAJOR_UNSIGNED, *n),
+            Self::Negative(n) => write_header(buf, MAJOR_NEGATIVE, *n),
+            Self::Bytes(bytes) => {
+                write_header(buf, MAJOR_BYTES, bytes.len() as u64);
+                buf.extend_from_slice(bytes);
+            }
+            Self::Text(text) => {
+                write_header(buf, MAJOR_TEXT, text.len() as u64);
+                buf.extend_from_slice(text.as_bytes());
+            }
+            Self::Array(values) => {
+                write_header(buf, MAJOR_ARRAY, values.len() as u64);
+                for value in values.iter() {
+                    value.encode_into(buf);
+                }
+            }
+            Self::Map(entries) => {
+                write_header(buf, MAJOR_MAP, entries.len() as u64);
+                for (key, value) in entries.iter() {
+                    key.encode_into(buf);
+                    value.encode_into(buf);
+                }
+            }
+            Self::Tag(tag, value) => {
+                write_header(buf, MAJOR_TAG, *tag);
+                value.encode_into(buf);
+            }
+            Self::Bool(false) => buf.push((MAJOR_SIMPLE << 5) | 20),
+            Self::Bool(true) => buf.push((MAJOR_SIMPLE << 5) | 21),
+            Self::Null => buf.push((MAJOR_SIMPLE << 5) | 22),
+        }
+    }
+
+    /// Get the value of an unsigned integer key of a map
+    pub fn get(&self, key: u64) -> Option<&Value> {
+        match self {
+            Self::Map(entries) => entries.iter().find_map(|(k, v)| match k {
+                Self::Unsigned(n) if *n == key => Some(v),
+                _ => None,
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            Self::Unsigned(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Self::Bytes(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Self::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    /// Remove the tag, if it's equal to `tag`
+    pub fn untag(&self, tag: u64) -> &Value {
+        match self {
+            Self::Tag(t, value) if *t == tag => value,
+            _ => self,
+        }
+    }
+}
+
+fn write_header(buf: &mut Vec<u8>, major: u8, n: u64) {
+    let major: u8 = major << 5;
+    if n < 24 {
+        buf.push(major | n as u8);
+    } else if n <= u8::MAX as u64 {
+        buf.push(major | 24);
+        buf.push(n as u8);
+    } else if n <= u16::MAX as u64 {
+        buf.push(major | 25);
+        buf.extend_from_slice(&(n as u16).to_be_bytes());
+    } else if n <= u32::MAX as u64 {
+        buf.push(major | 26);
+        buf.extend_from_slice(&(n as u32).to_be_bytes());
+    } else {
+        buf.push(major | 27);
+        buf.extend_from_slice(&n.to_be_bytes());
+    }
+}
+
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        let end: usize = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or(Error::Cbor("unexpected end of data"))?;
+        let slice: &[u8] = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn header(&mut self) -> Result<(u8, u8, u64), Error> {
+        let byte: u8 = self.take(1)?[0];
+        let major: u8 = byte >> 5;
+        let info: u8 = byte & 0x1f;
+        let n: u64 = match info {
+            0..=23 => info as u64,
+            24 => self.take(1)?[0] as u64,
+            25 => u16::from_be_bytes([self.take(1)?[0], self.take(1)?[0]]) as u64,
+            26 => {
+                let mut buf = [0u8; 4];
+                buf.copy_from_slice(self.take(4)?);
+                u32::from_be_bytes(buf) as u64
+            }
+            27 => {
+                let mut buf = [0u8; 8];
+                buf.copy_from_slice(self.take(8)?);
+                u64::from_be_bytes(buf)
+            }
+            _ => return Err(Error::Cbor("unsupported additional info")),
+        };
+        Ok((major, info, n))
+    }
+
+    fn len(&self, n: u64) -> Result<usize, Error> {
+        let len: usize = usize::try_from(n).map_err(|_| Error::Cbor("length overflow"))?;
+        // Each item takes at least 1 byte
+        if len > self.bytes.len() - self.pos {
+            return Err(Error::Cbor("unexpected end of data"));
+        }
+        Ok(len)
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Value, Error> {
+        if depth > MAX_DEPTH {
+            return Err(Error::Cbor("max depth exceeded"));
+        }
+
+        let (major, info, n) = self.header()?;
+        match major {
+            MAJOR_UNSIGNED => Ok(Value::Unsigned(n)),
+            MAJOR_NEGATIVE => Ok(Value::Negative(n)),
+            MAJOR_BYTES => {
+                let len: usize = self.len(n)?;
+                Ok(Value::Bytes(self.take(len)?.to_vec()))
+            }
+            MAJOR_TEXT => {
+                let len: usize = self.len(n)?;
+                let text: &str = core::str::from_utf8(self.take(len)?)
+                    .map_err(|_| Error::Cbor("invalid UTF-8 text"))?;
+                Ok(Value::Text(text.to_string()))
+            }
+            MAJOR_ARRAY => {
+                let len: usize = self.len(n)?;
+                let mut values: Vec<Value> = Vec::with_capacity(len);
+                for _ in 0..len {
+                    values.push(self.value(depth + 1)?);
+                }
+                Ok(Value::Array(values))
+            }
+            MAJOR_MAP => {
+                let len: usize = self.len(n)?;
+                let mut entries: Vec<(Value, Value)> = Vec::with_capacity(len);
+                for _ in 0..len {
+                    let key: Value = self.value(depth + 1)?;
+                    let value: Value = self.value(depth + 1)?;
+                    entries.push((key, value));
+                }
+                Ok(Value::Map(entries))
+            }
+            MAJOR_TAG => Ok(Value::Tag(n, Box::new(self.value(depth + 1)?))),
+            MAJOR_SIMPLE => match info {
+                20 => Ok(Value::Bool(false)),
+                21 => Ok(Value::Bool(true)),
+                22 => Ok(Value::Null),
+                _ => Err(Error::Cbor("unsupported simple value")),
+            },
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cbor_roundtrip() {
+        let value = Value::Tag(
+            303,
+            Box::new(Value::Map(vec![
+                (Value::Unsigned(3), Value::Bytes(vec![0x02; 33])),
+                (Value::Unsigned(8), Value::Unsigned(0xe9181cf3)),
+                (Value::Unsigned(2), Value::Bool(false)),
+                (Value::Unsigned(9), Value::Text(String::from("Keystone"))),
+            ])),
+        );
+        let encoded: Vec<u8> = value.encode();
+        assert_eq!(&encoded[..3], &[0xd9, 0x01, 0x2f]);
+        assert_eq!(Value::decode(&encoded).unwrap(), value);
+
+        assert!(Value::decode(&[0x58, 0x20, 0x00]).is_err());
+        assert!(Value::decode(&[0x01, 0x02]).is_err());
+    }
+}
@@ -0,0 +1,376 @@
+// Copyright (c) 2022-2024 Smart Vaults
+// Distributed under the MIT software license
+
+//! Uniform Resources (BC-UR)
+//!
+//! Encode and decode PSBTs (`crypto-psbt`) as animated QR code frames and import signers from
+//! `crypto-account` and `crypto-output` payloads.
+//!
+//! <https://github.com/BlockchainCommons/Research/blob/master/papers/bcr-2020-005-ur.md>
+
+use core::fmt;
+
+use ::ur::ur::{Decoder, Encoder, Kind};
+use keechain_core::bitcoin::psbt::{self, PartiallySignedTransaction};
+use keechain_core::bitcoin::{bip32, secp256k1, Network};
+
+mod cbor;
+mod registry;
+
+use self::cbor::Value;
+use crate::signer::{self, Signer};
+
+pub const CRYPTO_PSBT: &str = "crypto-psbt";
+pub const CRYPTO_ACCOUNT: &str = "crypto-account";
+pub const CRYPTO_OUTPUT: &str = "crypto-output";
+
+/// Default max fragment length, good for animated QR codes
+pub const DEFAULT_MAX_FRAGMENT_LEN: usize = 200;
+
+const SCHEME: &str = "ur:";
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Psbt(#[from] psbt::Error),
+    #[error(transparent)]
+    Secp256k1(#[from] secp256k1::Error),
+    #[error(transparent)]
+    BIP32(#[from] bip32::Error),
+    #[error(transparent)]
+    Miniscript(#[from] keechain_core::miniscript::Error),
+    #[error(transparent)]
+    Signer(#[from] signer::Error),
+    #[error("UR: {0}")]
+    Ur(String),
+    #[error("CBOR: {0}")]
+    Cbor(&'static str),
+    #[error("invalid UR: must start with `ur:<type>/`")]
+    InvalidScheme,
+    #[error("UR type mismatch: expected {expected}, found {found}")]
+    TypeMismatch { expected: String, found: String },
+    #[error("incomplete UR: more parts needed")]
+    Incomplete,
+    #[error("unsupported output descriptor")]
+    UnsupportedOutput,
+    #[error("invalid key: {0}")]
+    InvalidKey(&'static str),
+}
+
+impl From<::ur::ur::Error> for Error {
+    fn from(e: ::ur::ur::Error) -> Self {
+        Self::Ur(e.to_string())
+    }
+}
+
+/// Multi-part UR encoder
+///
+/// Parts after [`UrEncoder::fragment_count`] are fountain-coded: keep looping the QR frames until
+/// the other side has decoded the whole message.
+pub struct UrEncoder {
+    inner: Encoder,
+}
+
+impl fmt::Debug for UrEncoder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UrEncoder")
+            .field("fragment_count", &self.fragment_count())
+            .finish()
+    }
+}
+
+impl UrEncoder {
+    /// Encode PSBT as `crypto-psbt`
+    pub fn psbt(psbt: &PartiallySignedTransaction, max_fragment_len: usize) -> Result<Self, Error> {
+        let message: Vec<u8> = Value::Bytes(psbt.serialize()).encode();
+        Ok(Self {
+            inner: Encoder::new(&message, max_fragment_len, CRYPTO_PSBT)?,
+        })
+    }
+
+    /// Number of fragments needed to transmit the whole message
+    pub fn fragment_count(&self) -> usize {
+        self.inner.fragment_count()
+    }
+
+    /// Get next part (QR frame)
+    pub fn next_part(&mut self) -> Result<String, Error> {
+        Ok(self.inner.next_part()?)
+    }
+
+    /// Get the first [`UrEncoder::fragment_count`] parts
+    pub fn parts(mut self) -> Result<Vec<String>, Error> {
+        (0..self.fragment_count())
+            .map(|_| self.next_part())
+            .collect()
+    }
+}
+
+/// UR decoder
+///
+/// Accept both single-part and multi-part URs. Parts can be received in any order.
+#[derive(Default)]
+pub struct UrDecoder {
+    inner: Decoder,
+    ur_type: Option<String>,
+    message: Option<Vec<u8>>,
+}
+
+impl fmt::Debug for UrDecoder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UrDecoder")
+            .field("ur_type", &self.ur_type)
+            .field("complete", &self.is_complete())
+            .finish()
+    }
+}
+
+impl UrDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Receive a part (QR frame)
+    pub fn receive<S>(&mut self, part: S) -> Result<(), Error>
+    where
+        S: AsRef<str>,
+    {
+        // QR codes in alphanumeric mode are uppercase
+        let part: String = part.as_ref().trim().to_lowercase();
+        let ur_type: &str = parse_type(&part)?;
+
+        match &self.ur_type {
+            Some(expected) if expected != ur_type => {
+                return Err(Error::TypeMismatch {
+                    expected: expected.clone(),
+                    found: ur_type.to_string(),
+                })
+            }
+            Some(..) => (),
+            None => self.ur_type = Some(ur_type.to_string()),
+        }
+
+        if self.is_complete() {
+            return Ok(());
+        }
+
+        match ::ur::ur::decode(&part)? {
+            (Kind::SinglePart, message) => self.message = Some(message),
+            (Kind::MultiPart, _) => {
+                self.inner.receive(&part)?;
+                if self.inner.complete() {
+                    self.message = self.inner.message()?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check if the whole message was received
+    pub fn is_complete(&self) -> bool {
+        self.message.is_some()
+    }
+
+    /// UR type of the received parts
+    pub fn ur_type(&self) -> Option<&str> {
+        self.ur_type.as_deref()
+    }
+
+    fn decoded(&self, expected: &[&str]) -> Result<Value, Error> {
+        let ur_type: &str = self.ur_type().ok_or(Error::Incomplete)?;
+        if !expected.contains(&ur_type) {
+            return Err(Error::TypeMismatch {
+                expected: expected.join(" or "),
+                found: ur_type.to_string(),
+            });
+        }
+        let message: &[u8] = self.message.as_deref().ok_or(Error::Incomplete)?;
+        Value::decode(message)
+    }
+
+    /// Decode `crypto-psbt`
+    pub fn psbt(&self) -> Result<PartiallySignedTransaction, Error> {
+        let value: Value = self.decoded(&[CRYPTO_PSBT])?;
+        let bytes: &[u8] = value
+            .as_bytes()
+            .ok_or(Error::Cbor("expected byte string"))?;
+        Ok(PartiallySignedTransaction::deserialize(bytes)?)
+    }
+
+    /// Decode air-gapped [`Signer`] from `crypto-account` or `crypto-output`
+    pub fn signer<S>(&self, name: S, network: Network) -> Result<Signer, Error>
+    where
+        S: Into<String>,
+    {
+        let value: Value = self.decoded(&[CRYPTO_ACCOUNT, CRYPTO_OUTPUT])?;
+        let (fingerprint, descriptor) = match self.ur_type() {
+            Some(CRYPTO_ACCOUNT) => registry::account(&value, network)?,
+            _ => registry::output(&value, network)?,
+        };
+        Ok(Signer::airgap(
+            name.into(),
+            None,
+            fingerprint,
+            descriptor,
+            network,
+        )?)
+    }
+}
+
+fn parse_type(part: &str) -> Result<&str, Error> {
+    let ur_type: &str = part
+        .strip_prefix(SCHEME)
+        .and_then(|p| p.split('/').next())
+        .ok_or(Error::InvalidScheme)?;
+    if !ur_type.is_empty()
+        && ur_type
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+    {
+        Ok(ur_type)
+    } else {
+        Err(Error::InvalidScheme)
+    }
+}
+
+/// Decode `crypto-psbt` from UR parts
+pub fn decode_psbt<I, S>(parts: I) -> Result<PartiallySignedTransaction, Error>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let mut decoder = UrDecoder::new();
+    for part in parts.into_iter() {
+        decoder.receive(part)?;
+    }
+    decoder.psbt()
+}
+
+/// Decode air-gapped [`Signer`] from `crypto-account` or `crypto-output` UR parts
+pub fn decode_signer<I, S, N>(name: N, parts: I, network: Network) -> Result<Signer, Error>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+    N: Into<String>,
+{
+    let mut decoder = UrDecoder::new();
+    for part in parts.into_iter() {
+        decoder.receive(part)?;
+    }
+    decoder.signer(name, network)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use keechain_core::bips::bip39::Mnemonic;
+    use keechain_core::bitcoin::absolute::LockTime;
+    use keechain_core::bitcoin::{Address, Transaction, TxOut};
+    use keechain_core::miniscript::DescriptorPublicKey;
+    use keechain_core::Seed;
+
+    use super::*;
+
+    const NETWORK: Network = Network::Testnet;
+    const MNEMONIC: &str =
+        "possible suffer flavor boring essay zoo collect stairs day cabbage wasp tackle";
+
+    #[test]
+    fn test_psbt_roundtrip() {
+        let address = Address::from_str("tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx")
+            .unwrap()
+            .assume_checked();
+        let tx = Transaction {
+            version: 2,
+            lock_time: LockTime::ZERO,
+            input: Vec::new(),
+            output: vec![
+                TxOut {
+                    value: 1_000,
+                    script_pubkey: address.script_pubkey(),
+                };
+                20
+            ],
+        };
+        let psbt = PartiallySignedTransaction::from_unsigned_tx(tx).unwrap();
+
+        let encoder = UrEncoder::psbt(&psbt, 100).unwrap();
+        assert!(encoder.fragment_count() > 1);
+        let mut parts: Vec<String> = encoder.parts().unwrap();
+        assert!(parts[0].starts_with("ur:crypto-psbt/1-"));
+
+        // Any order, uppercase
+        parts.reverse();
+        let parts = parts.into_iter().map(|p| p.to_uppercase());
+        assert_eq!(decode_psbt(parts).unwrap(), psbt);
+
+        // Incomplete
+        let mut encoder = UrEncoder::psbt(&psbt, 100).unwrap();
+        let mut decoder = UrDecoder::new();
+        decoder.receive(encoder.next_part().unwrap()).unwrap();
+        assert!(!decoder.is_complete());
+        assert!(matches!(decoder.psbt(), Err(Error::Incomplete)));
+        assert!(matches!(
+            decoder.receive("ur:crypto-output/lpadaxcsencylobemohsgmoyadtaadenfgmok"),
+            Err(Error::TypeMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_signer_from_crypto_output() {
+        let seed = Seed::from_mnemonic(Mnemonic::from_str(MNEMONIC).unwrap());
+        let signer = Signer::from_seed("Test", None, seed, Some(0), NETWORK).unwrap();
+
+        let key = match signer.descriptor_public_key().unwrap() {
+            DescriptorPublicKey::XPub(key) => key,
+            _ => panic!("expected xpub"),
+        };
+        let (fingerprint, path) = key.origin.clone().unwrap();
+
+        let mut components: Vec<Value> = Vec::new();
+        for child in path.as_ref().iter() {
+            let (index, hardened) = match child {
+                bip32::ChildNumber::Normal { index } => (*index, false),
+                bip32::ChildNumber::Hardened { index } => (*index, true),
+            };
+            components.push(Value::Unsigned(index as u64));
+            components.push(Value::Bool(hardened));
+        }
+
+        let hdkey = Value::Map(vec![
+            (
+                Value::Unsigned(3),
+                Value::Bytes(key.xkey.public_key.serialize().to_vec()),
+            ),
+            (
+                Value::Unsigned(4),
+                Value::Bytes(key.xkey.chain_code.as_bytes().to_vec()),
+            ),
+            (
+                Value::Unsigned(6),
+                Value::Tag(
+                    304,
+                    Box::new(Value::Map(vec![
+                        (Value::Unsigned(1), Value::Array(components)),
+                        (
+                            Value::Unsigned(2),
+                            Value::Unsigned(u32::from_be_bytes(*fingerprint.as_bytes()) as u64),
+                        ),
+                    ])),
+                ),
+            ),
+            (
+                Value::Unsigned(8),
+                Value::Unsigned(u32::from_be_bytes(*key.xkey.parent_fingerprint.as_bytes()) as u64),
+            ),
+        ]);
+        let output = Value::Tag(409, Box::new(Value::Tag(303, Box::new(hdkey))));
+        let part: String = ::ur::ur::encode(&output.encode(), CRYPTO_OUTPUT);
+
+        let imported: Signer = decode_signer("Imported", [part], NETWORK).unwrap();
+        assert_eq!(imported.fingerprint(), signer.fingerprint());
+        assert_eq!(imported.descriptor(), signer.descriptor());
+    }
+}
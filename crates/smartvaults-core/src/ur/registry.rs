@@ -0,0 +1,200 @@
+// Copyright (c) 2022-2024 Smart Vaults
+// Distributed under the MIT software license
+
+//! UR registry types
+//!
+//! * `crypto-hdkey`: <https://github.com/BlockchainCommons/Research/blob/master/papers/bcr-2020-007-hdkey.md>
+//! * `crypto-output`: <https://github.com/BlockchainCommons/Research/blob/master/papers/bcr-2020-010-output-desc.md>
+//! * `crypto-account`: <https://github.com/BlockchainCommons/Research/blob/master/papers/bcr-2020-015-account.md>
+
+use core::str::FromStr;
+
+use keechain_core::bitcoin::bip32::{ChainCode, ChildNumber, ExtendedPubKey, Fingerprint};
+use keechain_core::bitcoin::secp256k1::PublicKey;
+use keechain_core::bitcoin::Network;
+use keechain_core::miniscript::{Descriptor, DescriptorPublicKey};
+
+use super::cbor::Value;
+use super::Error;
+
+const TAG_HDKEY: u64 = 303;
+const TAG_KEYPATH: u64 = 304;
+const TAG_SH: u64 = 400;
+const TAG_WSH: u64 = 401;
+const TAG_PK: u64 = 402;
+const TAG_TR: u64 = 409;
+const TAG_COSIGNER: u64 = 410;
+
+/// Supported output script types, sorted by preference
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum ScriptType {
+    Tr,
+    Wsh,
+    ShWsh,
+}
+
+/// Master fingerprint and descriptor of a `crypto-output`
+pub(crate) fn output(
+    value: &Value,
+    network: Network,
+) -> Result<(Fingerprint, Descriptor<DescriptorPublicKey>), Error> {
+    let (_, fingerprint, descriptor) = parse_output(value, None, network)?;
+    Ok((fingerprint, descriptor))
+}
+
+/// Master fingerprint and descriptor of the preferred output of a `crypto-account`
+///
+/// Taproot outputs are preferred over P2WSH and P2SH-P2WSH ones.
+pub(crate) fn account(
+    value: &Value,
+    network: Network,
+) -> Result<(Fingerprint, Descriptor<DescriptorPublicKey>), Error> {
+    let master_fingerprint: Fingerprint = value
+        .get(1)
+        .and_then(|v| v.as_u64())
+        .map(fingerprint)
+        .transpose()?
+        .ok_or(Error::InvalidKey("missing master fingerprint"))?;
+    let outputs: &[Value] = value
+        .get(2)
+        .and_then(|v| v.as_array())
+        .ok_or(Error::UnsupportedOutput)?;
+
+    outputs
+        .iter()
+        .filter_map(|output| parse_output(output, Some(master_fingerprint), network).ok())
+        .min_by_key(|(script_type, ..)| *script_type)
+        .map(|(_, fingerprint, descriptor)| (fingerprint, descriptor))
+        .ok_or(Error::UnsupportedOutput)
+}
+
+fn parse_output(
+    value: &Value,
+    master_fingerprint: Option<Fingerprint>,
+    network: Network,
+) -> Result<(ScriptType, Fingerprint, Descriptor<DescriptorPublicKey>), Error> {
+    let (script_type, key) = match value {
+        Value::Tag(TAG_TR, key) => (ScriptType::Tr, key.as_ref()),
+        Value::Tag(TAG_WSH, inner) => (ScriptType::Wsh, single_key(inner)?),
+        Value::Tag(TAG_SH, inner) => match inner.as_ref() {
+            Value::Tag(TAG_WSH, inner) => (ScriptType::ShWsh, single_key(inner)?),
+            _ => return Err(Error::UnsupportedOutput),
+        },
+        _ => return Err(Error::UnsupportedOutput),
+    };
+
+    let (fingerprint, key) = hdkey(key, master_fingerprint, network)?;
+    let descriptor: String = match script_type {
+        ScriptType::Tr => format!("tr({key})"),
+        ScriptType::Wsh => format!("wsh(pk({key}))"),
+        ScriptType::ShWsh => format!("sh(wsh(pk({key})))"),
+    };
+
+    Ok((script_type, fingerprint, Descriptor::from_str(&descriptor)?))
+}
+
+/// Key of a `pk` or `cosigner` script expression
+fn single_key(value: &Value) -> Result<&Value, Error> {
+    match value {
+        Value::Tag(TAG_PK | TAG_COSIGNER, key) => Ok(key),
+        _ => Err(Error::UnsupportedOutput),
+    }
+}
+
+/// Master fingerprint and descriptor key (external chain) of a `crypto-hdkey`
+fn hdkey(
+    value: &Value,
+    master_fingerprint: Option<Fingerprint>,
+    network: Network,
+) -> Result<(Fingerprint, String), Error> {
+    let value: &Value = value.untag(TAG_HDKEY);
+
+    if value.get(2).and_then(|v| v.as_bool()).unwrap_or(false) {
+        return Err(Error::InvalidKey("private keys not supported"));
+    }
+
+    let public_key: PublicKey = PublicKey::from_slice(
+        value
+            .get(3)
+            .and_then(|v| v.as_bytes())
+            .ok_or(Error::InvalidKey("missing key data"))?,
+    )?;
+    let chain_code: [u8; 32] = value
+        .get(4)
+        .and_then(|v| v.as_bytes())
+        .and_then(|b| b.try_into().ok())
+        .ok_or(Error::InvalidKey("missing chain code"))?;
+
+    // Origin
+    let origin: &Value = value
+        .get(6)
+        .ok_or(Error::InvalidKey("missing origin"))?
+        .untag(TAG_KEYPATH);
+    let components: Vec<ChildNumber> = keypath_components(origin)?;
+    let fingerprint: Fingerprint = match origin.get(2).and_then(|v| v.as_u64()) {
+        Some(fp) => self::fingerprint(fp)?,
+        None => master_fingerprint.ok_or(Error::InvalidKey("missing master fingerprint"))?,
+    };
+    let depth: u64 = origin
+        .get(3)
+        .and_then(|v| v.as_u64())
+        .unwrap_or(components.len() as u64);
+
+    let xpub = ExtendedPubKey {
+        network,
+        depth: u8::try_from(depth).map_err(|_| Error::InvalidKey("invalid depth"))?,
+        parent_fingerprint: match value.get(8).and_then(|v| v.as_u64()) {
+            Some(fp) => self::fingerprint(fp)?,
+            None => Fingerprint::from([0; 4]),
+        },
+        child_number: components
+            .last()
+            .copied()
+            .unwrap_or(ChildNumber::Normal { index: 0 }),
+        public_key,
+        chain_code: ChainCode::from(chain_code),
+    };
+
+    let path: String = components
+        .iter()
+        .map(|c| format!("/{c}"))
+        .collect::<Vec<String>>()
+        .concat();
+
+    Ok((fingerprint, format!("[{fingerprint}{path}]{xpub}/0/*")))
+}
+
+/// Components of a `crypto-keypath` (wildcards not allowed)
+fn keypath_components(value: &Value) -> Result<Vec<ChildNumber>, Error> {
+    let components: &[Value] = value
+        .get(1)
+        .and_then(|v| v.as_array())
+        .ok_or(Error::InvalidKey("missing keypath components"))?;
+
+    if components.len() % 2 != 0 {
+        return Err(Error::InvalidKey("invalid keypath components"));
+    }
+
+    components
+        .chunks(2)
+        .map(|pair| {
+            let index: u32 = pair[0]
+                .as_u64()
+                .and_then(|i| u32::try_from(i).ok())
+                .ok_or(Error::InvalidKey("invalid keypath index"))?;
+            let hardened: bool = pair[1]
+                .as_bool()
+                .ok_or(Error::InvalidKey("invalid keypath component"))?;
+            Ok(if hardened {
+                ChildNumber::from_hardened_idx(index)?
+            } else {
+                ChildNumber::from_normal_idx(index)?
+            })
+        })
+        .collect()
+}
+
+fn fingerprint(value: u64) -> Result<Fingerprint, Error> {
+    let value: u32 = u32::try_from(value).map_err(|_| Error::InvalidKey("invalid fingerprint"))?;
+    Ok(Fingerprint::from(value.to_be_bytes()))
+}
@@ -93,6 +93,7 @@ impl Activity {
                 proposal,
                 signed,
                 timestamp,
+                ..
             } in self.proposals.into_iter()
             {
                 let row = match proposal {
@@ -168,7 +169,11 @@ impl Activity {
                         .spacing(10)
                         .align_items(Alignment::Center)
                         .width(Length::Fill),
-                    Proposal::ProofOfReserve { message, .. } => Row::new()
+                    Proposal::ProofOfReserve { message, .. }
+                    | Proposal::FeeBump {
+                        description: message,
+                        ..
+                    } => Row::new()
                         .push(Space::with_width(Length::Fixed(70.0)))
                         .push(if self.hide_policy_id {
                             Text::new("").view()
@@ -258,6 +258,25 @@ impl State for CompletedProposalState {
                                 )
                                 .push(Text::new(format!("Description: {description}")).view());
                         }
+                        CompletedProposal::FeeBump {
+                            tx,
+                            replaced_txid,
+                            description,
+                        } => {
+                            let txid = tx.txid();
+                            content = content
+                                .push(Text::new("Type: fee-bump").view())
+                                .push(
+                                    Text::new(format!("Txid: {txid}"))
+                                        .on_press(Message::View(Stage::Transaction {
+                                            policy_id,
+                                            txid,
+                                        }))
+                                        .view(),
+                                )
+                                .push(Text::new(format!("Replaced txid: {replaced_txid}")).view())
+                                .push(Text::new(format!("Description: {description}")).view());
+                        }
                         CompletedProposal::ProofOfReserve { message, .. } => {
                             let mut status = Row::new().push(Text::new("Status: ").view());
 
@@ -196,6 +196,7 @@ impl State for ProposalState {
                                                         .approve_with_signed_psbt(
                                                             proposal_id,
                                                             signed_psbt,
+                                                            false,
                                                         )
                                                         .await?;
                                                 }
@@ -228,7 +229,7 @@ impl State for ProposalState {
                     let client = ctx.client.clone();
                     let proposal_id = self.proposal_id;
                     return Command::perform(
-                        async move { client.approve(password, proposal_id).await },
+                        async move { client.approve(password, proposal_id, false).await },
                         |res| match res {
                             Ok(_) => ProposalMessage::Reload.into(),
                             Err(e) => ProposalMessage::ErrorChanged(Some(e.to_string())).into(),
@@ -258,6 +259,12 @@ impl State for ProposalState {
                                             txid: tx.txid(),
                                         })
                                     }
+                                    CompletedProposal::FeeBump { tx, .. } => {
+                                        Message::View(Stage::Transaction {
+                                            policy_id,
+                                            txid: tx.txid(),
+                                        })
+                                    }
                                     CompletedProposal::ProofOfReserve { .. } => {
                                         Message::View(Stage::History)
                                     }
@@ -357,6 +364,7 @@ impl State for ProposalState {
                         Proposal::Spending {
                             to_address,
                             amount,
+                            other_recipients,
                             description,
                             psbt,
                             ..
@@ -377,6 +385,23 @@ impl State for ProposalState {
                                         .spacing(5),
                                 );
 
+                            for recipient in other_recipients.iter() {
+                                left_content = left_content
+                                    .push(
+                                        Text::new(format!(
+                                            "Address: {}",
+                                            recipient.address.clone().assume_checked()
+                                        ))
+                                        .view(),
+                                    )
+                                    .push(
+                                        Row::new()
+                                            .push(Text::new("Amount:").view())
+                                            .push(Amount::new(recipient.amount).bold().view())
+                                            .spacing(5),
+                                    );
+                            }
+
                             match psbt.fee() {
                                 Ok(fee) => {
                                     left_content = left_content.push(
@@ -446,6 +471,38 @@ impl State for ProposalState {
 
                             "Broadcast"
                         }
+                        Proposal::FeeBump {
+                            replaced_txid,
+                            description,
+                            psbt,
+                            ..
+                        } => {
+                            left_content = left_content
+                                .push(Text::new("Type: fee-bump").view())
+                                .push(Text::new(format!("Replaced txid: {replaced_txid}")).view());
+
+                            match psbt.fee() {
+                                Ok(fee) => {
+                                    left_content = left_content.push(
+                                        Text::new(format!(
+                                            "Fee: {} sat",
+                                            util::format::number(fee.to_sat())
+                                        ))
+                                        .view(),
+                                    )
+                                }
+                                Err(e) => {
+                                    tracing::error!("Impossible to calculate fee: {e}");
+                                }
+                            };
+
+                            if !description.is_empty() {
+                                left_content = left_content
+                                    .push(Text::new(format!("Description: {description}")).view());
+                            }
+
+                            "Broadcast"
+                        }
                         Proposal::ProofOfReserve { message, .. } => {
                             left_content = left_content
                                 .push(Text::new("Type: proof-of-reserve").view())
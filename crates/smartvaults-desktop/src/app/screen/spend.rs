@@ -189,8 +189,7 @@ impl SpendState {
                 let GetProposal { proposal_id, .. } = client
                     .spend(
                         policy_id,
-                        to_address,
-                        amount,
+                        vec![(to_address, amount)],
                         description,
                         fee_rate,
                         if selected_utxos.is_empty() {
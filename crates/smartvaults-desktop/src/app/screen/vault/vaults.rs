@@ -149,6 +149,7 @@ impl State for PoliciesState {
                     policy,
                     balance,
                     last_sync,
+                    ..
                 } in self.policies.iter()
                 {
                     let balance = if *last_sync != Timestamp::from(0) {
@@ -6,3 +6,4 @@
 pub extern crate nostr;
 
 pub mod v1;
+pub mod v2;
@@ -2,21 +2,25 @@
 // Distributed under the MIT software license
 
 use std::collections::HashMap;
+use std::ops::Add;
 
-use nostr::nips::nip04;
-use nostr::{Event, EventBuilder, EventId, Keys, PublicKey, Tag};
+use nostr::{Event, EventBuilder, EventId, Keys, PublicKey, Tag, Timestamp};
 use smartvaults_core::bitcoin::Network;
-use smartvaults_core::{Policy, Proposal, Signer};
+use smartvaults_core::{
+    ApprovedProposal, CompletedProposal, Policy, Proposal, SharedSigner, Signer,
+};
 use thiserror::Error;
 
 use super::constants::{
+    APPROVED_PROPOSAL_EXPIRATION, APPROVED_PROPOSAL_KIND, COMPLETED_PROPOSAL_KIND,
     KEY_AGENT_SIGNALING, KEY_AGENT_SIGNER_OFFERING_KIND, KEY_AGENT_VERIFIED, LABELS_KIND,
-    POLICY_KIND, PROPOSAL_KIND, SHARED_KEY_KIND,
+    POLICY_KIND, PROPOSAL_COMMENT_KIND, PROPOSAL_KIND, PROPOSAL_REJECTION_KIND, SHARED_KEY_KIND,
+    SHARED_SIGNERS_KIND, SIGNERS_KIND, VAULT_METADATA_KIND,
 };
 use super::key_agent::signer::SignerOffering;
 use super::key_agent::verified::VerifiedKeyAgentData;
-use super::util::{Encryption, EncryptionError};
-use super::{Label, Serde};
+use super::util::{Encryption, EncryptionError, EncryptionScheme};
+use super::{Label, ProposalComment, ProposalRejection, Serde, VaultMetadata};
 
 #[derive(Debug, Error)]
 pub enum Error {
@@ -25,8 +29,6 @@ pub enum Error {
     #[error(transparent)]
     EventBuilder(#[from] nostr::event::builder::Error),
     #[error(transparent)]
-    NIP04(#[from] nostr::nips::nip04::Error),
-    #[error(transparent)]
     Encryption(#[from] EncryptionError),
     #[error(transparent)]
     Label(#[from] super::label::Error),
@@ -39,7 +41,8 @@ pub trait SmartVaultsEventBuilder {
         receiver: &PublicKey,
         policy_id: EventId,
     ) -> Result<Event, Error> {
-        let encrypted_shared_key = nip04::encrypt(
+        let scheme = EncryptionScheme::default();
+        let encrypted_shared_key = scheme.encrypt(
             keys.secret_key()?,
             receiver,
             shared_key.secret_key()?.display_secret().to_string(),
@@ -47,7 +50,11 @@ pub trait SmartVaultsEventBuilder {
         let event: Event = EventBuilder::new(
             SHARED_KEY_KIND,
             encrypted_shared_key,
-            [Tag::event(policy_id), Tag::public_key(*receiver)],
+            [
+                Tag::event(policy_id),
+                Tag::public_key(*receiver),
+                scheme.tag(),
+            ],
         )
         .to_event(keys)?;
         Ok(event)
@@ -59,22 +66,139 @@ pub trait SmartVaultsEventBuilder {
         nostr_pubkeys: &[PublicKey],
     ) -> Result<Event, Error> {
         let content: String = policy.encrypt_with_keys(shared_key)?;
-        let tags = nostr_pubkeys.iter().copied().map(Tag::public_key);
+        let mut tags: Vec<Tag> = nostr_pubkeys.iter().copied().map(Tag::public_key).collect();
+        tags.push(EncryptionScheme::default().tag());
         Ok(EventBuilder::new(POLICY_KIND, content, tags).to_event(shared_key)?)
     }
 
+    /// Re-publish the policy of `vault_id` with a new `shared_key` and the current members
+    ///
+    /// The `e` tag references the original policy event, that remains the vault ID.
+    fn rotated_policy(
+        shared_key: &Keys,
+        vault_id: EventId,
+        policy: &Policy,
+        nostr_pubkeys: &[PublicKey],
+    ) -> Result<Event, Error> {
+        let content: String = policy.encrypt_with_keys(shared_key)?;
+        let mut tags: Vec<Tag> = nostr_pubkeys.iter().copied().map(Tag::public_key).collect();
+        tags.push(Tag::event(vault_id));
+        tags.push(EncryptionScheme::default().tag());
+        Ok(EventBuilder::new(POLICY_KIND, content, tags).to_event(shared_key)?)
+    }
+
+    /// Proposal, optionally expiring (NIP-40) at `expiration`
     fn proposal(
         shared_key: &Keys,
         policy_id: EventId,
         proposal: &Proposal,
+        expiration: Option<Timestamp>,
         nostr_pubkeys: &[PublicKey],
     ) -> Result<Event, Error> {
         let mut tags: Vec<Tag> = nostr_pubkeys.iter().copied().map(Tag::public_key).collect();
         tags.push(Tag::event(policy_id));
+        tags.push(EncryptionScheme::default().tag());
+        if let Some(expiration) = expiration {
+            tags.push(Tag::Expiration(expiration));
+        }
         let content: String = proposal.encrypt_with_keys(shared_key)?;
         Ok(EventBuilder::new(PROPOSAL_KIND, content, tags).to_event(shared_key)?)
     }
 
+    /// Approval signed by `keys` and encrypted with the `shared_key`
+    fn approval(
+        keys: &Keys,
+        shared_key: &Keys,
+        proposal_id: EventId,
+        policy_id: EventId,
+        approval: &ApprovedProposal,
+        nostr_pubkeys: &[PublicKey],
+    ) -> Result<Event, Error> {
+        let content: String = approval.encrypt_with_keys(shared_key)?;
+        let mut tags: Vec<Tag> = nostr_pubkeys.iter().copied().map(Tag::public_key).collect();
+        tags.push(Tag::event(proposal_id));
+        tags.push(Tag::event(policy_id));
+        tags.push(Tag::Expiration(
+            Timestamp::now().add(APPROVED_PROPOSAL_EXPIRATION),
+        ));
+        tags.push(EncryptionScheme::default().tag());
+        Ok(EventBuilder::new(APPROVED_PROPOSAL_KIND, content, tags).to_event(keys)?)
+    }
+
+    /// Comment signed by `keys` and encrypted with the `shared_key`
+    fn proposal_comment(
+        keys: &Keys,
+        shared_key: &Keys,
+        proposal_id: EventId,
+        policy_id: EventId,
+        comment: &ProposalComment,
+        nostr_pubkeys: &[PublicKey],
+    ) -> Result<Event, Error> {
+        let content: String = comment.encrypt_with_keys(shared_key)?;
+        let mut tags: Vec<Tag> = nostr_pubkeys.iter().copied().map(Tag::public_key).collect();
+        tags.push(Tag::event(proposal_id));
+        tags.push(Tag::event(policy_id));
+        tags.push(EncryptionScheme::default().tag());
+        Ok(EventBuilder::new(PROPOSAL_COMMENT_KIND, content, tags).to_event(keys)?)
+    }
+
+    /// Rejection signed by `keys` and encrypted with the `shared_key`
+    fn proposal_rejection(
+        keys: &Keys,
+        shared_key: &Keys,
+        proposal_id: EventId,
+        policy_id: EventId,
+        rejection: &ProposalRejection,
+        nostr_pubkeys: &[PublicKey],
+    ) -> Result<Event, Error> {
+        let content: String = rejection.encrypt_with_keys(shared_key)?;
+        let mut tags: Vec<Tag> = nostr_pubkeys.iter().copied().map(Tag::public_key).collect();
+        tags.push(Tag::event(proposal_id));
+        tags.push(Tag::event(policy_id));
+        tags.push(EncryptionScheme::default().tag());
+        Ok(EventBuilder::new(PROPOSAL_REJECTION_KIND, content, tags).to_event(keys)?)
+    }
+
+    fn completed_proposal(
+        shared_key: &Keys,
+        proposal_id: EventId,
+        policy_id: EventId,
+        completed_proposal: &CompletedProposal,
+        nostr_pubkeys: &[PublicKey],
+    ) -> Result<Event, Error> {
+        let content: String = completed_proposal.encrypt_with_keys(shared_key)?;
+        let mut tags: Vec<Tag> = nostr_pubkeys.iter().copied().map(Tag::public_key).collect();
+        tags.push(Tag::event(proposal_id));
+        tags.push(Tag::event(policy_id));
+        tags.push(EncryptionScheme::default().tag());
+        Ok(EventBuilder::new(COMPLETED_PROPOSAL_KIND, content, tags).to_event(shared_key)?)
+    }
+
+    fn signer(keys: &Keys, signer: &Signer) -> Result<Event, Error> {
+        let content: String = signer.encrypt_with_keys(keys)?;
+        Ok(
+            EventBuilder::new(SIGNERS_KIND, content, [EncryptionScheme::default().tag()])
+                .to_event(keys)?,
+        )
+    }
+
+    fn shared_signer(
+        keys: &Keys,
+        signer_id: EventId,
+        shared_signer: &SharedSigner,
+        receiver: &PublicKey,
+    ) -> Result<Event, Error> {
+        let scheme = EncryptionScheme::default();
+        let content: String =
+            scheme.encrypt(keys.secret_key()?, receiver, shared_signer.as_json())?;
+        let tags = [
+            Tag::event(signer_id),
+            Tag::public_key(*receiver),
+            scheme.tag(),
+        ];
+        Ok(EventBuilder::new(SHARED_SIGNERS_KIND, content, tags).to_event(keys)?)
+    }
+
     fn label(
         shared_key: &Keys,
         policy_id: EventId,
@@ -86,9 +210,25 @@ pub trait SmartVaultsEventBuilder {
         let mut tags: Vec<Tag> = nostr_pubkeys.iter().copied().map(Tag::public_key).collect();
         tags.push(Tag::Identifier(identifier));
         tags.push(Tag::event(policy_id));
+        tags.push(EncryptionScheme::default().tag());
         Ok(EventBuilder::new(LABELS_KIND, content, tags).to_event(shared_key)?)
     }
 
+    /// Vault metadata, replaceable by every member (signed with the `shared_key`)
+    fn vault_metadata(
+        shared_key: &Keys,
+        policy_id: EventId,
+        metadata: &VaultMetadata,
+        nostr_pubkeys: &[PublicKey],
+    ) -> Result<Event, Error> {
+        let content: String = metadata.encrypt_with_keys(shared_key)?;
+        let mut tags: Vec<Tag> = nostr_pubkeys.iter().copied().map(Tag::public_key).collect();
+        tags.push(Tag::Identifier(policy_id.to_hex()));
+        tags.push(Tag::event(policy_id));
+        tags.push(EncryptionScheme::default().tag());
+        Ok(EventBuilder::new(VAULT_METADATA_KIND, content, tags).to_event(shared_key)?)
+    }
+
     fn key_agent_signaling(keys: &Keys, network: Network) -> Result<Event, Error> {
         let identifier: String = network.magic().to_string();
         Ok(
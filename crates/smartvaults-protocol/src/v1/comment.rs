@@ -0,0 +1,26 @@
+// Copyright (c) 2022-2024 Smart Vaults
+// Distributed under the MIT software license
+
+//! Proposal comment
+
+use serde::{Deserialize, Serialize};
+
+use super::util::{Encryption, Serde};
+
+/// Comment attached to a proposal
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProposalComment {
+    pub text: String,
+}
+
+impl ProposalComment {
+    pub fn new<S>(text: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self { text: text.into() }
+    }
+}
+
+impl Serde for ProposalComment {}
+impl Encryption for ProposalComment {}
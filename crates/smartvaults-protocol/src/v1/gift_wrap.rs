@@ -0,0 +1,193 @@
+// Copyright (c) 2022-2024 Smart Vaults
+// Distributed under the MIT software license
+
+//! Gift wrap (NIP-59)
+//!
+//! Vault events are sealed and gift wrapped for every member, so the member list (`p` tags)
+//! of the vault is never published on relays.
+//!
+//! <https://github.com/nostr-protocol/nips/blob/master/59.md>
+
+use nostr::secp256k1::rand::{self, Rng};
+use nostr::{key, Event, EventBuilder, EventId, JsonUtil, Keys, Kind, PublicKey, Tag, Timestamp};
+use serde_json::Value;
+
+use super::util::{EncryptionError, EncryptionScheme};
+
+/// Max tweak of the `created_at` of seals and gift wraps (2 days)
+const MAX_TIMESTAMP_TWEAK: u64 = 2 * 24 * 60 * 60;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Keys(#[from] key::Error),
+    #[error(transparent)]
+    Event(#[from] nostr::event::Error),
+    #[error(transparent)]
+    EventBuilder(#[from] nostr::event::builder::Error),
+    #[error(transparent)]
+    Encryption(#[from] EncryptionError),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error("not a gift wrap")]
+    NotGiftWrap,
+    #[error("not a seal")]
+    NotSeal,
+    #[error("seal and rumor authors not match")]
+    AuthorMismatch,
+    #[error("invalid rumor")]
+    InvalidRumor,
+}
+
+/// Random timestamp in the past 2 days, for seals and gift wraps
+fn tweaked_timestamp() -> Timestamp {
+    let tweak: u64 = rand::thread_rng().gen_range(0..MAX_TIMESTAMP_TWEAK);
+    Timestamp::from(Timestamp::now().as_u64().saturating_sub(tweak))
+}
+
+/// Seal the rumor (unsigned copy) of `event` with `keys` (the author of the `event`) and gift wrap it for `receiver`
+///
+/// The signature of `event` is never published, so a leaked rumor can't be attributed to its author.
+pub fn wrap(keys: &Keys, receiver: &PublicKey, event: &Event) -> Result<Event, Error> {
+    if keys.public_key() != event.author() {
+        return Err(Error::AuthorMismatch);
+    }
+
+    let scheme = EncryptionScheme::Nip44;
+
+    // Rumor
+    let mut rumor: Value = serde_json::from_str(&event.as_json())?;
+    rumor
+        .as_object_mut()
+        .ok_or(Error::InvalidRumor)?
+        .remove("sig");
+
+    // Seal
+    let content: String = scheme.encrypt(keys.secret_key()?, receiver, rumor.to_string())?;
+    let seal: Event = EventBuilder::new(Kind::Seal, content, [])
+        .custom_created_at(tweaked_timestamp())
+        .to_event(keys)?;
+
+    // Gift wrap with ephemeral keys
+    let ephemeral = Keys::generate();
+    let content: String = scheme.encrypt(ephemeral.secret_key()?, receiver, seal.as_json())?;
+    Ok(
+        EventBuilder::new(Kind::GiftWrap, content, [Tag::public_key(*receiver)])
+            .custom_created_at(tweaked_timestamp())
+            .to_event(&ephemeral)?,
+    )
+}
+
+/// Local copy of a gift wrapped `event`, in the same form returned by [`unwrap`]
+///
+/// Must be saved in the local database in place of the signed `event`, that must never be published.
+pub fn rumor(keys: &Keys, event: &Event) -> Result<Event, Error> {
+    let gift_wrap: Event = wrap(keys, &keys.public_key(), event)?;
+    unwrap(keys, &gift_wrap)
+}
+
+/// Unwrap a gift wrap received by `keys` and return the sealed rumor
+///
+/// The rumor is authenticated by the seal. Being unsigned, the returned event carries the signature of
+/// the seal, so it can be saved in the local database: it must never be verified or published.
+pub fn unwrap(keys: &Keys, gift_wrap: &Event) -> Result<Event, Error> {
+    if gift_wrap.kind != Kind::GiftWrap {
+        return Err(Error::NotGiftWrap);
+    }
+
+    let scheme = EncryptionScheme::Nip44;
+    let secret_key = keys.secret_key()?;
+
+    // Open gift wrap
+    let json: String = scheme.decrypt(secret_key, gift_wrap.author_ref(), &gift_wrap.content)?;
+    let seal: Event = Event::from_json(json)?;
+    seal.verify()?;
+
+    if seal.kind != Kind::Seal {
+        return Err(Error::NotSeal);
+    }
+
+    // Open seal
+    let json: String = scheme.decrypt(secret_key, seal.author_ref(), &seal.content)?;
+    let mut rumor: Value = serde_json::from_str(&json)?;
+    let seal_json: Value = serde_json::from_str(&seal.as_json())?;
+    rumor
+        .as_object_mut()
+        .ok_or(Error::InvalidRumor)?
+        .insert(String::from("sig"), seal_json["sig"].clone());
+    let event: Event = Event::from_json(rumor.to_string())?;
+
+    if event.author() != seal.author() {
+        return Err(Error::AuthorMismatch);
+    }
+
+    // The ID must commit to the rumor content
+    let id = EventId::new(
+        event.author_ref(),
+        event.created_at,
+        &event.kind,
+        &event.tags,
+        &event.content,
+    );
+    if id != event.id {
+        return Err(Error::InvalidRumor);
+    }
+
+    Ok(event)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gift_wrap() {
+        let shared_key = Keys::generate();
+        let alice = Keys::generate();
+        let bob = Keys::generate();
+
+        let event: Event = EventBuilder::new(
+            Kind::Custom(9289),
+            "policy",
+            [
+                Tag::public_key(alice.public_key()),
+                Tag::public_key(bob.public_key()),
+            ],
+        )
+        .to_event(&shared_key)
+        .unwrap();
+
+        let gift_wrap: Event = wrap(&shared_key, &bob.public_key(), &event).unwrap();
+        assert_eq!(gift_wrap.kind, Kind::GiftWrap);
+        assert_ne!(gift_wrap.author(), shared_key.public_key());
+        assert_eq!(
+            gift_wrap.public_keys().copied().collect::<Vec<_>>(),
+            vec![bob.public_key()]
+        );
+
+        assert!(gift_wrap.created_at <= Timestamp::now());
+
+        // Rumor
+        let rumor: Event = unwrap(&bob, &gift_wrap).unwrap();
+        assert_eq!(rumor.id, event.id);
+        assert_eq!(rumor.author(), event.author());
+        assert_eq!(rumor.content, event.content);
+        assert_eq!(rumor.tags, event.tags);
+        assert!(rumor.verify().is_err());
+
+        // Local copy
+        let local: Event = rumor(&shared_key, &event).unwrap();
+        assert_eq!(local.id, event.id);
+        assert_eq!(local.tags, event.tags);
+        assert!(local.verify().is_err());
+
+        assert!(unwrap(&alice, &gift_wrap).is_err());
+        assert!(matches!(unwrap(&bob, &event), Err(Error::NotGiftWrap)));
+
+        // Only the author of the event can seal it
+        assert!(matches!(
+            wrap(&alice, &bob.public_key(), &event),
+            Err(Error::AuthorMismatch)
+        ));
+    }
+}
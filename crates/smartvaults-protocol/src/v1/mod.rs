@@ -2,16 +2,24 @@
 // Distributed under the MIT software license
 
 pub mod builder;
+pub mod comment;
 pub mod constants;
+pub mod gift_wrap;
 pub mod key_agent;
 pub mod label;
 mod network;
+pub mod rejection;
 pub mod util;
+pub mod vault_metadata;
 
 pub use self::builder::{Error as SmartVaultsEventBuilderError, SmartVaultsEventBuilder};
+pub use self::comment::ProposalComment;
+pub use self::gift_wrap::Error as GiftWrapError;
 pub use self::key_agent::{
     BasisPoints, DeviceType, KeyAgentMetadata, Price, SignerOffering, Temperature,
     VerifiedKeyAgentData, VerifiedKeyAgents,
 };
 pub use self::label::{Label, LabelData, LabelKind};
-pub use self::util::{Encryption, EncryptionError, Serde, SerdeSer};
+pub use self::rejection::ProposalRejection;
+pub use self::util::{Encryption, EncryptionError, EncryptionScheme, Serde, SerdeSer};
+pub use self::vault_metadata::VaultMetadata;
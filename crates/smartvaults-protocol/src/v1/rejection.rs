@@ -0,0 +1,29 @@
+// Copyright (c) 2022-2024 Smart Vaults
+// Distributed under the MIT software license
+
+//! Proposal rejection
+
+use serde::{Deserialize, Serialize};
+
+use super::util::{Encryption, Serde};
+
+/// Explicit "no" vote to a proposal
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProposalRejection {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl ProposalRejection {
+    pub fn new<S>(reason: Option<S>) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            reason: reason.map(|r| r.into()),
+        }
+    }
+}
+
+impl Serde for ProposalRejection {}
+impl Encryption for ProposalRejection {}
@@ -1,7 +1,11 @@
 // Copyright (c) 2022-2024 Smart Vaults
 // Distributed under the MIT software license
 
-use nostr::{key, util, Keys};
+use core::fmt;
+use core::str::FromStr;
+
+use nostr::nips::{nip04, nip44};
+use nostr::{key, util, Keys, PublicKey, Tag, TagKind};
 use smartvaults_core::bdk::wallet::ChangeSet;
 use smartvaults_core::bitcoin::secp256k1::SecretKey;
 use smartvaults_core::crypto::aes;
@@ -12,6 +16,9 @@ use smartvaults_core::{
 
 use super::serde::Serde;
 
+/// Name of the tag that marks the [`EncryptionScheme`] of an event
+pub const ENCRYPTION_TAG: &str = "encryption";
+
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error(transparent)]
@@ -22,15 +29,119 @@ pub enum Error {
     Keys(#[from] key::Error),
     #[error(transparent)]
     Secp256k1(#[from] secp256k1::Error),
+    #[error(transparent)]
+    NIP04(#[from] nip04::Error),
+    #[error(transparent)]
+    NIP44(#[from] nip44::Error),
+    #[error("unsupported encryption scheme: {0}")]
+    UnsupportedScheme(String),
+}
+
+/// Encryption scheme
+///
+/// New events use [`EncryptionScheme::Nip44`] and are marked with the [`ENCRYPTION_TAG`] tag.
+/// Events without the tag are [`EncryptionScheme::Legacy`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum EncryptionScheme {
+    /// AES-256-CBC for vault payloads and NIP-04 for the shared key and shared signers
+    Legacy,
+    /// NIP-44 (version 2): versioned, padded and authenticated
+    #[default]
+    Nip44,
+}
+
+impl fmt::Display for EncryptionScheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Legacy => write!(f, "legacy"),
+            Self::Nip44 => write!(f, "nip44"),
+        }
+    }
+}
+
+impl FromStr for EncryptionScheme {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "legacy" => Ok(Self::Legacy),
+            "nip44" => Ok(Self::Nip44),
+            other => Err(Error::UnsupportedScheme(other.to_string())),
+        }
+    }
+}
+
+impl EncryptionScheme {
+    /// Compose the tag that marks the scheme used in an event
+    pub fn tag(&self) -> Tag {
+        Tag::Generic(
+            TagKind::Custom(ENCRYPTION_TAG.to_string()),
+            vec![self.to_string()],
+        )
+    }
+
+    /// Get the scheme from the event tags
+    ///
+    /// Events without the [`ENCRYPTION_TAG`] tag are [`EncryptionScheme::Legacy`].
+    pub fn from_tags<'a, I>(tags: I) -> Result<Self, Error>
+    where
+        I: IntoIterator<Item = &'a Tag>,
+    {
+        for tag in tags.into_iter() {
+            if let [kind, scheme, ..] = tag.as_vec().as_slice() {
+                if kind == ENCRYPTION_TAG {
+                    return Self::from_str(scheme);
+                }
+            }
+        }
+        Ok(Self::Legacy)
+    }
+
+    /// Encrypt `content` for `receiver` (NIP-04 or NIP-44)
+    pub fn encrypt<T>(
+        &self,
+        secret_key: &SecretKey,
+        receiver: &PublicKey,
+        content: T,
+    ) -> Result<String, Error>
+    where
+        T: AsRef<str>,
+    {
+        match self {
+            Self::Legacy => Ok(nip04::encrypt(secret_key, receiver, content.as_ref())?),
+            Self::Nip44 => Ok(nip44::encrypt(
+                secret_key,
+                receiver,
+                content.as_ref(),
+                nip44::Version::V2,
+            )?),
+        }
+    }
+
+    /// Decrypt `content` received from `sender` (NIP-04 or NIP-44)
+    pub fn decrypt<T>(
+        &self,
+        secret_key: &SecretKey,
+        sender: &PublicKey,
+        content: T,
+    ) -> Result<String, Error>
+    where
+        T: AsRef<str>,
+    {
+        match self {
+            Self::Legacy => Ok(nip04::decrypt(secret_key, sender, content.as_ref())?),
+            Self::Nip44 => Ok(nip44::decrypt(secret_key, sender, content.as_ref())?),
+        }
+    }
 }
 
 pub trait Encryption: Serde {
-    /// Encrypt
+    /// Encrypt (legacy AES)
     fn encrypt(&self, key: [u8; 32]) -> String {
         aes::encrypt(key, self.as_json())
     }
 
-    /// Decrypt
+    /// Decrypt (legacy AES)
     fn decrypt<T>(key: [u8; 32], content: T) -> Result<Self, Error>
     where
         T: AsRef<[u8]>,
@@ -39,19 +150,48 @@ pub trait Encryption: Serde {
         Ok(deserialize(data)?)
     }
 
-    /// Encrypt
+    /// Encrypt with the default [`EncryptionScheme`]
+    ///
+    /// The event must be tagged with [`EncryptionScheme::tag`].
     fn encrypt_with_keys(&self, keys: &Keys) -> Result<String, Error> {
-        let key: [u8; 32] = util::generate_shared_key(keys.secret_key()?, &keys.public_key());
-        Ok(self.encrypt(key))
+        self.encrypt_with_scheme(keys, EncryptionScheme::default())
     }
 
-    /// Decrypt
-    fn decrypt_with_keys<T>(keys: &Keys, content: T) -> Result<Self, Error>
+    /// Encrypt with [`EncryptionScheme`]
+    fn encrypt_with_scheme(&self, keys: &Keys, scheme: EncryptionScheme) -> Result<String, Error> {
+        match scheme {
+            EncryptionScheme::Legacy => {
+                let key: [u8; 32] =
+                    util::generate_shared_key(keys.secret_key()?, &keys.public_key());
+                Ok(self.encrypt(key))
+            }
+            EncryptionScheme::Nip44 => {
+                scheme.encrypt(keys.secret_key()?, &keys.public_key(), self.as_json())
+            }
+        }
+    }
+
+    /// Decrypt with [`EncryptionScheme`]
+    fn decrypt_with_keys<T>(
+        keys: &Keys,
+        content: T,
+        scheme: EncryptionScheme,
+    ) -> Result<Self, Error>
     where
-        T: AsRef<[u8]>,
+        T: AsRef<str>,
     {
-        let key: [u8; 32] = util::generate_shared_key(keys.secret_key()?, &keys.public_key());
-        Self::decrypt(key, content)
+        match scheme {
+            EncryptionScheme::Legacy => {
+                let key: [u8; 32] =
+                    util::generate_shared_key(keys.secret_key()?, &keys.public_key());
+                Self::decrypt(key, content.as_ref())
+            }
+            EncryptionScheme::Nip44 => {
+                let json: String =
+                    scheme.decrypt(keys.secret_key()?, &keys.public_key(), content)?;
+                Ok(Self::from_json(json)?)
+            }
+        }
     }
 }
 
@@ -77,3 +217,45 @@ impl Encryption for Signer {}
 
 impl Serde for SharedSigner {}
 impl Encryption for SharedSigner {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encryption_schemes() {
+        let keys = Keys::generate();
+        let secret_key: SecretKey = *keys.secret_key().unwrap();
+
+        for scheme in [EncryptionScheme::Legacy, EncryptionScheme::Nip44] {
+            let content: String = secret_key.encrypt_with_scheme(&keys, scheme).unwrap();
+            let decrypted = SecretKey::decrypt_with_keys(&keys, &content, scheme).unwrap();
+            assert_eq!(decrypted, secret_key);
+        }
+
+        // Content encrypted with a scheme can't be decrypted with the other one
+        let content: String = secret_key.encrypt_with_keys(&keys).unwrap();
+        assert!(SecretKey::decrypt_with_keys(&keys, content, EncryptionScheme::Legacy).is_err());
+    }
+
+    #[test]
+    fn test_scheme_from_tags() {
+        let tags = vec![Tag::public_key(Keys::generate().public_key())];
+        assert_eq!(
+            EncryptionScheme::from_tags(&tags).unwrap(),
+            EncryptionScheme::Legacy
+        );
+
+        let tags = vec![EncryptionScheme::Nip44.tag()];
+        assert_eq!(
+            EncryptionScheme::from_tags(&tags).unwrap(),
+            EncryptionScheme::Nip44
+        );
+
+        let tags = vec![Tag::Generic(
+            TagKind::Custom(ENCRYPTION_TAG.to_string()),
+            vec![String::from("nip99")],
+        )];
+        assert!(EncryptionScheme::from_tags(&tags).is_err());
+    }
+}
@@ -4,5 +4,5 @@
 pub mod encryption;
 pub mod serde;
 
-pub use self::encryption::{Encryption, Error as EncryptionError};
+pub use self::encryption::{Encryption, EncryptionScheme, Error as EncryptionError};
 pub use self::serde::{Serde, SerdeSer};
@@ -0,0 +1,132 @@
+// Copyright (c) 2022-2024 Smart Vaults
+// Distributed under the MIT software license
+
+//! Vault metadata
+//!
+//! Editable after the vault creation: the latest event (parameterized replaceable) wins.
+
+use serde::{Deserialize, Serialize};
+use smartvaults_core::{Policy, SpendingRules};
+
+use super::util::{Encryption, Serde};
+
+/// Vault metadata
+///
+/// Fields left to `None` fallback to the values of the [`Policy`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VaultMetadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Icon (URL or emoji)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+    /// Color (i.e. `#f7931a`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Spending rules enforced by the clients
+    #[serde(default, skip_serializing_if = "SpendingRules::is_empty")]
+    pub spending_rules: SpendingRules,
+}
+
+impl VaultMetadata {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn name<S>(self, name: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            name: Some(name.into()),
+            ..self
+        }
+    }
+
+    pub fn description<S>(self, description: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            description: Some(description.into()),
+            ..self
+        }
+    }
+
+    pub fn icon<S>(self, icon: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            icon: Some(icon.into()),
+            ..self
+        }
+    }
+
+    pub fn color<S>(self, color: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            color: Some(color.into()),
+            ..self
+        }
+    }
+
+    pub fn tags<I, S>(self, tags: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            tags: tags.into_iter().map(|t| t.into()).collect(),
+            ..self
+        }
+    }
+
+    pub fn spending_rules(self, spending_rules: SpendingRules) -> Self {
+        Self {
+            spending_rules,
+            ..self
+        }
+    }
+
+    /// Apply name and description to the [`Policy`]
+    pub fn apply(&self, policy: &mut Policy) {
+        if let Some(name) = &self.name {
+            policy.set_name(name);
+        }
+        if let Some(description) = &self.description {
+            policy.set_description(description);
+        }
+    }
+}
+
+impl Serde for VaultMetadata {}
+impl Encryption for VaultMetadata {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vault_metadata_serde() {
+        let metadata = VaultMetadata::new().name("Savings").color("#f7931a");
+        let json: String = metadata.as_json();
+        assert!(!json.contains("icon"));
+        assert!(!json.contains("tags"));
+        assert!(!json.contains("spending_rules"));
+        assert_eq!(
+            VaultMetadata::from_json(metadata.as_json()).unwrap(),
+            metadata
+        );
+        assert_eq!(
+            VaultMetadata::from_json("{}").unwrap(),
+            VaultMetadata::new()
+        );
+    }
+}
@@ -0,0 +1,254 @@
+// Copyright (c) 2022-2024 Smart Vaults
+// Distributed under the MIT software license
+
+use std::ops::Add;
+
+use nostr::{Event, EventBuilder, EventId, Keys, PublicKey, Tag, Timestamp};
+use smartvaults_core::{
+    ApprovedProposal, CompletedProposal, Policy, Proposal, SharedSigner, Signer,
+};
+use thiserror::Error;
+
+use super::envelope::{Envelope, Error as EnvelopeError, Versioned};
+use super::ProtocolVersion;
+use crate::v1::constants::{
+    APPROVED_PROPOSAL_EXPIRATION, APPROVED_PROPOSAL_KIND, COMPLETED_PROPOSAL_KIND, LABELS_KIND,
+    POLICY_KIND, PROPOSAL_COMMENT_KIND, PROPOSAL_KIND, PROPOSAL_REJECTION_KIND,
+    SHARED_SIGNERS_KIND, SIGNERS_KIND, VAULT_METADATA_KIND,
+};
+use crate::v1::util::{EncryptionError, EncryptionScheme};
+use crate::v1::{Label, ProposalComment, ProposalRejection, VaultMetadata};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error(transparent)]
+    Keys(#[from] nostr::key::Error),
+    #[error(transparent)]
+    EventBuilder(#[from] nostr::event::builder::Error),
+    #[error(transparent)]
+    Encryption(#[from] EncryptionError),
+    #[error(transparent)]
+    Envelope(#[from] EnvelopeError),
+    #[error(transparent)]
+    Label(#[from] crate::v1::label::Error),
+}
+
+/// Common tags of the v2 events
+fn tags<I>(nostr_pubkeys: &[PublicKey], others: I) -> Vec<Tag>
+where
+    I: IntoIterator<Item = Tag>,
+{
+    let mut tags: Vec<Tag> = nostr_pubkeys.iter().copied().map(Tag::public_key).collect();
+    tags.extend(others);
+    tags.push(EncryptionScheme::Nip44.tag());
+    tags.push(ProtocolVersion::V2.tag());
+    tags
+}
+
+/// Smart Vaults v2 event builder
+///
+/// Same kinds and tags of v1, with the content wrapped in an [`Envelope`].
+pub trait SmartVaultsEventBuilder {
+    fn policy(
+        shared_key: &Keys,
+        policy: &Policy,
+        nostr_pubkeys: &[PublicKey],
+    ) -> Result<Event, Error> {
+        let content: String = policy.encrypt_envelope(shared_key)?;
+        let tags = tags(nostr_pubkeys, []);
+        Ok(EventBuilder::new(POLICY_KIND, content, tags).to_event(shared_key)?)
+    }
+
+    /// Re-publish the policy of `vault_id` with a new `shared_key` and the current members
+    fn rotated_policy(
+        shared_key: &Keys,
+        vault_id: EventId,
+        policy: &Policy,
+        nostr_pubkeys: &[PublicKey],
+    ) -> Result<Event, Error> {
+        let content: String = policy.encrypt_envelope(shared_key)?;
+        let tags = tags(nostr_pubkeys, [Tag::event(vault_id)]);
+        Ok(EventBuilder::new(POLICY_KIND, content, tags).to_event(shared_key)?)
+    }
+
+    /// Proposal, optionally expiring (NIP-40) at `expiration`
+    fn proposal(
+        shared_key: &Keys,
+        policy_id: EventId,
+        proposal: &Proposal,
+        expiration: Option<Timestamp>,
+        nostr_pubkeys: &[PublicKey],
+    ) -> Result<Event, Error> {
+        let content: String = proposal.encrypt_envelope(shared_key)?;
+        let others = [Tag::event(policy_id)]
+            .into_iter()
+            .chain(expiration.map(Tag::Expiration));
+        let tags = tags(nostr_pubkeys, others);
+        Ok(EventBuilder::new(PROPOSAL_KIND, content, tags).to_event(shared_key)?)
+    }
+
+    /// Approval signed by `keys` and encrypted with the `shared_key`
+    fn approval(
+        keys: &Keys,
+        shared_key: &Keys,
+        proposal_id: EventId,
+        policy_id: EventId,
+        approval: &ApprovedProposal,
+        nostr_pubkeys: &[PublicKey],
+    ) -> Result<Event, Error> {
+        let content: String = approval.encrypt_envelope(shared_key)?;
+        let tags = tags(
+            nostr_pubkeys,
+            [
+                Tag::event(proposal_id),
+                Tag::event(policy_id),
+                Tag::Expiration(Timestamp::now().add(APPROVED_PROPOSAL_EXPIRATION)),
+            ],
+        );
+        Ok(EventBuilder::new(APPROVED_PROPOSAL_KIND, content, tags).to_event(keys)?)
+    }
+
+    /// Comment signed by `keys` and encrypted with the `shared_key`
+    fn proposal_comment(
+        keys: &Keys,
+        shared_key: &Keys,
+        proposal_id: EventId,
+        policy_id: EventId,
+        comment: &ProposalComment,
+        nostr_pubkeys: &[PublicKey],
+    ) -> Result<Event, Error> {
+        let content: String = comment.encrypt_envelope(shared_key)?;
+        let tags = tags(
+            nostr_pubkeys,
+            [Tag::event(proposal_id), Tag::event(policy_id)],
+        );
+        Ok(EventBuilder::new(PROPOSAL_COMMENT_KIND, content, tags).to_event(keys)?)
+    }
+
+    /// Rejection signed by `keys` and encrypted with the `shared_key`
+    fn proposal_rejection(
+        keys: &Keys,
+        shared_key: &Keys,
+        proposal_id: EventId,
+        policy_id: EventId,
+        rejection: &ProposalRejection,
+        nostr_pubkeys: &[PublicKey],
+    ) -> Result<Event, Error> {
+        let content: String = rejection.encrypt_envelope(shared_key)?;
+        let tags = tags(
+            nostr_pubkeys,
+            [Tag::event(proposal_id), Tag::event(policy_id)],
+        );
+        Ok(EventBuilder::new(PROPOSAL_REJECTION_KIND, content, tags).to_event(keys)?)
+    }
+
+    fn completed_proposal(
+        shared_key: &Keys,
+        proposal_id: EventId,
+        policy_id: EventId,
+        completed_proposal: &CompletedProposal,
+        nostr_pubkeys: &[PublicKey],
+    ) -> Result<Event, Error> {
+        let content: String = completed_proposal.encrypt_envelope(shared_key)?;
+        let tags = tags(
+            nostr_pubkeys,
+            [Tag::event(proposal_id), Tag::event(policy_id)],
+        );
+        Ok(EventBuilder::new(COMPLETED_PROPOSAL_KIND, content, tags).to_event(shared_key)?)
+    }
+
+    fn signer(keys: &Keys, signer: &Signer) -> Result<Event, Error> {
+        let content: String = signer.encrypt_envelope(keys)?;
+        let tags = tags(&[], []);
+        Ok(EventBuilder::new(SIGNERS_KIND, content, tags).to_event(keys)?)
+    }
+
+    fn shared_signer(
+        keys: &Keys,
+        signer_id: EventId,
+        shared_signer: &SharedSigner,
+        receiver: &PublicKey,
+    ) -> Result<Event, Error> {
+        let envelope = Envelope::new(shared_signer)?;
+        let content: String =
+            EncryptionScheme::Nip44.encrypt(keys.secret_key()?, receiver, envelope.as_json())?;
+        let tags = tags(&[*receiver], [Tag::event(signer_id)]);
+        Ok(EventBuilder::new(SHARED_SIGNERS_KIND, content, tags).to_event(keys)?)
+    }
+
+    fn label(
+        shared_key: &Keys,
+        policy_id: EventId,
+        label: &Label,
+        nostr_pubkeys: &[PublicKey],
+    ) -> Result<Event, Error> {
+        let identifier: String = label.generate_identifier(shared_key)?;
+        let content: String = label.encrypt_envelope(shared_key)?;
+        let tags = tags(
+            nostr_pubkeys,
+            [Tag::Identifier(identifier), Tag::event(policy_id)],
+        );
+        Ok(EventBuilder::new(LABELS_KIND, content, tags).to_event(shared_key)?)
+    }
+
+    fn vault_metadata(
+        shared_key: &Keys,
+        policy_id: EventId,
+        metadata: &VaultMetadata,
+        nostr_pubkeys: &[PublicKey],
+    ) -> Result<Event, Error> {
+        let content: String = metadata.encrypt_envelope(shared_key)?;
+        let tags = tags(
+            nostr_pubkeys,
+            [Tag::Identifier(policy_id.to_hex()), Tag::event(policy_id)],
+        );
+        Ok(EventBuilder::new(VAULT_METADATA_KIND, content, tags).to_event(shared_key)?)
+    }
+}
+
+impl SmartVaultsEventBuilder for EventBuilder {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_v2_events() {
+        let keys = Keys::generate();
+        let shared_key = Keys::generate();
+        let policy = Policy::from_desc_or_policy(
+            "Vault",
+            "Description",
+            "thresh(2,pk([87131a00/86'/1'/784923']tpubDDEaK5JwGiGDTRkML9YKh8AF4rHPhkpnXzVjVMDBtzayJpnsWKeiFPxtiyYeGHQj8pnjsei7N98winwZ3ivGoVVKArZVMsEYGig73XVqbSX/0/*),pk([e157a520/86'/1'/784923']tpubDCCYFYCyDkxo1xAzDpoFNdtGcjD5BPLZbEJswjJmwqp67Weqd2C7fg6Jy1SBjgn3wYnKyUtoYKXG4VdQczjqb6FJnqHe3NmFdgy8vNBSty4/0/*))",
+            smartvaults_core::bitcoin::Network::Testnet,
+        )
+        .unwrap();
+
+        let event: Event = <EventBuilder as SmartVaultsEventBuilder>::policy(
+            &shared_key,
+            &policy,
+            &[keys.public_key()],
+        )
+        .unwrap();
+        assert_eq!(
+            ProtocolVersion::from_tags(&event.tags).unwrap(),
+            ProtocolVersion::V2
+        );
+        assert_eq!(
+            EncryptionScheme::from_tags(&event.tags).unwrap(),
+            EncryptionScheme::Nip44
+        );
+        assert_eq!(
+            Policy::decrypt_envelope(&shared_key, &event.content).unwrap(),
+            policy
+        );
+
+        // v1 clients can't read v2 payloads
+        assert!(<Policy as crate::v1::Encryption>::decrypt_with_keys(
+            &shared_key,
+            &event.content,
+            EncryptionScheme::Nip44
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,8 @@
+// Copyright (c) 2022-2024 Smart Vaults
+// Distributed under the MIT software license
+
+/// Current envelope version
+pub const PROTOCOL_VERSION: u8 = 2;
+
+/// Name of the tag that marks the [`ProtocolVersion`](super::ProtocolVersion) of an event
+pub const PROTOCOL_VERSION_TAG: &str = "protocol";
@@ -0,0 +1,236 @@
+// Copyright (c) 2022-2024 Smart Vaults
+// Distributed under the MIT software license
+
+//! Versioned envelope
+//!
+//! ```json
+//! {"version":2,"type":"proposal","data":{...}}
+//! ```
+
+use core::fmt;
+
+use nostr::{key, Keys};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use smartvaults_core::miniscript;
+use smartvaults_core::{policy, signer};
+
+use super::constants::PROTOCOL_VERSION;
+use crate::v1::util::{EncryptionError, EncryptionScheme};
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Keys(#[from] key::Error),
+    #[error(transparent)]
+    JSON(#[from] serde_json::Error),
+    #[error(transparent)]
+    Encryption(#[from] EncryptionError),
+    #[error(transparent)]
+    Miniscript(#[from] miniscript::Error),
+    #[error(transparent)]
+    Policy(#[from] policy::Error),
+    #[error(transparent)]
+    Signer(#[from] signer::Error),
+    #[error("invalid payload")]
+    InvalidPayload,
+    #[error("invalid protocol version: {0}")]
+    InvalidVersion(String),
+    #[error("unsupported protocol version: {0}")]
+    UnsupportedVersion(u8),
+    #[error("missing protocol version")]
+    MissingVersion,
+    #[error("unexpected object type: expected {expected}, found {found}")]
+    UnexpectedType {
+        expected: ObjectType,
+        found: ObjectType,
+    },
+}
+
+/// Type of the object wrapped in the [`Envelope`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ObjectType {
+    Vault,
+    Proposal,
+    Approval,
+    CompletedProposal,
+    Signer,
+    SharedSigner,
+    Label,
+    VaultMetadata,
+    ProposalComment,
+    ProposalRejection,
+}
+
+impl fmt::Display for ObjectType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Vault => write!(f, "vault"),
+            Self::Proposal => write!(f, "proposal"),
+            Self::Approval => write!(f, "approval"),
+            Self::CompletedProposal => write!(f, "completed_proposal"),
+            Self::Signer => write!(f, "signer"),
+            Self::SharedSigner => write!(f, "shared_signer"),
+            Self::Label => write!(f, "label"),
+            Self::VaultMetadata => write!(f, "vault_metadata"),
+            Self::ProposalComment => write!(f, "proposal_comment"),
+            Self::ProposalRejection => write!(f, "proposal_rejection"),
+        }
+    }
+}
+
+/// Versioned and self-describing payload
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Envelope {
+    pub version: u8,
+    #[serde(rename = "type")]
+    pub object_type: ObjectType,
+    pub data: Value,
+}
+
+impl Envelope {
+    /// Wrap object in a new envelope
+    pub fn new<T>(object: &T) -> Result<Self, Error>
+    where
+        T: Versioned,
+    {
+        Ok(Self {
+            version: PROTOCOL_VERSION,
+            object_type: T::TYPE,
+            data: serde_json::to_value(object.to_payload())?,
+        })
+    }
+
+    /// Deserialize envelope from `JSON` string
+    ///
+    /// The version is checked before the rest of the envelope, so a payload from a newer
+    /// client is rejected with [`Error::UnsupportedVersion`]. Envelopes exist only from v2:
+    /// lower versions are rejected too.
+    pub fn from_json<S>(json: S) -> Result<Self, Error>
+    where
+        S: AsRef<str>,
+    {
+        let value: Value = serde_json::from_str(json.as_ref())?;
+        let version: u64 = value
+            .get("version")
+            .and_then(Value::as_u64)
+            .ok_or(Error::MissingVersion)?;
+        if version < 2 || version > u64::from(PROTOCOL_VERSION) {
+            return Err(Error::UnsupportedVersion(
+                u8::try_from(version).unwrap_or(u8::MAX),
+            ));
+        }
+        Ok(serde_json::from_value(value)?)
+    }
+
+    /// Serialize envelope to `JSON` string
+    pub fn as_json(&self) -> String {
+        serde_json::json!(self).to_string()
+    }
+
+    /// Unwrap the object
+    pub fn open<T>(self) -> Result<T, Error>
+    where
+        T: Versioned,
+    {
+        if self.object_type != T::TYPE {
+            return Err(Error::UnexpectedType {
+                expected: T::TYPE,
+                found: self.object_type,
+            });
+        }
+        let payload: T::Payload = serde_json::from_value(self.data)?;
+        T::from_payload(payload)
+    }
+}
+
+/// Object that can be wrapped in an [`Envelope`]
+pub trait Versioned: Sized {
+    const TYPE: ObjectType;
+    /// v2 payload schema (see [`payload`](super::payload))
+    type Payload: Serialize + DeserializeOwned;
+
+    fn to_payload(&self) -> Self::Payload;
+
+    fn from_payload(payload: Self::Payload) -> Result<Self, Error>;
+
+    /// Wrap in an [`Envelope`] and encrypt it with NIP-44
+    fn encrypt_envelope(&self, keys: &Keys) -> Result<String, Error> {
+        let envelope = Envelope::new(self)?;
+        Ok(EncryptionScheme::Nip44.encrypt(
+            keys.secret_key()?,
+            &keys.public_key(),
+            envelope.as_json(),
+        )?)
+    }
+
+    /// Decrypt (NIP-44) the [`Envelope`] and unwrap it
+    fn decrypt_envelope<T>(keys: &Keys, content: T) -> Result<Self, Error>
+    where
+        T: AsRef<str>,
+    {
+        let json: String =
+            EncryptionScheme::Nip44.decrypt(keys.secret_key()?, &keys.public_key(), content)?;
+        Envelope::from_json(json)?.open()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use smartvaults_core::bitcoin::hashes::Hash;
+    use smartvaults_core::bitcoin::{OutPoint, Txid};
+    use smartvaults_core::ApprovedProposal;
+
+    use super::*;
+    use crate::v1::Label;
+
+    #[test]
+    fn test_envelope() {
+        let keys = Keys::generate();
+        let label = Label::utxo(OutPoint::new(Txid::all_zeros(), 1), "Cold storage");
+
+        let envelope = Envelope::new(&label).unwrap();
+        assert_eq!(envelope.version, PROTOCOL_VERSION);
+        assert_eq!(envelope.object_type, ObjectType::Label);
+
+        let json: String = envelope.as_json();
+        assert_eq!(Envelope::from_json(&json).unwrap(), envelope);
+        assert_eq!(envelope.clone().open::<Label>().unwrap(), label);
+        assert!(matches!(
+            envelope.open::<ApprovedProposal>(),
+            Err(Error::UnexpectedType {
+                expected: ObjectType::Approval,
+                found: ObjectType::Label
+            })
+        ));
+
+        let content: String = label.encrypt_envelope(&keys).unwrap();
+        assert_eq!(Label::decrypt_envelope(&keys, content).unwrap(), label);
+    }
+
+    #[test]
+    fn test_envelope_versions() {
+        // v1 payload (raw JSON)
+        assert!(matches!(
+            Envelope::from_json(r#"{"name":"vault"}"#),
+            Err(Error::MissingVersion)
+        ));
+
+        // Envelopes start from v2
+        assert!(matches!(
+            Envelope::from_json(r#"{"version":1,"type":"vault","data":{}}"#),
+            Err(Error::UnsupportedVersion(1))
+        ));
+
+        // Payload from a newer client
+        assert!(matches!(
+            Envelope::from_json(r#"{"version":3,"type":"vault","data":{}}"#),
+            Err(Error::UnsupportedVersion(3))
+        ));
+
+        // Unknown object type
+        assert!(Envelope::from_json(r#"{"version":2,"type":"unknown","data":{}}"#).is_err());
+    }
+}
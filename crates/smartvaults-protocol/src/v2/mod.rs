@@ -0,0 +1,18 @@
+// Copyright (c) 2022-2024 Smart Vaults
+// Distributed under the MIT software license
+
+//! Smart Vaults protocol v2
+//!
+//! Payloads are wrapped in a versioned and self-describing [`Envelope`], always encrypted with
+//! NIP-44. Events keep the v1 kinds and are marked with the [`ProtocolVersion`] tag, so v1 and v2
+//! clients can share the same vault during a migration.
+
+pub mod builder;
+pub mod constants;
+pub mod envelope;
+pub mod payload;
+mod version;
+
+pub use self::builder::{Error as SmartVaultsEventBuilderError, SmartVaultsEventBuilder};
+pub use self::envelope::{Envelope, Error as EnvelopeError, ObjectType, Versioned};
+pub use self::version::ProtocolVersion;
@@ -0,0 +1,852 @@
+// Copyright (c) 2022-2024 Smart Vaults
+// Distributed under the MIT software license
+
+//! v2 payload schemas
+//!
+//! The `data` of the [`Envelope`](super::Envelope) is never the serde representation of the core
+//! types: every [`ObjectType`] has its own schema, decoupled from the internal structs. Bitcoin
+//! data use the standard string encodings (descriptors, addresses, base64 PSBTs, hex transactions).
+//!
+//! Unknown fields are ignored, so new optional fields can be added without a new protocol version.
+
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use smartvaults_core::bitcoin::address::NetworkUnchecked;
+use smartvaults_core::bitcoin::bip32::Fingerprint;
+use smartvaults_core::bitcoin::psbt::PartiallySignedTransaction;
+use smartvaults_core::bitcoin::{Address, Network, OutPoint, Transaction, Txid};
+use smartvaults_core::miniscript::{Descriptor, DescriptorPublicKey};
+use smartvaults_core::proposal::{Period, Recipient};
+use smartvaults_core::spending_rules::SpendingWindow;
+use smartvaults_core::util::search_network_for_descriptor;
+use smartvaults_core::{
+    ApprovedProposal, CompletedProposal, Policy, Proposal, SharedSigner, Signer, SignerType,
+    SpendingRules,
+};
+
+use super::envelope::{Error, ObjectType, Versioned};
+use crate::v1::{Label, LabelData, ProposalComment, ProposalRejection, VaultMetadata};
+
+mod psbt_base64 {
+    use std::str::FromStr;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+    use smartvaults_core::bitcoin::psbt::PartiallySignedTransaction;
+
+    pub fn serialize<S>(psbt: &PartiallySignedTransaction, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&psbt.to_string())
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<PartiallySignedTransaction, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let psbt = String::deserialize(deserializer)?;
+        PartiallySignedTransaction::from_str(&psbt).map_err(serde::de::Error::custom)
+    }
+}
+
+mod tx_hex {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use smartvaults_core::bitcoin::consensus::encode;
+    use smartvaults_core::bitcoin::hashes::hex::FromHex;
+    use smartvaults_core::bitcoin::Transaction;
+
+    pub fn serialize<S>(tx: &Transaction, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&encode::serialize_hex(tx))
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Transaction, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let hex = String::deserialize(deserializer)?;
+        let bytes: Vec<u8> = Vec::from_hex(&hex).map_err(serde::de::Error::custom)?;
+        encode::deserialize(&bytes).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Vault payload
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VaultPayload {
+    pub name: String,
+    pub description: String,
+    pub descriptor: Descriptor<String>,
+    pub network: Network,
+}
+
+/// Recipient payload
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecipientPayload {
+    pub address: Address<NetworkUnchecked>,
+    /// Amount in sat
+    pub amount: u64,
+}
+
+impl From<Recipient> for RecipientPayload {
+    fn from(recipient: Recipient) -> Self {
+        Self {
+            address: recipient.address,
+            amount: recipient.amount,
+        }
+    }
+}
+
+impl From<RecipientPayload> for Recipient {
+    fn from(payload: RecipientPayload) -> Self {
+        Self {
+            address: payload.address,
+            amount: payload.amount,
+        }
+    }
+}
+
+/// Period payload (unix timestamps)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PeriodPayload {
+    pub from: u64,
+    pub to: u64,
+}
+
+impl From<Period> for PeriodPayload {
+    fn from(period: Period) -> Self {
+        Self {
+            from: period.from,
+            to: period.to,
+        }
+    }
+}
+
+impl From<PeriodPayload> for Period {
+    fn from(payload: PeriodPayload) -> Self {
+        Self {
+            from: payload.from,
+            to: payload.to,
+        }
+    }
+}
+
+/// Proposal payload
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ProposalPayload {
+    Spending {
+        descriptor: Descriptor<String>,
+        recipients: Vec<RecipientPayload>,
+        description: String,
+        #[serde(with = "psbt_base64")]
+        psbt: PartiallySignedTransaction,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        policy_path: Option<BTreeMap<String, Vec<usize>>>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        cpfp_parent: Option<Txid>,
+    },
+    ProofOfReserve {
+        descriptor: Descriptor<String>,
+        message: String,
+        #[serde(with = "psbt_base64")]
+        psbt: PartiallySignedTransaction,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        height: Option<u32>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        other_descriptors: Vec<Descriptor<String>>,
+    },
+    KeyAgentPayment {
+        descriptor: Descriptor<String>,
+        signer_descriptor: Descriptor<String>,
+        amount: u64,
+        description: String,
+        period: PeriodPayload,
+        #[serde(with = "psbt_base64")]
+        psbt: PartiallySignedTransaction,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        policy_path: Option<BTreeMap<String, Vec<usize>>>,
+    },
+    FeeBump {
+        descriptor: Descriptor<String>,
+        replaced_txid: Txid,
+        description: String,
+        #[serde(with = "psbt_base64")]
+        psbt: PartiallySignedTransaction,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        policy_path: Option<BTreeMap<String, Vec<usize>>>,
+    },
+}
+
+/// Kind of the approved proposal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalKind {
+    Spending,
+    ProofOfReserve,
+    KeyAgentPayment,
+    FeeBump,
+}
+
+/// Approval payload
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApprovalPayload {
+    pub kind: ApprovalKind,
+    /// Signed PSBT
+    #[serde(with = "psbt_base64")]
+    pub psbt: PartiallySignedTransaction,
+}
+
+/// Completed proposal payload
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CompletedProposalPayload {
+    Spending {
+        #[serde(with = "tx_hex")]
+        tx: Transaction,
+        description: String,
+    },
+    ProofOfReserve {
+        descriptor: Descriptor<String>,
+        message: String,
+        #[serde(with = "psbt_base64")]
+        psbt: PartiallySignedTransaction,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        height: Option<u32>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        other_descriptors: Vec<Descriptor<String>>,
+    },
+    KeyAgentPayment {
+        #[serde(with = "tx_hex")]
+        tx: Transaction,
+        signer_descriptor: Descriptor<String>,
+        description: String,
+        period: PeriodPayload,
+    },
+    FeeBump {
+        #[serde(with = "tx_hex")]
+        tx: Transaction,
+        replaced_txid: Txid,
+        description: String,
+    },
+}
+
+/// Signer type payload
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignerTypePayload {
+    Seed,
+    Hardware,
+    AirGap,
+}
+
+impl From<SignerType> for SignerTypePayload {
+    fn from(signer_type: SignerType) -> Self {
+        match signer_type {
+            SignerType::Seed => Self::Seed,
+            SignerType::Hardware => Self::Hardware,
+            SignerType::AirGap => Self::AirGap,
+        }
+    }
+}
+
+impl From<SignerTypePayload> for SignerType {
+    fn from(payload: SignerTypePayload) -> Self {
+        match payload {
+            SignerTypePayload::Seed => Self::Seed,
+            SignerTypePayload::Hardware => Self::Hardware,
+            SignerTypePayload::AirGap => Self::AirGap,
+        }
+    }
+}
+
+/// Signer payload
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignerPayload {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub fingerprint: Fingerprint,
+    pub descriptor: Descriptor<DescriptorPublicKey>,
+    pub signer_type: SignerTypePayload,
+}
+
+/// Shared signer payload
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SharedSignerPayload {
+    pub fingerprint: Fingerprint,
+    pub descriptor: Descriptor<DescriptorPublicKey>,
+}
+
+/// Labeled data
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum LabelDataPayload {
+    Address { address: Address<NetworkUnchecked> },
+    Utxo { txid: Txid, vout: u32 },
+    Txid { txid: Txid },
+}
+
+/// Label payload
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LabelPayload {
+    #[serde(flatten)]
+    pub data: LabelDataPayload,
+    pub text: String,
+}
+
+/// Spending window payload
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpendingWindowPayload {
+    /// Max amount in sat
+    pub max: u64,
+    /// Window length in secs
+    pub period: u64,
+}
+
+/// Spending rules payload
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpendingRulesPayload {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_per_tx: Option<u64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub windows: Vec<SpendingWindowPayload>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowlist: Vec<Address<NetworkUnchecked>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description_required_above: Option<u64>,
+}
+
+impl From<SpendingRules> for SpendingRulesPayload {
+    fn from(rules: SpendingRules) -> Self {
+        Self {
+            max_per_tx: rules.max_per_tx,
+            windows: rules
+                .windows
+                .into_iter()
+                .map(|w| SpendingWindowPayload {
+                    max: w.max,
+                    period: w.period,
+                })
+                .collect(),
+            allowlist: rules.allowlist,
+            description_required_above: rules.description_required_above,
+        }
+    }
+}
+
+impl From<SpendingRulesPayload> for SpendingRules {
+    fn from(payload: SpendingRulesPayload) -> Self {
+        Self {
+            max_per_tx: payload.max_per_tx,
+            windows: payload
+                .windows
+                .into_iter()
+                .map(|w| SpendingWindow::new(w.max, w.period))
+                .collect(),
+            allowlist: payload.allowlist,
+            description_required_above: payload.description_required_above,
+        }
+    }
+}
+
+/// Vault metadata payload
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VaultMetadataPayload {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spending_rules: Option<SpendingRulesPayload>,
+}
+
+/// Proposal comment payload
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProposalCommentPayload {
+    pub text: String,
+}
+
+/// Proposal rejection payload
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProposalRejectionPayload {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl Versioned for Policy {
+    const TYPE: ObjectType = ObjectType::Vault;
+    type Payload = VaultPayload;
+
+    fn to_payload(&self) -> Self::Payload {
+        VaultPayload {
+            name: self.name(),
+            description: self.description(),
+            descriptor: self.descriptor(),
+            network: self.network(),
+        }
+    }
+
+    fn from_payload(payload: Self::Payload) -> Result<Self, Error> {
+        Ok(Self::new(
+            payload.name,
+            payload.description,
+            payload.descriptor,
+            payload.network,
+        )?)
+    }
+}
+
+impl Versioned for Proposal {
+    const TYPE: ObjectType = ObjectType::Proposal;
+    type Payload = ProposalPayload;
+
+    fn to_payload(&self) -> Self::Payload {
+        match self.clone() {
+            Self::Spending {
+                descriptor,
+                to_address,
+                amount,
+                other_recipients,
+                description,
+                psbt,
+                policy_path,
+                cpfp_parent,
+            } => {
+                let recipient = Recipient {
+                    address: to_address,
+                    amount,
+                };
+                ProposalPayload::Spending {
+                    descriptor,
+                    recipients: std::iter::once(recipient)
+                        .chain(other_recipients)
+                        .map(RecipientPayload::from)
+                        .collect(),
+                    description,
+                    psbt,
+                    policy_path,
+                    cpfp_parent,
+                }
+            }
+            Self::ProofOfReserve {
+                descriptor,
+                message,
+                psbt,
+                height,
+                other_descriptors,
+            } => ProposalPayload::ProofOfReserve {
+                descriptor,
+                message,
+                psbt,
+                height,
+                other_descriptors,
+            },
+            Self::KeyAgentPayment {
+                descriptor,
+                signer_descriptor,
+                amount,
+                description,
+                period,
+                psbt,
+                policy_path,
+            } => ProposalPayload::KeyAgentPayment {
+                descriptor,
+                signer_descriptor,
+                amount,
+                description,
+                period: period.into(),
+                psbt,
+                policy_path,
+            },
+            Self::FeeBump {
+                descriptor,
+                replaced_txid,
+                description,
+                psbt,
+                policy_path,
+            } => ProposalPayload::FeeBump {
+                descriptor,
+                replaced_txid,
+                description,
+                psbt,
+                policy_path,
+            },
+        }
+    }
+
+    fn from_payload(payload: Self::Payload) -> Result<Self, Error> {
+        match payload {
+            ProposalPayload::Spending {
+                descriptor,
+                recipients,
+                description,
+                psbt,
+                policy_path,
+                cpfp_parent,
+            } => {
+                let mut recipients = recipients.into_iter().map(Recipient::from);
+                let Recipient { address, amount } =
+                    recipients.next().ok_or(Error::InvalidPayload)?;
+                Ok(Self::Spending {
+                    descriptor,
+                    to_address: address,
+                    amount,
+                    other_recipients: recipients.collect(),
+                    description,
+                    psbt,
+                    policy_path,
+                    cpfp_parent,
+                })
+            }
+            ProposalPayload::ProofOfReserve {
+                descriptor,
+                message,
+                psbt,
+                height,
+                other_descriptors,
+            } => Ok(Self::ProofOfReserve {
+                descriptor,
+                message,
+                psbt,
+                height,
+                other_descriptors,
+            }),
+            ProposalPayload::KeyAgentPayment {
+                descriptor,
+                signer_descriptor,
+                amount,
+                description,
+                period,
+                psbt,
+                policy_path,
+            } => Ok(Self::KeyAgentPayment {
+                descriptor,
+                signer_descriptor,
+                amount,
+                description,
+                period: period.into(),
+                psbt,
+                policy_path,
+            }),
+            ProposalPayload::FeeBump {
+                descriptor,
+                replaced_txid,
+                description,
+                psbt,
+                policy_path,
+            } => Ok(Self::FeeBump {
+                descriptor,
+                replaced_txid,
+                description,
+                psbt,
+                policy_path,
+            }),
+        }
+    }
+}
+
+impl Versioned for ApprovedProposal {
+    const TYPE: ObjectType = ObjectType::Approval;
+    type Payload = ApprovalPayload;
+
+    fn to_payload(&self) -> Self::Payload {
+        let (kind, psbt) = match self.clone() {
+            Self::Spending { psbt } => (ApprovalKind::Spending, psbt),
+            Self::ProofOfReserve { psbt } => (ApprovalKind::ProofOfReserve, psbt),
+            Self::KeyAgentPayment { psbt } => (ApprovalKind::KeyAgentPayment, psbt),
+            Self::FeeBump { psbt } => (ApprovalKind::FeeBump, psbt),
+        };
+        ApprovalPayload { kind, psbt }
+    }
+
+    fn from_payload(payload: Self::Payload) -> Result<Self, Error> {
+        let ApprovalPayload { kind, psbt } = payload;
+        Ok(match kind {
+            ApprovalKind::Spending => Self::spending(psbt),
+            ApprovalKind::ProofOfReserve => Self::proof_of_reserve(psbt),
+            ApprovalKind::KeyAgentPayment => Self::key_agent_payment(psbt),
+            ApprovalKind::FeeBump => Self::fee_bump(psbt),
+        })
+    }
+}
+
+impl Versioned for CompletedProposal {
+    const TYPE: ObjectType = ObjectType::CompletedProposal;
+    type Payload = CompletedProposalPayload;
+
+    fn to_payload(&self) -> Self::Payload {
+        match self.clone() {
+            Self::Spending { tx, description } => {
+                CompletedProposalPayload::Spending { tx, description }
+            }
+            Self::ProofOfReserve {
+                message,
+                descriptor,
+                psbt,
+                height,
+                other_descriptors,
+            } => CompletedProposalPayload::ProofOfReserve {
+                descriptor,
+                message,
+                psbt,
+                height,
+                other_descriptors,
+            },
+            Self::KeyAgentPayment {
+                tx,
+                signer_descriptor,
+                description,
+                period,
+            } => CompletedProposalPayload::KeyAgentPayment {
+                tx,
+                signer_descriptor,
+                description,
+                period: period.into(),
+            },
+            Self::FeeBump {
+                tx,
+                replaced_txid,
+                description,
+            } => CompletedProposalPayload::FeeBump {
+                tx,
+                replaced_txid,
+                description,
+            },
+        }
+    }
+
+    fn from_payload(payload: Self::Payload) -> Result<Self, Error> {
+        Ok(match payload {
+            CompletedProposalPayload::Spending { tx, description } => {
+                Self::Spending { tx, description }
+            }
+            CompletedProposalPayload::ProofOfReserve {
+                descriptor,
+                message,
+                psbt,
+                height,
+                other_descriptors,
+            } => Self::ProofOfReserve {
+                message,
+                descriptor,
+                psbt,
+                height,
+                other_descriptors,
+            },
+            CompletedProposalPayload::KeyAgentPayment {
+                tx,
+                signer_descriptor,
+                description,
+                period,
+            } => Self::KeyAgentPayment {
+                tx,
+                signer_descriptor,
+                description,
+                period: period.into(),
+            },
+            CompletedProposalPayload::FeeBump {
+                tx,
+                replaced_txid,
+                description,
+            } => Self::FeeBump {
+                tx,
+                replaced_txid,
+                description,
+            },
+        })
+    }
+}
+
+impl Versioned for Signer {
+    const TYPE: ObjectType = ObjectType::Signer;
+    type Payload = SignerPayload;
+
+    fn to_payload(&self) -> Self::Payload {
+        SignerPayload {
+            name: self.name(),
+            description: self.description(),
+            fingerprint: self.fingerprint(),
+            descriptor: self.descriptor(),
+            signer_type: self.signer_type().into(),
+        }
+    }
+
+    fn from_payload(payload: Self::Payload) -> Result<Self, Error> {
+        let descriptor: Descriptor<String> = Descriptor::from_str(&payload.descriptor.to_string())?;
+        let network: Network =
+            search_network_for_descriptor(&descriptor).ok_or(Error::InvalidPayload)?;
+        Ok(Self::new(
+            payload.name,
+            payload.description,
+            payload.fingerprint,
+            payload.descriptor,
+            payload.signer_type.into(),
+            network,
+        )?)
+    }
+}
+
+impl Versioned for SharedSigner {
+    const TYPE: ObjectType = ObjectType::SharedSigner;
+    type Payload = SharedSignerPayload;
+
+    fn to_payload(&self) -> Self::Payload {
+        SharedSignerPayload {
+            fingerprint: self.fingerprint(),
+            descriptor: self.descriptor(),
+        }
+    }
+
+    fn from_payload(payload: Self::Payload) -> Result<Self, Error> {
+        Ok(Self::new(payload.fingerprint, payload.descriptor))
+    }
+}
+
+impl Versioned for Label {
+    const TYPE: ObjectType = ObjectType::Label;
+    type Payload = LabelPayload;
+
+    fn to_payload(&self) -> Self::Payload {
+        let data = match self.data() {
+            LabelData::Address(address) => LabelDataPayload::Address { address },
+            LabelData::Utxo(OutPoint { txid, vout }) => LabelDataPayload::Utxo { txid, vout },
+            LabelData::Txid(txid) => LabelDataPayload::Txid { txid },
+        };
+        LabelPayload {
+            data,
+            text: self.text(),
+        }
+    }
+
+    fn from_payload(payload: Self::Payload) -> Result<Self, Error> {
+        let data = match payload.data {
+            LabelDataPayload::Address { address } => LabelData::Address(address),
+            LabelDataPayload::Utxo { txid, vout } => LabelData::Utxo(OutPoint::new(txid, vout)),
+            LabelDataPayload::Txid { txid } => LabelData::Txid(txid),
+        };
+        Ok(Self::new(data, payload.text))
+    }
+}
+
+impl Versioned for VaultMetadata {
+    const TYPE: ObjectType = ObjectType::VaultMetadata;
+    type Payload = VaultMetadataPayload;
+
+    fn to_payload(&self) -> Self::Payload {
+        let metadata = self.clone();
+        VaultMetadataPayload {
+            name: metadata.name,
+            description: metadata.description,
+            icon: metadata.icon,
+            color: metadata.color,
+            tags: metadata.tags,
+            spending_rules: if metadata.spending_rules.is_empty() {
+                None
+            } else {
+                Some(metadata.spending_rules.into())
+            },
+        }
+    }
+
+    fn from_payload(payload: Self::Payload) -> Result<Self, Error> {
+        Ok(Self {
+            name: payload.name,
+            description: payload.description,
+            icon: payload.icon,
+            color: payload.color,
+            tags: payload.tags,
+            spending_rules: payload.spending_rules.map(Into::into).unwrap_or_default(),
+        })
+    }
+}
+
+impl Versioned for ProposalComment {
+    const TYPE: ObjectType = ObjectType::ProposalComment;
+    type Payload = ProposalCommentPayload;
+
+    fn to_payload(&self) -> Self::Payload {
+        ProposalCommentPayload {
+            text: self.text.clone(),
+        }
+    }
+
+    fn from_payload(payload: Self::Payload) -> Result<Self, Error> {
+        Ok(Self::new(payload.text))
+    }
+}
+
+impl Versioned for ProposalRejection {
+    const TYPE: ObjectType = ObjectType::ProposalRejection;
+    type Payload = ProposalRejectionPayload;
+
+    fn to_payload(&self) -> Self::Payload {
+        ProposalRejectionPayload {
+            reason: self.reason.clone(),
+        }
+    }
+
+    fn from_payload(payload: Self::Payload) -> Result<Self, Error> {
+        Ok(Self::new(payload.reason))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use smartvaults_core::bitcoin::hashes::Hash;
+
+    use super::*;
+
+    #[test]
+    fn test_label_schema() {
+        let label = Label::utxo(OutPoint::new(Txid::all_zeros(), 1), "Cold storage");
+        let value = serde_json::to_value(label.to_payload()).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "kind": "utxo",
+                "txid": Txid::all_zeros().to_string(),
+                "vout": 1,
+                "text": "Cold storage"
+            })
+        );
+
+        let payload: LabelPayload = serde_json::from_value(value).unwrap();
+        assert_eq!(Label::from_payload(payload).unwrap(), label);
+    }
+
+    #[test]
+    fn test_vault_metadata_schema() {
+        let mut metadata = VaultMetadata::new().name("Savings");
+        assert_eq!(
+            serde_json::to_value(metadata.to_payload()).unwrap(),
+            json!({ "name": "Savings" })
+        );
+
+        metadata.spending_rules = SpendingRules {
+            max_per_tx: Some(100_000),
+            ..Default::default()
+        };
+        let value = serde_json::to_value(metadata.to_payload()).unwrap();
+        assert_eq!(
+            value,
+            json!({ "name": "Savings", "spending_rules": { "max_per_tx": 100000 } })
+        );
+
+        // Unknown fields are ignored
+        let mut value = value;
+        value["unknown"] = json!(true);
+        let payload: VaultMetadataPayload = serde_json::from_value(value).unwrap();
+        assert_eq!(VaultMetadata::from_payload(payload).unwrap(), metadata);
+    }
+}
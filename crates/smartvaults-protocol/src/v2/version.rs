@@ -0,0 +1,116 @@
+// Copyright (c) 2022-2024 Smart Vaults
+// Distributed under the MIT software license
+
+use core::fmt;
+use core::str::FromStr;
+
+use nostr::{Tag, TagKind};
+use serde::{Deserialize, Serialize};
+
+use super::constants::PROTOCOL_VERSION_TAG;
+use super::envelope::Error;
+
+/// Protocol version of the vault events
+///
+/// Events without the [`PROTOCOL_VERSION_TAG`] tag are [`ProtocolVersion::V1`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "u8", into = "u8")]
+pub enum ProtocolVersion {
+    /// Raw JSON of the core types, encrypted with NIP-44
+    ///
+    /// Readable by the v1 clients that support the encryption tag, not by the older ones that only
+    /// know the [`EncryptionScheme::Legacy`](crate::v1::EncryptionScheme::Legacy) scheme.
+    #[default]
+    V1,
+    /// Versioned [`Envelope`](super::Envelope) with explicit [`payload`](super::payload) schemas
+    V2,
+}
+
+impl fmt::Display for ProtocolVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", u8::from(*self))
+    }
+}
+
+impl From<ProtocolVersion> for u8 {
+    fn from(version: ProtocolVersion) -> Self {
+        match version {
+            ProtocolVersion::V1 => 1,
+            ProtocolVersion::V2 => 2,
+        }
+    }
+}
+
+impl TryFrom<u8> for ProtocolVersion {
+    type Error = Error;
+
+    fn try_from(version: u8) -> Result<Self, Self::Error> {
+        match version {
+            1 => Ok(Self::V1),
+            2 => Ok(Self::V2),
+            v => Err(Error::UnsupportedVersion(v)),
+        }
+    }
+}
+
+impl FromStr for ProtocolVersion {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let version: u8 = s
+            .parse()
+            .map_err(|_| Error::InvalidVersion(s.to_string()))?;
+        Self::try_from(version)
+    }
+}
+
+impl ProtocolVersion {
+    /// Compose the tag that marks the protocol version of an event
+    pub fn tag(&self) -> Tag {
+        Tag::Generic(
+            TagKind::Custom(PROTOCOL_VERSION_TAG.to_string()),
+            vec![self.to_string()],
+        )
+    }
+
+    /// Get the protocol version from the event tags
+    pub fn from_tags<'a, I>(tags: I) -> Result<Self, Error>
+    where
+        I: IntoIterator<Item = &'a Tag>,
+    {
+        for tag in tags.into_iter() {
+            if let [kind, version, ..] = tag.as_vec().as_slice() {
+                if kind == PROTOCOL_VERSION_TAG {
+                    return Self::from_str(version);
+                }
+            }
+        }
+        Ok(Self::V1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_version_from_tags() {
+        assert_eq!(
+            ProtocolVersion::from_tags(&Vec::<Tag>::new()).unwrap(),
+            ProtocolVersion::V1
+        );
+        assert_eq!(
+            ProtocolVersion::from_tags(&[ProtocolVersion::V2.tag()]).unwrap(),
+            ProtocolVersion::V2
+        );
+
+        let tags = vec![Tag::Generic(
+            TagKind::Custom(PROTOCOL_VERSION_TAG.to_string()),
+            vec![String::from("3")],
+        )];
+        assert!(matches!(
+            ProtocolVersion::from_tags(&tags),
+            Err(Error::UnsupportedVersion(3))
+        ));
+    }
+}
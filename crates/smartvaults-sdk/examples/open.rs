@@ -39,8 +39,10 @@ async fn main() {
     let proposal = client
         .spend(
             policies.first().unwrap().policy_id,
-            Address::from_str("mohjSavDdQYHRYXcS3uS6ttaHP8amyvX78").unwrap(),
-            Amount::Custom(10_934), // Or, `Amount::Max` to send all
+            vec![(
+                Address::from_str("mohjSavDdQYHRYXcS3uS6ttaHP8amyvX78").unwrap(),
+                Amount::Custom(10_934), // Or, `Amount::Max` to send all
+            )], // Add more recipients to make a batch payment
             "Back to the faucet",
             FeeRate::Priority(Priority::Medium), // Or, FeeRate::Rate(1.0) to specify the sat/vByte
             None,                                // Specify the UTXOs to use (optional)
@@ -59,7 +61,7 @@ async fn main() {
 
     // Approve a proposal
     client
-        .approve("password", proposal.proposal_id)
+        .approve("password", proposal.proposal_id, false)
         .await
         .unwrap();
     // other approvals ...
@@ -0,0 +1,100 @@
+// Copyright (c) 2022-2024 Smart Vaults
+// Distributed under the MIT software license
+
+use nostr_sdk::database::NostrDatabaseExt;
+use nostr_sdk::{Event, EventId, Keys, Timestamp};
+use smartvaults_protocol::v1::ProposalComment;
+use smartvaults_protocol::v2::ProtocolVersion;
+
+use super::{Error, SmartVaults};
+use crate::compat;
+use crate::storage::{InternalPolicy, InternalProposal, InternalProposalComment};
+use crate::types::GetProposalComment;
+
+impl SmartVaults {
+    /// Comment a proposal
+    pub async fn comment_on_proposal<S>(
+        &self,
+        proposal_id: EventId,
+        text: S,
+    ) -> Result<EventId, Error>
+    where
+        S: Into<String>,
+    {
+        let InternalProposal { policy_id, .. } = self.storage.proposal(&proposal_id).await?;
+        let comment = ProposalComment::new(text);
+        self.publish_comment(policy_id, proposal_id, comment).await
+    }
+
+    /// Publish own [`ProposalComment`] and index it
+    pub(super) async fn publish_comment(
+        &self,
+        policy_id: EventId,
+        proposal_id: EventId,
+        comment: ProposalComment,
+    ) -> Result<EventId, Error> {
+        let keys: &Keys = self.keys();
+        let shared_key: Keys = self.storage.shared_key(&policy_id).await?;
+        let InternalPolicy { public_keys, .. } = self.storage.vault(&policy_id).await?;
+
+        // Compose event
+        let version: ProtocolVersion = self.config.protocol_version().await;
+        let event: Event = compat::proposal_comment(
+            version,
+            keys,
+            &shared_key,
+            proposal_id,
+            policy_id,
+            &comment,
+            &public_keys,
+        )?;
+        let timestamp: Timestamp = event.created_at;
+
+        // Publish event
+        let comment_id: EventId = self.send_vault_event(keys, event, &public_keys).await?;
+
+        // Index comment
+        self.storage
+            .save_comment(
+                comment_id,
+                InternalProposalComment {
+                    proposal_id,
+                    policy_id,
+                    public_key: keys.public_key(),
+                    comment,
+                    timestamp,
+                },
+            )
+            .await;
+
+        Ok(comment_id)
+    }
+
+    /// Get proposal comments, from the oldest to the newest
+    pub async fn get_proposal_comments(
+        &self,
+        proposal_id: EventId,
+    ) -> Result<Vec<GetProposalComment>, Error> {
+        let comments = self.storage.comments_by_proposal_id(&proposal_id).await;
+        let mut list = Vec::with_capacity(comments.len());
+        for (
+            comment_id,
+            InternalProposalComment {
+                public_key,
+                comment,
+                timestamp,
+                ..
+            },
+        ) in comments.into_iter()
+        {
+            list.push(GetProposalComment {
+                comment_id,
+                user: self.client.database().profile(public_key).await?,
+                comment,
+                timestamp,
+            });
+        }
+        list.sort();
+        Ok(list)
+    }
+}
@@ -234,11 +234,10 @@ impl SmartVaults {
     where
         S: Into<String>,
     {
-        let mut prop: GetProposal = self
-            .spend(
+        let proposal: Proposal = self
+            .build_spending_proposal(
                 policy_id,
-                address,
-                amount,
+                vec![(address, amount)],
                 description,
                 fee_rate,
                 utxos,
@@ -252,9 +251,9 @@ impl SmartVaults {
             description,
             psbt,
             ..
-        } = prop.proposal
+        } = proposal
         {
-            prop.proposal = Proposal::KeyAgentPayment {
+            let proposal = Proposal::KeyAgentPayment {
                 descriptor,
                 signer_descriptor,
                 amount,
@@ -263,7 +262,7 @@ impl SmartVaults {
                 psbt,
                 policy_path,
             };
-            Ok(prop)
+            self.publish_proposal(policy_id, proposal).await
         } else {
             Err(Error::UnexpectedProposal)
         }
@@ -1,10 +1,12 @@
 // Copyright (c) 2022-2024 Smart Vaults
 // Distributed under the MIT software license
 
-use nostr_sdk::{Event, EventBuilder, EventId, Keys};
-use smartvaults_protocol::v1::{Label, SmartVaultsEventBuilder};
+use nostr_sdk::{Event, EventId, Keys};
+use smartvaults_protocol::v1::Label;
+use smartvaults_protocol::v2::ProtocolVersion;
 
 use super::{Error, SmartVaults};
+use crate::compat;
 use crate::storage::InternalPolicy;
 
 impl SmartVaults {
@@ -15,10 +17,13 @@ impl SmartVaults {
         // TODO: check if address or UTXO actually belong to the policy
 
         // Compose event
-        let event: Event = EventBuilder::label(&shared_key, policy_id, &label, &public_keys)?;
+        let version: ProtocolVersion = self.config.protocol_version().await;
+        let event: Event = compat::label(version, &shared_key, policy_id, &label, &public_keys)?;
 
         // Publish event
-        let event_id: EventId = self.client.send_event(event).await?;
+        let event_id: EventId = self
+            .send_vault_event(&shared_key, event, &public_keys)
+            .await?;
 
         // Save to db
         let identifier: String = label.generate_identifier(&shared_key)?;
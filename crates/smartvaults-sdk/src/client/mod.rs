@@ -1,10 +1,12 @@
 // Copyright (c) 2022-2024 Smart Vaults
 // Distributed under the MIT software license
 
+use std::cmp;
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::net::SocketAddr;
 use std::ops::Add;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
 use std::time::Duration;
@@ -17,52 +19,65 @@ use nostr_sdk::database::{NostrDatabaseExt, Order};
 use nostr_sdk::nips::nip06::FromMnemonic;
 use nostr_sdk::pool::pool;
 use nostr_sdk::{
-    nips, Client, ClientBuilder, ClientMessage, Contact, Event, EventBuilder, EventId, Filter,
-    JsonUtil, Keys, Kind, Metadata, Options, Profile, PublicKey, Relay, RelayOptions,
-    RelayPoolNotification, RelaySendOptions, Result, SQLiteDatabase, SubscribeAutoCloseOptions,
-    SubscribeOptions, Tag, Timestamp, TryIntoUrl, UncheckedUrl, Url,
+    Client, ClientBuilder, Contact, Event, EventBuilder, EventId, Filter, JsonUtil, Keys, Kind,
+    Metadata, Options, Profile, PublicKey, Relay, RelayOptions, RelayPoolNotification,
+    RelaySendOptions, Result, SQLiteDatabase, SubscribeAutoCloseOptions, SubscribeOptions, Tag,
+    Timestamp, TryIntoUrl, UncheckedUrl, Url,
 };
 use parking_lot::RwLock as ParkingLotRwLock;
 use smartvaults_core::bdk::chain::ConfirmationTime;
 use smartvaults_core::bdk::wallet::{AddressIndex, Balance};
 use smartvaults_core::bdk::FeeRate as BdkFeeRate;
+use smartvaults_core::bip21::{Bip21, Error as Bip21Error};
 use smartvaults_core::bips::bip39::Mnemonic;
 use smartvaults_core::bitcoin::address::NetworkUnchecked;
 use smartvaults_core::bitcoin::bip32::Fingerprint;
 use smartvaults_core::bitcoin::psbt::PartiallySignedTransaction;
-use smartvaults_core::bitcoin::{Address, Network, OutPoint, ScriptBuf, Txid};
+use smartvaults_core::bitcoin::{Address, Network, OutPoint, ScriptBuf, TxOut, Txid};
 use smartvaults_core::miniscript::Descriptor;
+use smartvaults_core::policy::UtxoTimelock;
+use smartvaults_core::reserves::{self, AggregatedProofAmounts};
 use smartvaults_core::signer::smartvaults_signer;
 use smartvaults_core::types::{KeeChain, Keychain, Seed, WordCount};
 use smartvaults_core::{
-    Amount, ApprovedProposal, CompletedProposal, FeeRate, Policy, PolicyTemplate, Proposal, Signer,
-    SECP256K1,
+    Amount, ApprovedProposal, CompletedProposal, FeeRate, Policy, PolicyTemplate, Proposal,
+    ProposalSigner, Signer, SignerType, SECP256K1,
 };
-use smartvaults_protocol::v1::constants::{
-    APPROVED_PROPOSAL_EXPIRATION, APPROVED_PROPOSAL_KIND, COMPLETED_PROPOSAL_KIND, PROPOSAL_KIND,
-    SHARED_KEY_KIND,
+use smartvaults_protocol::v1::constants::{COMPLETED_PROPOSAL_KIND, PROPOSAL_KIND};
+use smartvaults_protocol::v1::{
+    gift_wrap, Label, LabelData, SmartVaultsEventBuilder, VaultMetadata,
 };
-use smartvaults_protocol::v1::{Encryption, Label, LabelData, SmartVaultsEventBuilder};
+use smartvaults_protocol::v2::ProtocolVersion;
 use smartvaults_sdk_sqlite::Store;
 use tokio::sync::broadcast::{self, Sender};
 
+mod comment;
 mod connect;
 mod key_agent;
 mod label;
+mod proposal_signer;
+mod psbt_file;
+mod rebroadcast;
+mod rejection;
+mod seed_signers;
 mod signers;
+mod spending_rules;
 mod sync;
+mod ur;
+mod vault_metadata;
 
 pub use self::sync::{EventHandled, Message};
+use crate::compat;
 use crate::config::{Config, ElectrumEndpoint};
 use crate::constants::{MAINNET_RELAYS, SEND_TIMEOUT, TESTNET_RELAYS};
 use crate::manager::{Manager, SmartVaultsWallet, TransactionDetails};
 use crate::storage::{
     InternalApproval, InternalCompletedProposal, InternalPolicy, InternalProposal,
-    SmartVaultsStorage,
+    InternalProposalComment, SmartVaultsStorage,
 };
 use crate::types::{
     GetAddress, GetApproval, GetApprovedProposals, GetCompletedProposal, GetPolicy, GetProposal,
-    GetTransaction, GetUtxo, PolicyBackup,
+    GetTransaction, GetUtxo, MigratedProposal, PolicyBackup, SharedKeyRotation,
 };
 use crate::{util, Error};
 
@@ -80,6 +95,7 @@ pub struct SmartVaults {
     syncing: Arc<AtomicBool>,
     sync_channel: Sender<Message>,
     default_signer: Signer,
+    proposal_signers: Arc<ParkingLotRwLock<HashMap<SignerType, Arc<dyn ProposalSigner>>>>,
 }
 
 impl SmartVaults {
@@ -136,6 +152,7 @@ impl SmartVaults {
             syncing: Arc::new(AtomicBool::new(false)),
             sync_channel: sender,
             default_signer: smartvaults_signer(seed, network)?,
+            proposal_signers: Arc::new(ParkingLotRwLock::new(HashMap::new())),
         };
 
         this.init().await?;
@@ -695,9 +712,17 @@ impl SmartVaults {
 
     #[tracing::instrument(skip_all, level = "trace")]
     pub async fn get_policy_by_id(&self, policy_id: EventId) -> Result<GetPolicy, Error> {
+        let InternalPolicy { mut policy, .. } = self.storage.vault(&policy_id).await?;
+        let metadata: VaultMetadata = self
+            .storage
+            .vault_metadata(&policy_id)
+            .await
+            .unwrap_or_default();
+        metadata.apply(&mut policy);
         Ok(GetPolicy {
             policy_id,
-            policy: self.storage.vault(&policy_id).await?.policy,
+            policy,
+            metadata,
             balance: self.manager.get_balance(policy_id).await?,
             last_sync: self.manager.last_sync(policy_id).await?,
         })
@@ -709,6 +734,7 @@ impl SmartVaults {
             policy_id,
             proposal,
             timestamp,
+            expiration,
         } = self.storage.proposal(&proposal_id).await?;
         let approvals = self
             .storage
@@ -723,6 +749,7 @@ impl SmartVaults {
             signed: proposal.finalize(approvals, self.network).is_ok(),
             proposal,
             timestamp,
+            expiration,
         })
     }
 
@@ -731,15 +758,21 @@ impl SmartVaults {
         &self,
         completed_proposal_id: EventId,
     ) -> Result<GetCompletedProposal, Error> {
-        self.storage
+        let p = self
+            .storage
             .completed_proposal(&completed_proposal_id)
-            .await
-            .map(|p| GetCompletedProposal {
-                policy_id: p.policy_id,
-                completed_proposal_id,
-                proposal: p.proposal,
-                timestamp: p.timestamp,
-            })
+            .await?;
+        let replaced_by: Option<EventId> = match p.proposal.tx() {
+            Some(tx) => self.storage.replaced_by(&p.policy_id, &tx.txid()).await,
+            None => None,
+        };
+        Ok(GetCompletedProposal {
+            policy_id: p.policy_id,
+            completed_proposal_id,
+            proposal: p.proposal,
+            timestamp: p.timestamp,
+            replaced_by,
+        })
     }
 
     #[tracing::instrument(skip_all, level = "trace")]
@@ -747,28 +780,37 @@ impl SmartVaults {
         let event = self.client.database().event_by_id(policy_id).await?;
         let author = event.author();
 
-        // Get nostr pubkeys and shared keys
-        let shared_key: Keys = self.storage.shared_key(&policy_id).await?;
+        // Get nostr pubkeys and shared keys (current and previous)
+        let shared_keys: Vec<Keys> = self.storage.shared_keys(&policy_id).await;
         let InternalPolicy { public_keys, .. } = self.storage.vault(&policy_id).await?;
 
-        if author == shared_key.public_key() {
-            let mut tags: Vec<Tag> = public_keys.into_iter().map(Tag::public_key).collect();
-            tags.push(Tag::event(policy_id));
-
-            // Get all events linked to the policy
-            let filter = Filter::new().event(policy_id).author(author);
-            let event_ids = self
-                .client
-                .database()
-                .event_ids_by_filters(vec![filter], Order::Desc)
-                .await?
-                .into_iter()
-                .map(Tag::event);
-            tags.extend(event_ids);
+        if shared_keys.iter().any(|k| k.public_key() == author) {
+            // Every shared key deletes the events it signed
+            for shared_key in shared_keys.iter() {
+                let mut tags: Vec<Tag> = public_keys.iter().copied().map(Tag::public_key).collect();
+                if shared_key.public_key() == author {
+                    tags.push(Tag::event(policy_id));
+                }
 
-            // Delete policy
-            let event = EventBuilder::new(Kind::EventDeletion, "", tags).to_event(&shared_key)?;
-            self.client.send_event(event).await?;
+                // Get all events linked to the policy
+                let filter = Filter::new()
+                    .event(policy_id)
+                    .author(shared_key.public_key());
+                let event_ids = self
+                    .client
+                    .database()
+                    .event_ids_by_filters(vec![filter], Order::Desc)
+                    .await?
+                    .into_iter()
+                    .map(Tag::event);
+                tags.extend(event_ids);
+
+                // Delete policy
+                let event =
+                    EventBuilder::new(Kind::EventDeletion, "", tags).to_event(shared_key)?;
+                self.send_vault_event(shared_key, event, &public_keys)
+                    .await?;
+            }
 
             self.storage.delete_vault(&policy_id).await;
 
@@ -793,10 +835,12 @@ impl SmartVaults {
             .next()
             .ok_or(Error::PolicyNotFound)?;
 
-        // Get shared key
-        let shared_key: Keys = self.storage.shared_key(policy_id).await?;
-
-        if proposal_event.author() == shared_key.public_key() {
+        // Get the shared key that signed the event (current or previous)
+        if let Ok(shared_key) = self
+            .storage
+            .shared_key_by_public_key(policy_id, proposal_event.author_ref())
+            .await
+        {
             // Extract `p` tags from proposal event to notify users about proposal deletion
             let mut tags: Vec<Tag> = proposal_event
                 .public_keys()
@@ -817,8 +861,10 @@ impl SmartVaults {
             // }
             // }
 
+            let public_keys: Vec<PublicKey> = proposal_event.public_keys().copied().collect();
             let event = EventBuilder::new(Kind::EventDeletion, "", tags).to_event(&shared_key)?;
-            self.client.send_event(event).await?;
+            self.send_vault_event(&shared_key, event, &public_keys)
+                .await?;
 
             self.storage.delete_proposal(&proposal_id).await;
 
@@ -847,10 +893,12 @@ impl SmartVaults {
             .nth(1)
             .ok_or(Error::PolicyNotFound)?;
 
-        // Get shared key
-        let shared_key: Keys = self.storage.shared_key(policy_id).await?;
-
-        if proposal_event.author() == shared_key.public_key() {
+        // Get the shared key that signed the event (current or previous)
+        if let Ok(shared_key) = self
+            .storage
+            .shared_key_by_public_key(policy_id, proposal_event.author_ref())
+            .await
+        {
             // Extract `p` tags from proposal event to notify users about proposal deletion
             let mut tags: Vec<Tag> = proposal_event
                 .public_keys()
@@ -860,8 +908,10 @@ impl SmartVaults {
 
             tags.push(Tag::event(completed_proposal_id));
 
+            let public_keys: Vec<PublicKey> = proposal_event.public_keys().copied().collect();
             let event = EventBuilder::new(Kind::EventDeletion, "", tags).to_event(&shared_key)?;
-            self.client.send_event(event).await?;
+            self.send_vault_event(&shared_key, event, &public_keys)
+                .await?;
 
             self.storage
                 .delete_completed_proposal(&completed_proposal_id)
@@ -878,10 +928,14 @@ impl SmartVaults {
         let items = self.storage.vaults().await;
         let mut policies: Vec<GetPolicy> = Vec::with_capacity(items.len());
 
-        for (id, internal) in items.into_iter() {
+        for (id, InternalPolicy { mut policy, .. }) in items.into_iter() {
+            let metadata: VaultMetadata =
+                self.storage.vault_metadata(&id).await.unwrap_or_default();
+            metadata.apply(&mut policy);
             policies.push(GetPolicy {
                 policy_id: id,
-                policy: internal.policy,
+                policy,
+                metadata,
                 balance: self.manager.get_balance(id).await?,
                 last_sync: self.manager.last_sync(id).await?,
             });
@@ -910,6 +964,7 @@ impl SmartVaults {
                 signed: p.proposal.finalize(approvals, self.network).is_ok(),
                 proposal: p.proposal,
                 timestamp: p.timestamp,
+                expiration: p.expiration,
             });
         }
         list.sort();
@@ -940,6 +995,7 @@ impl SmartVaults {
                 signed: p.proposal.finalize(approvals, self.network).is_ok(),
                 proposal: p.proposal,
                 timestamp: p.timestamp,
+                expiration: p.expiration,
             });
         }
         list.sort();
@@ -978,12 +1034,25 @@ impl SmartVaults {
 
     #[tracing::instrument(skip_all, level = "trace")]
     pub async fn get_completed_proposals(&self) -> Result<Vec<GetCompletedProposal>, Error> {
-        let mut list: Vec<GetCompletedProposal> = self
-            .storage
-            .completed_proposals()
-            .await
+        let completed_proposals = self.storage.completed_proposals().await;
+
+        // Fee bumps by replaced TXID
+        let replacements: HashMap<(EventId, Txid), EventId> = completed_proposals
+            .iter()
+            .filter_map(|(id, p)| {
+                p.proposal
+                    .replaced_txid()
+                    .map(|txid| ((p.policy_id, txid), *id))
+            })
+            .collect();
+
+        let mut list: Vec<GetCompletedProposal> = completed_proposals
             .into_iter()
             .map(|(id, p)| GetCompletedProposal {
+                replaced_by: p
+                    .proposal
+                    .tx()
+                    .and_then(|tx| replacements.get(&(p.policy_id, tx.txid())).copied()),
                 policy_id: p.policy_id,
                 completed_proposal_id: id,
                 proposal: p.proposal,
@@ -1025,25 +1094,29 @@ impl SmartVaults {
 
         // Compose the event
         // Publish it with `shared_key` so every owner can delete it
-        let policy_event: Event = EventBuilder::policy(&shared_key, &policy, &nostr_pubkeys)?;
+        let version: ProtocolVersion = self.config.protocol_version().await;
+        let policy_event: Event = compat::policy(version, &shared_key, &policy, &nostr_pubkeys)?;
         let policy_id = policy_event.id;
+        let timestamp = policy_event.created_at;
 
         // Publish the shared key
         for pubkey in nostr_pubkeys.iter() {
             let event: Event =
                 EventBuilder::shared_key(self.keys(), &shared_key, pubkey, policy_id)?;
-            let event_id: EventId = event.id;
-
-            // TODO: use send_batch_event method from nostr-sdk
-            self.client.send_event(event).await?;
+            let event_id: EventId = self
+                .send_vault_event(self.keys(), event, &[*pubkey])
+                .await?;
             tracing::info!("Published shared key for {pubkey} at event {event_id}");
         }
 
         // Publish the event
-        self.client.send_event(policy_event).await?;
+        self.send_vault_event(&shared_key, policy_event, &nostr_pubkeys)
+            .await?;
 
         // Index event
-        self.storage.save_shared_key(policy_id, shared_key).await;
+        self.storage
+            .save_shared_key(policy_id, shared_key, timestamp)
+            .await;
         self.storage
             .save_vault(
                 policy_id,
@@ -1110,36 +1183,54 @@ impl SmartVaults {
     }
 
     /// Make a spending proposal
+    ///
+    /// Each recipient is an `(address, amount)` pair: use more recipients to make a batch payment.
+    /// Only one recipient can use [`Amount::Max`] (send all the remaining funds).
     pub async fn spend<S>(
         &self,
         policy_id: EventId,
-        address: Address<NetworkUnchecked>,
-        amount: Amount,
+        recipients: Vec<(Address<NetworkUnchecked>, Amount)>,
         description: S,
         fee_rate: FeeRate,
         utxos: Option<Vec<OutPoint>>,
         policy_path: Option<BTreeMap<String, Vec<usize>>>,
         skip_frozen_utxos: bool,
     ) -> Result<GetProposal, Error>
+    where
+        S: Into<String>,
+    {
+        let proposal: Proposal = self
+            .build_spending_proposal(
+                policy_id,
+                recipients,
+                description,
+                fee_rate,
+                utxos,
+                policy_path,
+                skip_frozen_utxos,
+            )
+            .await?;
+        self.publish_proposal(policy_id, proposal).await
+    }
+
+    /// Build a spending [`Proposal`], checked against the spending rules, without publishing it
+    async fn build_spending_proposal<S>(
+        &self,
+        policy_id: EventId,
+        recipients: Vec<(Address<NetworkUnchecked>, Amount)>,
+        description: S,
+        fee_rate: FeeRate,
+        utxos: Option<Vec<OutPoint>>,
+        policy_path: Option<BTreeMap<String, Vec<usize>>>,
+        skip_frozen_utxos: bool,
+    ) -> Result<Proposal, Error>
     where
         S: Into<String>,
     {
         let description: &str = &description.into();
 
         // Check and calculate fee rate
-        if !fee_rate.is_valid() {
-            return Err(Error::InvalidFeeRate);
-        }
-
-        let fee_rate: BdkFeeRate = match fee_rate {
-            FeeRate::Priority(priority) => {
-                let blockchain = self.blockchain().await?;
-                let btc_per_kvb: f32 =
-                    blockchain.estimate_fee(priority.target_blocks() as usize)? as f32;
-                BdkFeeRate::from_btc_per_kvb(btc_per_kvb)
-            }
-            FeeRate::Rate(rate) => BdkFeeRate::from_sat_per_vb(rate),
-        };
+        let fee_rate: BdkFeeRate = self.calculate_fee_rate(fee_rate).await?;
 
         let mut frozen_utxos: Option<Vec<OutPoint>> = None;
         if !skip_frozen_utxos {
@@ -1160,8 +1251,7 @@ impl SmartVaults {
             .manager
             .spend(
                 policy_id,
-                address,
-                amount,
+                recipients,
                 description,
                 fee_rate,
                 utxos,
@@ -1170,63 +1260,230 @@ impl SmartVaults {
             )
             .await?;
 
-        if let Proposal::Spending { psbt, .. } = &proposal {
-            // Get shared keys
-            let shared_key: Keys = self.storage.shared_key(&policy_id).await?;
+        if let Proposal::Spending { .. } = &proposal {
+            self.check_spending_rules(policy_id, &proposal).await?;
+            Ok(proposal)
+        } else {
+            Err(Error::UnexpectedProposal)
+        }
+    }
 
-            // Compose the event
-            let InternalPolicy { public_keys, .. } = self.storage.vault(&policy_id).await?;
-            let event: Event =
-                EventBuilder::proposal(&shared_key, policy_id, &proposal, &public_keys)?;
-            let timestamp = event.created_at;
-            let proposal_id = self.client.send_event(event).await?;
-
-            // Send DM msg
-            // TODO: send withoud wait for OK
-            /* let sender = self.client.keys().public_key();
-            let mut msg = String::from("New spending proposal:\n");
-            msg.push_str(&format!(
-                "- Amount: {} sat\n",
-                util::format::big_number(*amount)
-            ));
-            msg.push_str(&format!("- Description: {description}"));
-            for pubkey in nostr_pubkeys.into_iter() {
-                if sender != pubkey {
-                    self.client.send_direct_msg(pubkey, &msg, None).await?;
-                }
-            } */
+    /// Make a spending proposal from a BIP21 payment request URI
+    ///
+    /// Address and amount are taken from the URI, `label` and `message` become the proposal description.
+    /// The URI `label` is also saved as label of the destination address.
+    pub async fn spend_bip21<S>(
+        &self,
+        policy_id: EventId,
+        uri: S,
+        fee_rate: FeeRate,
+        utxos: Option<Vec<OutPoint>>,
+        policy_path: Option<BTreeMap<String, Vec<usize>>>,
+        skip_frozen_utxos: bool,
+    ) -> Result<GetProposal, Error>
+    where
+        S: AsRef<str>,
+    {
+        let request: Bip21 = Bip21::from_str(uri.as_ref())?;
+        request.require_network(self.network)?;
+        let amount: u64 = request.amount.ok_or(Bip21Error::MissingAmount)?;
 
-            // Index proposal
-            self.storage
-                .save_proposal(
-                    proposal_id,
-                    InternalProposal {
-                        policy_id,
-                        proposal: proposal.clone(),
-                        timestamp,
-                    },
-                )
-                .await;
+        let proposal: GetProposal = self
+            .spend(
+                policy_id,
+                vec![(request.address.clone(), Amount::Custom(amount))],
+                request.description(),
+                fee_rate,
+                utxos,
+                policy_path,
+                skip_frozen_utxos,
+            )
+            .await?;
 
-            // Froze UTXOs
-            self.storage
-                .freeze_utxos(
-                    policy_id,
-                    psbt.unsigned_tx
-                        .input
-                        .iter()
-                        .map(|txin| txin.previous_output),
-                )
-                .await;
+        if let Some(label) = request.label {
+            self.save_label(policy_id, Label::address(request.address, label))
+                .await?;
+        }
+
+        Ok(proposal)
+    }
+
+    /// Check and convert [`FeeRate`] to BDK fee rate
+    async fn calculate_fee_rate(&self, fee_rate: FeeRate) -> Result<BdkFeeRate, Error> {
+        if !fee_rate.is_valid() {
+            return Err(Error::InvalidFeeRate);
+        }
 
-            // Compose output
-            Ok(GetProposal {
+        match fee_rate {
+            FeeRate::Priority(priority) => {
+                let blockchain = self.blockchain().await?;
+                let btc_per_kvb: f32 =
+                    blockchain.estimate_fee(priority.target_blocks() as usize)? as f32;
+                Ok(BdkFeeRate::from_btc_per_kvb(btc_per_kvb))
+            }
+            FeeRate::Rate(rate) => Ok(BdkFeeRate::from_sat_per_vb(rate)),
+        }
+    }
+
+    /// Publish a vault event signed by `keys`
+    ///
+    /// Every event that carries the members of a vault (shared keys, deletions, ...) must be sent
+    /// with this method. If gift wrap is enabled in the [`Config`], only a gift wrap (NIP-59) for each
+    /// member is sent to relays and only the rumor of the event is saved in the local database.
+    async fn send_vault_event(
+        &self,
+        keys: &Keys,
+        event: Event,
+        public_keys: &[PublicKey],
+    ) -> Result<EventId, Error> {
+        if self.config.gift_wrap().await {
+            let event_id: EventId = event.id;
+            for public_key in public_keys.iter() {
+                let gift_wrap: Event = gift_wrap::wrap(keys, public_key, &event)?;
+                // TODO: use send_batch_event method from nostr-sdk
+                self.client.send_event(gift_wrap).await?;
+            }
+            // Save the rumor in place of the signed event, so it can't be rebroadcasted
+            let rumor: Event = gift_wrap::rumor(keys, &event)?;
+            self.client.database().save_event(&rumor).await?;
+            Ok(event_id)
+        } else {
+            Ok(self.client.send_event(event).await?)
+        }
+    }
+
+    /// Expiration of a new proposal, according to the config
+    async fn new_proposal_expiration(&self) -> Option<Timestamp> {
+        self.config
+            .proposal_expiration()
+            .await
+            .map(|expiration| Timestamp::now().add(expiration))
+    }
+
+    /// Publish [`Proposal`], index it and froze its UTXOs
+    async fn publish_proposal(
+        &self,
+        policy_id: EventId,
+        proposal: Proposal,
+    ) -> Result<GetProposal, Error> {
+        let expiration: Option<Timestamp> = self.new_proposal_expiration().await;
+        self.publish_proposal_with_expiration(policy_id, proposal, expiration)
+            .await
+    }
+
+    /// Publish [`Proposal`] with a custom expiration, index it and froze its UTXOs
+    async fn publish_proposal_with_expiration(
+        &self,
+        policy_id: EventId,
+        proposal: Proposal,
+        expiration: Option<Timestamp>,
+    ) -> Result<GetProposal, Error> {
+        // Get shared keys
+        let shared_key: Keys = self.storage.shared_key(&policy_id).await?;
+
+        // Compose the event
+        let InternalPolicy { public_keys, .. } = self.storage.vault(&policy_id).await?;
+        let version: ProtocolVersion = self.config.protocol_version().await;
+        let event: Event = compat::proposal(
+            version,
+            &shared_key,
+            policy_id,
+            &proposal,
+            expiration,
+            &public_keys,
+        )?;
+        let timestamp = event.created_at;
+        let proposal_id = self
+            .send_vault_event(&shared_key, event, &public_keys)
+            .await?;
+
+        // Index proposal
+        self.storage
+            .save_proposal(
                 proposal_id,
+                InternalProposal {
+                    policy_id,
+                    proposal: proposal.clone(),
+                    timestamp,
+                    expiration,
+                },
+            )
+            .await;
+
+        // Froze UTXOs
+        self.storage
+            .freeze_proposal_utxos(policy_id, &proposal)
+            .await;
+
+        // Compose output
+        Ok(GetProposal {
+            proposal_id,
+            policy_id,
+            proposal,
+            signed: false,
+            timestamp,
+            expiration,
+        })
+    }
+
+    /// Bump the fee (RBF) of an unconfirmed [`Policy`] transaction
+    ///
+    /// The replacement is proposed and must be approved like any other proposal.
+    pub async fn bump_fee(
+        &self,
+        policy_id: EventId,
+        txid: Txid,
+        fee_rate: FeeRate,
+        policy_path: Option<BTreeMap<String, Vec<usize>>>,
+    ) -> Result<GetProposal, Error> {
+        let fee_rate: BdkFeeRate = self.calculate_fee_rate(fee_rate).await?;
+
+        let description: String = match self.storage.description_by_txid(policy_id, txid).await {
+            Some(description) => format!("Fee bump of {}: {description}", util::cut_txid(txid)),
+            None => format!("Fee bump of {}", util::cut_txid(txid)),
+        };
+
+        let proposal: Proposal = self
+            .manager
+            .bump_fee(policy_id, txid, description, fee_rate, policy_path)
+            .await?;
+
+        self.publish_proposal(policy_id, proposal).await
+    }
+
+    /// Speed up an unconfirmed incoming transaction with a child-pays-for-parent (CPFP) spending proposal
+    ///
+    /// The child spend the [`Policy`] outputs of `parent_txid` to a new address of the same [`Policy`],
+    /// paying enough fee to reach the target `fee_rate` for the whole package.
+    pub async fn cpfp(
+        &self,
+        policy_id: EventId,
+        parent_txid: Txid,
+        fee_rate: FeeRate,
+        policy_path: Option<BTreeMap<String, Vec<usize>>>,
+    ) -> Result<GetProposal, Error> {
+        let fee_rate: BdkFeeRate = self.calculate_fee_rate(fee_rate).await?;
+
+        let address = self
+            .get_address(policy_id, AddressIndex::New)
+            .await?
+            .address;
+        let description: String = format!("CPFP of {}", util::cut_txid(parent_txid));
+
+        let proposal: Proposal = self
+            .manager
+            .cpfp(
                 policy_id,
-                proposal,
-                signed: false,
-                timestamp,
-            })
+                parent_txid,
+                Address::new(self.network, address.payload),
+                description,
+                fee_rate,
+                policy_path,
+            )
+            .await?;
+
+        if let Proposal::Spending { .. } = &proposal {
+            self.publish_proposal(policy_id, proposal).await
         } else {
             Err(Error::UnexpectedProposal)
         }
@@ -1254,8 +1511,7 @@ impl SmartVaults {
         );
         self.spend(
             from_policy_id,
-            Address::new(self.network, address.payload),
-            amount,
+            vec![(Address::new(self.network, address.payload), amount)],
             description,
             fee_rate,
             utxos,
@@ -1265,6 +1521,62 @@ impl SmartVaults {
         .await
     }
 
+    /// Get the UTXOs that are within `threshold` blocks of reaching a relative timelock
+    ///
+    /// UTXOs already frozen by other proposals are skipped.
+    pub async fn get_utxos_to_refresh(
+        &self,
+        policy_id: EventId,
+        threshold: u32,
+    ) -> Result<Vec<OutPoint>, Error> {
+        let InternalPolicy { policy, .. } = self.storage.vault(&policy_id).await?;
+        let frozen_utxos: HashSet<OutPoint> = self.storage.get_frozen_utxos(&policy_id).await;
+        let utxos = self.manager.get_utxos(policy_id).await?;
+        let current_height: u32 = self.manager.block_height();
+        Ok(policy
+            .utxos_to_refresh(utxos.iter(), current_height, threshold)?
+            .into_iter()
+            .filter(|outpoint| !frozen_utxos.contains(outpoint))
+            .collect())
+    }
+
+    /// Refresh the UTXOs that are close to reach a relative timelock, sending them to a new address of the same [`Policy`]
+    ///
+    /// Use [`UTXO_REFRESH_THRESHOLD_BLOCKS`](crate::constants::UTXO_REFRESH_THRESHOLD_BLOCKS) as default `threshold`.
+    pub async fn refresh_utxos(
+        &self,
+        policy_id: EventId,
+        threshold: u32,
+        fee_rate: FeeRate,
+        policy_path: Option<BTreeMap<String, Vec<usize>>>,
+    ) -> Result<GetProposal, Error> {
+        let utxos: Vec<OutPoint> = self.get_utxos_to_refresh(policy_id, threshold).await?;
+
+        if utxos.is_empty() {
+            return Err(Error::NoUtxosToRefresh);
+        }
+
+        let address = self
+            .get_address(policy_id, AddressIndex::New)
+            .await?
+            .address;
+        let description: String = format!(
+            "Refresh of {} UTXOs of policy #{} (relative timelock within {threshold} blocks)",
+            utxos.len(),
+            util::cut_event_id(policy_id),
+        );
+        self.spend(
+            policy_id,
+            vec![(Address::new(self.network, address.payload), Amount::Max)],
+            description,
+            fee_rate,
+            Some(utxos),
+            policy_path,
+            false,
+        )
+        .await
+    }
+
     /* async fn is_internal_key<S>(&self, descriptor: S) -> Result<bool, Error>
     where
         S: Into<String>,
@@ -1277,10 +1589,20 @@ impl SmartVaults {
         )
     } */
 
+    /// Sign and approve a proposal
+    ///
+    /// The proposal is signed by every own signer involved in the vault with a registered [`ProposalSigner`]
+    /// for its [`SignerType`] and by the seed accounts involved in the vault: the signed PSBTs are combined.
+    /// The seed is read with `password` only if it has keys in the vault. A failing signer doesn't stop the
+    /// others: [`Error::ApprovalFailed`] is returned only if no signer signed the proposal.
+    ///
+    /// Proposals that break the vault [`SpendingRules`](smartvaults_core::SpendingRules), or that are older than the
+    /// last change of the rules, are refused, unless `override_spending_rules` is `true`.
     pub async fn approve<T>(
         &self,
         password: T,
         proposal_id: EventId,
+        override_spending_rules: bool,
     ) -> Result<(EventId, ApprovedProposal), Error>
     where
         T: AsRef<[u8]>,
@@ -1292,39 +1614,88 @@ impl SmartVaults {
             ..
         } = self.get_proposal_by_id(proposal_id).await?;
 
-        let keys: &Keys = self.keys();
+        if !override_spending_rules {
+            self.check_proposal_spending_rules(proposal_id).await?;
+        }
 
-        /* // Sign PSBT
-        // Custom signer
-        let signer = SignerWrapper::new(
-            PrivateKey::new(**keys.secret_key()?, self.network),
-            SignerContext::Tap {
-                is_internal_key: self
-                    .is_internal_key(policy.as_descriptor().to_string())
-                    .await?,
-            },
-        ); */
-        let seed: Seed = self.keechain.read().seed(password)?;
-        let approved_proposal = proposal.approve(&seed, Vec::new(), self.network)?;
+        // Sign with the registered proposal signers
+        let mut psbts: Vec<PartiallySignedTransaction> = Vec::new();
+        let mut errors: Vec<String> = Vec::new();
+        for (signer, proposal_signer) in self.vault_proposal_signers(policy_id).await?.iter() {
+            match proposal.approve_with_signer(signer, proposal_signer.as_ref()) {
+                Ok(approved_proposal) => psbts.push(approved_proposal.psbt()),
+                Err(e) => {
+                    tracing::warn!("Impossible to sign proposal {proposal_id} with {signer}: {e}");
+                    errors.push(format!("{}: {e}", signer.name()));
+                }
+            }
+        }
+
+        // Sign with the seed, only if it has keys in the vault
+        let InternalPolicy { policy, .. } = self.storage.vault(&policy_id).await?;
+        if policy.is_fingerprint_involved(&self.default_signer.fingerprint())? {
+            let seed: Seed = self.keechain.read().seed(password)?;
+            let signers: Vec<Signer> = self.vault_seed_signers(policy_id, &seed).await?;
+            let res = if signers.is_empty() {
+                // Legacy vaults: keys of the seed not saved as signers
+                proposal.approve(&seed, Vec::new(), self.network)
+            } else {
+                proposal.approve_with_seed_signers(&seed, &signers, self.network)
+            };
+            match res {
+                Ok(approved_proposal) => psbts.push(approved_proposal.psbt()),
+                Err(e) => {
+                    tracing::warn!("Impossible to sign proposal {proposal_id} with seed: {e}");
+                    errors.push(format!("seed: {e}"));
+                }
+            }
+        }
+
+        if psbts.is_empty() {
+            return if errors.is_empty() {
+                Err(Error::SignerNotFound)
+            } else {
+                Err(Error::ApprovalFailed(errors))
+            };
+        }
+
+        // Combine the signed PSBTs
+        let approved_proposal: ApprovedProposal = proposal.approve_with_signed_psbts(psbts)?;
+
+        let event_id: EventId = self
+            .publish_approval(policy_id, proposal_id, approved_proposal.clone())
+            .await?;
+        Ok((event_id, approved_proposal))
+    }
+
+    /// Publish own [`ApprovedProposal`] and index it
+    async fn publish_approval(
+        &self,
+        policy_id: EventId,
+        proposal_id: EventId,
+        approved_proposal: ApprovedProposal,
+    ) -> Result<EventId, Error> {
+        let keys: &Keys = self.keys();
 
         // Get shared keys
         let shared_key: Keys = self.storage.shared_key(&policy_id).await?;
 
         // Compose the event
-        let content = approved_proposal.encrypt_with_keys(&shared_key)?;
         let InternalPolicy { public_keys, .. } = self.storage.vault(&policy_id).await?;
-        let mut tags: Vec<Tag> = public_keys.into_iter().map(Tag::public_key).collect();
-        tags.push(Tag::event(proposal_id));
-        tags.push(Tag::event(policy_id));
-        tags.push(Tag::Expiration(
-            Timestamp::now().add(APPROVED_PROPOSAL_EXPIRATION),
-        ));
-
-        let event = EventBuilder::new(APPROVED_PROPOSAL_KIND, content, tags).to_event(keys)?;
+        let version: ProtocolVersion = self.config.protocol_version().await;
+        let event: Event = compat::approval(
+            version,
+            keys,
+            &shared_key,
+            proposal_id,
+            policy_id,
+            &approved_proposal,
+            &public_keys,
+        )?;
         let timestamp = event.created_at;
 
         // Publish the event
-        let event_id = self.client.send_event(event).await?;
+        let event_id = self.send_vault_event(keys, event, &public_keys).await?;
 
         // Index approved proposal
         self.storage
@@ -1334,22 +1705,25 @@ impl SmartVaults {
                     proposal_id,
                     policy_id,
                     public_key: keys.public_key(),
-                    approval: approved_proposal.clone(),
+                    approval: approved_proposal,
                     timestamp,
                 },
             )
             .await;
 
-        Ok((event_id, approved_proposal))
+        Ok(event_id)
     }
 
+    /// Approve a proposal with a PSBT signed externally
+    ///
+    /// Proposals that break the vault [`SpendingRules`](smartvaults_core::SpendingRules), or that are older than the
+    /// last change of the rules, are refused, unless `override_spending_rules` is `true`.
     pub async fn approve_with_signed_psbt(
         &self,
         proposal_id: EventId,
         signed_psbt: PartiallySignedTransaction,
+        override_spending_rules: bool,
     ) -> Result<(EventId, ApprovedProposal), Error> {
-        let keys: &Keys = self.keys();
-
         // Get proposal and policy
         let GetProposal {
             policy_id,
@@ -1357,41 +1731,15 @@ impl SmartVaults {
             ..
         } = self.get_proposal_by_id(proposal_id).await?;
 
-        let approved_proposal = proposal.approve_with_signed_psbt(signed_psbt)?;
-
-        // Get shared keys
-        let shared_key: Keys = self.storage.shared_key(&policy_id).await?;
-
-        // Compose the event
-        let content = approved_proposal.encrypt_with_keys(&shared_key)?;
-        let InternalPolicy { public_keys, .. } = self.storage.vault(&policy_id).await?;
-        let mut tags: Vec<Tag> = public_keys.into_iter().map(Tag::public_key).collect();
-        tags.push(Tag::event(proposal_id));
-        tags.push(Tag::event(policy_id));
-        tags.push(Tag::Expiration(
-            Timestamp::now().add(APPROVED_PROPOSAL_EXPIRATION),
-        ));
-
-        let event = EventBuilder::new(APPROVED_PROPOSAL_KIND, content, tags).to_event(keys)?;
-        let timestamp = event.created_at;
-
-        // Publish the event
-        let event_id = self.client.send_event(event).await?;
+        if !override_spending_rules {
+            self.check_proposal_spending_rules(proposal_id).await?;
+        }
 
-        // Index approved proposal
-        self.storage
-            .save_approval(
-                event_id,
-                InternalApproval {
-                    proposal_id,
-                    policy_id,
-                    public_key: keys.public_key(),
-                    approval: approved_proposal.clone(),
-                    timestamp,
-                },
-            )
-            .await;
+        let approved_proposal = proposal.approve_with_signed_psbt(signed_psbt)?;
 
+        let event_id: EventId = self
+            .publish_approval(policy_id, proposal_id, approved_proposal.clone())
+            .await?;
         Ok((event_id, approved_proposal))
     }
 
@@ -1455,11 +1803,11 @@ impl SmartVaults {
             // Get nostr pubkeys linked to policyit?;
             let InternalPolicy { public_keys, .. } = self.storage.vault(&policy_id).await?;
 
-            let mut tags: Vec<Tag> = public_keys.into_iter().map(Tag::public_key).collect();
+            let mut tags: Vec<Tag> = public_keys.iter().copied().map(Tag::public_key).collect();
             tags.push(Tag::event(approval_id));
 
-            let event = EventBuilder::new(Kind::EventDeletion, "", tags);
-            self.client.send_event_builder(event).await?;
+            let event = EventBuilder::new(Kind::EventDeletion, "", tags).to_event(keys)?;
+            self.send_vault_event(keys, event, &public_keys).await?;
 
             self.storage.delete_approval(&approval_id).await;
 
@@ -1475,11 +1823,21 @@ impl SmartVaults {
         let GetApprovedProposals {
             policy_id,
             proposal,
-            approved_proposals,
+            mut approved_proposals,
         } = self.storage.approvals_by_proposal_id(&proposal_id).await?;
 
-        let shared_key: Keys = self.storage.shared_key(&policy_id).await?;
-        let InternalPolicy { public_keys, .. } = self.storage.vault(&policy_id).await?;
+        // Aggregated proof of reserve: collect the approvals of every participating vault
+        let aggregated_proposals: Vec<(EventId, InternalProposal)> = self
+            .storage
+            .aggregated_proof_proposals(&proposal_id)
+            .await?;
+        for (id, ..) in aggregated_proposals.iter() {
+            let GetApprovedProposals {
+                approved_proposals: approvals,
+                ..
+            } = self.storage.approvals_by_proposal_id(id).await?;
+            approved_proposals.extend(approvals);
+        }
 
         // Finalize proposal
         let completed_proposal: CompletedProposal =
@@ -1514,17 +1872,59 @@ impl SmartVaults {
             }
         }
 
+        self.publish_completed_proposal(proposal_id, policy_id, completed_proposal.clone())
+            .await?;
+
+        // Every participating vault gets its own completed proof of reserve
+        if let CompletedProposal::ProofOfReserve {
+            message,
+            psbt,
+            height,
+            ..
+        } = &completed_proposal
+        {
+            for (id, internal) in aggregated_proposals.into_iter() {
+                let completed = CompletedProposal::proof_of_reserve(
+                    message,
+                    internal.proposal.descriptor(),
+                    internal.proposal.other_descriptors(),
+                    psbt.clone(),
+                    *height,
+                );
+                self.publish_completed_proposal(id, internal.policy_id, completed)
+                    .await?;
+            }
+        }
+
+        Ok(completed_proposal)
+    }
+
+    /// Publish [`CompletedProposal`], delete the related proposal and index it
+    async fn publish_completed_proposal(
+        &self,
+        proposal_id: EventId,
+        policy_id: EventId,
+        completed_proposal: CompletedProposal,
+    ) -> Result<EventId, Error> {
+        let shared_key: Keys = self.storage.shared_key(&policy_id).await?;
+        let InternalPolicy { public_keys, .. } = self.storage.vault(&policy_id).await?;
+
         // Compose the event
-        let content: String = completed_proposal.encrypt_with_keys(&shared_key)?;
-        let mut tags: Vec<Tag> = public_keys.iter().copied().map(Tag::public_key).collect();
-        tags.push(Tag::event(proposal_id));
-        tags.push(Tag::event(policy_id));
-        let event =
-            EventBuilder::new(COMPLETED_PROPOSAL_KIND, content, tags).to_event(&shared_key)?;
+        let version: ProtocolVersion = self.config.protocol_version().await;
+        let event: Event = compat::completed_proposal(
+            version,
+            &shared_key,
+            proposal_id,
+            policy_id,
+            &completed_proposal,
+            &public_keys,
+        )?;
         let timestamp = event.created_at;
 
         // Publish the event
-        let event_id = self.client.send_event(event).await?;
+        let event_id = self
+            .send_vault_event(&shared_key, event, &public_keys)
+            .await?;
 
         // Delete the proposal
         if let Err(e) = self.delete_proposal_by_id(proposal_id).await {
@@ -1537,13 +1937,13 @@ impl SmartVaults {
                 event_id,
                 InternalCompletedProposal {
                     policy_id,
-                    proposal: completed_proposal.clone(),
+                    proposal: completed_proposal,
                     timestamp,
                 },
             )
             .await;
 
-        Ok(completed_proposal)
+        Ok(event_id)
     }
 
     pub async fn new_proof_proposal<S>(
@@ -1559,18 +1959,12 @@ impl SmartVaults {
         // Build proposal
         let proposal: Proposal = self.manager.proof_of_reserve(policy_id, message).await?;
 
-        // Get shared keys
-        let shared_key: Keys = self.storage.shared_key(&policy_id).await?;
-
-        // Compose the event
-        let InternalPolicy { public_keys, .. } = self.storage.vault(&policy_id).await?;
-        let mut tags: Vec<Tag> = public_keys.iter().copied().map(Tag::public_key).collect();
-        tags.push(Tag::event(policy_id));
-        let content = proposal.encrypt_with_keys(&shared_key)?;
-        // Publish proposal with `shared_key` so every owner can delete it
-        let event = EventBuilder::new(PROPOSAL_KIND, content, tags).to_event(&shared_key)?;
-        let timestamp = event.created_at;
-        let proposal_id = self.client.send_event(event).await?;
+        // Publish proposal
+        let GetProposal {
+            proposal_id,
+            proposal,
+            ..
+        } = self.publish_proposal(policy_id, proposal).await?;
 
         // Send DM msg
         // TODO: send withoud wait for OK
@@ -1583,21 +1977,62 @@ impl SmartVaults {
         // }
         // }
 
-        // Index proposal
-        self.storage
-            .save_proposal(
-                proposal_id,
-                InternalProposal {
-                    policy_id,
-                    proposal: proposal.clone(),
-                    timestamp,
-                },
-            )
-            .await;
-
         Ok((proposal_id, proposal, policy_id))
     }
 
+    /// Create a proof of reserve spanning several vaults
+    ///
+    /// The proof has a single challenge input and the UTXOs of every vault.
+    /// A proposal, sharing the same PSBT, is published for each vault, so its members can sign their inputs.
+    pub async fn new_aggregated_proof_proposal<S>(
+        &self,
+        policy_ids: Vec<EventId>,
+        message: S,
+    ) -> Result<Vec<GetProposal>, Error>
+    where
+        S: Into<String>,
+    {
+        let message: &str = &message.into();
+
+        // Build the proof of every vault
+        let mut descriptors: Vec<Descriptor<String>> = Vec::with_capacity(policy_ids.len());
+        let mut proofs: Vec<PartiallySignedTransaction> = Vec::with_capacity(policy_ids.len());
+        let mut height: u32 = 0;
+        for policy_id in policy_ids.iter() {
+            let proposal: Proposal = self.manager.proof_of_reserve(*policy_id, message).await?;
+            if let Proposal::ProofOfReserve {
+                height: Some(h), ..
+            } = &proposal
+            {
+                height = cmp::max(height, *h);
+            }
+            descriptors.push(proposal.descriptor());
+            proofs.push(proposal.psbt());
+        }
+
+        // Merge proofs
+        let psbt: PartiallySignedTransaction = reserves::merge_proofs(proofs)?;
+
+        let mut proposals: Vec<GetProposal> = Vec::with_capacity(policy_ids.len());
+        for (policy_id, descriptor) in policy_ids.into_iter().zip(descriptors.iter()) {
+            let other_descriptors: Vec<Descriptor<String>> = descriptors
+                .iter()
+                .filter(|d| *d != descriptor)
+                .cloned()
+                .collect();
+            let proposal = Proposal::aggregated_proof_of_reserve(
+                descriptor.clone(),
+                other_descriptors,
+                message,
+                psbt.clone(),
+                height,
+            );
+            proposals.push(self.publish_proposal(policy_id, proposal).await?);
+        }
+
+        Ok(proposals)
+    }
+
     pub async fn verify_proof_by_id(&self, completed_proposal_id: EventId) -> Result<u64, Error> {
         let GetCompletedProposal {
             proposal,
@@ -1606,8 +2041,73 @@ impl SmartVaults {
         } = self
             .get_completed_proposal_by_id(completed_proposal_id)
             .await?;
-        if let CompletedProposal::ProofOfReserve { message, psbt, .. } = proposal {
-            Ok(self.manager.verify_proof(policy_id, &psbt, message).await?)
+        if let CompletedProposal::ProofOfReserve {
+            message,
+            psbt,
+            height,
+            other_descriptors,
+            ..
+        } = proposal
+        {
+            if !other_descriptors.is_empty() {
+                let amounts = self
+                    .verify_aggregated_proof_by_id(completed_proposal_id)
+                    .await?;
+                return Ok(amounts.total);
+            }
+
+            match height {
+                Some(height) => Ok(self
+                    .manager
+                    .verify_proof_at_height(policy_id, &psbt, message, height)
+                    .await?),
+                None => Ok(self.manager.verify_proof(policy_id, &psbt, message).await?),
+            }
+        } else {
+            Err(Error::UnexpectedProposal)
+        }
+    }
+
+    /// Verify an aggregated proof of reserve
+    ///
+    /// Returns the spendable amount of every vault and the total.
+    pub async fn verify_aggregated_proof_by_id(
+        &self,
+        completed_proposal_id: EventId,
+    ) -> Result<AggregatedProofAmounts<EventId>, Error> {
+        let GetCompletedProposal {
+            proposal,
+            policy_id,
+            ..
+        } = self
+            .get_completed_proposal_by_id(completed_proposal_id)
+            .await?;
+        if let CompletedProposal::ProofOfReserve {
+            message,
+            psbt,
+            height,
+            other_descriptors,
+            ..
+        } = proposal
+        {
+            let mut vaults: BTreeMap<EventId, Vec<(OutPoint, TxOut)>> = BTreeMap::new();
+            vaults.insert(
+                policy_id,
+                self.manager.proof_outputs(policy_id, height).await?,
+            );
+            for descriptor in other_descriptors.iter() {
+                let vault_id: EventId = self.storage.vault_id_by_descriptor(descriptor).await?;
+                vaults.insert(
+                    vault_id,
+                    self.manager.proof_outputs(vault_id, height).await?,
+                );
+            }
+            Ok(reserves::verify_aggregated_proof(
+                &psbt,
+                message,
+                vaults,
+                self.network,
+            )?)
         } else {
             Err(Error::UnexpectedProposal)
         }
@@ -1781,6 +2281,17 @@ impl SmartVaults {
             .collect())
     }
 
+    /// Get when each UTXO become spendable under each selectable condition of the policy
+    pub async fn get_utxo_timelock_schedule(
+        &self,
+        policy_id: EventId,
+    ) -> Result<Vec<UtxoTimelock>, Error> {
+        let InternalPolicy { policy, .. } = self.storage.vault(&policy_id).await?;
+        let utxos = self.manager.get_utxos(policy_id).await?;
+        let current_height: u32 = self.manager.block_height();
+        Ok(policy.timelock_schedule(utxos.iter(), current_height)?)
+    }
+
     #[tracing::instrument(skip_all, level = "trace")]
     pub async fn get_total_balance(&self) -> Result<Balance, Error> {
         let vaults: HashMap<EventId, InternalPolicy> = self.storage.vaults().await;
@@ -1811,80 +2322,177 @@ impl SmartVaults {
         Ok(txs)
     }
 
-    pub async fn rebroadcast_all_events(&self) -> Result<(), Error> {
-        let pool = self.client.pool();
-        let events: Vec<Event> = self
-            .client
-            .database()
-            .query(vec![Filter::new()], Order::Asc)
-            .await?;
-        for event in events.into_iter() {
-            pool.send_msg(
-                ClientMessage::event(event),
-                RelaySendOptions::new().skip_send_confirmation(true),
-            )
-            .await?;
+    pub async fn republish_shared_key_for_policy(&self, policy_id: EventId) -> Result<(), Error> {
+        let keys: &Keys = self.keys();
+        let shared_key: Keys = self.storage.shared_key(&policy_id).await?;
+        let InternalPolicy { public_keys, .. } = self.storage.vault(&policy_id).await?;
+        // Publish the shared key
+        for public_key in public_keys.into_iter() {
+            let event: Event = EventBuilder::shared_key(keys, &shared_key, &public_key, policy_id)?;
+            let event_id: EventId = self.send_vault_event(keys, event, &[public_key]).await?;
+            tracing::info!("Published shared key for {public_key} at event {event_id}");
         }
-        // TODO: save last rebroadcast timestamp
         Ok(())
     }
 
-    pub async fn rebroadcast_to<S>(&self, url: S) -> Result<(), Error>
-    where
-        S: Into<String>,
-    {
-        let url: String = url.into();
-        let pool = self.client.pool();
-        let events: Vec<Event> = self
-            .client
-            .database()
-            .query(vec![Filter::new()], Order::Asc)
+    /// Rotate the shared key of the vault
+    ///
+    /// A new shared key is sent only to `public_keys` (the current members, own public key is
+    /// always included) and the policy is re-published with it. Pending proposals and labels are
+    /// migrated to the new shared key.
+    ///
+    /// The migrated proposals keep their expiration and own approvals, comments and rejections are
+    /// re-published for them, but the ones of the other members can't be re-signed: lost approvals
+    /// and rejections are returned in the [`SharedKeyRotation`], so the members can be asked to
+    /// approve or reject again.
+    ///
+    /// Previous shared keys are kept, so the history of the vault still decrypts. Events signed or
+    /// encrypted with a superseded shared key after the rotation are rejected.
+    pub async fn rotate_shared_key(
+        &self,
+        policy_id: EventId,
+        mut public_keys: Vec<PublicKey>,
+    ) -> Result<SharedKeyRotation, Error> {
+        let keys: &Keys = self.keys();
+
+        // Check members before generating and publishing anything
+        if public_keys.is_empty() {
+            return Err(Error::NotEnoughPublicKeys);
+        }
+
+        if !public_keys.contains(&keys.public_key()) {
+            public_keys.push(keys.public_key());
+        }
+
+        public_keys.sort();
+        public_keys.dedup();
+
+        let InternalPolicy { policy, .. } = self.storage.vault(&policy_id).await?;
+
+        // Generate a new shared key
+        let shared_key = Keys::generate();
+
+        // Publish the new shared key to the current members
+        for public_key in public_keys.iter() {
+            let event: Event = EventBuilder::shared_key(keys, &shared_key, public_key, policy_id)?;
+            let event_id: EventId = self.send_vault_event(keys, event, &[*public_key]).await?;
+            tracing::info!("Published rotated shared key for {public_key} at event {event_id}");
+        }
+
+        // Re-publish the policy with the new shared key
+        let version: ProtocolVersion = self.config.protocol_version().await;
+        let event: Event =
+            compat::rotated_policy(version, &shared_key, policy_id, &policy, &public_keys)?;
+        let timestamp = event.created_at;
+        let event_id: EventId = self
+            .send_vault_event(&shared_key, event, &public_keys)
             .await?;
-        for event in events.into_iter() {
-            pool.send_msg_to(
-                [&*url],
-                ClientMessage::event(event),
-                RelaySendOptions::new().skip_send_confirmation(true),
+
+        // Index
+        self.storage
+            .save_shared_key(policy_id, shared_key, timestamp)
+            .await;
+        self.storage
+            .save_vault(
+                policy_id,
+                InternalPolicy {
+                    policy,
+                    public_keys,
+                },
             )
-            .await?;
+            .await;
+
+        // Migrate pending proposals
+        let approvals: HashMap<EventId, InternalApproval> = self.storage.approvals().await;
+        let mut migrated_proposals: Vec<MigratedProposal> = Vec::new();
+        for (proposal_id, internal) in self.storage.proposals().await.into_iter() {
+            if internal.policy_id == policy_id {
+                let comments = self.storage.comments_by_proposal_id(&proposal_id).await;
+                let rejections = self.storage.rejections_by_proposal_id(&proposal_id).await;
+
+                self.delete_proposal_by_id(proposal_id).await?;
+                let GetProposal {
+                    proposal_id: new_proposal_id,
+                    ..
+                } = self
+                    .publish_proposal_with_expiration(
+                        policy_id,
+                        internal.proposal,
+                        internal.expiration,
+                    )
+                    .await?;
+                tracing::info!("Proposal {proposal_id} migrated to {new_proposal_id}");
+
+                // Re-link own approvals, the others are lost
+                let mut lost_approvals: BTreeSet<PublicKey> = BTreeSet::new();
+                for approval in approvals.values().filter(|a| a.proposal_id == proposal_id) {
+                    if approval.public_key == keys.public_key() {
+                        self.publish_approval(
+                            policy_id,
+                            new_proposal_id,
+                            approval.approval.clone(),
+                        )
+                        .await?;
+                    } else {
+                        lost_approvals.insert(approval.public_key);
+                    }
+                }
+
+                // Re-link own comments and rejections, the others are lost
+                let mut comments: Vec<InternalProposalComment> = comments.into_values().collect();
+                comments.sort_by_key(|c| c.timestamp);
+                for comment in comments.into_iter() {
+                    if comment.public_key == keys.public_key() {
+                        self.publish_comment(policy_id, new_proposal_id, comment.comment)
+                            .await?;
+                    }
+                }
+
+                let mut lost_rejections: BTreeSet<PublicKey> = BTreeSet::new();
+                for rejection in rejections.into_values() {
+                    if rejection.public_key == keys.public_key() {
+                        self.publish_rejection(policy_id, new_proposal_id, rejection.rejection)
+                            .await?;
+                    } else {
+                        lost_rejections.insert(rejection.public_key);
+                    }
+                }
+
+                migrated_proposals.push(MigratedProposal {
+                    proposal_id,
+                    new_proposal_id,
+                    lost_approvals,
+                    lost_rejections,
+                });
+            }
         }
-        // TODO: save last rebroadcast timestamp
-        Ok(())
-    }
 
-    pub async fn republish_shared_key_for_policy(&self, policy_id: EventId) -> Result<(), Error> {
-        let keys: &Keys = self.keys();
-        let shared_key: Keys = self.storage.shared_key(&policy_id).await?;
-        let InternalPolicy { public_keys, .. } = self.storage.vault(&policy_id).await?;
-        // Publish the shared key
-        for public_key in public_keys.into_iter() {
-            let encrypted_shared_key = nips::nip04::encrypt(
-                keys.secret_key()?,
-                &public_key,
-                shared_key.secret_key()?.display_secret().to_string(),
-            )?;
-            let event: Event = EventBuilder::new(
-                SHARED_KEY_KIND,
-                encrypted_shared_key,
-                [Tag::event(policy_id), Tag::public_key(public_key)],
-            )
-            .to_event(keys)?;
-            let event_id: EventId = event.id;
+        // Migrate labels
+        for label in self.storage.get_labels(policy_id).await.into_iter() {
+            self.save_label(policy_id, label).await?;
+        }
 
-            // TODO: use send_batch_event method from nostr-sdk
-            self.client.send_event(event).await?;
-            tracing::info!("Published shared key for {public_key} at event {event_id}");
+        // Migrate metadata
+        if let Some(metadata) = self.storage.vault_metadata(&policy_id).await {
+            self.save_vault_metadata(policy_id, metadata).await?;
         }
-        Ok(())
+
+        Ok(SharedKeyRotation {
+            event_id,
+            migrated_proposals,
+        })
     }
 
     #[tracing::instrument(skip_all, level = "trace")]
     pub async fn export_policy_backup(&self, policy_id: EventId) -> Result<PolicyBackup, Error> {
         let InternalPolicy {
-            policy,
+            mut policy,
             public_keys,
             ..
         } = self.storage.vault(&policy_id).await?;
+        if let Some(metadata) = self.storage.vault_metadata(&policy_id).await {
+            metadata.apply(&mut policy);
+        }
         Ok(PolicyBackup::new(
             policy.name(),
             policy.description(),
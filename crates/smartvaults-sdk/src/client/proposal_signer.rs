@@ -0,0 +1,56 @@
+// Copyright (c) 2022-2024 Smart Vaults
+// Distributed under the MIT software license
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use nostr_sdk::EventId;
+use smartvaults_core::{ProposalSigner, Signer, SignerType};
+
+use super::{Error, SmartVaults};
+use crate::storage::InternalPolicy;
+
+impl SmartVaults {
+    /// Register a [`ProposalSigner`] for a [`SignerType`] (replace the previous one, if any)
+    ///
+    /// Used by [`SmartVaults::approve`] to sign proposals.
+    pub fn register_proposal_signer(
+        &self,
+        signer_type: SignerType,
+        proposal_signer: Arc<dyn ProposalSigner>,
+    ) {
+        let mut proposal_signers = self.proposal_signers.write();
+        proposal_signers.insert(signer_type, proposal_signer);
+    }
+
+    /// Unregister the [`ProposalSigner`] of a [`SignerType`]
+    pub fn unregister_proposal_signer(&self, signer_type: SignerType) {
+        let mut proposal_signers = self.proposal_signers.write();
+        proposal_signers.remove(&signer_type);
+    }
+
+    /// Get the own [`Signer`]s involved in the vault with a registered [`ProposalSigner`]
+    pub(crate) async fn vault_proposal_signers(
+        &self,
+        policy_id: EventId,
+    ) -> Result<Vec<(Signer, Arc<dyn ProposalSigner>)>, Error> {
+        let proposal_signers: HashMap<SignerType, Arc<dyn ProposalSigner>> =
+            self.proposal_signers.read().clone();
+
+        if proposal_signers.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let InternalPolicy { policy, .. } = self.storage.vault(&policy_id).await?;
+        let mut signers: Vec<Signer> = self.storage.signers().await.into_values().collect();
+        signers.sort();
+
+        Ok(policy
+            .search_used_signers(signers.into_iter())
+            .filter_map(|signer| {
+                let proposal_signer = proposal_signers.get(&signer.signer_type())?.clone();
+                Some((signer, proposal_signer))
+            })
+            .collect())
+    }
+}
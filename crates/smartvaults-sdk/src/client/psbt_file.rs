@@ -0,0 +1,148 @@
+// Copyright (c) 2022-2024 Smart Vaults
+// Distributed under the MIT software license
+
+//! PSBT files, for air-gapped signers using SD cards (i.e. Coldcard)
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use nostr_sdk::EventId;
+use smartvaults_core::bitcoin::psbt::PartiallySignedTransaction;
+
+use super::{Error, SmartVaults};
+use crate::types::{GetPolicy, GetProposal, PsbtFileFormat};
+use crate::util;
+
+/// Binary PSBT magic bytes
+const PSBT_MAGIC: &[u8] = b"psbt\xff";
+
+impl SmartVaults {
+    /// Export the PSBT of a proposal to `<dir>/<proposal-id>.psbt`
+    pub async fn export_proposal_psbt<P>(
+        &self,
+        proposal_id: EventId,
+        dir: P,
+        format: PsbtFileFormat,
+    ) -> Result<PathBuf, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let GetProposal { proposal, .. } = self.get_proposal_by_id(proposal_id).await?;
+        let psbt: PartiallySignedTransaction = proposal.psbt();
+        let path: PathBuf = dir
+            .as_ref()
+            .join(format!("{}.psbt", util::cut_event_id(proposal_id)));
+        match format {
+            PsbtFileFormat::Binary => fs::write(&path, psbt.serialize())?,
+            PsbtFileFormat::Base64 => fs::write(&path, psbt.to_string())?,
+        }
+        Ok(path)
+    }
+
+    /// Export the Coldcard registration file of a vault to `<dir>/<policy-id>-coldcard.txt`
+    ///
+    /// The vault must be registered on the Coldcard before signing its PSBTs.
+    pub async fn export_vault_coldcard_registration<P>(
+        &self,
+        policy_id: EventId,
+        dir: P,
+    ) -> Result<PathBuf, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let GetPolicy { policy, .. } = self.get_policy_by_id(policy_id).await?;
+        let path: PathBuf = dir
+            .as_ref()
+            .join(format!("{}-coldcard.txt", util::cut_event_id(policy_id)));
+        fs::write(&path, policy.coldcard_registration()?)?;
+        Ok(path)
+    }
+
+    /// Approve the pending proposals with the signed `.psbt` files (binary or base64) found in `dir`
+    ///
+    /// Files are matched to the proposals by unsigned transaction id. Files that can't be read,
+    /// that don't match any proposal or that are already approved are skipped.
+    ///
+    /// Return the list of `(proposal_id, approval_id)`.
+    pub async fn approve_signed_psbts_from_dir<P>(
+        &self,
+        dir: P,
+        override_spending_rules: bool,
+    ) -> Result<Vec<(EventId, EventId)>, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let proposals: Vec<GetProposal> = self.get_proposals().await?;
+        let my_public_key = self.keys().public_key();
+
+        let mut approved: Vec<(EventId, EventId)> = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let path: PathBuf = entry?.path();
+
+            if !path.is_file() || path.extension().map_or(true, |ext| ext != "psbt") {
+                continue;
+            }
+
+            let signed_psbt: PartiallySignedTransaction = match read_psbt_file(&path) {
+                Ok(psbt) => psbt,
+                Err(e) => {
+                    tracing::warn!("Impossible to read PSBT file {}: {e}", path.display());
+                    continue;
+                }
+            };
+
+            let txid = signed_psbt.unsigned_tx.txid();
+            let proposal: &GetProposal = match proposals
+                .iter()
+                .find(|p| p.proposal.psbt().unsigned_tx.txid() == txid)
+            {
+                Some(p) => p,
+                None => {
+                    tracing::debug!("No proposal found for PSBT file {}", path.display());
+                    continue;
+                }
+            };
+            let proposal_id: EventId = proposal.proposal_id;
+
+            // Skip not signed PSBTs (i.e. the exported ones)
+            if signed_psbt == proposal.proposal.psbt() {
+                continue;
+            }
+
+            // Skip already approved PSBTs
+            let already_approved: bool = self.storage.approvals().await.into_values().any(|a| {
+                a.proposal_id == proposal_id
+                    && a.public_key == my_public_key
+                    && a.approval.psbt() == signed_psbt
+            });
+            if already_approved {
+                continue;
+            }
+
+            match self
+                .approve_with_signed_psbt(proposal_id, signed_psbt, override_spending_rules)
+                .await
+            {
+                Ok((approval_id, ..)) => approved.push((proposal_id, approval_id)),
+                Err(e) => tracing::error!(
+                    "Impossible to approve proposal {proposal_id} with {}: {e}",
+                    path.display()
+                ),
+            }
+        }
+
+        Ok(approved)
+    }
+}
+
+/// Read a binary or base64 PSBT file
+fn read_psbt_file(path: &Path) -> Result<PartiallySignedTransaction, Error> {
+    let content: Vec<u8> = fs::read(path)?;
+    if content.starts_with(PSBT_MAGIC) {
+        Ok(PartiallySignedTransaction::deserialize(&content)?)
+    } else {
+        let content: String = String::from_utf8_lossy(&content).into_owned();
+        Ok(PartiallySignedTransaction::from_str(content.trim())?)
+    }
+}
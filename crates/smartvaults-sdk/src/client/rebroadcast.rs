@@ -0,0 +1,143 @@
+// Copyright (c) 2022-2024 Smart Vaults
+// Distributed under the MIT software license
+
+use nostr_sdk::database::Order;
+use nostr_sdk::{ClientMessage, Event, Filter, Kind, RelaySendOptions};
+use smartvaults_protocol::v1::constants::{
+    APPROVED_PROPOSAL_KIND, COMPLETED_PROPOSAL_KIND, LABELS_KIND, POLICY_KIND,
+    PROPOSAL_COMMENT_KIND, PROPOSAL_KIND, PROPOSAL_REJECTION_KIND, SHARED_KEY_KIND,
+    VAULT_METADATA_KIND,
+};
+
+use super::{Error, SmartVaults};
+
+/// Kinds sent only as gift wraps when gift wrap is enabled
+const VAULT_KINDS: [Kind; 9] = [
+    SHARED_KEY_KIND,
+    POLICY_KIND,
+    PROPOSAL_KIND,
+    APPROVED_PROPOSAL_KIND,
+    COMPLETED_PROPOSAL_KIND,
+    PROPOSAL_COMMENT_KIND,
+    PROPOSAL_REJECTION_KIND,
+    LABELS_KIND,
+    VAULT_METADATA_KIND,
+];
+
+/// Filter the events of the local database that can be rebroadcasted
+///
+/// Unwrapped rumors (carrying the signature of the seal) are always skipped. If gift wrap is enabled,
+/// vault events (and deletions of them) are skipped too, so the members of a vault are published only
+/// inside the gift wraps.
+fn rebroadcastable(events: Vec<Event>, gift_wrap: bool) -> Vec<Event> {
+    events
+        .into_iter()
+        .filter(|event| event.verify().is_ok())
+        .filter(|event| {
+            !gift_wrap
+                || !(VAULT_KINDS.contains(&event.kind)
+                    || (event.kind == Kind::EventDeletion && event.public_keys().next().is_some()))
+        })
+        .collect()
+}
+
+impl SmartVaults {
+    async fn rebroadcastable_events(&self) -> Result<Vec<Event>, Error> {
+        let events: Vec<Event> = self
+            .client
+            .database()
+            .query(vec![Filter::new()], Order::Asc)
+            .await?;
+        let gift_wrap: bool = self.config.gift_wrap().await;
+        Ok(rebroadcastable(events, gift_wrap))
+    }
+
+    pub async fn rebroadcast_all_events(&self) -> Result<(), Error> {
+        let pool = self.client.pool();
+        for event in self.rebroadcastable_events().await?.into_iter() {
+            pool.send_msg(
+                ClientMessage::event(event),
+                RelaySendOptions::new().skip_send_confirmation(true),
+            )
+            .await?;
+        }
+        // TODO: save last rebroadcast timestamp
+        Ok(())
+    }
+
+    pub async fn rebroadcast_to<S>(&self, url: S) -> Result<(), Error>
+    where
+        S: Into<String>,
+    {
+        let url: String = url.into();
+        let pool = self.client.pool();
+        for event in self.rebroadcastable_events().await?.into_iter() {
+            pool.send_msg_to(
+                [&*url],
+                ClientMessage::event(event),
+                RelaySendOptions::new().skip_send_confirmation(true),
+            )
+            .await?;
+        }
+        // TODO: save last rebroadcast timestamp
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nostr_sdk::{EventBuilder, Keys, Tag};
+    use smartvaults_protocol::v1::gift_wrap;
+
+    use super::*;
+
+    #[test]
+    fn test_rebroadcastable_gift_wrapped_vault_event() {
+        let shared_key = Keys::generate();
+        let alice = Keys::generate();
+        let bob = Keys::generate();
+        let members = [alice.public_key(), bob.public_key()];
+
+        let event: Event = EventBuilder::new(
+            POLICY_KIND,
+            "policy",
+            members.iter().copied().map(Tag::public_key),
+        )
+        .to_event(&shared_key)
+        .unwrap();
+        let deletion: Event = EventBuilder::new(
+            Kind::EventDeletion,
+            "",
+            [Tag::event(event.id), Tag::public_key(alice.public_key())],
+        )
+        .to_event(&shared_key)
+        .unwrap();
+        let metadata: Event = EventBuilder::new(Kind::Metadata, "{}", [])
+            .to_event(&alice)
+            .unwrap();
+
+        // Local database after `send_vault_event`, with the signed event saved by older versions
+        let mut events: Vec<Event> = Vec::new();
+        for public_key in members.iter() {
+            events.push(gift_wrap::wrap(&shared_key, public_key, &event).unwrap());
+        }
+        events.push(gift_wrap::rumor(&shared_key, &event).unwrap());
+        events.push(event.clone());
+        events.push(deletion.clone());
+        events.push(metadata.clone());
+
+        let rebroadcast: Vec<Event> = rebroadcastable(events.clone(), true);
+        assert_eq!(rebroadcast.len(), 3);
+        assert!(rebroadcast.iter().all(|e| e.id != event.id));
+        assert!(rebroadcast
+            .iter()
+            .all(|e| e.kind == Kind::GiftWrap || e.public_keys().next().is_none()));
+        assert!(rebroadcast.contains(&metadata));
+
+        // Without gift wrap only the rumor is skipped
+        let rebroadcast: Vec<Event> = rebroadcastable(events, false);
+        assert_eq!(rebroadcast.len(), 5);
+        assert!(rebroadcast.contains(&event));
+        assert!(rebroadcast.contains(&deletion));
+    }
+}
@@ -0,0 +1,215 @@
+// Copyright (c) 2022-2024 Smart Vaults
+// Distributed under the MIT software license
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use nostr_sdk::database::NostrDatabaseExt;
+use nostr_sdk::{Event, EventId, Keys, PublicKey, Timestamp};
+use smartvaults_core::bitcoin::bip32::Fingerprint;
+use smartvaults_protocol::v1::ProposalRejection;
+use smartvaults_protocol::v2::ProtocolVersion;
+
+use super::{Error, SmartVaults};
+use crate::compat;
+use crate::storage::{
+    InternalApproval, InternalPolicy, InternalProposal, InternalProposalRejection,
+};
+use crate::types::{GetProposalRejection, ProposalStatus};
+
+impl SmartVaults {
+    /// Reject a proposal
+    ///
+    /// The proposal is closed (deleted) if, after this rejection, the threshold can't be met anymore.
+    pub async fn reject_proposal<S>(
+        &self,
+        proposal_id: EventId,
+        reason: Option<S>,
+    ) -> Result<EventId, Error>
+    where
+        S: Into<String>,
+    {
+        let InternalProposal { policy_id, .. } = self.storage.proposal(&proposal_id).await?;
+        let rejection = ProposalRejection::new(reason);
+        let rejection_id: EventId = self
+            .publish_rejection(policy_id, proposal_id, rejection)
+            .await?;
+
+        // Close proposal
+        if self.get_proposal_status(proposal_id).await?.is_rejected() {
+            if let Err(e) = self.delete_proposal_by_id(proposal_id).await {
+                tracing::error!("Impossible to close rejected proposal {proposal_id}: {e}");
+            }
+        }
+
+        Ok(rejection_id)
+    }
+
+    /// Publish own [`ProposalRejection`] and index it
+    pub(super) async fn publish_rejection(
+        &self,
+        policy_id: EventId,
+        proposal_id: EventId,
+        rejection: ProposalRejection,
+    ) -> Result<EventId, Error> {
+        let keys: &Keys = self.keys();
+        let shared_key: Keys = self.storage.shared_key(&policy_id).await?;
+        let InternalPolicy { public_keys, .. } = self.storage.vault(&policy_id).await?;
+
+        // Compose event
+        let version: ProtocolVersion = self.config.protocol_version().await;
+        let event: Event = compat::proposal_rejection(
+            version,
+            keys,
+            &shared_key,
+            proposal_id,
+            policy_id,
+            &rejection,
+            &public_keys,
+        )?;
+        let timestamp: Timestamp = event.created_at;
+
+        // Publish event
+        let rejection_id: EventId = self.send_vault_event(keys, event, &public_keys).await?;
+
+        // Index rejection
+        self.storage
+            .save_rejection(
+                rejection_id,
+                InternalProposalRejection {
+                    proposal_id,
+                    policy_id,
+                    public_key: keys.public_key(),
+                    rejection,
+                    timestamp,
+                },
+            )
+            .await;
+
+        Ok(rejection_id)
+    }
+
+    /// Get proposal rejections
+    pub async fn get_proposal_rejections(
+        &self,
+        proposal_id: EventId,
+    ) -> Result<Vec<GetProposalRejection>, Error> {
+        let rejections = self.storage.rejections_by_proposal_id(&proposal_id).await;
+        let mut list = Vec::with_capacity(rejections.len());
+        for (
+            rejection_id,
+            InternalProposalRejection {
+                public_key,
+                rejection,
+                timestamp,
+                ..
+            },
+        ) in rejections.into_iter()
+        {
+            list.push(GetProposalRejection {
+                rejection_id,
+                user: self.client.database().profile(public_key).await?,
+                rejection,
+                timestamp,
+            });
+        }
+        list.sort();
+        Ok(list)
+    }
+
+    /// Get the aggregated [`ProposalStatus`], combining approvals and rejections
+    pub async fn get_proposal_status(&self, proposal_id: EventId) -> Result<ProposalStatus, Error> {
+        let InternalProposal {
+            policy_id,
+            proposal,
+            ..
+        } = self.storage.proposal(&proposal_id).await?;
+        let InternalPolicy { policy, .. } = self.storage.vault(&policy_id).await?;
+
+        let approvals: Vec<InternalApproval> = self
+            .storage
+            .approvals()
+            .await
+            .into_values()
+            .filter(|a| a.proposal_id == proposal_id)
+            .collect();
+        let rejections = self.storage.rejections_by_proposal_id(&proposal_id).await;
+
+        // Keep the latest vote of every member (`true` if approved)
+        let mut votes: HashMap<PublicKey, (Timestamp, bool)> = HashMap::new();
+        let all = approvals
+            .iter()
+            .map(|a| (a.public_key, a.timestamp, true))
+            .chain(
+                rejections
+                    .values()
+                    .map(|r| (r.public_key, r.timestamp, false)),
+            );
+        for (public_key, timestamp, approved) in all {
+            match votes.get(&public_key) {
+                Some((t, ..)) if *t >= timestamp => (),
+                _ => {
+                    votes.insert(public_key, (timestamp, approved));
+                }
+            }
+        }
+
+        let approved_by: BTreeSet<PublicKey> = votes
+            .iter()
+            .filter(|(_, (_, approved))| *approved)
+            .map(|(p, ..)| *p)
+            .collect();
+        let rejected_by: BTreeSet<PublicKey> = votes
+            .iter()
+            .filter(|(_, (_, approved))| !*approved)
+            .map(|(p, ..)| *p)
+            .collect();
+
+        let signed: bool = proposal
+            .finalize(
+                approvals
+                    .into_iter()
+                    .filter(|a| approved_by.contains(&a.public_key))
+                    .map(|a| a.approval),
+                self.network,
+            )
+            .is_ok();
+
+        // Exclude the keys of who rejected
+        let mut excluded: HashSet<Fingerprint> = HashSet::new();
+        for public_key in rejected_by.iter() {
+            excluded.extend(self.member_fingerprints(public_key).await);
+        }
+        let approvable: bool = policy.satisfiable_on_path_without(
+            excluded.iter(),
+            proposal.policy_path().as_ref(),
+            &proposal.psbt().unsigned_tx,
+        )?;
+
+        Ok(ProposalStatus {
+            approved_by,
+            rejected_by,
+            signed,
+            approvable,
+        })
+    }
+
+    /// Fingerprints of the known signers of a member
+    async fn member_fingerprints(&self, public_key: &PublicKey) -> HashSet<Fingerprint> {
+        if *public_key == self.keys().public_key() {
+            self.storage
+                .signers()
+                .await
+                .into_values()
+                .map(|s| s.fingerprint())
+                .collect()
+        } else {
+            self.storage
+                .shared_signers()
+                .await
+                .into_values()
+                .filter(|s| s.owner_public_key == *public_key)
+                .map(|s| s.shared_signer.fingerprint())
+                .collect()
+        }
+    }
+}
@@ -0,0 +1,92 @@
+// Copyright (c) 2022-2024 Smart Vaults
+// Distributed under the MIT software license
+
+use std::iter;
+
+use nostr_sdk::EventId;
+use smartvaults_core::bips::bip32::{Bip32, Fingerprint};
+use smartvaults_core::types::Seed;
+use smartvaults_core::{Policy, Signer, SignerType, SECP256K1};
+
+use super::{Error, SmartVaults};
+use crate::storage::InternalPolicy;
+use crate::types::SeedAccountReuse;
+
+impl SmartVaults {
+    /// Derive a seed [`Signer`] from the keychain seed, using `account` as BIP86 account index, and save it
+    pub async fn save_seed_signer<T, S>(
+        &self,
+        password: T,
+        name: S,
+        account: u32,
+    ) -> Result<EventId, Error>
+    where
+        T: AsRef<[u8]>,
+        S: Into<String>,
+    {
+        let seed: Seed = self.keechain.read().seed(password)?;
+        let signer: Signer = Signer::from_seed(name, None, seed, Some(account), self.network)?;
+        self.save_signer(signer).await
+    }
+
+    /// Get the seed [`Signer`]s (accounts) derived from `seed` and involved in the vault
+    pub(crate) async fn vault_seed_signers(
+        &self,
+        policy_id: EventId,
+        seed: &Seed,
+    ) -> Result<Vec<Signer>, Error> {
+        let fingerprint: Fingerprint = seed.fingerprint(self.network, &SECP256K1)?;
+        let InternalPolicy { policy, .. } = self.storage.vault(&policy_id).await?;
+        let mut signers: Vec<Signer> = self
+            .storage
+            .signers()
+            .await
+            .into_values()
+            .filter(|s| s.signer_type() == SignerType::Seed && s.fingerprint() == fingerprint)
+            .collect();
+        signers.sort();
+        Ok(policy.search_used_signers(signers.into_iter()).collect())
+    }
+
+    /// Check if the seed [`Signer`]s (accounts) involved in a new vault are already used in other vaults
+    ///
+    /// Should be called before [`SmartVaults::save_policy`], to warn the user about the account reuse.
+    pub async fn seed_account_reuse<S>(
+        &self,
+        desc_or_policy: S,
+    ) -> Result<Vec<SeedAccountReuse>, Error>
+    where
+        S: AsRef<str>,
+    {
+        let policy: Policy = Policy::from_desc_or_policy("", "", desc_or_policy, self.network)?;
+        let mut signers: Vec<Signer> = self
+            .storage
+            .signers()
+            .await
+            .into_values()
+            .filter(|s| s.signer_type() == SignerType::Seed)
+            .collect();
+        signers.sort();
+
+        let vaults = self.storage.vaults().await;
+        let mut list: Vec<SeedAccountReuse> = Vec::new();
+        for signer in policy.search_used_signers(signers.into_iter()) {
+            let mut policy_ids: Vec<EventId> = vaults
+                .iter()
+                .filter(|(_, InternalPolicy { policy, .. })| {
+                    policy
+                        .search_used_signers(iter::once(signer.clone()))
+                        .next()
+                        .is_some()
+                })
+                .map(|(policy_id, ..)| *policy_id)
+                .collect();
+            if !policy_ids.is_empty() {
+                policy_ids.sort();
+                list.push(SeedAccountReuse { signer, policy_ids });
+            }
+        }
+
+        Ok(list)
+    }
+}
@@ -4,17 +4,16 @@
 use std::collections::{BTreeMap, HashSet};
 
 use nostr_sdk::database::NostrDatabaseExt;
-use nostr_sdk::nips::nip04;
 use nostr_sdk::{
     ClientMessage, Event, EventBuilder, EventId, Keys, Kind, Profile, PublicKey, RelaySendOptions,
     Tag,
 };
 use smartvaults_core::miniscript::Descriptor;
 use smartvaults_core::signer::{SharedSigner, Signer};
-use smartvaults_protocol::v1::constants::{SHARED_SIGNERS_KIND, SIGNERS_KIND};
-use smartvaults_protocol::v1::util::{Encryption, Serde};
+use smartvaults_protocol::v2::ProtocolVersion;
 
 use super::{Error, SmartVaults};
+use crate::compat;
 use crate::storage::InternalSharedSigner;
 use crate::types::{GetAllSigners, GetSharedSigner, GetSigner};
 
@@ -57,11 +56,9 @@ impl SmartVaults {
             return Err(Error::SignerDescriptorAlreadyExists);
         }
 
-        // Compose the event
-        let content: String = signer.encrypt_with_keys(keys)?;
-
         // Compose signer event
-        let event = EventBuilder::new(SIGNERS_KIND, content, []).to_event(keys)?;
+        let version: ProtocolVersion = self.config.protocol_version().await;
+        let event: Event = compat::signer(version, keys, &signer)?;
 
         // Publish the event
         let signer_id = self.client.send_event(event).await?;
@@ -72,6 +69,19 @@ impl SmartVaults {
         Ok(signer_id)
     }
 
+    /// Save an air-gapped [`Signer`] from a wallet export, detecting its format
+    ///
+    /// Supported formats: Sparrow/Specter wallet exports, output descriptors, Coldcard, Keystone,
+    /// Passport and Jade account exports, key origins (`[fingerprint/path]xpub`) and SLIP-132 keys.
+    pub async fn save_signer_from_export<N, E>(&self, name: N, export: E) -> Result<EventId, Error>
+    where
+        N: Into<String>,
+        E: AsRef<str>,
+    {
+        let signer: Signer = Signer::from_export(name, export, self.network)?;
+        self.save_signer(signer).await
+    }
+
     pub async fn smartvaults_signer_exists(&self) -> bool {
         self.storage
             .signer_descriptor_exists(self.default_signer.descriptor())
@@ -131,11 +141,9 @@ impl SmartVaults {
             let keys: &Keys = self.keys();
             let signer: Signer = self.get_signer_by_id(signer_id).await?;
             let shared_signer: SharedSigner = signer.to_shared_signer();
-            let content: String =
-                nip04::encrypt(keys.secret_key()?, &public_key, shared_signer.as_json())?;
-            let tags = [Tag::event(signer_id), Tag::public_key(public_key)];
+            let version: ProtocolVersion = self.config.protocol_version().await;
             let event: Event =
-                EventBuilder::new(SHARED_SIGNERS_KIND, content, tags).to_event(keys)?;
+                compat::shared_signer(version, keys, signer_id, &shared_signer, &public_key)?;
             let event_id = self.client.send_event(event).await?;
             self.storage
                 .save_my_shared_signer(signer_id, event_id, public_key)
@@ -158,6 +166,7 @@ impl SmartVaults {
         let keys: &Keys = self.keys();
         let signer: Signer = self.get_signer_by_id(signer_id).await?;
         let shared_signer: SharedSigner = signer.to_shared_signer();
+        let version: ProtocolVersion = self.config.protocol_version().await;
 
         for public_key in public_keys.into_iter() {
             if self
@@ -167,11 +176,8 @@ impl SmartVaults {
             {
                 tracing::warn!("Signer {signer_id} already shared with {public_key}");
             } else {
-                let content: String =
-                    nip04::encrypt(keys.secret_key()?, &public_key, shared_signer.as_json())?;
-                let tags = [Tag::event(signer_id), Tag::public_key(public_key)];
                 let event: Event =
-                    EventBuilder::new(SHARED_SIGNERS_KIND, content, tags).to_event(keys)?;
+                    compat::shared_signer(version, keys, signer_id, &shared_signer, &public_key)?;
                 let event_id: EventId = event.id;
 
                 // TODO: use send_batch_event method from nostr-sdk
@@ -0,0 +1,170 @@
+// Copyright (c) 2022-2024 Smart Vaults
+// Distributed under the MIT software license
+
+use nostr_sdk::{EventId, Timestamp};
+use smartvaults_core::bitcoin::{Address, Transaction};
+use smartvaults_core::spending_rules::{OutgoingPayment, SpendingRecord};
+use smartvaults_core::{CompletedProposal, Proposal, SpendingRules};
+
+use super::{Error, SmartVaults};
+use crate::manager::SmartVaultsWallet;
+use crate::storage::{InternalCompletedProposal, InternalProposal};
+
+impl SmartVaults {
+    /// Get the [`SpendingRules`] of the vault (empty if never set)
+    pub async fn get_spending_rules(&self, policy_id: EventId) -> Result<SpendingRules, Error> {
+        Ok(self.get_vault_metadata(policy_id).await?.spending_rules)
+    }
+
+    /// Check a new spending [`Proposal`] against the [`SpendingRules`] of the vault
+    ///
+    /// The completed and the pending spending proposals of the vault are used as spending history.
+    pub async fn check_spending_rules(
+        &self,
+        policy_id: EventId,
+        proposal: &Proposal,
+    ) -> Result<(), Error> {
+        self.internal_check_spending_rules(policy_id, proposal, true)
+            .await
+    }
+
+    /// Check a published spending proposal against the [`SpendingRules`] of the vault
+    ///
+    /// Rules set, edited or removed after the proposal are refused, so they can't be silently
+    /// replaced before an approval. Only the completed proposals are used as spending history: the
+    /// pending ones were already counted when the proposal was created.
+    pub(crate) async fn check_proposal_spending_rules(
+        &self,
+        proposal_id: EventId,
+    ) -> Result<(), Error> {
+        let InternalProposal {
+            policy_id,
+            proposal,
+            timestamp,
+            ..
+        } = self.storage.proposal(&proposal_id).await?;
+
+        if !is_spending(&proposal) {
+            return Ok(());
+        }
+
+        if let Some(changed_at) = self.storage.spending_rules_timestamp(&policy_id).await {
+            if changed_at > timestamp {
+                return Err(Error::SpendingRulesChanged(changed_at));
+            }
+        }
+
+        self.internal_check_spending_rules(policy_id, &proposal, false)
+            .await
+    }
+
+    async fn internal_check_spending_rules(
+        &self,
+        policy_id: EventId,
+        proposal: &Proposal,
+        count_pending: bool,
+    ) -> Result<(), Error> {
+        let rules: SpendingRules = self.get_spending_rules(policy_id).await?;
+
+        if rules.is_empty() {
+            return Ok(());
+        }
+
+        if !is_spending(proposal) {
+            return Ok(());
+        }
+
+        let wallet: SmartVaultsWallet = self.manager.wallet(policy_id).await?;
+        let now: Timestamp = Timestamp::now();
+
+        // Outgoing payments
+        let payments: Vec<OutgoingPayment> = self
+            .outgoing_payments(&wallet, &proposal.psbt().unsigned_tx)
+            .await;
+
+        // Spending history
+        let mut history: Vec<SpendingRecord> = Vec::new();
+        for (
+            _,
+            InternalCompletedProposal {
+                proposal,
+                timestamp,
+                ..
+            },
+        ) in self
+            .storage
+            .completed_proposals()
+            .await
+            .into_iter()
+            .filter(|(_, p)| p.policy_id == policy_id)
+        {
+            // Fee bumps are skipped to not count twice the replaced payments
+            if let CompletedProposal::Spending { tx, .. }
+            | CompletedProposal::KeyAgentPayment { tx, .. } = proposal
+            {
+                history.push(SpendingRecord {
+                    timestamp: timestamp.as_u64(),
+                    amount: self.outgoing_amount(&wallet, &tx).await,
+                });
+            }
+        }
+
+        // Pending proposals may be spent at any time: count them as spent now
+        if count_pending {
+            for (_, InternalProposal { proposal, .. }) in self
+                .storage
+                .proposals()
+                .await
+                .into_iter()
+                .filter(|(_, p)| p.policy_id == policy_id)
+            {
+                if let Proposal::Spending { psbt, .. } | Proposal::KeyAgentPayment { psbt, .. } =
+                    proposal
+                {
+                    history.push(SpendingRecord {
+                        timestamp: now.as_u64(),
+                        amount: self.outgoing_amount(&wallet, &psbt.unsigned_tx).await,
+                    });
+                }
+            }
+        }
+
+        Ok(rules.check(&payments, &proposal.description(), history, now.as_u64())?)
+    }
+
+    /// Outputs of `tx` not owned by the vault
+    async fn outgoing_payments(
+        &self,
+        wallet: &SmartVaultsWallet,
+        tx: &Transaction,
+    ) -> Vec<OutgoingPayment> {
+        let mut payments: Vec<OutgoingPayment> = Vec::new();
+        for txout in tx.output.iter() {
+            if !wallet.is_mine(&txout.script_pubkey).await {
+                let address = Address::from_script(&txout.script_pubkey, self.network).ok();
+                payments.push(OutgoingPayment {
+                    address: address.map(|a| Address::new(self.network, a.payload)),
+                    amount: txout.value,
+                });
+            }
+        }
+        payments
+    }
+
+    /// Total amount sent by `tx` to outputs not owned by the vault
+    async fn outgoing_amount(&self, wallet: &SmartVaultsWallet, tx: &Transaction) -> u64 {
+        self.outgoing_payments(wallet, tx)
+            .await
+            .into_iter()
+            .map(|p| p.amount)
+            .sum()
+    }
+}
+
+/// Check if the proposal spends from the vault
+fn is_spending(proposal: &Proposal) -> bool {
+    matches!(
+        proposal,
+        Proposal::Spending { .. } | Proposal::KeyAgentPayment { .. }
+    )
+}
@@ -22,10 +22,12 @@ use smartvaults_core::bitcoin::Network;
 use smartvaults_core::{CompletedProposal, Priority};
 use smartvaults_protocol::v1::constants::{
     APPROVED_PROPOSAL_KIND, COMPLETED_PROPOSAL_KIND, KEY_AGENT_SIGNALING,
-    KEY_AGENT_SIGNER_OFFERING_KIND, KEY_AGENT_VERIFIED, LABELS_KIND, POLICY_KIND, PROPOSAL_KIND,
-    SHARED_KEY_KIND, SHARED_SIGNERS_KIND, SIGNERS_KIND, SMARTVAULTS_MAINNET_PUBLIC_KEY,
-    SMARTVAULTS_TESTNET_PUBLIC_KEY,
+    KEY_AGENT_SIGNER_OFFERING_KIND, KEY_AGENT_VERIFIED, LABELS_KIND, POLICY_KIND,
+    PROPOSAL_COMMENT_KIND, PROPOSAL_KIND, PROPOSAL_REJECTION_KIND, SHARED_KEY_KIND,
+    SHARED_SIGNERS_KIND, SIGNERS_KIND, SMARTVAULTS_MAINNET_PUBLIC_KEY,
+    SMARTVAULTS_TESTNET_PUBLIC_KEY, VAULT_METADATA_KIND,
 };
+use smartvaults_protocol::v1::gift_wrap;
 use tokio::sync::broadcast::Receiver;
 
 use super::{Error, SmartVaults};
@@ -36,8 +38,18 @@ use crate::storage::{InternalCompletedProposal, InternalPolicy};
 pub enum EventHandled {
     SharedKey(EventId),
     Policy(EventId),
+    /// Members of the vault updated after a shared key rotation
+    PolicyUpdated(EventId),
     Proposal(EventId),
-    Approval { proposal_id: EventId },
+    Approval {
+        proposal_id: EventId,
+    },
+    ProposalComment {
+        proposal_id: EventId,
+    },
+    ProposalRejection {
+        proposal_id: EventId,
+    },
     CompletedProposal(EventId),
     Signer(EventId),
     MySharedSigner(EventId),
@@ -46,6 +58,8 @@ pub enum EventHandled {
     Metadata(PublicKey),
     NostrConnectRequest(EventId),
     Label,
+    /// Vault metadata updated
+    VaultMetadata(EventId),
     EventDeletion,
     RelayList,
     KeyAgentSignerOffering,
@@ -56,6 +70,8 @@ pub enum EventHandled {
 pub enum Message {
     EventHandled(EventHandled),
     WalletSyncCompleted(EventId),
+    /// Proposal expired: deleted and its UTXOs unfrozen
+    ProposalExpired(EventId),
     BlockHeightUpdated,
     MempoolFeesUpdated(BTreeMap<Priority, FeeRate>),
 }
@@ -144,6 +160,21 @@ impl SmartVaults {
         })?)
     }
 
+    fn proposals_expiration_checker(&self) -> Result<AbortHandle, Error> {
+        let this = self.clone();
+        Ok(thread::abortable(async move {
+            loop {
+                for proposal_id in this.storage.delete_expired_proposals().await.into_iter() {
+                    tracing::info!("Proposal {proposal_id} expired");
+                    let _ = this
+                        .sync_channel
+                        .send(Message::ProposalExpired(proposal_id));
+                }
+                thread::sleep(Duration::from_secs(60)).await;
+            }
+        })?)
+    }
+
     pub fn sync_notifications(&self) -> Receiver<Message> {
         self.sync_channel.subscribe()
     }
@@ -153,11 +184,14 @@ impl SmartVaults {
             POLICY_KIND,
             PROPOSAL_KIND,
             APPROVED_PROPOSAL_KIND,
+            PROPOSAL_COMMENT_KIND,
+            PROPOSAL_REJECTION_KIND,
             COMPLETED_PROPOSAL_KIND,
             SHARED_KEY_KIND,
             SIGNERS_KIND,
             SHARED_SIGNERS_KIND,
             LABELS_KIND,
+            VAULT_METADATA_KIND,
             Kind::EventDeletion,
         ]);
 
@@ -172,6 +206,10 @@ impl SmartVaults {
 
         let author_filter: Filter = base_filter.clone().author(public_key).since(since);
         let pubkey_filter: Filter = base_filter.pubkey(public_key).since(since);
+        let gift_wrap_filter: Filter = Filter::new()
+            .pubkey(public_key)
+            .kind(Kind::GiftWrap)
+            .since(since);
         let nostr_connect_filter = Filter::new()
             .pubkey(public_key)
             .kind(Kind::NostrConnect)
@@ -193,6 +231,7 @@ impl SmartVaults {
         let mut filters = vec![
             author_filter,
             pubkey_filter,
+            gift_wrap_filter,
             nostr_connect_filter,
             other_filters,
             key_agents,
@@ -223,6 +262,10 @@ impl SmartVaults {
                 // Pending events handler
                 let pending_event_handler = this.handle_pending_events()?;
 
+                // Expired proposals
+                let proposals_expiration_checker: AbortHandle =
+                    this.proposals_expiration_checker()?;
+
                 for (relay_url, relay) in this.client.relays().await {
                     let last_sync: Timestamp =
                         match this.db.get_last_relay_sync(relay_url.clone()).await {
@@ -277,6 +320,7 @@ impl SmartVaults {
                                 mempool_fees_syncer.abort();
                                 policies_syncer.abort();
                                 pending_event_handler.abort();
+                                proposals_expiration_checker.abort();
                                 let _ = this.syncing.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |_| Some(false));
                             }
                         }
@@ -292,7 +336,14 @@ impl SmartVaults {
             // Negentropy reconciliation
             let this = self.clone();
             thread::spawn(async move {
-                let opts = NegentropyOptions::new().direction(NegentropyDirection::Both);
+                // With gift wrap, the local database holds the unwrapped rumors of the vault
+                // events: never upload them
+                let direction = if this.config.gift_wrap().await {
+                    NegentropyDirection::Down
+                } else {
+                    NegentropyDirection::Both
+                };
+                let opts = NegentropyOptions::new().direction(direction);
                 for filter in this.sync_filters(Timestamp::from(0)).await.into_iter() {
                     this.client.reconcile(filter, opts).await.unwrap();
                 }
@@ -303,6 +354,19 @@ impl SmartVaults {
     }
 
     async fn handle_event(&self, event: Event) -> Result<()> {
+        // Unwrap gift wrapped vault events and save them in the local database
+        let event: Event = if event.kind == Kind::GiftWrap {
+            let event: Event = gift_wrap::unwrap(self.keys(), &event)?;
+            if event.is_expired() {
+                tracing::warn!("Gift wrapped event {} expired", event.id);
+                return Ok(());
+            }
+            self.client.database().save_event(&event).await?;
+            event
+        } else {
+            event
+        };
+
         if event.kind == Kind::ContactList {
             let pubkeys = event.public_keys().copied();
             let filter: Filter = Filter::new().authors(pubkeys).kind(Kind::Metadata);
@@ -440,7 +504,7 @@ impl SmartVaults {
             match h {
                 EventHandled::Policy(vault_id) => {
                     let InternalPolicy { policy, .. } = self.storage.vault(&vault_id).await?;
-                    self.manager.load_policy(event.id, policy).await?;
+                    self.manager.load_policy(vault_id, policy).await?;
                 }
                 EventHandled::CompletedProposal(completed_proposal_id) => {
                     let InternalCompletedProposal {
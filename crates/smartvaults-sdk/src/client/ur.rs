@@ -0,0 +1,51 @@
+// Copyright (c) 2022-2024 Smart Vaults
+// Distributed under the MIT software license
+
+use nostr_sdk::EventId;
+use smartvaults_core::bitcoin::psbt::PartiallySignedTransaction;
+use smartvaults_core::ur::{self, UrEncoder};
+use smartvaults_core::{ApprovedProposal, Signer};
+
+use super::{Error, SmartVaults};
+use crate::types::GetProposal;
+
+impl SmartVaults {
+    /// Get the `crypto-psbt` UR encoder of a proposal, to show it as animated QR code to an air-gapped signer
+    ///
+    /// Use [`DEFAULT_MAX_FRAGMENT_LEN`](smartvaults_core::ur::DEFAULT_MAX_FRAGMENT_LEN) as default `max_fragment_len`.
+    pub async fn proposal_ur_encoder(
+        &self,
+        proposal_id: EventId,
+        max_fragment_len: usize,
+    ) -> Result<UrEncoder, Error> {
+        let GetProposal { proposal, .. } = self.get_proposal_by_id(proposal_id).await?;
+        Ok(UrEncoder::psbt(&proposal.psbt(), max_fragment_len)?)
+    }
+
+    /// Approve a proposal with the signed PSBT received as `crypto-psbt` UR parts (any order)
+    pub async fn approve_with_ur<I, S>(
+        &self,
+        proposal_id: EventId,
+        parts: I,
+        override_spending_rules: bool,
+    ) -> Result<(EventId, ApprovedProposal), Error>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let signed_psbt: PartiallySignedTransaction = ur::decode_psbt(parts)?;
+        self.approve_with_signed_psbt(proposal_id, signed_psbt, override_spending_rules)
+            .await
+    }
+
+    /// Save an air-gapped [`Signer`] from `crypto-account` or `crypto-output` UR parts (any order)
+    pub async fn save_signer_from_ur<N, I, S>(&self, name: N, parts: I) -> Result<EventId, Error>
+    where
+        N: Into<String>,
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let signer: Signer = ur::decode_signer(name, parts, self.network)?;
+        self.save_signer(signer).await
+    }
+}
@@ -0,0 +1,51 @@
+// Copyright (c) 2022-2024 Smart Vaults
+// Distributed under the MIT software license
+
+use nostr_sdk::{Event, EventId, Keys, Timestamp};
+use smartvaults_protocol::v1::VaultMetadata;
+use smartvaults_protocol::v2::ProtocolVersion;
+
+use super::{Error, SmartVaults};
+use crate::compat;
+use crate::storage::InternalPolicy;
+
+impl SmartVaults {
+    /// Publish the [`VaultMetadata`], replacing the previous one
+    pub async fn save_vault_metadata(
+        &self,
+        policy_id: EventId,
+        metadata: VaultMetadata,
+    ) -> Result<EventId, Error> {
+        let shared_key: Keys = self.storage.shared_key(&policy_id).await?;
+        let InternalPolicy { public_keys, .. } = self.storage.vault(&policy_id).await?;
+
+        // Compose event
+        let version: ProtocolVersion = self.config.protocol_version().await;
+        let event: Event =
+            compat::vault_metadata(version, &shared_key, policy_id, &metadata, &public_keys)?;
+        let timestamp: Timestamp = event.created_at;
+
+        // Publish event
+        let event_id: EventId = self
+            .send_vault_event(&shared_key, event, &public_keys)
+            .await?;
+
+        // Save to db
+        self.storage
+            .save_vault_metadata(policy_id, metadata, timestamp)
+            .await;
+
+        Ok(event_id)
+    }
+
+    /// Get the latest [`VaultMetadata`] (empty if never published)
+    pub async fn get_vault_metadata(&self, policy_id: EventId) -> Result<VaultMetadata, Error> {
+        // Check if vault exists
+        self.storage.vault(&policy_id).await?;
+        Ok(self
+            .storage
+            .vault_metadata(&policy_id)
+            .await
+            .unwrap_or_default())
+    }
+}
@@ -0,0 +1,333 @@
+// Copyright (c) 2022-2024 Smart Vaults
+// Distributed under the MIT software license
+
+//! Protocol v1/v2 compatibility
+//!
+//! Events are always read, whatever their version, and built with the [`ProtocolVersion`]
+//! set in the config.
+
+use std::ops::Add;
+
+use nostr_sdk::{Event, EventBuilder, EventId, Keys, Kind, PublicKey, Tag, Timestamp};
+use smartvaults_core::{
+    ApprovedProposal, CompletedProposal, Policy, Proposal, SharedSigner, Signer,
+};
+use smartvaults_protocol::v1::constants::{
+    APPROVED_PROPOSAL_EXPIRATION, APPROVED_PROPOSAL_KIND, COMPLETED_PROPOSAL_KIND, LABELS_KIND,
+    POLICY_KIND, PROPOSAL_COMMENT_KIND, PROPOSAL_KIND, PROPOSAL_REJECTION_KIND, SIGNERS_KIND,
+    VAULT_METADATA_KIND,
+};
+use smartvaults_protocol::v1::{
+    self, Encryption, EncryptionScheme, Label, ProposalComment, ProposalRejection, Serde,
+    VaultMetadata,
+};
+use smartvaults_protocol::v2::{self, Envelope, ProtocolVersion, Versioned};
+
+use crate::Error;
+
+/// Decrypt event content encrypted with `keys`
+pub(crate) fn decrypt<T>(keys: &Keys, event: &Event) -> Result<T, Error>
+where
+    T: Encryption + Versioned,
+{
+    match ProtocolVersion::from_tags(&event.tags)? {
+        ProtocolVersion::V1 => {
+            // Events without the encryption tag use the legacy scheme
+            let scheme = EncryptionScheme::from_tags(&event.tags)?;
+            Ok(<T as Encryption>::decrypt_with_keys(
+                keys,
+                &event.content,
+                scheme,
+            )?)
+        }
+        ProtocolVersion::V2 => Ok(T::decrypt_envelope(keys, &event.content)?),
+    }
+}
+
+/// Decrypt [`SharedSigner`] received from the event author
+pub(crate) fn decrypt_shared_signer(keys: &Keys, event: &Event) -> Result<SharedSigner, Error> {
+    let version = ProtocolVersion::from_tags(&event.tags)?;
+    let scheme = EncryptionScheme::from_tags(&event.tags)?;
+    let json: String = scheme.decrypt(keys.secret_key()?, event.author_ref(), &event.content)?;
+    match version {
+        ProtocolVersion::V1 => Ok(SharedSigner::from_json(json)?),
+        ProtocolVersion::V2 => Ok(Envelope::from_json(json)?.open()?),
+    }
+}
+
+/// Compose an event of `kind` signed by `keys`, with the `object` encrypted with `shared_key`
+///
+/// The `object` is encrypted as raw JSON for [`ProtocolVersion::V1`] or wrapped in an
+/// [`Envelope`] for [`ProtocolVersion::V2`]. The `p` tags of `nostr_pubkeys` come first, followed
+/// by `tags` and by the encryption and version tags.
+fn build<T, I>(
+    version: ProtocolVersion,
+    keys: &Keys,
+    shared_key: &Keys,
+    kind: Kind,
+    object: &T,
+    nostr_pubkeys: &[PublicKey],
+    tags: I,
+) -> Result<Event, Error>
+where
+    T: Encryption + Versioned,
+    I: IntoIterator<Item = Tag>,
+{
+    let content: String = match version {
+        ProtocolVersion::V1 => object.encrypt_with_keys(shared_key)?,
+        ProtocolVersion::V2 => object.encrypt_envelope(shared_key)?,
+    };
+    let mut tags: Vec<Tag> = nostr_pubkeys
+        .iter()
+        .copied()
+        .map(Tag::public_key)
+        .chain(tags)
+        .collect();
+    tags.push(EncryptionScheme::default().tag());
+    if version != ProtocolVersion::V1 {
+        tags.push(version.tag());
+    }
+    Ok(EventBuilder::new(kind, content, tags).to_event(keys)?)
+}
+
+pub(crate) fn policy(
+    version: ProtocolVersion,
+    shared_key: &Keys,
+    policy: &Policy,
+    nostr_pubkeys: &[PublicKey],
+) -> Result<Event, Error> {
+    build(
+        version,
+        shared_key,
+        shared_key,
+        POLICY_KIND,
+        policy,
+        nostr_pubkeys,
+        [],
+    )
+}
+
+/// Re-publish the policy of `vault_id` with a new `shared_key` and the current members
+pub(crate) fn rotated_policy(
+    version: ProtocolVersion,
+    shared_key: &Keys,
+    vault_id: EventId,
+    policy: &Policy,
+    nostr_pubkeys: &[PublicKey],
+) -> Result<Event, Error> {
+    build(
+        version,
+        shared_key,
+        shared_key,
+        POLICY_KIND,
+        policy,
+        nostr_pubkeys,
+        [Tag::event(vault_id)],
+    )
+}
+
+/// Proposal, optionally expiring (NIP-40) at `expiration`
+pub(crate) fn proposal(
+    version: ProtocolVersion,
+    shared_key: &Keys,
+    policy_id: EventId,
+    proposal: &Proposal,
+    expiration: Option<Timestamp>,
+    nostr_pubkeys: &[PublicKey],
+) -> Result<Event, Error> {
+    let tags = [Tag::event(policy_id)]
+        .into_iter()
+        .chain(expiration.map(Tag::Expiration));
+    build(
+        version,
+        shared_key,
+        shared_key,
+        PROPOSAL_KIND,
+        proposal,
+        nostr_pubkeys,
+        tags,
+    )
+}
+
+/// Approval signed by `keys` and encrypted with the `shared_key`
+pub(crate) fn approval(
+    version: ProtocolVersion,
+    keys: &Keys,
+    shared_key: &Keys,
+    proposal_id: EventId,
+    policy_id: EventId,
+    approval: &ApprovedProposal,
+    nostr_pubkeys: &[PublicKey],
+) -> Result<Event, Error> {
+    build(
+        version,
+        keys,
+        shared_key,
+        APPROVED_PROPOSAL_KIND,
+        approval,
+        nostr_pubkeys,
+        [
+            Tag::event(proposal_id),
+            Tag::event(policy_id),
+            Tag::Expiration(Timestamp::now().add(APPROVED_PROPOSAL_EXPIRATION)),
+        ],
+    )
+}
+
+/// Comment signed by `keys` and encrypted with the `shared_key`
+pub(crate) fn proposal_comment(
+    version: ProtocolVersion,
+    keys: &Keys,
+    shared_key: &Keys,
+    proposal_id: EventId,
+    policy_id: EventId,
+    comment: &ProposalComment,
+    nostr_pubkeys: &[PublicKey],
+) -> Result<Event, Error> {
+    build(
+        version,
+        keys,
+        shared_key,
+        PROPOSAL_COMMENT_KIND,
+        comment,
+        nostr_pubkeys,
+        [Tag::event(proposal_id), Tag::event(policy_id)],
+    )
+}
+
+/// Rejection signed by `keys` and encrypted with the `shared_key`
+pub(crate) fn proposal_rejection(
+    version: ProtocolVersion,
+    keys: &Keys,
+    shared_key: &Keys,
+    proposal_id: EventId,
+    policy_id: EventId,
+    rejection: &ProposalRejection,
+    nostr_pubkeys: &[PublicKey],
+) -> Result<Event, Error> {
+    build(
+        version,
+        keys,
+        shared_key,
+        PROPOSAL_REJECTION_KIND,
+        rejection,
+        nostr_pubkeys,
+        [Tag::event(proposal_id), Tag::event(policy_id)],
+    )
+}
+
+pub(crate) fn completed_proposal(
+    version: ProtocolVersion,
+    shared_key: &Keys,
+    proposal_id: EventId,
+    policy_id: EventId,
+    completed_proposal: &CompletedProposal,
+    nostr_pubkeys: &[PublicKey],
+) -> Result<Event, Error> {
+    build(
+        version,
+        shared_key,
+        shared_key,
+        COMPLETED_PROPOSAL_KIND,
+        completed_proposal,
+        nostr_pubkeys,
+        [Tag::event(proposal_id), Tag::event(policy_id)],
+    )
+}
+
+/// Signer encrypted with own `keys`
+pub(crate) fn signer(
+    version: ProtocolVersion,
+    keys: &Keys,
+    signer: &Signer,
+) -> Result<Event, Error> {
+    build(version, keys, keys, SIGNERS_KIND, signer, &[], [])
+}
+
+/// Shared signer encrypted for the `receiver`
+pub(crate) fn shared_signer(
+    version: ProtocolVersion,
+    keys: &Keys,
+    signer_id: EventId,
+    shared_signer: &SharedSigner,
+    receiver: &PublicKey,
+) -> Result<Event, Error> {
+    match version {
+        ProtocolVersion::V1 => Ok(
+            <EventBuilder as v1::SmartVaultsEventBuilder>::shared_signer(
+                keys,
+                signer_id,
+                shared_signer,
+                receiver,
+            )?,
+        ),
+        ProtocolVersion::V2 => Ok(
+            <EventBuilder as v2::SmartVaultsEventBuilder>::shared_signer(
+                keys,
+                signer_id,
+                shared_signer,
+                receiver,
+            )?,
+        ),
+    }
+}
+
+pub(crate) fn label(
+    version: ProtocolVersion,
+    shared_key: &Keys,
+    policy_id: EventId,
+    label: &Label,
+    nostr_pubkeys: &[PublicKey],
+) -> Result<Event, Error> {
+    let identifier: String = label.generate_identifier(shared_key)?;
+    build(
+        version,
+        shared_key,
+        shared_key,
+        LABELS_KIND,
+        label,
+        nostr_pubkeys,
+        [Tag::Identifier(identifier), Tag::event(policy_id)],
+    )
+}
+
+/// Vault metadata, replaceable by every member (signed with the `shared_key`)
+pub(crate) fn vault_metadata(
+    version: ProtocolVersion,
+    shared_key: &Keys,
+    policy_id: EventId,
+    metadata: &VaultMetadata,
+    nostr_pubkeys: &[PublicKey],
+) -> Result<Event, Error> {
+    build(
+        version,
+        shared_key,
+        shared_key,
+        VAULT_METADATA_KIND,
+        metadata,
+        nostr_pubkeys,
+        [Tag::Identifier(policy_id.to_hex()), Tag::event(policy_id)],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use smartvaults_core::bitcoin::hashes::Hash;
+    use smartvaults_core::bitcoin::{OutPoint, Txid};
+
+    use super::*;
+
+    #[test]
+    fn test_read_both_versions() {
+        let shared_key = Keys::generate();
+        let policy_id = EventId::all_zeros();
+        let label = Label::utxo(OutPoint::new(Txid::all_zeros(), 0), "Cold storage");
+
+        for version in [ProtocolVersion::V1, ProtocolVersion::V2] {
+            let event: Event = super::label(version, &shared_key, policy_id, &label, &[]).unwrap();
+            assert_eq!(ProtocolVersion::from_tags(&event.tags).unwrap(), version);
+            assert_eq!(decrypt::<Label>(&shared_key, &event).unwrap(), label);
+            assert_eq!(event.event_ids().next(), Some(&policy_id));
+        }
+    }
+}
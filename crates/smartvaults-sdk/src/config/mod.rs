@@ -8,11 +8,13 @@ use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::sync::Arc;
+use std::time::Duration;
 
 use nostr_sdk::Url;
 use serde::{Deserialize, Serialize};
 use smartvaults_core::bitcoin::Network;
 use smartvaults_core::util;
+use smartvaults_protocol::v2::ProtocolVersion;
 use thiserror::Error;
 use tokio::sync::RwLock;
 
@@ -187,9 +189,22 @@ struct BitcoinFile {
     block_explorer: Option<Url>,
 }
 
+#[derive(Default, Serialize, Deserialize)]
+struct NostrFile {
+    #[serde(default)]
+    gift_wrap: bool,
+    #[serde(default)]
+    protocol_version: ProtocolVersion,
+    /// Seconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    proposal_expiration: Option<u64>,
+}
+
 #[derive(Serialize, Deserialize)]
 struct ConfigFile {
     bitcoin: BitcoinFile,
+    #[serde(default)]
+    nostr: NostrFile,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -199,10 +214,21 @@ pub struct Bitcoin {
     pub block_explorer: Arc<RwLock<Option<Url>>>,
 }
 
+#[derive(Debug, Clone, Default)]
+pub struct Nostr {
+    /// Seal and gift wrap (NIP-59) vault events
+    pub gift_wrap: Arc<RwLock<bool>>,
+    /// Protocol version of the published vault events
+    pub protocol_version: Arc<RwLock<ProtocolVersion>>,
+    /// Expiration of the new proposals (never if `None`)
+    pub proposal_expiration: Arc<RwLock<Option<Duration>>>,
+}
+
 #[derive(Debug, Clone)]
 pub struct Config {
     pub config_file_path: PathBuf,
     pub bitcoin: Bitcoin,
+    pub nostr: Nostr,
 }
 
 impl Config {
@@ -232,6 +258,18 @@ impl Config {
                                 config_file.bitcoin.block_explorer,
                             )),
                         },
+                        nostr: Nostr {
+                            gift_wrap: Arc::new(RwLock::new(config_file.nostr.gift_wrap)),
+                            protocol_version: Arc::new(RwLock::new(
+                                config_file.nostr.protocol_version,
+                            )),
+                            proposal_expiration: Arc::new(RwLock::new(
+                                config_file
+                                    .nostr
+                                    .proposal_expiration
+                                    .map(Duration::from_secs),
+                            )),
+                        },
                     })
                 }
                 Err(e) => tracing::error!("Impossible to deserialize config file: {e}"),
@@ -280,6 +318,7 @@ impl Config {
                 block_explorer: Arc::new(RwLock::new(block_explorer)),
                 ..Default::default()
             },
+            nostr: Nostr::default(),
         })
     }
 
@@ -290,6 +329,16 @@ impl Config {
                 proxy: *self.bitcoin.proxy.read().await,
                 block_explorer: (*self.bitcoin.block_explorer.read().await).clone(),
             },
+            nostr: NostrFile {
+                gift_wrap: *self.nostr.gift_wrap.read().await,
+                protocol_version: *self.nostr.protocol_version.read().await,
+                proposal_expiration: self
+                    .nostr
+                    .proposal_expiration
+                    .read()
+                    .await
+                    .map(|d| d.as_secs()),
+            },
         }
     }
 
@@ -346,6 +395,46 @@ impl Config {
         block_explorer.clone().ok_or(Error::BlockExplorerNotSet)
     }
 
+    /// Enable or disable the gift wrap (NIP-59) of vault events
+    ///
+    /// Members using a client without gift wrap support will not receive the wrapped events.
+    pub async fn set_gift_wrap(&self, enabled: bool) {
+        let mut e = self.nostr.gift_wrap.write().await;
+        *e = enabled;
+    }
+
+    pub async fn gift_wrap(&self) -> bool {
+        *self.nostr.gift_wrap.read().await
+    }
+
+    /// Set the protocol version used to publish vault events
+    ///
+    /// Events of every version are always read. Keep [`ProtocolVersion::V1`] until all the
+    /// members of the vaults have upgraded, since older clients can't read v2 events.
+    ///
+    /// Both versions are encrypted with NIP-44: clients that only support the legacy encryption
+    /// can't read them.
+    pub async fn set_protocol_version(&self, version: ProtocolVersion) {
+        let mut e = self.nostr.protocol_version.write().await;
+        *e = version;
+    }
+
+    pub async fn protocol_version(&self) -> ProtocolVersion {
+        *self.nostr.protocol_version.read().await
+    }
+
+    /// Set after how long the new proposals expire (`None` to never expire them)
+    ///
+    /// Expired proposals are deleted and their UTXOs unfrozen.
+    pub async fn set_proposal_expiration(&self, expiration: Option<Duration>) {
+        let mut e = self.nostr.proposal_expiration.write().await;
+        *e = expiration;
+    }
+
+    pub async fn proposal_expiration(&self) -> Option<Duration> {
+        *self.nostr.proposal_expiration.read().await
+    }
+
     pub async fn as_pretty_json(&self) -> Result<String, Error> {
         let config_file: ConfigFile = self.to_config_file().await;
         Ok(nostr_sdk::serde_json::to_string_pretty(&config_file)?)
@@ -425,4 +514,13 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn test_config_file_without_nostr() {
+        let json = r#"{"bitcoin":{"electrum_server":"127.0.0.1:50001:t","proxy":null,"block_explorer":null}}"#;
+        let config_file: ConfigFile = nostr_sdk::serde_json::from_str(json).unwrap();
+        assert!(!config_file.nostr.gift_wrap);
+        assert_eq!(config_file.nostr.protocol_version, ProtocolVersion::V1);
+        assert!(config_file.nostr.proposal_expiration.is_none());
+    }
 }
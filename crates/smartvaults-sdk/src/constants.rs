@@ -19,6 +19,9 @@ pub const MEMPOOL_TX_FEES_SYNC_INTERVAL: Duration = Duration::from_secs(60);
 pub const WALLET_SYNC_INTERVAL: Duration = Duration::from_secs(60);
 pub const METADATA_SYNC_INTERVAL: Duration = Duration::from_secs(3600);
 
+// UTXO refresh
+pub const UTXO_REFRESH_THRESHOLD_BLOCKS: u32 = 144 * 7;
+
 // Timeout
 pub(crate) const SEND_TIMEOUT: Duration = Duration::from_secs(20);
 
@@ -2,9 +2,12 @@
 // Distributed under the MIT software license
 
 use nostr_sdk::database::DatabaseError;
-use nostr_sdk::SQLiteError;
+use nostr_sdk::{SQLiteError, Timestamp};
 use smartvaults_protocol::v1::util::EncryptionError;
-use smartvaults_protocol::v1::SmartVaultsEventBuilderError;
+use smartvaults_protocol::v1::{GiftWrapError, SmartVaultsEventBuilderError};
+use smartvaults_protocol::v2::{
+    EnvelopeError, SmartVaultsEventBuilderError as SmartVaultsEventBuilderV2Error,
+};
 use thiserror::Error;
 
 use crate::manager::{Error as ManagerError, WalletError};
@@ -45,18 +48,36 @@ pub enum Error {
     #[error(transparent)]
     SmartVaultsEventBuilder(#[from] SmartVaultsEventBuilderError),
     #[error(transparent)]
+    SmartVaultsEventBuilderV2(#[from] SmartVaultsEventBuilderV2Error),
+    #[error(transparent)]
+    Envelope(#[from] EnvelopeError),
+    #[error(transparent)]
     Relay(#[from] nostr_sdk::pool::relay::Error),
     #[error(transparent)]
+    Bip21(#[from] smartvaults_core::bip21::Error),
+    #[error(transparent)]
     Policy(#[from] smartvaults_core::policy::Error),
     #[error(transparent)]
     Proposal(#[from] smartvaults_core::proposal::Error),
     #[error(transparent)]
+    SpendingRules(#[from] smartvaults_core::spending_rules::Error),
+    #[error(transparent)]
+    Psbt(#[from] smartvaults_core::bitcoin::psbt::Error),
+    #[error(transparent)]
+    PsbtParse(#[from] smartvaults_core::bitcoin::psbt::PsbtParseError),
+    #[error(transparent)]
+    Ur(#[from] smartvaults_core::ur::Error),
+    #[error(transparent)]
+    Proof(#[from] smartvaults_core::reserves::ProofError),
+    #[error(transparent)]
     Secp256k1(#[from] smartvaults_core::bitcoin::secp256k1::Error),
     #[error(transparent)]
     Address(#[from] smartvaults_core::bitcoin::address::Error),
     #[error(transparent)]
     Encryption(#[from] EncryptionError),
     #[error(transparent)]
+    GiftWrap(#[from] GiftWrapError),
+    #[error(transparent)]
     NIP04(#[from] nostr_sdk::nips::nip04::Error),
     #[error(transparent)]
     NIP06(#[from] nostr_sdk::nips::nip06::Error),
@@ -108,8 +129,16 @@ pub enum Error {
     CantGenerateNostrConnectResponse,
     #[error("invalid fee rate")]
     InvalidFeeRate,
+    #[error("no UTXOs to refresh")]
+    NoUtxosToRefresh,
     #[error("impossible to delete a not owned event")]
     TryingToDeleteNotOwnedEvent,
+    #[error("event encrypted with a superseded shared key")]
+    SupersededSharedKey,
+    #[error("spending rules changed after the proposal (at {0}): review them and override the spending rules to approve")]
+    SpendingRulesChanged(Timestamp),
+    #[error("impossible to sign the proposal: {}", .0.join(", "))]
+    ApprovalFailed(Vec<String>),
     #[error("not found")]
     NotFound,
     #[error("{0}")]
@@ -9,6 +9,7 @@
 pub use {nostr_sdk as nostr, smartvaults_core as core, smartvaults_protocol as protocol};
 
 pub mod client;
+mod compat;
 pub mod config;
 pub mod constants;
 mod error;
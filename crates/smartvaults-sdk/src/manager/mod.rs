@@ -21,7 +21,7 @@ use smartvaults_core::bdk::wallet::{AddressIndex, AddressInfo, Balance, NewOrLoa
 use smartvaults_core::bdk::{FeeRate, LocalOutput, Wallet};
 use smartvaults_core::bitcoin::address::NetworkUnchecked;
 use smartvaults_core::bitcoin::psbt::PartiallySignedTransaction;
-use smartvaults_core::bitcoin::{Address, Network, OutPoint, ScriptBuf, Transaction, Txid};
+use smartvaults_core::bitcoin::{Address, Network, OutPoint, ScriptBuf, Transaction, TxOut, Txid};
 use smartvaults_core::{Amount, Policy, Priority, Proposal};
 use smartvaults_sdk_sqlite::Store;
 use thiserror::Error;
@@ -417,8 +417,7 @@ impl Manager {
     pub async fn spend<S>(
         &self,
         policy_id: EventId,
-        address: Address<NetworkUnchecked>,
-        amount: Amount,
+        recipients: Vec<(Address<NetworkUnchecked>, Amount)>,
         description: S,
         fee_rate: FeeRate,
         utxos: Option<Vec<OutPoint>>,
@@ -432,8 +431,7 @@ impl Manager {
             .wallet(policy_id)
             .await?
             .spend(
-                address,
-                amount,
+                recipients,
                 description,
                 fee_rate,
                 utxos,
@@ -443,6 +441,43 @@ impl Manager {
             .await?)
     }
 
+    pub async fn bump_fee<S>(
+        &self,
+        policy_id: EventId,
+        txid: Txid,
+        description: S,
+        fee_rate: FeeRate,
+        policy_path: Option<BTreeMap<String, Vec<usize>>>,
+    ) -> Result<Proposal, Error>
+    where
+        S: Into<String>,
+    {
+        Ok(self
+            .wallet(policy_id)
+            .await?
+            .bump_fee(txid, description, fee_rate, policy_path)
+            .await?)
+    }
+
+    pub async fn cpfp<S>(
+        &self,
+        policy_id: EventId,
+        parent_txid: Txid,
+        address: Address<NetworkUnchecked>,
+        description: S,
+        fee_rate: FeeRate,
+        policy_path: Option<BTreeMap<String, Vec<usize>>>,
+    ) -> Result<Proposal, Error>
+    where
+        S: Into<String>,
+    {
+        Ok(self
+            .wallet(policy_id)
+            .await?
+            .cpfp(parent_txid, address, description, fee_rate, policy_path)
+            .await?)
+    }
+
     pub async fn proof_of_reserve<S>(
         &self,
         policy_id: EventId,
@@ -473,4 +508,29 @@ impl Manager {
             .verify_proof(psbt, message)
             .await?)
     }
+
+    pub async fn proof_outputs(
+        &self,
+        policy_id: EventId,
+        height: Option<u32>,
+    ) -> Result<Vec<(OutPoint, TxOut)>, Error> {
+        Ok(self.wallet(policy_id).await?.proof_outputs(height).await)
+    }
+
+    pub async fn verify_proof_at_height<S>(
+        &self,
+        policy_id: EventId,
+        psbt: &PartiallySignedTransaction,
+        message: S,
+        height: u32,
+    ) -> Result<u64, Error>
+    where
+        S: Into<String>,
+    {
+        Ok(self
+            .wallet(policy_id)
+            .await?
+            .verify_proof_at_height(psbt, message, height)
+            .await?)
+    }
 }
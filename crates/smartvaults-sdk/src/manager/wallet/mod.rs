@@ -23,7 +23,7 @@ use smartvaults_core::bdk::wallet::{AddressIndex, AddressInfo, Balance, Update};
 use smartvaults_core::bdk::{FeeRate, KeychainKind, LocalOutput, Wallet};
 use smartvaults_core::bitcoin::address::NetworkUnchecked;
 use smartvaults_core::bitcoin::psbt::PartiallySignedTransaction;
-use smartvaults_core::bitcoin::{Address, OutPoint, Script, ScriptBuf, Transaction, Txid};
+use smartvaults_core::bitcoin::{Address, OutPoint, Script, ScriptBuf, Transaction, TxOut, Txid};
 use smartvaults_core::reserves::ProofOfReserves;
 use smartvaults_core::{Amount, Policy, Proposal};
 use thiserror::Error;
@@ -64,6 +64,8 @@ pub enum Error {
     AlreadySyncing,
     #[error("impossible to insert tx: {0}")]
     InsertTx(String),
+    #[error("impossible to calculate fee: {0}")]
+    CalculateFee(String),
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -530,8 +532,7 @@ impl SmartVaultsWallet {
 
     pub async fn spend<S>(
         &self,
-        address: Address<NetworkUnchecked>,
-        amount: Amount,
+        recipients: Vec<(Address<NetworkUnchecked>, Amount)>,
         description: S,
         fee_rate: FeeRate,
         utxos: Option<Vec<OutPoint>>,
@@ -544,8 +545,7 @@ impl SmartVaultsWallet {
         let mut wallet = self.wallet.write().await;
         let proposal = self.policy.spend(
             &mut wallet,
-            address,
-            amount,
+            recipients,
             description,
             fee_rate,
             utxos,
@@ -555,6 +555,61 @@ impl SmartVaultsWallet {
         Ok(proposal)
     }
 
+    pub async fn bump_fee<S>(
+        &self,
+        txid: Txid,
+        description: S,
+        fee_rate: FeeRate,
+        policy_path: Option<BTreeMap<String, Vec<usize>>>,
+    ) -> Result<Proposal, Error>
+    where
+        S: Into<String>,
+    {
+        let mut wallet = self.wallet.write().await;
+        let proposal =
+            self.policy
+                .bump_fee(&mut wallet, txid, description, fee_rate, policy_path)?;
+        Ok(proposal)
+    }
+
+    /// Build a child-pays-for-parent (CPFP) proposal
+    ///
+    /// Fee and vsize of the parent are taken from the [`TxGraph`].
+    pub async fn cpfp<S>(
+        &self,
+        parent_txid: Txid,
+        address: Address<NetworkUnchecked>,
+        description: S,
+        fee_rate: FeeRate,
+        policy_path: Option<BTreeMap<String, Vec<usize>>>,
+    ) -> Result<Proposal, Error>
+    where
+        S: Into<String>,
+    {
+        let mut wallet = self.wallet.write().await;
+
+        let (parent_fee, parent_vsize): (u64, usize) = {
+            let graph: &TxGraph<ConfirmationTimeHeightAnchor> = wallet.tx_graph();
+            let tx = graph.get_tx(parent_txid).ok_or(Error::NotFound)?;
+            let fee: u64 = graph
+                .calculate_fee(&tx)
+                .map_err(|e| Error::CalculateFee(format!("{e:?}")))?;
+            (fee, tx.vsize())
+        };
+
+        let proposal = self.policy.cpfp(
+            &mut wallet,
+            parent_txid,
+            parent_fee,
+            parent_vsize,
+            address,
+            description,
+            fee_rate,
+            policy_path,
+        )?;
+        Ok(proposal)
+    }
+
     pub async fn proof_of_reserve<S>(&self, message: S) -> Result<Proposal, Error>
     where
         S: Into<String>,
@@ -574,4 +629,34 @@ impl SmartVaultsWallet {
     {
         Ok(self.wallet.read().await.verify_proof(psbt, message, None)?)
     }
+
+    /// Get the outputs to verify a proof of reserve against
+    ///
+    /// If `height` is passed, the outputs unspent at that block height are returned, otherwise the current UTXOs.
+    pub async fn proof_outputs(&self, height: Option<u32>) -> Vec<(OutPoint, TxOut)> {
+        let wallet = self.wallet.read().await;
+        match height {
+            Some(height) => wallet.historical_outputs(height),
+            None => wallet
+                .list_unspent()
+                .map(|utxo| (utxo.outpoint, utxo.txout))
+                .collect(),
+        }
+    }
+
+    pub async fn verify_proof_at_height<S>(
+        &self,
+        psbt: &PartiallySignedTransaction,
+        message: S,
+        height: u32,
+    ) -> Result<u64, Error>
+    where
+        S: Into<String>,
+    {
+        Ok(self
+            .wallet
+            .read()
+            .await
+            .verify_proof_at_height(psbt, message, height)?)
+    }
 }
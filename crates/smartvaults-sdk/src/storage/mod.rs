@@ -15,18 +15,25 @@ use smartvaults_core::{
 };
 use smartvaults_protocol::v1::constants::{
     APPROVED_PROPOSAL_KIND, COMPLETED_PROPOSAL_KIND, KEY_AGENT_VERIFIED, LABELS_KIND, POLICY_KIND,
-    PROPOSAL_KIND, SHARED_KEY_KIND, SHARED_SIGNERS_KIND, SIGNERS_KIND,
-    SMARTVAULTS_MAINNET_PUBLIC_KEY, SMARTVAULTS_TESTNET_PUBLIC_KEY,
+    PROPOSAL_COMMENT_KIND, PROPOSAL_KIND, PROPOSAL_REJECTION_KIND, SHARED_KEY_KIND,
+    SHARED_SIGNERS_KIND, SIGNERS_KIND, SMARTVAULTS_MAINNET_PUBLIC_KEY,
+    SMARTVAULTS_TESTNET_PUBLIC_KEY, VAULT_METADATA_KIND,
 };
-use smartvaults_protocol::v1::{Encryption, Label, LabelData, LabelKind, Serde, VerifiedKeyAgents};
+use smartvaults_protocol::v1::{
+    Encryption, EncryptionScheme, Label, LabelData, LabelKind, ProposalComment, ProposalRejection,
+    VaultMetadata, VerifiedKeyAgents,
+};
+use smartvaults_protocol::v2::Versioned;
 use tokio::sync::RwLock;
 
 mod model;
 
 pub(crate) use self::model::{
     InternalApproval, InternalCompletedProposal, InternalLabel, InternalPolicy, InternalProposal,
-    InternalSharedSigner,
+    InternalProposalComment, InternalProposalRejection, InternalSharedSigner,
+    InternalVaultMetadata, SharedKeys,
 };
+use crate::compat;
 use crate::types::GetApprovedProposals;
 use crate::{Error, EventHandled};
 
@@ -51,15 +58,41 @@ impl Ord for WrappedEvent {
     }
 }
 
+/// Decrypt the event with the shared key that signed it or, if not signed by a shared key
+/// (i.e. approvals), with the first one that works.
+///
+/// Only the shared keys not superseded at the event timestamp are used: events signed with a
+/// superseded key are rejected.
+///
+/// Return `None` if none of the shared keys can decrypt it (i.e. key not received yet).
+fn decrypt_with_shared_keys<T>(shared_keys: &SharedKeys, event: &Event) -> Result<Option<T>, Error>
+where
+    T: Encryption + Versioned,
+{
+    if shared_keys.contains(event.author_ref()) {
+        let shared_key: &Keys = shared_keys
+            .by_public_key(event.author_ref(), event.created_at)
+            .ok_or(Error::SupersededSharedKey)?;
+        return Ok(Some(compat::decrypt(shared_key, event)?));
+    }
+
+    Ok(shared_keys
+        .valid_at(event.created_at)
+        .find_map(|shared_key| compat::decrypt(shared_key, event).ok()))
+}
+
 /// Smart Vaults In-Memory Storage
 #[derive(Debug, Clone)]
 pub(crate) struct SmartVaultsStorage {
     keys: Keys,
     database: Arc<DynNostrDatabase>,
-    shared_keys: Arc<RwLock<HashMap<EventId, Keys>>>,
+    shared_keys: Arc<RwLock<HashMap<EventId, SharedKeys>>>,
     vaults: Arc<RwLock<HashMap<EventId, InternalPolicy>>>,
+    vaults_metadata: Arc<RwLock<HashMap<EventId, InternalVaultMetadata>>>,
     proposals: Arc<RwLock<HashMap<EventId, InternalProposal>>>,
     approvals: Arc<RwLock<HashMap<EventId, InternalApproval>>>,
+    comments: Arc<RwLock<HashMap<EventId, InternalProposalComment>>>,
+    rejections: Arc<RwLock<HashMap<EventId, InternalProposalRejection>>>,
     completed_proposals: Arc<RwLock<HashMap<EventId, InternalCompletedProposal>>>,
     signers: Arc<RwLock<HashMap<EventId, Signer>>>,
     my_shared_signers: Arc<RwLock<HashMap<EventId, (EventId, PublicKey)>>>, /* Signer ID, Shared Signer ID, pubkey */
@@ -83,8 +116,11 @@ impl SmartVaultsStorage {
             database,
             shared_keys: Arc::new(RwLock::new(HashMap::new())),
             vaults: Arc::new(RwLock::new(HashMap::new())),
+            vaults_metadata: Arc::new(RwLock::new(HashMap::new())),
             proposals: Arc::new(RwLock::new(HashMap::new())),
             approvals: Arc::new(RwLock::new(HashMap::new())),
+            comments: Arc::new(RwLock::new(HashMap::new())),
+            rejections: Arc::new(RwLock::new(HashMap::new())),
             completed_proposals: Arc::new(RwLock::new(HashMap::new())),
             signers: Arc::new(RwLock::new(HashMap::new())),
             my_shared_signers: Arc::new(RwLock::new(HashMap::new())),
@@ -104,6 +140,9 @@ impl SmartVaultsStorage {
             SIGNERS_KIND,
             SHARED_SIGNERS_KIND,
             LABELS_KIND,
+            VAULT_METADATA_KIND,
+            PROPOSAL_COMMENT_KIND,
+            PROPOSAL_REJECTION_KIND,
         ]);
         let pubkey_filter: Filter = Filter::new().pubkey(this.keys.public_key()).kinds([
             SHARED_KEY_KIND,
@@ -114,6 +153,9 @@ impl SmartVaultsStorage {
             SIGNERS_KIND,
             SHARED_SIGNERS_KIND,
             LABELS_KIND,
+            VAULT_METADATA_KIND,
+            PROPOSAL_COMMENT_KIND,
+            PROPOSAL_REJECTION_KIND,
         ]);
         let smartvaults: Filter = Filter::new()
             .author(match network {
@@ -166,21 +208,108 @@ impl SmartVaultsStorage {
                 .next()
                 .copied()
                 .ok_or(Error::PolicyNotFound)?;
+
+            // Events without the encryption tag use the legacy scheme
+            let scheme = EncryptionScheme::from_tags(&event.tags)?;
+            let content =
+                scheme.decrypt(self.keys.secret_key()?, event.author_ref(), &event.content)?;
+            let sk = SecretKey::from_str(&content)?;
+            let shared_key = Keys::new(sk);
+
+            // Accept shared keys only from the members of the vault
+            let vault_members: Option<Vec<PublicKey>> = self
+                .vaults
+                .read()
+                .await
+                .get(&policy_id)
+                .map(|internal| internal.public_keys.clone());
+            let members: Option<Vec<PublicKey>> = match vault_members {
+                Some(public_keys) => Some(public_keys),
+                // Vault not known yet: use the members of the policy signed with this shared key
+                None => match self.database.event_by_id(policy_id).await {
+                    // First shared key
+                    Ok(policy)
+                        if policy.kind == POLICY_KIND
+                            && policy.author() == shared_key.public_key() =>
+                    {
+                        Some(policy.public_keys().copied().collect())
+                    }
+                    // Rotated shared key, received by the members added by the rotation
+                    _ => self.rotated_policy_members(policy_id, &shared_key).await?,
+                },
+            };
+            match members {
+                Some(members) => {
+                    if !members.contains(event.author_ref()) {
+                        tracing::warn!(
+                            "Shared key {} not published by a member of vault {policy_id}",
+                            event.id
+                        );
+                        return Ok(None);
+                    }
+                }
+                None => {
+                    // Wait for the policy
+                    pending.insert(event.clone());
+                    return Ok(None);
+                }
+            }
+
             let mut shared_keys = self.shared_keys.write().await;
-            if let HashMapEntry::Vacant(e) = shared_keys.entry(policy_id) {
-                let content =
-                    nip04::decrypt(self.keys.secret_key()?, event.author_ref(), &event.content)?;
-                let sk = SecretKey::from_str(&content)?;
-                let shared_key = Keys::new(sk);
-                e.insert(shared_key);
+            if shared_keys
+                .entry(policy_id)
+                .or_default()
+                .insert(event.created_at, shared_key)
+            {
                 return Ok(Some(EventHandled::SharedKey(event.id)));
             }
         } else if event.kind == POLICY_KIND {
             let shared_keys = self.shared_keys.read().await;
             let mut vaults = self.vaults.write().await;
-            if let HashMapEntry::Vacant(e) = vaults.entry(event.id) {
-                if let Some(shared_key) = shared_keys.get(&event.id) {
-                    let policy = Policy::decrypt_with_keys(shared_key, &event.content)?;
+            // Policy re-published after a shared key rotation
+            if let Some(vault_id) = event.event_ids().next().copied() {
+                match shared_keys
+                    .get(&vault_id)
+                    .filter(|k| k.contains(event.author_ref()))
+                    .and_then(|k| k.current())
+                {
+                    // Only the policy signed with the latest shared key updates the members
+                    Some(current) if current.public_key() == event.author() => {
+                        let public_keys: Vec<PublicKey> = event.public_keys().copied().collect();
+                        if public_keys.is_empty() {
+                            tracing::error!("Policy {} not contains any nostr pubkey", event.id);
+                            return Ok(None);
+                        }
+                        match vaults.entry(vault_id) {
+                            HashMapEntry::Occupied(mut e) => {
+                                let vault: &mut InternalPolicy = e.get_mut();
+                                if vault.public_keys != public_keys {
+                                    vault.public_keys = public_keys;
+                                    return Ok(Some(EventHandled::PolicyUpdated(vault_id)));
+                                }
+                            }
+                            // Member added by the rotation: the vault is known only from this policy
+                            HashMapEntry::Vacant(e) => {
+                                let policy: Policy = compat::decrypt(current, event)?;
+                                e.insert(InternalPolicy {
+                                    policy,
+                                    public_keys,
+                                });
+                                return Ok(Some(EventHandled::Policy(vault_id)));
+                            }
+                        }
+                    }
+                    Some(..) => (),
+                    None => {
+                        pending.insert(event.clone());
+                    }
+                }
+            } else if let HashMapEntry::Vacant(e) = vaults.entry(event.id) {
+                if let Some(shared_key) = shared_keys
+                    .get(&event.id)
+                    .and_then(|k| k.by_public_key(event.author_ref(), event.created_at))
+                {
+                    let policy: Policy = compat::decrypt(shared_key, event)?;
                     let mut nostr_pubkeys: Vec<PublicKey> = Vec::new();
                     for tag in event.tags.iter() {
                         if let Tag::PublicKey { public_key, .. } = tag {
@@ -201,31 +330,33 @@ impl SmartVaultsStorage {
                 }
             }
         } else if event.kind == PROPOSAL_KIND {
+            // Expired proposals are dead: don't froze their UTXOs
+            if event.is_expired() {
+                return Ok(None);
+            }
+
             let shared_keys = self.shared_keys.read().await;
             let mut proposals = self.proposals.write().await;
             if let HashMapEntry::Vacant(e) = proposals.entry(event.id) {
                 if let Some(policy_id) = event.event_ids().next() {
-                    if let Some(shared_key) = shared_keys.get(policy_id) {
-                        // Decrypt proposal
-                        let proposal: Proposal =
-                            Proposal::decrypt_with_keys(shared_key, &event.content)?;
-
+                    if let Some(proposal) = shared_keys
+                        .get(policy_id)
+                        .map(|k| decrypt_with_shared_keys::<Proposal>(k, event))
+                        .transpose()?
+                        .flatten()
+                    {
                         // Froze UTXOs
-                        let psbt = proposal.psbt();
-                        self.freeze_utxos(
-                            *policy_id,
-                            psbt.unsigned_tx
-                                .input
-                                .iter()
-                                .map(|txin| txin.previous_output),
-                        )
-                        .await;
+                        self.freeze_proposal_utxos(*policy_id, &proposal).await;
 
                         // Insert proposal
                         e.insert(InternalProposal {
                             policy_id: *policy_id,
                             proposal,
                             timestamp: event.created_at,
+                            expiration: event.tags.iter().find_map(|t| match t {
+                                Tag::Expiration(timestamp) => Some(*timestamp),
+                                _ => None,
+                            }),
                         });
 
                         return Ok(Some(EventHandled::Proposal(event.id)));
@@ -243,9 +374,12 @@ impl SmartVaultsStorage {
                 let mut ids = event.event_ids();
                 if let Some(proposal_id) = ids.next().copied() {
                     if let Some(policy_id) = ids.next() {
-                        if let Some(shared_key) = shared_keys.get(policy_id) {
-                            let approved_proposal =
-                                ApprovedProposal::decrypt_with_keys(shared_key, &event.content)?;
+                        if let Some(approved_proposal) = shared_keys
+                            .get(policy_id)
+                            .map(|k| decrypt_with_shared_keys::<ApprovedProposal>(k, event))
+                            .transpose()?
+                            .flatten()
+                        {
                             e.insert(InternalApproval {
                                 proposal_id,
                                 policy_id: *policy_id,
@@ -267,17 +401,86 @@ impl SmartVaultsStorage {
                     );
                 }
             }
-        } else if event.kind == COMPLETED_PROPOSAL_KIND {
+        } else if event.kind == PROPOSAL_COMMENT_KIND {
             let shared_keys = self.shared_keys.read().await;
-            let mut completed_proposals = self.completed_proposals.write().await;
-            if let HashMapEntry::Vacant(e) = completed_proposals.entry(event.id) {
+            let mut comments = self.comments.write().await;
+            if let HashMapEntry::Vacant(e) = comments.entry(event.id) {
                 let mut ids = event.event_ids();
-                if let Some(proposal_id) = ids.next() {
-                    self.delete_proposal(proposal_id).await;
+                if let (Some(proposal_id), Some(policy_id)) =
+                    (ids.next().copied(), ids.next().copied())
+                {
+                    if let Some(comment) = shared_keys
+                        .get(&policy_id)
+                        .map(|k| decrypt_with_shared_keys::<ProposalComment>(k, event))
+                        .transpose()?
+                        .flatten()
+                    {
+                        e.insert(InternalProposalComment {
+                            proposal_id,
+                            policy_id,
+                            public_key: event.author(),
+                            comment,
+                            timestamp: event.created_at,
+                        });
+                        return Ok(Some(EventHandled::ProposalComment { proposal_id }));
+                    } else {
+                        pending.insert(event.clone());
+                    }
+                } else {
+                    tracing::error!(
+                        "Impossible to find proposal or policy id in comment {}",
+                        event.id
+                    );
+                }
+            }
+        } else if event.kind == PROPOSAL_REJECTION_KIND {
+            let shared_keys = self.shared_keys.read().await;
+            let mut rejections = self.rejections.write().await;
+            if let HashMapEntry::Vacant(e) = rejections.entry(event.id) {
+                let mut ids = event.event_ids();
+                if let (Some(proposal_id), Some(policy_id)) =
+                    (ids.next().copied(), ids.next().copied())
+                {
+                    if let Some(rejection) = shared_keys
+                        .get(&policy_id)
+                        .map(|k| decrypt_with_shared_keys::<ProposalRejection>(k, event))
+                        .transpose()?
+                        .flatten()
+                    {
+                        e.insert(InternalProposalRejection {
+                            proposal_id,
+                            policy_id,
+                            public_key: event.author(),
+                            rejection,
+                            timestamp: event.created_at,
+                        });
+                        return Ok(Some(EventHandled::ProposalRejection { proposal_id }));
+                    } else {
+                        pending.insert(event.clone());
+                    }
+                } else {
+                    tracing::error!(
+                        "Impossible to find proposal or policy id in rejection {}",
+                        event.id
+                    );
+                }
+            }
+        } else if event.kind == COMPLETED_PROPOSAL_KIND {
+            let mut ids = event.event_ids();
+            if let Some(proposal_id) = ids.next() {
+                // Delete before locking the completed proposals, needed to unfreeze the UTXOs of fee bumps
+                self.delete_proposal(proposal_id).await;
+
+                let shared_keys = self.shared_keys.read().await;
+                let mut completed_proposals = self.completed_proposals.write().await;
+                if let HashMapEntry::Vacant(e) = completed_proposals.entry(event.id) {
                     if let Some(policy_id) = ids.next() {
-                        if let Some(shared_key) = shared_keys.get(policy_id) {
-                            let completed_proposal =
-                                CompletedProposal::decrypt_with_keys(shared_key, &event.content)?;
+                        if let Some(completed_proposal) = shared_keys
+                            .get(policy_id)
+                            .map(|k| decrypt_with_shared_keys::<CompletedProposal>(k, event))
+                            .transpose()?
+                            .flatten()
+                        {
                             e.insert(InternalCompletedProposal {
                                 policy_id: *policy_id,
                                 proposal: completed_proposal,
@@ -298,7 +501,7 @@ impl SmartVaultsStorage {
         } else if event.kind == SIGNERS_KIND {
             let mut signers = self.signers.write().await;
             if let HashMapEntry::Vacant(e) = signers.entry(event.id) {
-                let signer = Signer::decrypt_with_keys(&self.keys, &event.content)?;
+                let signer: Signer = compat::decrypt(&self.keys, event)?;
                 e.insert(signer);
                 return Ok(Some(EventHandled::Signer(event.id)));
             }
@@ -319,12 +522,8 @@ impl SmartVaultsStorage {
             } else {
                 let mut shared_signers = self.shared_signers.write().await;
                 if let HashMapEntry::Vacant(e) = shared_signers.entry(event.id) {
-                    let shared_signer: String = nip04::decrypt(
-                        self.keys.secret_key()?,
-                        event.author_ref(),
-                        &event.content,
-                    )?;
-                    let shared_signer: SharedSigner = SharedSigner::from_json(shared_signer)?;
+                    let shared_signer: SharedSigner =
+                        compat::decrypt_shared_signer(&self.keys, event)?;
                     e.insert(InternalSharedSigner {
                         owner_public_key: event.author(),
                         shared_signer,
@@ -337,8 +536,12 @@ impl SmartVaultsStorage {
             let shared_keys = self.shared_keys.read().await;
             if let Some(policy_id) = event.event_ids().next() {
                 if let Some(identifier) = event.identifier() {
-                    if let Some(shared_key) = shared_keys.get(policy_id) {
-                        let label = Label::decrypt_with_keys(shared_key, &event.content)?;
+                    if let Some(label) = shared_keys
+                        .get(policy_id)
+                        .map(|k| decrypt_with_shared_keys::<Label>(k, event))
+                        .transpose()?
+                        .flatten()
+                    {
                         labels.insert(
                             identifier.to_string(),
                             InternalLabel {
@@ -356,6 +559,42 @@ impl SmartVaultsStorage {
             } else {
                 tracing::error!("Impossible to find policy id in proposal {}", event.id);
             }
+        } else if event.kind == VAULT_METADATA_KIND {
+            let shared_keys = self.shared_keys.read().await;
+            let mut vaults_metadata = self.vaults_metadata.write().await;
+            if let Some(policy_id) = event.event_ids().next().copied() {
+                // Keep only the latest metadata
+                let is_newer: bool = vaults_metadata
+                    .get(&policy_id)
+                    .map_or(true, |m| event.created_at > m.timestamp);
+                if is_newer {
+                    // Must be signed with a shared key of the vault, not superseded
+                    let keys: Option<&SharedKeys> = shared_keys.get(&policy_id);
+                    match keys.and_then(|k| k.by_public_key(event.author_ref(), event.created_at)) {
+                        Some(shared_key) => {
+                            let metadata: VaultMetadata = compat::decrypt(shared_key, event)?;
+                            let internal = InternalVaultMetadata::replace(
+                                vaults_metadata.get(&policy_id),
+                                metadata,
+                                event.created_at,
+                            );
+                            vaults_metadata.insert(policy_id, internal);
+                            return Ok(Some(EventHandled::VaultMetadata(policy_id)));
+                        }
+                        None if keys.map_or(false, |k| k.contains(event.author_ref())) => {
+                            return Err(Error::SupersededSharedKey);
+                        }
+                        None => {
+                            pending.insert(event.clone());
+                        }
+                    }
+                }
+            } else {
+                tracing::error!(
+                    "Impossible to find policy id in vault metadata {}",
+                    event.id
+                );
+            }
         } else if event.kind == Kind::EventDeletion {
             for event_id in event.event_ids() {
                 if let Ok(true) = self.database.has_event_id_been_deleted(event_id).await {
@@ -394,6 +633,24 @@ impl SmartVaultsStorage {
         Ok(None)
     }
 
+    /// Members of the policy of `vault_id` re-published with the rotated `shared_key`
+    async fn rotated_policy_members(
+        &self,
+        vault_id: EventId,
+        shared_key: &Keys,
+    ) -> Result<Option<Vec<PublicKey>>, Error> {
+        let filter: Filter = Filter::new()
+            .kind(POLICY_KIND)
+            .author(shared_key.public_key())
+            .event(vault_id);
+        Ok(self
+            .database
+            .query(vec![filter], Order::Desc)
+            .await?
+            .first()
+            .map(|policy| policy.public_keys().copied().collect()))
+    }
+
     pub async fn pending_events(&self) -> BTreeSet<Event> {
         self.pending.read().await.clone()
     }
@@ -412,6 +669,14 @@ impl SmartVaultsStorage {
             return;
         }
 
+        if self.delete_comment(event_id).await {
+            return;
+        }
+
+        if self.delete_rejection(event_id).await {
+            return;
+        }
+
         if self.delete_completed_proposal(event_id).await {
             return;
         }
@@ -423,15 +688,50 @@ impl SmartVaultsStorage {
         self.delete_shared_signer(event_id).await;
     }
 
-    pub async fn save_shared_key(&self, policy_id: EventId, shared_key: Keys) {
+    pub async fn save_shared_key(
+        &self,
+        policy_id: EventId,
+        shared_key: Keys,
+        timestamp: Timestamp,
+    ) {
         let mut shared_keys = self.shared_keys.write().await;
-        shared_keys.insert(policy_id, shared_key);
+        shared_keys
+            .entry(policy_id)
+            .or_default()
+            .insert(timestamp, shared_key);
     }
 
-    /// Get shared key
+    /// Get current shared key
     pub async fn shared_key(&self, vault_id: &EventId) -> Result<Keys, Error> {
         let shared_keys = self.shared_keys.read().await;
-        shared_keys.get(vault_id).cloned().ok_or(Error::NotFound)
+        shared_keys
+            .get(vault_id)
+            .and_then(|k| k.current())
+            .cloned()
+            .ok_or(Error::NotFound)
+    }
+
+    /// Get shared key (current or previous) by its public key
+    pub async fn shared_key_by_public_key(
+        &self,
+        vault_id: &EventId,
+        public_key: &PublicKey,
+    ) -> Result<Keys, Error> {
+        let shared_keys = self.shared_keys.read().await;
+        shared_keys
+            .get(vault_id)
+            .and_then(|k| k.iter().find(|k| k.public_key() == *public_key))
+            .cloned()
+            .ok_or(Error::NotFound)
+    }
+
+    /// Get all the shared keys (current and previous) of the vault, from the latest to the oldest
+    pub async fn shared_keys(&self, vault_id: &EventId) -> Vec<Keys> {
+        let shared_keys = self.shared_keys.read().await;
+        shared_keys
+            .get(vault_id)
+            .map(|k| k.iter().cloned().collect())
+            .unwrap_or_default()
     }
 
     pub async fn save_vault(&self, policy_id: EventId, internal: InternalPolicy) {
@@ -441,9 +741,37 @@ impl SmartVaultsStorage {
 
     pub async fn delete_vault(&self, vault_id: &EventId) -> bool {
         let mut vaults = self.vaults.write().await;
+        let mut vaults_metadata = self.vaults_metadata.write().await;
+        vaults_metadata.remove(vault_id);
         vaults.remove(vault_id).is_some()
     }
 
+    pub async fn save_vault_metadata(
+        &self,
+        vault_id: EventId,
+        metadata: VaultMetadata,
+        timestamp: Timestamp,
+    ) {
+        let mut vaults_metadata = self.vaults_metadata.write().await;
+        let internal =
+            InternalVaultMetadata::replace(vaults_metadata.get(&vault_id), metadata, timestamp);
+        vaults_metadata.insert(vault_id, internal);
+    }
+
+    /// Get latest [`VaultMetadata`]
+    pub async fn vault_metadata(&self, vault_id: &EventId) -> Option<VaultMetadata> {
+        let vaults_metadata = self.vaults_metadata.read().await;
+        vaults_metadata.get(vault_id).map(|m| m.metadata.clone())
+    }
+
+    /// Get when the spending rules of the vault were last set, edited or removed
+    pub async fn spending_rules_timestamp(&self, vault_id: &EventId) -> Option<Timestamp> {
+        let vaults_metadata = self.vaults_metadata.read().await;
+        vaults_metadata
+            .get(vault_id)
+            .and_then(|m| m.spending_rules_timestamp)
+    }
+
     /// Get vaults
     pub async fn vaults(&self) -> HashMap<EventId, InternalPolicy> {
         self.vaults
@@ -460,6 +788,19 @@ impl SmartVaultsStorage {
         vaults.get(vault_id).cloned().ok_or(Error::NotFound)
     }
 
+    /// Get [`Vault`] ID by descriptor
+    pub async fn vault_id_by_descriptor(
+        &self,
+        descriptor: &Descriptor<String>,
+    ) -> Result<EventId, Error> {
+        let vaults = self.vaults.read().await;
+        vaults
+            .iter()
+            .find(|(_, internal)| internal.policy.as_descriptor() == descriptor)
+            .map(|(id, ..)| *id)
+            .ok_or(Error::NotFound)
+    }
+
     pub async fn save_proposal(&self, proposal_id: EventId, internal: InternalProposal) {
         let mut proposals = self.proposals.write().await;
         proposals.insert(proposal_id, internal);
@@ -471,16 +812,8 @@ impl SmartVaultsStorage {
         match proposals.remove(proposal_id) {
             Some(p) => {
                 // Unfreeze UTXOs
-                self.unfreeze_utxos(
-                    p.policy_id,
-                    p.proposal
-                        .psbt()
-                        .unsigned_tx
-                        .input
-                        .iter()
-                        .map(|txin| txin.previous_output),
-                )
-                .await;
+                let utxos: Vec<OutPoint> = self.proposal_utxos(&p.policy_id, &p.proposal).await;
+                self.unfreeze_utxos(p.policy_id, utxos).await;
 
                 true
             }
@@ -488,6 +821,25 @@ impl SmartVaultsStorage {
         }
     }
 
+    /// Delete the expired proposals, unfreezing their UTXOs
+    ///
+    /// Return the IDs of the deleted proposals
+    pub async fn delete_expired_proposals(&self) -> Vec<EventId> {
+        let now = Timestamp::now();
+        let expired: Vec<EventId> = self
+            .proposals
+            .read()
+            .await
+            .iter()
+            .filter(|(_, p)| matches!(p.expiration, Some(expiration) if expiration <= now))
+            .map(|(id, ..)| *id)
+            .collect();
+        for proposal_id in expired.iter() {
+            self.delete_proposal(proposal_id).await;
+        }
+        expired
+    }
+
     /// Get proposals
     pub async fn proposals(&self) -> HashMap<EventId, InternalProposal> {
         self.proposals
@@ -503,6 +855,30 @@ impl SmartVaultsStorage {
         proposals.get(proposal_id).cloned().ok_or(Error::NotFound)
     }
 
+    /// Get the other proposals of an aggregated proof of reserve
+    ///
+    /// Every participating vault has its own proposal, sharing the same PSBT.
+    pub async fn aggregated_proof_proposals(
+        &self,
+        proposal_id: &EventId,
+    ) -> Result<Vec<(EventId, InternalProposal)>, Error> {
+        let proposals = self.proposals.read().await;
+        let internal: &InternalProposal = proposals.get(proposal_id).ok_or(Error::NotFound)?;
+
+        if internal.proposal.other_descriptors().is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let txid: Txid = internal.proposal.psbt().unsigned_tx.txid();
+        Ok(proposals
+            .iter()
+            .filter(|(id, _)| *id != proposal_id)
+            .filter(|(_, i)| matches!(i.proposal, Proposal::ProofOfReserve { .. }))
+            .filter(|(_, i)| i.proposal.psbt().unsigned_tx.txid() == txid)
+            .map(|(id, i)| (*id, i.clone()))
+            .collect())
+    }
+
     pub async fn save_approval(&self, approval_id: EventId, internal: InternalApproval) {
         let mut approvals = self.approvals.write().await;
         approvals.insert(approval_id, internal);
@@ -528,6 +904,54 @@ impl SmartVaultsStorage {
         approvals.get(approval_id).cloned().ok_or(Error::NotFound)
     }
 
+    pub async fn save_comment(&self, comment_id: EventId, internal: InternalProposalComment) {
+        let mut comments = self.comments.write().await;
+        comments.insert(comment_id, internal);
+    }
+
+    pub async fn delete_comment(&self, comment_id: &EventId) -> bool {
+        let mut comments = self.comments.write().await;
+        comments.remove(comment_id).is_some()
+    }
+
+    /// Comments by proposal ID
+    pub async fn comments_by_proposal_id(
+        &self,
+        proposal_id: &EventId,
+    ) -> HashMap<EventId, InternalProposalComment> {
+        self.comments
+            .read()
+            .await
+            .iter()
+            .filter(|(_, c)| c.proposal_id == *proposal_id)
+            .map(|(id, internal)| (*id, internal.clone()))
+            .collect()
+    }
+
+    pub async fn save_rejection(&self, rejection_id: EventId, internal: InternalProposalRejection) {
+        let mut rejections = self.rejections.write().await;
+        rejections.insert(rejection_id, internal);
+    }
+
+    pub async fn delete_rejection(&self, rejection_id: &EventId) -> bool {
+        let mut rejections = self.rejections.write().await;
+        rejections.remove(rejection_id).is_some()
+    }
+
+    /// Rejections by proposal ID
+    pub async fn rejections_by_proposal_id(
+        &self,
+        proposal_id: &EventId,
+    ) -> HashMap<EventId, InternalProposalRejection> {
+        self.rejections
+            .read()
+            .await
+            .iter()
+            .filter(|(_, r)| r.proposal_id == *proposal_id)
+            .map(|(id, internal)| (*id, internal.clone()))
+            .collect()
+    }
+
     /// Approvals by proposal ID
     pub async fn approvals_by_proposal_id(
         &self,
@@ -595,6 +1019,9 @@ impl SmartVaultsStorage {
         {
             if let CompletedProposal::Spending {
                 tx, description, ..
+            }
+            | CompletedProposal::FeeBump {
+                tx, description, ..
             } = proposal
             {
                 if tx.txid() == txid {
@@ -614,6 +1041,9 @@ impl SmartVaultsStorage {
         {
             if let CompletedProposal::Spending {
                 tx, description, ..
+            }
+            | CompletedProposal::FeeBump {
+                tx, description, ..
             } = proposal
             {
                 if let HashMapEntry::Vacant(e) = map.entry(tx.txid()) {
@@ -624,6 +1054,46 @@ impl SmartVaultsStorage {
         map
     }
 
+    /// Get the ID of the completed proposal that replaced (fee bump) the passed transaction
+    pub async fn replaced_by(&self, policy_id: &EventId, txid: &Txid) -> Option<EventId> {
+        let completed_proposals = self.completed_proposals.read().await;
+        completed_proposals
+            .iter()
+            .filter(|(_, i)| i.policy_id == *policy_id)
+            .find(|(_, i)| i.proposal.replaced_txid() == Some(*txid))
+            .map(|(id, ..)| *id)
+    }
+
+    /// Get the UTXOs locked by a proposal
+    ///
+    /// For fee bumps, include the outputs of the replaced transaction, invalidated by the replacement.
+    pub async fn proposal_utxos(&self, policy_id: &EventId, proposal: &Proposal) -> Vec<OutPoint> {
+        let mut utxos: Vec<OutPoint> = proposal
+            .psbt()
+            .unsigned_tx
+            .input
+            .iter()
+            .map(|txin| txin.previous_output)
+            .collect();
+
+        if let Proposal::FeeBump { replaced_txid, .. } = proposal {
+            let completed_proposals = self.completed_proposals.read().await;
+            match completed_proposals
+                .values()
+                .filter(|i| i.policy_id == *policy_id)
+                .filter_map(|i| i.proposal.tx())
+                .find(|tx| tx.txid() == *replaced_txid)
+            {
+                Some(tx) => utxos.extend(
+                    (0..tx.output.len() as u32).map(|vout| OutPoint::new(*replaced_txid, vout)),
+                ),
+                None => tracing::warn!("Replaced transaction {replaced_txid} not found"),
+            }
+        }
+
+        utxos
+    }
+
     pub async fn save_signer(&self, signer_id: EventId, signer: Signer) {
         let mut signers = self.signers.write().await;
         signers.insert(signer_id, signer);
@@ -775,6 +1245,17 @@ impl SmartVaultsStorage {
         labels.insert(identifier.into(), InternalLabel { policy_id, label });
     }
 
+    /// Get labels of the vault
+    pub async fn get_labels(&self, policy_id: EventId) -> Vec<Label> {
+        self.labels
+            .read()
+            .await
+            .values()
+            .filter(|i| i.policy_id == policy_id)
+            .map(|i| i.label.clone())
+            .collect()
+    }
+
     pub async fn get_addresses_labels(&self, policy_id: EventId) -> HashMap<ScriptBuf, Label> {
         self.labels
             .read()
@@ -833,6 +1314,12 @@ impl SmartVaultsStorage {
             .extend(utxos);
     }
 
+    /// Freeze the UTXOs locked by a proposal (see [`SmartVaultsStorage::proposal_utxos`])
+    pub async fn freeze_proposal_utxos(&self, policy_id: EventId, proposal: &Proposal) {
+        let utxos: Vec<OutPoint> = self.proposal_utxos(&policy_id, proposal).await;
+        self.freeze_utxos(policy_id, utxos).await;
+    }
+
     pub async fn unfreeze_utxos<I>(&self, policy_id: EventId, utxos: I)
     where
         I: IntoIterator<Item = OutPoint> + Clone,
@@ -858,3 +1345,82 @@ impl SmartVaultsStorage {
         self.verified_key_agents.read().await.clone()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use nostr_sdk::database::IntoNostrDatabase;
+    use nostr_sdk::SQLiteDatabase;
+    use smartvaults_protocol::v1::SmartVaultsEventBuilder;
+    use smartvaults_protocol::v2::ProtocolVersion;
+
+    use super::*;
+
+    const DESCRIPTOR: &str = "tr([9bf4354b/86'/1'/784923']tpubDCT8uwnkZj7woaY71Xr5hU7Wvjr7B1BXJEpwMzzDLd1H6HLnKTiaLPtt6ZfEizDMwdQ8PT8JCmKbB4ESVXTkCzv51oxhJhX5FLBvkeN9nJ3/0/*,pk([7356e457/86'/1'/784923']tpubDCvLwbJPseNux9EtPbrbA2tgDayzptK4HNkky14Cw6msjHuqyZCE88miedZD86TZUb29Rof3sgtREU4wtzofte7QDSWDiw8ZU6ZYHmAxY9d/0/*))#rs0udsfg";
+
+    #[tokio::test]
+    async fn test_member_added_by_rotation() {
+        let alice = Keys::generate();
+        let bob = Keys::generate();
+        let carol = Keys::generate();
+        let policy = Policy::from_descriptor("Vault", "", DESCRIPTOR, Network::Testnet).unwrap();
+
+        // Vault of alice and bob
+        let first_shared_key = Keys::generate();
+        let policy_event: Event = compat::policy(
+            ProtocolVersion::V1,
+            &first_shared_key,
+            &policy,
+            &[alice.public_key(), bob.public_key()],
+        )
+        .unwrap();
+        let vault_id: EventId = policy_event.id;
+
+        // Rotation by alice: bob removed, carol added
+        let shared_key = Keys::generate();
+        let members: Vec<PublicKey> = vec![alice.public_key(), carol.public_key()];
+        let shared_key_event: Event =
+            EventBuilder::shared_key(&alice, &shared_key, &carol.public_key(), vault_id).unwrap();
+        let rotated_policy: Event = compat::rotated_policy(
+            ProtocolVersion::V1,
+            &shared_key,
+            vault_id,
+            &policy,
+            &members,
+        )
+        .unwrap();
+
+        // Same shared key sent to carol by the removed member
+        let removed_shared_key_event: Event =
+            EventBuilder::shared_key(&bob, &shared_key, &carol.public_key(), vault_id).unwrap();
+
+        // Carol never receives the first policy
+        let path = std::env::temp_dir().join(format!(
+            "smartvaults-rotation-{}.db",
+            carol.public_key().to_hex()
+        ));
+        let database = SQLiteDatabase::open(path).await.unwrap();
+        let database: Arc<DynNostrDatabase> = database.into_nostr_database();
+        database
+            .save_event(&removed_shared_key_event)
+            .await
+            .unwrap();
+        database.save_event(&shared_key_event).await.unwrap();
+        database.save_event(&rotated_policy).await.unwrap();
+
+        let storage = SmartVaultsStorage::build(carol.clone(), database, Network::Testnet)
+            .await
+            .unwrap();
+
+        let InternalPolicy {
+            policy: vault_policy,
+            public_keys,
+        } = storage.vault(&vault_id).await.unwrap();
+        assert_eq!(vault_policy.descriptor(), policy.descriptor());
+        assert_eq!(public_keys, members);
+        assert_eq!(
+            storage.shared_key(&vault_id).await.unwrap().public_key(),
+            shared_key.public_key()
+        );
+        assert!(storage.pending_events().await.is_empty());
+    }
+}
@@ -3,9 +3,66 @@
 
 use std::cmp::Ordering;
 
-use nostr_sdk::{EventId, PublicKey, Timestamp};
+use nostr_sdk::{EventId, Keys, PublicKey, Timestamp};
 use smartvaults_core::{ApprovedProposal, CompletedProposal, Policy, Proposal, SharedSigner};
-use smartvaults_protocol::v1::Label;
+use smartvaults_protocol::v1::{Label, ProposalComment, ProposalRejection, VaultMetadata};
+
+/// Shared keys of a vault
+///
+/// Older keys are kept after a rotation so the history still decrypts, but a key is valid
+/// only for the events created before the timestamp of the next key.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SharedKeys {
+    /// Sorted by timestamp
+    keys: Vec<(Timestamp, Keys)>,
+}
+
+impl SharedKeys {
+    /// Add shared key
+    ///
+    /// Return `false` if the key was already known
+    pub fn insert(&mut self, timestamp: Timestamp, shared_key: Keys) -> bool {
+        let public_key: PublicKey = shared_key.public_key();
+        if self.contains(&public_key) {
+            return false;
+        }
+        let pos: usize = self.keys.partition_point(|(t, ..)| *t <= timestamp);
+        self.keys.insert(pos, (timestamp, shared_key));
+        true
+    }
+
+    /// Latest shared key
+    pub fn current(&self) -> Option<&Keys> {
+        self.keys.last().map(|(_, k)| k)
+    }
+
+    /// Check if the shared key is known (current or superseded)
+    pub fn contains(&self, public_key: &PublicKey) -> bool {
+        self.keys.iter().any(|(_, k)| k.public_key() == *public_key)
+    }
+
+    /// Get the shared key with `public_key`, if not superseded at `timestamp`
+    pub fn by_public_key(&self, public_key: &PublicKey, timestamp: Timestamp) -> Option<&Keys> {
+        self.valid_at(timestamp)
+            .find(|k| k.public_key() == *public_key)
+    }
+
+    /// Iterate the shared keys not superseded at `timestamp`, from the latest to the oldest
+    pub fn valid_at(&self, timestamp: Timestamp) -> impl Iterator<Item = &Keys> {
+        let superseded: usize = self
+            .keys
+            .iter()
+            .skip(1)
+            .take_while(|(t, ..)| *t <= timestamp)
+            .count();
+        self.keys.iter().skip(superseded).rev().map(|(_, k)| k)
+    }
+
+    /// Iterate shared keys from the latest to the oldest
+    pub fn iter(&self) -> impl Iterator<Item = &Keys> {
+        self.keys.iter().rev().map(|(_, k)| k)
+    }
+}
 
 #[derive(Debug, Clone)]
 pub(crate) struct InternalPolicy {
@@ -13,11 +70,39 @@ pub(crate) struct InternalPolicy {
     pub public_keys: Vec<PublicKey>,
 }
 
+#[derive(Debug, Clone)]
+pub(crate) struct InternalVaultMetadata {
+    pub metadata: VaultMetadata,
+    pub timestamp: Timestamp,
+    /// When the spending rules were last set, edited or removed
+    pub spending_rules_timestamp: Option<Timestamp>,
+}
+
+impl InternalVaultMetadata {
+    /// Metadata replacing `previous`, keeping the timestamp of the last change of the spending rules
+    pub fn replace(previous: Option<&Self>, metadata: VaultMetadata, timestamp: Timestamp) -> Self {
+        let changed: bool = match previous {
+            Some(previous) => previous.metadata.spending_rules != metadata.spending_rules,
+            None => !metadata.spending_rules.is_empty(),
+        };
+        Self {
+            metadata,
+            timestamp,
+            spending_rules_timestamp: if changed {
+                Some(timestamp)
+            } else {
+                previous.and_then(|p| p.spending_rules_timestamp)
+            },
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) struct InternalProposal {
     pub policy_id: EventId,
     pub proposal: Proposal,
     pub timestamp: Timestamp,
+    pub expiration: Option<Timestamp>,
 }
 
 impl PartialOrd for InternalProposal {
@@ -61,6 +146,24 @@ impl Ord for InternalApproval {
     }
 }
 
+#[derive(Debug, Clone)]
+pub(crate) struct InternalProposalComment {
+    pub proposal_id: EventId,
+    pub policy_id: EventId,
+    pub public_key: PublicKey,
+    pub comment: ProposalComment,
+    pub timestamp: Timestamp,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct InternalProposalRejection {
+    pub proposal_id: EventId,
+    pub policy_id: EventId,
+    pub public_key: PublicKey,
+    pub rejection: ProposalRejection,
+    pub timestamp: Timestamp,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) struct InternalCompletedProposal {
     pub policy_id: EventId,
@@ -95,3 +198,96 @@ pub(crate) struct InternalLabel {
     pub policy_id: EventId,
     pub label: Label,
 }
+
+#[cfg(test)]
+mod tests {
+    use smartvaults_core::SpendingRules;
+
+    use super::*;
+
+    #[test]
+    fn test_spending_rules_timestamp() {
+        let rules = SpendingRules {
+            max_per_tx: Some(100_000),
+            ..Default::default()
+        };
+
+        let first = InternalVaultMetadata::replace(
+            None,
+            VaultMetadata::new().name("Vault"),
+            Timestamp::from(100),
+        );
+        assert_eq!(first.spending_rules_timestamp, None);
+
+        // Rules set
+        let second = InternalVaultMetadata::replace(
+            Some(&first),
+            VaultMetadata::new()
+                .name("Vault")
+                .spending_rules(rules.clone()),
+            Timestamp::from(200),
+        );
+        assert_eq!(second.spending_rules_timestamp, Some(Timestamp::from(200)));
+
+        // Renamed
+        let third = InternalVaultMetadata::replace(
+            Some(&second),
+            VaultMetadata::new().name("Savings").spending_rules(rules),
+            Timestamp::from(300),
+        );
+        assert_eq!(third.spending_rules_timestamp, Some(Timestamp::from(200)));
+
+        // Rules removed
+        let fourth = InternalVaultMetadata::replace(
+            Some(&third),
+            VaultMetadata::new().name("Savings"),
+            Timestamp::from(400),
+        );
+        assert_eq!(fourth.spending_rules_timestamp, Some(Timestamp::from(400)));
+    }
+
+    #[test]
+    fn test_shared_keys() {
+        let mut shared_keys = SharedKeys::default();
+        assert!(shared_keys.current().is_none());
+
+        let first = Keys::generate();
+        let second = Keys::generate();
+
+        // Received out of order
+        assert!(shared_keys.insert(Timestamp::from(200), second.clone()));
+        assert!(shared_keys.insert(Timestamp::from(100), first.clone()));
+        assert!(!shared_keys.insert(Timestamp::from(300), first.clone()));
+
+        assert_eq!(
+            shared_keys.current().map(|k| k.public_key()),
+            Some(second.public_key())
+        );
+        assert!(shared_keys.contains(&first.public_key()));
+
+        // Superseded key
+        assert!(shared_keys
+            .by_public_key(&first.public_key(), Timestamp::from(150))
+            .is_some());
+        assert!(shared_keys
+            .by_public_key(&first.public_key(), Timestamp::from(200))
+            .is_none());
+        assert!(shared_keys
+            .by_public_key(&second.public_key(), Timestamp::from(250))
+            .is_some());
+        assert_eq!(
+            shared_keys
+                .valid_at(Timestamp::from(250))
+                .map(|k| k.public_key())
+                .collect::<Vec<_>>(),
+            vec![second.public_key()]
+        );
+        assert_eq!(
+            shared_keys
+                .iter()
+                .map(|k| k.public_key())
+                .collect::<Vec<_>>(),
+            vec![second.public_key(), first.public_key()]
+        );
+    }
+}
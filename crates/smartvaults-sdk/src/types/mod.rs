@@ -2,10 +2,10 @@
 // Distributed under the MIT software license
 
 use std::cmp::Ordering;
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashSet};
 use std::ops::Deref;
 
-use nostr_sdk::{EventId, Profile, Timestamp};
+use nostr_sdk::{EventId, Profile, PublicKey, Timestamp};
 use smartvaults_core::bdk::wallet::Balance;
 use smartvaults_core::bdk::LocalOutput;
 use smartvaults_core::bitcoin::address::NetworkUnchecked;
@@ -13,7 +13,7 @@ use smartvaults_core::bitcoin::Address;
 use smartvaults_core::{
     ApprovedProposal, CompletedProposal, Policy, Proposal, SharedSigner, Signer,
 };
-use smartvaults_protocol::v1::SignerOffering;
+use smartvaults_protocol::v1::{ProposalComment, ProposalRejection, SignerOffering, VaultMetadata};
 pub use smartvaults_sdk_sqlite::model::*;
 
 pub mod backup;
@@ -24,7 +24,9 @@ use crate::manager::TransactionDetails;
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct GetPolicy {
     pub policy_id: EventId,
+    /// Policy with the latest [`VaultMetadata`] name and description
     pub policy: Policy,
+    pub metadata: VaultMetadata,
     pub balance: Balance,
     pub last_sync: Timestamp,
 }
@@ -56,6 +58,8 @@ pub struct GetProposal {
     pub proposal: Proposal,
     pub signed: bool,
     pub timestamp: Timestamp,
+    /// The proposal is deleted, and its UTXOs unfrozen, after this timestamp
+    pub expiration: Option<Timestamp>,
 }
 
 impl PartialOrd for GetProposal {
@@ -98,6 +102,76 @@ impl Ord for GetApproval {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GetProposalComment {
+    pub comment_id: EventId,
+    pub user: Profile,
+    pub comment: ProposalComment,
+    pub timestamp: Timestamp,
+}
+
+impl PartialOrd for GetProposalComment {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for GetProposalComment {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Oldest first, to read the thread in order
+        if self.timestamp != other.timestamp {
+            self.timestamp.cmp(&other.timestamp)
+        } else {
+            self.comment_id.cmp(&other.comment_id)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GetProposalRejection {
+    pub rejection_id: EventId,
+    pub user: Profile,
+    pub rejection: ProposalRejection,
+    pub timestamp: Timestamp,
+}
+
+impl PartialOrd for GetProposalRejection {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for GetProposalRejection {
+    fn cmp(&self, other: &Self) -> Ordering {
+        if self.timestamp != other.timestamp {
+            self.timestamp.cmp(&other.timestamp).reverse()
+        } else {
+            self.rejection_id.cmp(&other.rejection_id)
+        }
+    }
+}
+
+/// Aggregated status of a proposal
+///
+/// Only the latest vote of every member is counted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProposalStatus {
+    pub approved_by: BTreeSet<PublicKey>,
+    pub rejected_by: BTreeSet<PublicKey>,
+    /// Enough approvals to finalize the proposal
+    pub signed: bool,
+    /// The threshold of the path used by the proposal can still be met without the keys of the
+    /// members that rejected
+    pub approvable: bool,
+}
+
+impl ProposalStatus {
+    /// Approval became impossible
+    pub fn is_rejected(&self) -> bool {
+        !self.signed && !self.approvable
+    }
+}
+
 pub struct GetApprovedProposals {
     pub policy_id: EventId,
     pub proposal: Proposal,
@@ -110,6 +184,8 @@ pub struct GetCompletedProposal {
     pub completed_proposal_id: EventId,
     pub proposal: CompletedProposal,
     pub timestamp: Timestamp,
+    /// Completed fee bump proposal that replaced this transaction
+    pub replaced_by: Option<EventId>,
 }
 
 impl PartialOrd for GetCompletedProposal {
@@ -276,3 +352,41 @@ pub struct GetSignerOffering {
     pub signer: GetSigner,
     pub offering: SignerOffering,
 }
+
+/// PSBT file format
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum PsbtFileFormat {
+    /// BIP174 binary
+    #[default]
+    Binary,
+    /// Base64
+    Base64,
+}
+
+/// Seed account already used in other vaults
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SeedAccountReuse {
+    pub signer: Signer,
+    pub policy_ids: Vec<EventId>,
+}
+
+/// Pending proposal migrated to a new shared key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigratedProposal {
+    /// ID of the deleted proposal
+    pub proposal_id: EventId,
+    /// ID of the proposal re-published with the new shared key
+    pub new_proposal_id: EventId,
+    /// Members that approved the deleted proposal and must approve the new one again
+    pub lost_approvals: BTreeSet<PublicKey>,
+    /// Members that rejected the deleted proposal and must reject the new one again
+    pub lost_rejections: BTreeSet<PublicKey>,
+}
+
+/// Shared key rotation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SharedKeyRotation {
+    /// ID of the policy re-published with the new shared key
+    pub event_id: EventId,
+    pub migrated_proposals: Vec<MigratedProposal>,
+}